@@ -0,0 +1,65 @@
+//! Error types.
+
+use std::error::Error as StdError;
+use std::io;
+use std::result::Result as StdResult;
+
+use thiserror::Error;
+
+/// Result with the module error type.
+pub type Result<T> = StdResult<T, Error>;
+
+/// Module error.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// Udev device access failed.
+    #[error("udev {subsystem} error: {source}")]
+    Udev { subsystem: &'static str, source: io::Error },
+
+    /// Required hardware is not present.
+    #[error("no {0} device found")]
+    DeviceNotFound(&'static str),
+
+    /// Compositor IPC is not available.
+    #[error("{0} IPC socket not found")]
+    CompositorNotFound(&'static str),
+
+    /// System service is not installed or not running.
+    #[error("{0} service not found")]
+    ServiceNotFound(&'static str),
+
+    /// Module requires configuration before it can be used.
+    #[error("{0} is not configured")]
+    NotConfigured(&'static str),
+
+    /// XDG base directory could not be determined.
+    #[error("unable to find {0} directory")]
+    MissingDirectory(&'static str),
+
+    /// Compositor IPC request failed.
+    #[error("compositor IPC failed: {0}")]
+    Compositor(String),
+
+    /// Failure reported by the application hosting the module.
+    #[error(transparent)]
+    Host(Box<dyn StdError>),
+
+    /// Generic I/O failure.
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+}
+
+impl Error {
+    /// Check if the module can't work on this device.
+    ///
+    /// Since not every device has every module, these errors are expected.
+    pub fn is_unavailable(&self) -> bool {
+        matches!(
+            self,
+            Self::DeviceNotFound(_)
+                | Self::CompositorNotFound(_)
+                | Self::ServiceNotFound(_)
+                | Self::NotConfigured(_)
+        )
+    }
+}
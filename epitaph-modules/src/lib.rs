@@ -0,0 +1,354 @@
+//! Module interface of the Epitaph panel.
+//!
+//! Modules provide the content of the panel and the drawer. Each module
+//! implements [`Module`], exposing its panel and drawer content through
+//! [`PanelModule`] and [`DrawerModule`], while the application drives its
+//! updates based on the module's [`Source`]s.
+//!
+//! Applications declare their modules with [`registry!`].
+//!
+//! This crate does not contain any module implementations, the built-in
+//! modules are part of the Epitaph binary.
+
+use std::io;
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use std::time::Duration;
+
+use epitaph_ui::text::Svg;
+use serde::Deserialize;
+
+pub use crate::error::{Error, Result};
+
+mod error;
+
+/// Declare a registry of modules, addressable by an ID enum.
+///
+/// This generates the registry struct, along with methods to look up modules
+/// by their ID and to list all registered modules in declaration order.
+///
+/// Attributes on entries are forwarded, so modules can be excluded from the
+/// build using `#[cfg(feature = "...")]`. Lookups of excluded modules return
+/// `None`, so their IDs can stay valid in configuration files.
+///
+/// ```ignore
+/// epitaph_modules::registry! {
+///     /// Built-in modules.
+///     pub struct Registry: ModuleId {
+///         Clock => clock: Clock,
+///         #[cfg(feature = "udev")]
+///         Battery => battery: Battery,
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! registry {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $registry:ident: $id:ident {
+            $($(#[$attr:meta])* $variant:ident => $field:ident: $module:ty),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $registry {
+            $($(#[$attr])* pub $field: $module,)*
+        }
+
+        impl $registry {
+            /// Get a module by its ID.
+            ///
+            /// Returns `None` if the module was excluded from the build.
+            pub fn get(&self, id: $id) -> Option<&dyn $crate::Module> {
+                match id {
+                    $($(#[$attr])* $id::$variant => Some(&self.$field),)*
+                    #[allow(unreachable_patterns)]
+                    _ => None,
+                }
+            }
+
+            /// Get a module by its ID.
+            ///
+            /// Returns `None` if the module was excluded from the build.
+            pub fn get_mut(&mut self, id: $id) -> Option<&mut dyn $crate::Module> {
+                match id {
+                    $($(#[$attr])* $id::$variant => Some(&mut self.$field),)*
+                    #[allow(unreachable_patterns)]
+                    _ => None,
+                }
+            }
+
+            /// Get all modules in declaration order.
+            pub fn all(&self) -> Vec<($id, &dyn $crate::Module)> {
+                let mut modules: Vec<($id, &dyn $crate::Module)> = Vec::new();
+                $($(#[$attr])* modules.push(($id::$variant, &self.$field));)*
+                modules
+            }
+
+            /// Get all modules in declaration order.
+            pub fn all_mut(&mut self) -> Vec<($id, &mut dyn $crate::Module)> {
+                let mut modules: Vec<($id, &mut dyn $crate::Module)> = Vec::new();
+                $($(#[$attr])* modules.push(($id::$variant, &mut self.$field));)*
+                modules
+            }
+        }
+    };
+}
+
+/// Panel module.
+pub trait Module {
+    /// Panel module implementation.
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        None
+    }
+
+    /// Drawer module implementation.
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        None
+    }
+
+    /// Drawer section containing this module.
+    fn section(&self) -> Section {
+        Section::default()
+    }
+
+    /// Check hardware availability and load the initial module state.
+    fn probe(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Event sources driving this module's updates.
+    fn sources(&self) -> Vec<Source> {
+        Vec::new()
+    }
+
+    /// Handle an event from the source at `index` in [`Module::sources`].
+    ///
+    /// Returns `true` if the module's content changed and requires a redraw.
+    fn update(&mut self, _index: usize, _event: Event) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+/// Event source for module updates.
+pub enum Source {
+    /// Timer firing at a fixed interval.
+    Interval(Duration),
+    /// Timer firing at the start of every minute.
+    Minute,
+    /// Timer firing at the start of every local day, even after suspend.
+    ///
+    /// This also fires whenever the system clock or timezone changes.
+    Midnight,
+    /// Udev device changes in a subsystem.
+    Udev(&'static str),
+    /// Command periodically run in the background.
+    Command(Box<dyn Fn() -> Command>, Duration),
+    /// Socket streaming updates, reconnected whenever it is closed.
+    Socket(fn() -> Result<UnixStream>),
+    /// Named FIFO streaming updates, or stdin if no path is specified.
+    Pipe(Option<PathBuf>),
+    /// Process streaming updates to stdout, restarted whenever it exits.
+    Process(Box<dyn Fn() -> Command>),
+    /// Command run in the background whenever a monitoring process prints to stdout.
+    ///
+    /// The command also runs once the monitor was started, so changes while it
+    /// wasn't running are not missed. The monitor's output is not forwarded.
+    Watch(Box<dyn Fn() -> Command>, Box<dyn Fn() -> Command>),
+    /// Route netlink socket subscribed to multicast groups.
+    Netlink(u32),
+}
+
+/// Module update event.
+pub enum Event {
+    /// Timer has fired.
+    Timer,
+    /// Udev device has changed.
+    Udev,
+    /// Command has completed.
+    Output(io::Result<Output>),
+    /// Socket or pipe has been connected.
+    Connected,
+    /// Socket or pipe has received data.
+    Data(Vec<u8>),
+    /// Socket or pipe has been closed.
+    Disconnected,
+}
+
+/// Collapsible group of drawer modules.
+#[derive(Deserialize, Copy, Clone, Default, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Section {
+    Connectivity,
+    Display,
+    #[default]
+    Device,
+}
+
+impl Section {
+    /// All sections in drawer order.
+    pub const ALL: [Self; 3] = [Self::Connectivity, Self::Display, Self::Device];
+
+    /// Section header text.
+    pub const fn label(&self) -> &'static str {
+        match self {
+            Self::Connectivity => "Connectivity",
+            Self::Display => "Display",
+            Self::Device => "Device",
+        }
+    }
+}
+
+/// Module alignment.
+#[derive(Deserialize, Copy, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Alignment {
+    Left,
+    Center,
+    Right,
+}
+
+/// Module in the panel.
+pub trait PanelModule {
+    /// Module alignment.
+    fn alignment(&self) -> Alignment;
+
+    /// Renderable panel content.
+    fn content(&self) -> PanelModuleContent;
+
+    /// Renderable panel content in the icon-only panel mode.
+    fn condensed_content(&self) -> PanelModuleContent {
+        match self.content() {
+            PanelModuleContent::Status { svg: Some(svg), color, .. } => {
+                PanelModuleContent::Status { text: String::new(), svg: Some(svg), color }
+            },
+            content => content,
+        }
+    }
+}
+
+/// Panel module renderable.
+pub enum PanelModuleContent {
+    Text(String),
+    Svg(Svg),
+    /// Colored text with an optional leading SVG.
+    Status {
+        text: String,
+        svg: Option<Svg>,
+        color: [u8; 3],
+    },
+}
+
+impl PanelModuleContent {
+    /// Check if there is nothing to render.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Self::Text(text) => text.is_empty(),
+            Self::Svg(_) => false,
+            Self::Status { text, svg, .. } => text.is_empty() && svg.is_none(),
+        }
+    }
+}
+
+/// Module in the drawer.
+pub enum DrawerModule<'a> {
+    Toggle(&'a mut dyn Toggle),
+    Slider(&'a mut dyn Slider),
+    List(&'a mut dyn List),
+    Graph(&'a mut dyn Graph),
+}
+
+/// Drawer slider module.
+pub trait Slider {
+    /// Handle slider updates.
+    fn set_value(&mut self, value: f64) -> Result<()>;
+
+    /// Get current slider value.
+    fn get_value(&self) -> f64;
+
+    /// Get symbol for this slider.
+    fn svg(&self) -> Svg;
+}
+
+/// Drawer toggle button module.
+pub trait Toggle {
+    /// Toggle button status.
+    fn toggle(&mut self) -> Result<()>;
+
+    /// Get button status.
+    fn enabled(&self) -> bool;
+
+    /// Get renderable SVG.
+    fn svg(&self) -> Svg;
+
+    /// Get text shown below the SVG.
+    fn caption(&self) -> Option<String> {
+        None
+    }
+
+    /// Close the drawer after toggling.
+    fn dismisses_drawer(&self) -> bool {
+        false
+    }
+
+    /// Get text encoded in a QR code, shown below the button after a long-press.
+    fn qr_code(&self) -> Option<String> {
+        None
+    }
+
+    /// Get the intensity adjustable by dragging vertically, from `0.0` to `1.0`.
+    fn level(&self) -> Option<f64> {
+        None
+    }
+
+    /// Handle intensity updates, enabling the toggle.
+    fn set_level(&mut self, _level: f64) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Drawer module with an expandable list of choices.
+pub trait List {
+    /// Activate the choice at `index` in [`List::entries`].
+    fn select(&mut self, index: usize) -> Result<()>;
+
+    /// Get all available choices.
+    fn entries(&self) -> Vec<String>;
+
+    /// Get index of the active choice.
+    fn selected(&self) -> Option<usize>;
+
+    /// Get text shown while the list is collapsed.
+    fn label(&self) -> String;
+
+    /// Get symbol for this list.
+    fn svg(&self) -> Svg;
+
+    /// Handle the start of a touch on the choice at `index` in [`List::entries`].
+    ///
+    /// Entries can change while they're held down, so this should remember the
+    /// pressed choice for [`List::long_press`].
+    fn press(&mut self, _index: usize) {}
+
+    /// Handle a long-press on the choice pressed last.
+    ///
+    /// This is used for destructive actions, which shouldn't be triggered by accident.
+    fn long_press(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Drawer module plotting recent values.
+pub trait Graph {
+    /// Get samples in chronological order.
+    ///
+    /// Both the position within the plotted time range and the value range
+    /// from `0.0` to `1.0`.
+    fn points(&self) -> Vec<(f64, f64)>;
+
+    /// Get text shown above the graph.
+    fn label(&self) -> String;
+
+    /// Get symbol for this graph.
+    fn svg(&self) -> Svg;
+}
@@ -2,16 +2,25 @@ use std::env;
 use std::fs::File;
 use std::path::Path;
 
-use gl_generator::{Api, Fallbacks, GlobalGenerator, Profile, Registry};
+use gl_generator::{Api, Fallbacks, GlobalGenerator, Profile, Registry, StructGenerator};
 
 fn main() {
     let dest = env::var("OUT_DIR").unwrap();
     let mut file = File::create(Path::new(&dest).join("gl_bindings.rs")).unwrap();
 
-    Registry::new(Api::Gles2, (2, 0), Profile::Core, Fallbacks::All, [
+    Registry::new(Api::Gles2, (3, 0), Profile::Core, Fallbacks::All, [
         "GL_OES_vertex_array_object",
         "GL_EXT_blend_func_extended",
     ])
     .write_bindings(GlobalGenerator, &mut file)
     .unwrap();
+
+    let mut file = File::create(Path::new(&dest).join("egl_bindings.rs")).unwrap();
+
+    Registry::new(Api::Egl, (1, 5), Profile::Core, Fallbacks::All, [
+        "EGL_KHR_partial_update",
+        "EGL_KHR_swap_buffers_with_damage",
+    ])
+    .write_bindings(StructGenerator, &mut file)
+    .unwrap();
 }
@@ -0,0 +1,8 @@
+#version 100
+
+varying mediump vec4 color;
+
+void main() {
+    // Premultiply alpha for translucent surfaces.
+    gl_FragColor = vec4(color.rgb * color.a, color.a);
+}
@@ -0,0 +1,24 @@
+#version 100
+
+attribute vec2 aPos;
+attribute vec4 aColor;
+
+varying mediump vec4 color;
+
+#ifdef SRGB
+// Convert an sRGB color to linear color space.
+vec3 srgbToLinear(vec3 color) {
+    vec3 low = color / 12.92;
+    vec3 high = pow((color + 0.055) / 1.055, vec3(2.4));
+    return mix(low, high, step(0.04045, color));
+}
+#endif
+
+void main() {
+#ifdef SRGB
+    color = vec4(srgbToLinear(aColor.rgb), aColor.a);
+#else
+    color = aColor;
+#endif
+    gl_Position = vec4(aPos.x, aPos.y, 0.0, 1.0);
+}
@@ -0,0 +1,10 @@
+#version 300 es
+
+in mediump vec4 color;
+
+out mediump vec4 fragColor;
+
+void main() {
+    // Premultiply alpha for translucent surfaces.
+    fragColor = vec4(color.rgb * color.a, color.a);
+}
@@ -0,0 +1,25 @@
+#version 300 es
+
+layout(location = 0) in vec2 aCorner;
+layout(location = 1) in vec4 aRect;
+layout(location = 2) in vec4 aColor;
+
+out mediump vec4 color;
+
+#ifdef SRGB
+// Convert an sRGB color to linear color space.
+vec3 srgbToLinear(vec3 color) {
+    vec3 low = color / 12.92;
+    vec3 high = pow((color + 0.055) / 1.055, vec3(2.4));
+    return mix(low, high, step(0.04045, color));
+}
+#endif
+
+void main() {
+#ifdef SRGB
+    color = vec4(srgbToLinear(aColor.rgb), aColor.a);
+#else
+    color = aColor;
+#endif
+    gl_Position = vec4(aRect.xy + aCorner * aRect.zw, 0.0, 1.0);
+}
@@ -0,0 +1,46 @@
+#version 100
+#extension GL_EXT_blend_func_extended: require
+#define COLORED 1
+
+varying mediump vec2 v_UV;
+varying mediump float v_Flags;
+varying mediump vec4 v_Color;
+
+uniform sampler2D u_Texture;
+
+#ifdef SRGB
+// Convert an sRGB color to linear color space.
+mediump vec3 srgbToLinear(mediump vec3 color) {
+    mediump vec3 low = color / 12.92;
+    mediump vec3 high = pow((color + 0.055) / 1.055, vec3(2.4));
+    return mix(low, high, step(0.04045, color));
+}
+#endif
+
+void main() {
+    if (v_Flags >= 1.) {
+        // Color glyphs, like emojis, or prerendered content.
+        gl_FragColor = texture2D(u_Texture, v_UV);
+        gl_SecondaryFragColorEXT = vec4(gl_FragColor.a * v_Color.a);
+
+        // Revert alpha premultiplication.
+        if (gl_FragColor.a != 0.0) {
+            gl_FragColor.rgb = vec3(gl_FragColor.rgb / gl_FragColor.a);
+        }
+
+#ifdef SRGB
+        // Prerendered content is already in linear color space.
+        if (v_Flags == 1.) {
+            gl_FragColor.rgb = srgbToLinear(gl_FragColor.rgb);
+        }
+#endif
+
+        // Tint colored glyphs, which has no effect for the default white.
+        gl_FragColor = vec4(gl_FragColor.rgb * v_Color.rgb, 1.0);
+    } else {
+        // Regular text glyphs.
+        mediump vec3 textColor = texture2D(u_Texture, v_UV).rgb * v_Color.a;
+        gl_SecondaryFragColorEXT = vec4(textColor, textColor.r);
+        gl_FragColor = vec4(v_Color.rgb, 1.0);
+    }
+}
@@ -0,0 +1,33 @@
+#version 100
+
+attribute vec2 a_Position;
+attribute vec2 a_UV;
+attribute float a_Flags;
+attribute vec4 a_Color;
+
+varying vec2 v_UV;
+varying float v_Flags;
+varying vec4 v_Color;
+
+uniform vec4 u_Projection;
+
+#ifdef SRGB
+// Convert an sRGB color to linear color space.
+vec3 srgbToLinear(vec3 color) {
+    vec3 low = color / 12.92;
+    vec3 high = pow((color + 0.055) / 1.055, vec3(2.4));
+    return mix(low, high, step(0.04045, color));
+}
+#endif
+
+void main() {
+    v_Flags = a_Flags;
+#ifdef SRGB
+    v_Color = vec4(srgbToLinear(a_Color.rgb), a_Color.a);
+#else
+    v_Color = a_Color;
+#endif
+    v_UV = a_UV;
+    vec2 finalPosition = u_Projection.xy + a_Position * u_Projection.zw;
+    gl_Position = vec4(finalPosition, 0., 1.);
+}
@@ -0,0 +1,36 @@
+//! Error types.
+
+use std::result::Result as StdResult;
+
+use thiserror::Error;
+
+/// Result with the rendering error type.
+pub type Result<T> = StdResult<T, Error>;
+
+/// Rendering error.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// EGL or OpenGL context failure.
+    #[error("EGL error: {0}")]
+    Egl(#[from] glutin::error::Error),
+
+    /// Renderer was used before its surface was created.
+    #[error("attempted to bind EGL context without surface")]
+    MissingSurface,
+
+    /// Font loading or glyph rasterization failed.
+    #[error("font error: {0}")]
+    Font(#[from] crossfont::Error),
+
+    /// SVG parsing failed.
+    #[error("SVG error: {0}")]
+    Svg(#[from] usvg::Error),
+
+    /// Texture could not be created.
+    #[error("texture error: {0}")]
+    Texture(String),
+
+    /// Theme could not be loaded.
+    #[error("theme error: {0}")]
+    Theme(String),
+}
@@ -0,0 +1,117 @@
+//! Rendering layer of the Epitaph panel.
+//!
+//! This contains the OpenGL renderer, text and icon rasterization, and the
+//! visual themes used by Epitaph, allowing companion surfaces like lock
+//! screens or launchers to match its look.
+//!
+//! The OpenGL symbols must be loaded with [`gl::load_with`] before creating
+//! any [`renderer::Renderer`].
+
+use std::ops::Mul;
+
+pub use crate::error::{Error, Result};
+
+pub mod renderer;
+pub mod text;
+pub mod theme;
+pub mod vertex;
+
+mod error;
+
+/// Generated OpenGL ES bindings.
+pub mod gl {
+    #![allow(clippy::all)]
+    include!(concat!(env!("OUT_DIR"), "/gl_bindings.rs"));
+}
+
+/// Generated EGL bindings, for extensions not exposed by glutin.
+mod egl {
+    #![allow(clippy::all, dead_code, non_camel_case_types)]
+
+    // Platform types which must be provided for the generated bindings.
+    pub type khronos_utime_nanoseconds_t = khronos_uint64_t;
+    pub type khronos_uint64_t = u64;
+    pub type khronos_ssize_t = std::os::raw::c_long;
+    pub type EGLint = i32;
+    pub type EGLNativeDisplayType = *const std::os::raw::c_void;
+    pub type EGLNativePixmapType = *const std::os::raw::c_void;
+    pub type EGLNativeWindowType = *const std::os::raw::c_void;
+    pub type NativeDisplayType = EGLNativeDisplayType;
+    pub type NativePixmapType = EGLNativePixmapType;
+    pub type NativeWindowType = EGLNativeWindowType;
+
+    include!(concat!(env!("OUT_DIR"), "/egl_bindings.rs"));
+}
+
+/// Surface size.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub struct Size<T = i32> {
+    pub width: T,
+    pub height: T,
+}
+
+impl<T> Size<T> {
+    pub fn new(width: T, height: T) -> Self {
+        Self { width, height }
+    }
+}
+
+impl From<Size> for Size<f32> {
+    fn from(from: Size) -> Self {
+        Self { width: from.width as f32, height: from.height as f32 }
+    }
+}
+
+impl Mul<f64> for Size {
+    type Output = Self;
+
+    fn mul(mut self, factor: f64) -> Self {
+        self.width = (self.width as f64 * factor) as i32;
+        self.height = (self.height as f64 * factor) as i32;
+        self
+    }
+}
+
+/// Rectangle in physical pixels, with its origin at the top left.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    /// Check if the rectangle covers no pixels.
+    pub fn is_empty(&self) -> bool {
+        self.width <= 0 || self.height <= 0
+    }
+
+    /// Smallest rectangle containing both rectangles.
+    pub fn union(self, other: Self) -> Self {
+        if self.is_empty() {
+            return other;
+        } else if other.is_empty() {
+            return self;
+        }
+
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = (self.x + self.width).max(other.x + other.width);
+        let bottom = (self.y + self.height).max(other.y + other.height);
+        Self::new(x, y, right - x, bottom - y)
+    }
+
+    /// Area covered by both rectangles.
+    pub fn intersection(self, other: Self) -> Self {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = (self.x + self.width).min(other.x + other.width);
+        let bottom = (self.y + self.height).min(other.y + other.height);
+        Self::new(x, y, (right - x).max(0), (bottom - y).max(0))
+    }
+}
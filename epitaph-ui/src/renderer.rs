@@ -0,0 +1,1287 @@
+//! OpenGL rendering.
+
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::ffi::{CStr, CString};
+use std::hash::Hasher;
+use std::num::NonZeroU32;
+use std::ops::Deref;
+use std::result::Result as StdResult;
+use std::time::{Duration, Instant};
+use std::{mem, ptr};
+
+use glutin::api::egl::config::Config;
+use glutin::api::egl::context::{NotCurrentContext, PossiblyCurrentContext};
+use glutin::api::egl::display::Display;
+use glutin::api::egl::surface::Surface;
+use glutin::context::{ContextApi, ContextAttributesBuilder, Version};
+use glutin::display::{AsRawDisplay, GetGlDisplay, RawDisplay};
+use glutin::prelude::*;
+use glutin::surface::{AsRawSurface, RawSurface, WindowSurface};
+use tiny_skia::Pixmap;
+
+use crate::error::Error;
+use crate::gl::types::{GLenum, GLfloat, GLshort, GLuint};
+use crate::text::{GlRasterizer, GlSubTexture};
+use crate::theme::Theme;
+use crate::vertex::{GlyphVertex, RectInstance, RectVertex, VertexBatcher};
+use crate::{egl, gl, Rect, Result, Size};
+
+/// Default font.
+const FONT: &str = "Sans";
+
+/// Default font size.
+const FONT_SIZE: f32 = 6.;
+
+/// Maximum items to be drawn in a batch.
+///
+/// We use the closest number to `u16::MAX` dividable by 4 (amount of vertices
+/// we push for a glyph), since it's the maximum possible index in
+/// `glDrawElements` in GLES2.
+const BATCH_MAX: usize = (u16::MAX - u16::MAX % 4) as usize;
+
+/// Maximum buffer age for which damage is tracked.
+///
+/// Older buffers are redrawn completely.
+const MAX_BUFFER_AGE: usize = 4;
+
+const TEXT_VERTEX_SHADER: &str = include_str!("../shaders/text.v.glsl");
+const TEXT_FRAGMENT_SHADER: &str = include_str!("../shaders/text.f.glsl");
+const RECT_VERTEX_SHADER: &str = include_str!("../shaders/rect.v.glsl");
+const RECT_FRAGMENT_SHADER: &str = include_str!("../shaders/rect.f.glsl");
+const RECT_INSTANCED_VERTEX_SHADER: &str = include_str!("../shaders/rect_instanced.v.glsl");
+const RECT_INSTANCED_FRAGMENT_SHADER: &str = include_str!("../shaders/rect_instanced.f.glsl");
+
+/// GLES context with its supported rendering features.
+pub struct Context {
+    pub egl_context: NotCurrentContext,
+    /// Whether surfaces are rendered in linear sRGB color space.
+    pub srgb: bool,
+}
+
+/// Create a GLES context, preferring GLES 3 over GLES 2.
+///
+/// sRGB rendering is only enabled if `srgb` is requested and supported by the
+/// created context.
+pub fn create_context(egl_config: &Config, srgb: bool) -> Result<Context> {
+    let egl_display = egl_config.display();
+
+    let gles3_attributes = ContextAttributesBuilder::new()
+        .with_context_api(ContextApi::Gles(Some(Version::new(3, 0))))
+        .build(None);
+    if let Ok(egl_context) = unsafe { egl_display.create_context(egl_config, &gles3_attributes) } {
+        return Ok(Context { egl_context, srgb });
+    }
+
+    let gles2_attributes = ContextAttributesBuilder::new()
+        .with_context_api(ContextApi::Gles(Some(Version::new(2, 0))))
+        .build(None);
+    let egl_context = unsafe { egl_display.create_context(egl_config, &gles2_attributes)? };
+
+    // sRGB framebuffer textures are not available with GLES 2.
+    Ok(Context { egl_context, srgb: false })
+}
+
+/// Renderer performance statistics.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct FrameStats {
+    pub frame_time: Duration,
+    pub draw_calls: usize,
+    pub texture_memory: usize,
+}
+
+/// OpenGL renderer.
+pub struct Renderer {
+    pub text_batcher: VertexBatcher<TextRenderer>,
+    pub rect_batcher: VertexBatcher<RectRenderer>,
+    pub line_batcher: VertexBatcher<LineRenderer>,
+    pub rasterizer: GlRasterizer,
+    pub scale_factor: i32,
+    pub size: Size<f32>,
+
+    egl_surface: Option<Surface<WindowSurface>>,
+    egl_context: PossiblyCurrentContext,
+    framebuffer: Option<Framebuffer>,
+    partial_update: Option<PartialUpdate>,
+    damage_history: VecDeque<Rect>,
+    frame_damage: Option<Rect>,
+    frame_discarded: bool,
+    srgb: bool,
+    cache: Option<CachedLayer>,
+    stats: FrameStats,
+}
+
+impl Renderer {
+    /// Initialize a new renderer.
+    pub fn new(context: Context, scale_factor: i32) -> Result<Self> {
+        let Context { egl_context, srgb } = context;
+
+        unsafe {
+            // Enable the OpenGL context.
+            let egl_context = egl_context.make_current_surfaceless()?;
+
+            // Set background color and blending.
+            gl::ClearColor(0.1, 0.1, 0.1, 1.0);
+            gl::Enable(gl::BLEND);
+
+            Ok(Renderer {
+                partial_update: PartialUpdate::new(&egl_context.display()),
+                scale_factor,
+                egl_context,
+                rasterizer: GlRasterizer::new(FONT, FONT_SIZE, scale_factor)?,
+                srgb,
+                text_batcher: VertexBatcher::new(srgb),
+                rect_batcher: VertexBatcher::new(srgb),
+                line_batcher: VertexBatcher::new(srgb),
+                egl_surface: Default::default(),
+                framebuffer: Default::default(),
+                damage_history: Default::default(),
+                frame_damage: Default::default(),
+                frame_discarded: Default::default(),
+                cache: Default::default(),
+                stats: Default::default(),
+                size: Default::default(),
+            })
+        }
+    }
+
+    /// Check if surfaces are rendered in linear sRGB color space.
+    pub fn srgb(&self) -> bool {
+        self.srgb
+    }
+
+    /// Update viewport size.
+    pub fn resize(&mut self, size: Size, scale_factor: i32) -> Result<()> {
+        // XXX: Resize here **must** be performed before making the EGL context current,
+        // to avoid locking the back buffer and delaying the resize by one
+        // frame.
+        if let Some(egl_surface) = &self.egl_surface {
+            egl_surface.resize(
+                &self.egl_context,
+                NonZeroU32::new(size.width as u32).unwrap(),
+                NonZeroU32::new(size.height as u32).unwrap(),
+            );
+        }
+
+        self.bind()?;
+
+        unsafe { gl::Viewport(0, 0, size.width, size.height) };
+        self.size = size.into();
+
+        // Calculate OpenGL projection.
+        let scale_x = 2. / size.width as f32;
+        let scale_y = -2. / size.height as f32;
+        let offset_x = -1.;
+        let offset_y = 1.;
+
+        unsafe {
+            gl::Uniform4f(0, offset_x, offset_y, scale_x, scale_y);
+        }
+
+        // Update rasterizer's scale factor.
+        self.rasterizer.set_scale_factor(scale_factor);
+        self.scale_factor = scale_factor;
+
+        Ok(())
+    }
+
+    /// Perform drawing with this renderer.
+    ///
+    /// All vertices batched by `fun` are drawn together once it returns.
+    ///
+    /// The error type of `fun` can be chosen freely, as long as it can hold
+    /// rendering errors.
+    #[cfg_attr(feature = "profiling", tracing::instrument(skip_all))]
+    pub fn draw<E, F>(&mut self, mut fun: F) -> StdResult<(), E>
+    where
+        E: From<Error>,
+        F: FnMut(&mut Renderer) -> StdResult<(), E>,
+    {
+        let start = Instant::now();
+
+        self.bind()?;
+
+        // Redraw everything, unless `fun` reports the damaged region.
+        if self.frame_damage.take().is_some() {
+            unsafe { gl::Disable(gl::SCISSOR_TEST) };
+        }
+
+        fun(self)?;
+
+        // Keep the last frame on screen, without drawing anything.
+        if mem::take(&mut self.frame_discarded) {
+            self.text_batcher.clear();
+            self.rect_batcher.clear();
+            self.line_batcher.clear();
+            return Ok(());
+        }
+
+        self.flush();
+
+        let damage = self.frame_damage.take();
+        if damage.is_some() {
+            unsafe { gl::Disable(gl::SCISSOR_TEST) };
+        }
+
+        unsafe { gl::Flush() };
+
+        if let Some(egl_surface) = &self.egl_surface {
+            // Only report the damaged region to the compositor.
+            let size = Size::new(self.size.width as i32, self.size.height as i32);
+            let swapped = match (damage, &self.partial_update) {
+                (Some(damage), Some(partial_update)) => {
+                    partial_update.swap_buffers_with_damage(egl_surface, egl_rect(damage, size))
+                },
+                _ => false,
+            };
+            if !swapped {
+                egl_surface.swap_buffers(&self.egl_context).map_err(Error::from)?;
+            }
+
+            // Remember damage, to redraw all changes since a buffer was last used.
+            let damage = damage.unwrap_or_else(|| Rect::new(0, 0, size.width, size.height));
+            self.damage_history.push_front(damage);
+            self.damage_history.truncate(MAX_BUFFER_AGE);
+        }
+
+        // Update performance statistics.
+        self.stats = FrameStats {
+            draw_calls: self.text_batcher.take_draw_calls()
+                + self.rect_batcher.take_draw_calls()
+                + self.line_batcher.take_draw_calls(),
+            texture_memory: self.rasterizer.texture_memory(),
+            frame_time: start.elapsed(),
+        };
+
+        Ok(())
+    }
+
+    /// Limit redrawing of the current frame to its damaged region.
+    ///
+    /// This must be called by the [`Self::draw`] closure before anything is
+    /// drawn, otherwise the entire surface is redrawn. Damage is ignored
+    /// without `EGL_KHR_partial_update` support.
+    pub fn set_damage(&mut self, damage: Rect) {
+        let (partial_update, egl_surface) = match (&self.partial_update, &self.egl_surface) {
+            (Some(partial_update), Some(egl_surface)) => (partial_update, egl_surface),
+            _ => return,
+        };
+
+        // The damage region can only be set once per frame.
+        if self.frame_damage.is_some() {
+            return;
+        }
+
+        // Include everything changed since the buffer was last drawn.
+        let size = Size::new(self.size.width as i32, self.size.height as i32);
+        let bounds = Rect::new(0, 0, size.width, size.height);
+        let age = partial_update.buffer_age(egl_surface);
+        let region = match age.checked_sub(1) {
+            Some(frames) if frames <= self.damage_history.len() => {
+                let history = self.damage_history.iter().take(frames);
+                let region = history.fold(damage, |region, previous| region.union(*previous));
+                region.intersection(bounds)
+            },
+            _ => bounds,
+        };
+
+        let rect = egl_rect(region, size);
+        if !partial_update.set_damage_region(egl_surface, rect) {
+            return;
+        }
+
+        // Skip drawing outside of the damaged region.
+        unsafe {
+            gl::Enable(gl::SCISSOR_TEST);
+            gl::Scissor(rect[0], rect[1], rect[2], rect[3]);
+        }
+
+        self.frame_damage = Some(damage);
+    }
+
+    /// Skip drawing the current frame, keeping the previous one on screen.
+    ///
+    /// This must be called by the [`Self::draw`] closure, all vertices it
+    /// batched are discarded.
+    pub fn discard_frame(&mut self) {
+        self.frame_discarded = true;
+    }
+
+    /// Draw all batched vertices.
+    ///
+    /// Rectangles are drawn first, so lines and text are always rendered on top of them.
+    fn flush(&mut self) {
+        let mut rect_batches = self.rect_batcher.batches();
+        while let Some(rect_batch) = rect_batches.next() {
+            rect_batch.draw();
+        }
+
+        let mut line_batches = self.line_batcher.batches();
+        while let Some(line_batch) = line_batches.next() {
+            line_batch.draw();
+        }
+
+        let mut text_batches = self.text_batcher.batches();
+        while let Some(text_batch) = text_batches.next() {
+            text_batch.draw();
+        }
+    }
+
+    /// Draw all batched vertices through an offscreen cache.
+    ///
+    /// When the batched vertices are identical to the previous call, they are
+    /// discarded and the cached rendering is drawn instead. The cache covers
+    /// the entire viewport.
+    pub fn flush_cached(&mut self, size: Size) -> Result<()> {
+        let mut hasher = DefaultHasher::new();
+        self.rect_batcher.hash(&mut hasher);
+        self.line_batcher.hash(&mut hasher);
+        self.text_batcher.hash(&mut hasher);
+        let key = hasher.finish();
+
+        match self.cache.take() {
+            Some(cache) if cache.key == key && cache.size == size => {
+                self.rect_batcher.clear();
+                self.line_batcher.clear();
+                self.text_batcher.clear();
+                self.cache = Some(cache);
+            },
+            cache => {
+                let framebuffer = match cache {
+                    Some(cache) if cache.size == size => cache.framebuffer,
+                    _ => Framebuffer::new(size, self.srgb)?,
+                };
+                self.render_cache(&framebuffer, size);
+                self.cache = Some(CachedLayer { framebuffer, size, key });
+            },
+        }
+
+        // Draw the cached layer as a single textured quad.
+        let texture = &self.cache.as_ref().unwrap().framebuffer.texture;
+        let subtexture = GlSubTexture {
+            texture_id: texture.id,
+            multicolor: true,
+            width: size.width as i16,
+            height: size.height as i16,
+            uv_width: 1.,
+            // Flip vertically, since OpenGL's origin is at the bottom left.
+            uv_bot: 1.,
+            uv_height: -1.,
+            uv_left: 0.,
+            advance: (0, 0),
+            left: 0,
+            top: 0,
+        };
+        for mut vertex in subtexture.vertices(0, 0).into_iter().flatten() {
+            // Skip color space conversion, since the content is already converted.
+            vertex.flags = 2.;
+            self.text_batcher.push(texture.id, vertex);
+        }
+        self.flush();
+
+        Ok(())
+    }
+
+    /// Render all batched vertices into the cache framebuffer.
+    fn render_cache(&mut self, framebuffer: &Framebuffer, size: Size) {
+        unsafe {
+            // Store state of the actual render target.
+            let mut viewport = [0; 4];
+            gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+            let scissor = gl::IsEnabled(gl::SCISSOR_TEST) == gl::TRUE;
+            let target = self.framebuffer.as_ref().map_or(0, |framebuffer| framebuffer.id);
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer.id);
+            gl::Disable(gl::SCISSOR_TEST);
+            gl::Viewport(0, 0, size.width, size.height);
+
+            gl::ClearColor(0.0, 0.0, 0.0, 0.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+
+            self.flush();
+
+            // Restore the actual render target.
+            gl::BindFramebuffer(gl::FRAMEBUFFER, target);
+            gl::Viewport(viewport[0], viewport[1], viewport[2], viewport[3]);
+            if scissor {
+                gl::Enable(gl::SCISSOR_TEST);
+            }
+        }
+    }
+
+    /// Switch to the font and icons of a theme.
+    pub fn set_theme(&mut self, theme: &Theme) -> Result<()> {
+        self.egl_context.make_current_surfaceless()?;
+        self.rasterizer.set_theme(theme);
+        self.cache = None;
+        Ok(())
+    }
+
+    /// Free GPU memory which can be recreated on demand.
+    pub fn release_resources(&mut self) -> Result<()> {
+        self.egl_context.make_current_surfaceless()?;
+
+        self.rasterizer.clear_cache();
+        self.text_batcher.shrink_to_fit();
+        self.rect_batcher.shrink_to_fit();
+        self.line_batcher.shrink_to_fit();
+        self.framebuffer = None;
+        self.cache = None;
+
+        Ok(())
+    }
+
+    /// Performance statistics of the last frame.
+    pub fn stats(&self) -> FrameStats {
+        self.stats
+    }
+
+    /// Get the renderer's EGL context.
+    pub fn egl_context(&self) -> &PossiblyCurrentContext {
+        &self.egl_context
+    }
+
+    /// Update the renderer's active EGL surface.
+    pub fn set_surface(&mut self, egl_surface: Option<Surface<WindowSurface>>) {
+        self.egl_surface = egl_surface;
+        self.damage_history.clear();
+    }
+
+    /// Render into an offscreen framebuffer instead of a window surface.
+    pub fn set_offscreen(&mut self, size: Size) -> Result<()> {
+        self.egl_context.make_current_surfaceless()?;
+        self.framebuffer = Some(Framebuffer::new(size, self.srgb)?);
+        Ok(())
+    }
+
+    /// Read back the content of the offscreen framebuffer.
+    pub fn read_pixels(&self) -> Result<Pixmap> {
+        let framebuffer = self.framebuffer.as_ref().ok_or(Error::MissingSurface)?;
+        self.bind()?;
+
+        let (width, height) = (framebuffer.texture.width, framebuffer.texture.height);
+        let mut pixmap = Pixmap::new(width as u32, height as u32).ok_or_else(|| {
+            Error::Texture(format!("Invalid framebuffer size: {width}x{height}"))
+        })?;
+
+        let data = pixmap.data_mut();
+        unsafe {
+            gl::ReadPixels(
+                0,
+                0,
+                width,
+                height,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                data.as_mut_ptr() as *mut _,
+            );
+        }
+
+        // Flip rows, since OpenGL's origin is at the bottom left.
+        let stride = width as usize * 4;
+        for row in 0..height as usize / 2 {
+            let mirrored_row = height as usize - row - 1;
+            let (top, bottom) = data.split_at_mut(mirrored_row * stride);
+            top[row * stride..(row + 1) * stride].swap_with_slice(&mut bottom[..stride]);
+        }
+
+        Ok(pixmap)
+    }
+
+    /// Bind this renderer's program and buffers.
+    fn bind(&self) -> Result<()> {
+        match (&self.egl_surface, &self.framebuffer) {
+            (Some(egl_surface), _) => self.egl_context.make_current(egl_surface)?,
+            (None, Some(framebuffer)) => {
+                self.egl_context.make_current_surfaceless()?;
+                unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer.id) };
+            },
+            (None, None) => return Err(Error::MissingSurface),
+        }
+
+        Ok(())
+    }
+}
+
+/// Support for `EGL_KHR_partial_update`.
+///
+/// This allows limiting redraws to the region which changed since a buffer was
+/// last used, which saves bandwidth on tiled GPUs.
+struct PartialUpdate {
+    egl: egl::Egl,
+    display: egl::types::EGLDisplay,
+    swap_with_damage: bool,
+}
+
+impl PartialUpdate {
+    /// Load the extension, if the display supports it.
+    fn new(display: &Display) -> Option<Self> {
+        let raw_display = match display.raw_display() {
+            RawDisplay::Egl(raw_display) => raw_display,
+            #[allow(unreachable_patterns)]
+            _ => return None,
+        };
+
+        let egl = egl::Egl::load_with(|symbol| {
+            let symbol = CString::new(symbol).unwrap();
+            display.get_proc_address(symbol.as_c_str())
+        });
+        if !egl.QueryString.is_loaded()
+            || !egl.QuerySurface.is_loaded()
+            || !egl.SetDamageRegionKHR.is_loaded()
+        {
+            return None;
+        }
+
+        let extensions = unsafe { egl.QueryString(raw_display, egl::EXTENSIONS as _) };
+        if extensions.is_null() {
+            return None;
+        }
+
+        let extensions = unsafe { CStr::from_ptr(extensions) }.to_string_lossy();
+        let supported = |name| extensions.split_whitespace().any(|extension| extension == name);
+        if !supported("EGL_KHR_partial_update") {
+            return None;
+        }
+
+        let swap_with_damage = egl.SwapBuffersWithDamageKHR.is_loaded()
+            && supported("EGL_KHR_swap_buffers_with_damage");
+
+        Some(Self { egl, display: raw_display, swap_with_damage })
+    }
+
+    /// Number of frames since the surface's back buffer was last drawn.
+    ///
+    /// An age of zero indicates that the buffer's content is undefined.
+    fn buffer_age(&self, egl_surface: &Surface<WindowSurface>) -> usize {
+        let mut age = 0;
+        unsafe {
+            let surface = raw_surface(egl_surface);
+            self.egl.QuerySurface(self.display, surface, egl::BUFFER_AGE_KHR as _, &mut age);
+        }
+        usize::try_from(age).unwrap_or_default()
+    }
+
+    /// Limit the modified area of the surface's back buffer.
+    ///
+    /// The `rect` is `[x, y, width, height]`, with the origin at the bottom left.
+    fn set_damage_region(&self, egl_surface: &Surface<WindowSurface>, mut rect: [i32; 4]) -> bool {
+        unsafe {
+            let surface = raw_surface(egl_surface);
+            let rects = rect.as_mut_ptr();
+            self.egl.SetDamageRegionKHR(self.display, surface, rects, 1) == egl::TRUE
+        }
+    }
+
+    /// Swap buffers, only reporting the changed area to the compositor.
+    ///
+    /// The `rect` is `[x, y, width, height]`, with the origin at the bottom left.
+    fn swap_buffers_with_damage(
+        &self,
+        egl_surface: &Surface<WindowSurface>,
+        mut rect: [i32; 4],
+    ) -> bool {
+        if !self.swap_with_damage {
+            return false;
+        }
+
+        unsafe {
+            let surface = raw_surface(egl_surface);
+            let rects = rect.as_mut_ptr();
+            self.egl.SwapBuffersWithDamageKHR(self.display, surface, rects, 1) == egl::TRUE
+        }
+    }
+}
+
+/// Convert a rectangle to EGL's `[x, y, width, height]` with the origin at the bottom left.
+fn egl_rect(rect: Rect, size: Size<i32>) -> [i32; 4] {
+    let y = size.height - rect.y - rect.height;
+    [rect.x, y, rect.width, rect.height]
+}
+
+/// Raw EGL handle of a surface.
+fn raw_surface(egl_surface: &Surface<WindowSurface>) -> egl::types::EGLSurface {
+    match egl_surface.raw_surface() {
+        RawSurface::Egl(surface) => surface,
+        #[allow(unreachable_patterns)]
+        _ => ptr::null(),
+    }
+}
+
+/// Abstraction over shader programs.
+pub trait RenderProgram {
+    /// Type of the vertex used for this program.
+    type Vertex;
+
+    /// Create the program, with color space conversion if `srgb` is enabled.
+    fn new(srgb: bool) -> Self;
+
+    /// Make this renderer active for drawing.
+    fn bind(&self);
+
+    /// Upload and draw a batch of vertices.
+    fn draw(&self, vertices: &[Self::Vertex]) {
+        draw_elements(vertices);
+    }
+}
+
+/// Upload vertices and draw them as indexed quads.
+fn draw_elements<V>(vertices: &[V]) {
+    unsafe {
+        gl::BufferSubData(
+            gl::ARRAY_BUFFER,
+            0,
+            mem::size_of_val(vertices) as isize,
+            vertices.as_ptr() as *const _,
+        );
+
+        let num_indices = (vertices.len() / 4 * 6) as i32;
+        gl::DrawElements(gl::TRIANGLES, num_indices, gl::UNSIGNED_SHORT, ptr::null());
+    }
+}
+
+/// Renderer for glyphs and SVGs.
+pub struct TextRenderer {
+    id: GLuint,
+    vao: GLuint,
+    vbo: GLuint,
+    ebo: GLuint,
+    gles3: bool,
+}
+
+impl RenderProgram for TextRenderer {
+    type Vertex = GlyphVertex;
+
+    fn new(srgb: bool) -> Self {
+        // Create buffer with all possible vertex indices.
+        let mut vertex_indices = Vec::with_capacity(BATCH_MAX / 4 * 6);
+        for index in 0..(BATCH_MAX / 4) as u16 {
+            let index = index * 4;
+            vertex_indices.push(index);
+            vertex_indices.push(index + 1);
+            vertex_indices.push(index + 3);
+
+            vertex_indices.push(index + 1);
+            vertex_indices.push(index + 2);
+            vertex_indices.push(index + 3);
+        }
+
+        unsafe {
+            // Create vertex shader.
+            let vertex_shader = Shader::new(gl::VERTEX_SHADER, TEXT_VERTEX_SHADER, srgb);
+
+            // Create fragment shader.
+            let fragment_shader = Shader::new(gl::FRAGMENT_SHADER, TEXT_FRAGMENT_SHADER, srgb);
+
+            // Create shader program.
+            let id = gl::CreateProgram();
+            gl::AttachShader(id, *vertex_shader);
+            gl::AttachShader(id, *fragment_shader);
+            gl::LinkProgram(id);
+            gl::UseProgram(id);
+
+            // Generate VAO.
+            let gles3 = gles_major_version() >= 3;
+            let vao = gen_vertex_array(gles3);
+            bind_vertex_array(gles3, vao);
+
+            // Generate EBO.
+            let mut ebo = 0;
+            gl::GenBuffers(1, &mut ebo);
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
+            gl::BufferData(
+                gl::ELEMENT_ARRAY_BUFFER,
+                (vertex_indices.capacity() * mem::size_of::<u16>()) as isize,
+                vertex_indices.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+
+            // Generate VBO.
+            let mut vbo = 0;
+            gl::GenBuffers(1, &mut vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (BATCH_MAX * mem::size_of::<GlyphVertex>()) as isize,
+                ptr::null(),
+                gl::STREAM_DRAW,
+            );
+
+            // Glyph position.
+            let mut offset = 0;
+            gl::VertexAttribPointer(
+                0,
+                2,
+                gl::SHORT,
+                gl::FALSE,
+                mem::size_of::<GlyphVertex>() as i32,
+                offset as *const _,
+            );
+            gl::EnableVertexAttribArray(0);
+            offset += 2 * mem::size_of::<GLshort>();
+
+            // UV position.
+            gl::VertexAttribPointer(
+                1,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                mem::size_of::<GlyphVertex>() as i32,
+                offset as *const _,
+            );
+            gl::EnableVertexAttribArray(1);
+            offset += 2 * mem::size_of::<GLfloat>();
+
+            // Glyph flags.
+            gl::VertexAttribPointer(
+                2,
+                1,
+                gl::FLOAT,
+                gl::FALSE,
+                mem::size_of::<GlyphVertex>() as i32,
+                offset as *const _,
+            );
+            gl::EnableVertexAttribArray(2);
+            offset += mem::size_of::<GLfloat>();
+
+            // Text color.
+            gl::VertexAttribPointer(
+                3,
+                4,
+                gl::UNSIGNED_BYTE,
+                gl::TRUE,
+                mem::size_of::<GlyphVertex>() as i32,
+                offset as *const _,
+            );
+            gl::EnableVertexAttribArray(3);
+
+            Self { id, vao, vbo, ebo, gles3 }
+        }
+    }
+
+    fn bind(&self) {
+        unsafe {
+            gl::UseProgram(self.id);
+            bind_vertex_array(self.gles3, self.vao);
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.ebo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+            gl::BlendFunc(gl::SRC1_COLOR_EXT, gl::ONE_MINUS_SRC1_COLOR_EXT);
+        }
+    }
+}
+
+impl Drop for TextRenderer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.vbo);
+            gl::DeleteBuffers(1, &self.ebo);
+            delete_vertex_array(self.gles3, self.vao);
+        }
+    }
+}
+
+/// Renderer for single-color rectangles.
+///
+/// With GLES 3, every rectangle is drawn as an instance of a shared quad,
+/// reducing the uploaded data to a quarter.
+pub struct RectRenderer {
+    id: GLuint,
+    vao: GLuint,
+    vbo: GLuint,
+    ebo: GLuint,
+    corners: GLuint,
+    gles3: bool,
+}
+
+impl RectRenderer {
+    /// Setup buffers for drawing four indexed vertices per rectangle.
+    ///
+    /// Returns the vertex and element buffer.
+    unsafe fn setup_indexed() -> (GLuint, GLuint) {
+        // Create buffer with all possible vertex indices.
+        let mut vertex_indices = Vec::with_capacity(BATCH_MAX / 4 * 6);
+        for index in 0..(BATCH_MAX / 4) as u16 {
+            let index = index * 4;
+            vertex_indices.push(index);
+            vertex_indices.push(index + 1);
+            vertex_indices.push(index + 3);
+
+            vertex_indices.push(index + 1);
+            vertex_indices.push(index + 2);
+            vertex_indices.push(index + 3);
+        }
+
+        // Generate EBO.
+        let mut ebo = 0;
+        gl::GenBuffers(1, &mut ebo);
+        gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
+        gl::BufferData(
+            gl::ELEMENT_ARRAY_BUFFER,
+            (vertex_indices.capacity() * mem::size_of::<u16>()) as isize,
+            vertex_indices.as_ptr() as *const _,
+            gl::STATIC_DRAW,
+        );
+
+        let vbo = setup_rect_vertices();
+
+        (vbo, ebo)
+    }
+
+    /// Setup buffers for drawing one quad instance per rectangle.
+    ///
+    /// Returns the instance and quad corner buffer.
+    unsafe fn setup_instanced() -> (GLuint, GLuint) {
+        // Generate VBO for the quad corners shared by all instances.
+        let quad: [GLfloat; 8] = [0., 0., 0., 1., 1., 0., 1., 1.];
+        let mut corners = 0;
+        gl::GenBuffers(1, &mut corners);
+        gl::BindBuffer(gl::ARRAY_BUFFER, corners);
+        gl::BufferData(
+            gl::ARRAY_BUFFER,
+            mem::size_of_val(&quad) as isize,
+            quad.as_ptr() as *const _,
+            gl::STATIC_DRAW,
+        );
+
+        // Quad corner.
+        gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, 0, ptr::null());
+        gl::EnableVertexAttribArray(0);
+
+        // Generate VBO for the per-rectangle data.
+        let mut vbo = 0;
+        gl::GenBuffers(1, &mut vbo);
+        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+        gl::BufferData(
+            gl::ARRAY_BUFFER,
+            (BATCH_MAX / 4 * mem::size_of::<RectInstance>()) as isize,
+            ptr::null(),
+            gl::STREAM_DRAW,
+        );
+
+        // Rectangle position and size.
+        let mut offset = 0;
+        gl::VertexAttribPointer(
+            1,
+            4,
+            gl::FLOAT,
+            gl::FALSE,
+            mem::size_of::<RectInstance>() as i32,
+            offset as *const _,
+        );
+        gl::VertexAttribDivisor(1, 1);
+        gl::EnableVertexAttribArray(1);
+        offset += mem::size_of::<GLfloat>() * 4;
+
+        // Rectangle color.
+        gl::VertexAttribPointer(
+            2,
+            4,
+            gl::UNSIGNED_BYTE,
+            gl::TRUE,
+            mem::size_of::<RectInstance>() as i32,
+            offset as *const _,
+        );
+        gl::VertexAttribDivisor(2, 1);
+        gl::EnableVertexAttribArray(2);
+
+        (vbo, corners)
+    }
+}
+
+impl RenderProgram for RectRenderer {
+    type Vertex = RectVertex;
+
+    fn new(srgb: bool) -> Self {
+        unsafe {
+            let gles3 = gles_major_version() >= 3;
+
+            // Create shaders.
+            let (vertex_source, fragment_source) = if gles3 {
+                (RECT_INSTANCED_VERTEX_SHADER, RECT_INSTANCED_FRAGMENT_SHADER)
+            } else {
+                (RECT_VERTEX_SHADER, RECT_FRAGMENT_SHADER)
+            };
+            let vertex_shader = Shader::new(gl::VERTEX_SHADER, vertex_source, srgb);
+            let fragment_shader = Shader::new(gl::FRAGMENT_SHADER, fragment_source, srgb);
+
+            // Create shader program.
+            let id = gl::CreateProgram();
+            gl::AttachShader(id, *vertex_shader);
+            gl::AttachShader(id, *fragment_shader);
+            gl::LinkProgram(id);
+            gl::UseProgram(id);
+
+            // Generate VAO.
+            let vao = gen_vertex_array(gles3);
+            bind_vertex_array(gles3, vao);
+
+            if gles3 {
+                let (vbo, corners) = Self::setup_instanced();
+                Self { id, vao, vbo, corners, gles3, ebo: 0 }
+            } else {
+                let (vbo, ebo) = Self::setup_indexed();
+                Self { id, vao, vbo, ebo, gles3, corners: 0 }
+            }
+        }
+    }
+
+    fn bind(&self) {
+        unsafe {
+            gl::UseProgram(self.id);
+            bind_vertex_array(self.gles3, self.vao);
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.ebo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+            gl::BlendFunc(gl::ONE, gl::ONE_MINUS_SRC_ALPHA);
+        }
+    }
+
+    fn draw(&self, vertices: &[RectVertex]) {
+        if !self.gles3 {
+            draw_elements(vertices);
+            return;
+        }
+
+        let instances: Vec<_> = vertices.chunks_exact(4).map(RectInstance::from_quad).collect();
+        unsafe {
+            gl::BufferSubData(
+                gl::ARRAY_BUFFER,
+                0,
+                mem::size_of_val(instances.as_slice()) as isize,
+                instances.as_ptr() as *const _,
+            );
+
+            gl::DrawArraysInstanced(gl::TRIANGLE_STRIP, 0, 4, instances.len() as i32);
+        }
+    }
+}
+
+impl Drop for RectRenderer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.vbo);
+            gl::DeleteBuffers(1, &self.ebo);
+            gl::DeleteBuffers(1, &self.corners);
+            delete_vertex_array(self.gles3, self.vao);
+        }
+    }
+}
+
+/// Renderer for single-color triangles, like graph lines.
+///
+/// Unlike [`RectRenderer`], shapes are not limited to axis-aligned rectangles.
+pub struct LineRenderer {
+    id: GLuint,
+    vao: GLuint,
+    vbo: GLuint,
+    gles3: bool,
+}
+
+impl RenderProgram for LineRenderer {
+    type Vertex = RectVertex;
+
+    fn new(srgb: bool) -> Self {
+        unsafe {
+            let gles3 = gles_major_version() >= 3;
+
+            // Create shaders.
+            let vertex_shader = Shader::new(gl::VERTEX_SHADER, RECT_VERTEX_SHADER, srgb);
+            let fragment_shader = Shader::new(gl::FRAGMENT_SHADER, RECT_FRAGMENT_SHADER, srgb);
+
+            // Create shader program.
+            let id = gl::CreateProgram();
+            gl::AttachShader(id, *vertex_shader);
+            gl::AttachShader(id, *fragment_shader);
+            gl::LinkProgram(id);
+            gl::UseProgram(id);
+
+            // Generate VAO.
+            let vao = gen_vertex_array(gles3);
+            bind_vertex_array(gles3, vao);
+
+            let vbo = setup_rect_vertices();
+
+            Self { id, vao, vbo, gles3 }
+        }
+    }
+
+    fn bind(&self) {
+        unsafe {
+            gl::UseProgram(self.id);
+            bind_vertex_array(self.gles3, self.vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+            gl::BlendFunc(gl::ONE, gl::ONE_MINUS_SRC_ALPHA);
+        }
+    }
+
+    fn draw(&self, vertices: &[RectVertex]) {
+        unsafe {
+            gl::BufferSubData(
+                gl::ARRAY_BUFFER,
+                0,
+                mem::size_of_val(vertices) as isize,
+                vertices.as_ptr() as *const _,
+            );
+
+            gl::DrawArrays(gl::TRIANGLES, 0, vertices.len() as i32);
+        }
+    }
+}
+
+impl Drop for LineRenderer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.vbo);
+            delete_vertex_array(self.gles3, self.vao);
+        }
+    }
+}
+
+/// Setup a buffer for drawing vertices of the rectangle shader.
+///
+/// Returns the vertex buffer.
+unsafe fn setup_rect_vertices() -> GLuint {
+    // Generate VBO.
+    let mut vbo = 0;
+    gl::GenBuffers(1, &mut vbo);
+    gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+    gl::BufferData(
+        gl::ARRAY_BUFFER,
+        (BATCH_MAX * mem::size_of::<RectVertex>()) as isize,
+        ptr::null(),
+        gl::STREAM_DRAW,
+    );
+
+    // Vertex position.
+    let mut offset = 0;
+    gl::VertexAttribPointer(
+        0,
+        2,
+        gl::FLOAT,
+        gl::FALSE,
+        mem::size_of::<RectVertex>() as i32,
+        offset as *const _,
+    );
+    gl::EnableVertexAttribArray(0);
+    offset += mem::size_of::<GLfloat>() * 2;
+
+    // Vertex color.
+    gl::VertexAttribPointer(
+        1,
+        4,
+        gl::UNSIGNED_BYTE,
+        gl::TRUE,
+        mem::size_of::<RectVertex>() as i32,
+        offset as *const _,
+    );
+    gl::EnableVertexAttribArray(1);
+
+    vbo
+}
+
+struct Shader {
+    id: GLuint,
+}
+
+impl Deref for Shader {
+    type Target = GLuint;
+
+    fn deref(&self) -> &Self::Target {
+        &self.id
+    }
+}
+
+impl Shader {
+    fn new(shader_type: GLenum, source: &str, srgb: bool) -> Self {
+        // Enable color space conversion after the version directive.
+        let source = match source.split_once('\n') {
+            Some((version, body)) if srgb => Cow::Owned(format!("{version}\n#define SRGB\n{body}")),
+            _ => Cow::Borrowed(source),
+        };
+
+        unsafe {
+            let id = gl::CreateShader(shader_type);
+            gl::ShaderSource(
+                id,
+                1,
+                [source.as_ptr()].as_ptr() as *const _,
+                &(source.len() as i32) as *const _,
+            );
+            gl::CompileShader(id);
+
+            Self { id }
+        }
+    }
+}
+
+/// Major version of the current GLES context.
+fn gles_major_version() -> u32 {
+    let version = unsafe { gl::GetString(gl::VERSION) };
+    if version.is_null() {
+        return 2;
+    }
+
+    // Version strings look like `OpenGL ES 3.2 Mesa 23.0.0`.
+    let version = unsafe { CStr::from_ptr(version as *const _) };
+    version
+        .to_str()
+        .ok()
+        .and_then(|version| version.strip_prefix("OpenGL ES ")?.chars().next()?.to_digit(10))
+        .unwrap_or(2)
+}
+
+/// Create a new vertex array object.
+unsafe fn gen_vertex_array(gles3: bool) -> GLuint {
+    let mut vao = 0;
+    if gles3 {
+        gl::GenVertexArrays(1, &mut vao);
+    } else {
+        gl::GenVertexArraysOES(1, &mut vao);
+    }
+    vao
+}
+
+/// Bind a vertex array object.
+unsafe fn bind_vertex_array(gles3: bool, vao: GLuint) {
+    if gles3 {
+        gl::BindVertexArray(vao);
+    } else {
+        gl::BindVertexArrayOES(vao);
+    }
+}
+
+/// Delete a vertex array object.
+unsafe fn delete_vertex_array(gles3: bool, vao: GLuint) {
+    if gles3 {
+        gl::DeleteVertexArrays(1, &vao);
+    } else {
+        gl::DeleteVertexArraysOES(1, &vao);
+    }
+}
+
+/// OpenGL texture.
+pub struct Texture {
+    pub id: GLuint,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Texture {
+    /// Create a new texture.
+    pub fn new(width: i32, height: i32) -> Self {
+        Self::with_format(width, height, gl::RGBA)
+    }
+
+    /// Create a new texture with a specific internal format.
+    fn with_format(width: i32, height: i32, internal_format: GLenum) -> Self {
+        let mut id = 0;
+        unsafe {
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_2D, id);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                internal_format as i32,
+                width,
+                height,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                ptr::null(),
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+
+        Self { id, width, height }
+    }
+
+    /// Texture memory in bytes.
+    pub fn size_bytes(&self) -> usize {
+        self.width as usize * self.height as usize * 4
+    }
+
+    /// Upload buffer to texture.
+    pub fn upload_buffer(&self, x: i32, y: i32, width: i32, height: i32, buffer: &[u8]) {
+        assert_eq!(width * height * 4, buffer.len() as i32);
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+
+            gl::TexSubImage2D(
+                gl::TEXTURE_2D,
+                0,
+                x,
+                y,
+                width,
+                height,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                buffer.as_ptr() as *const _,
+            );
+
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+    }
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.id);
+        }
+    }
+}
+
+/// Offscreen rendering of previously batched vertices.
+struct CachedLayer {
+    framebuffer: Framebuffer,
+    size: Size,
+    key: u64,
+}
+
+/// Offscreen render target.
+struct Framebuffer {
+    texture: Texture,
+    id: GLuint,
+}
+
+impl Framebuffer {
+    fn new(size: Size, srgb: bool) -> Result<Self> {
+        // Match the color space of the window surfaces.
+        let format = if srgb { gl::SRGB8_ALPHA8 } else { gl::RGBA };
+        let texture = Texture::with_format(size.width, size.height, format);
+
+        let mut id = 0;
+        unsafe {
+            gl::GenFramebuffers(1, &mut id);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, id);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                texture.id,
+                0,
+            );
+        }
+        let framebuffer = Self { texture, id };
+
+        if unsafe { gl::CheckFramebufferStatus(gl::FRAMEBUFFER) } != gl::FRAMEBUFFER_COMPLETE {
+            return Err(Error::Texture("incomplete offscreen framebuffer".into()));
+        }
+
+        Ok(framebuffer)
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.id);
+        }
+    }
+}
@@ -9,11 +9,14 @@ use crossfont::{
     BitmapBuffer, FontDesc, FontKey, GlyphKey, Metrics, Rasterize, RasterizedGlyph, Rasterizer,
     Size as FontSize, Slant, Style, Weight,
 };
+use serde::Deserialize;
 use tiny_skia::{Pixmap, Transform};
 use usvg::{FitTo, Options, Tree};
 
+use crate::error::Error;
 use crate::gl::types::GLuint;
 use crate::renderer::Texture;
+use crate::theme::{IconStyle, Theme};
 use crate::Result;
 
 /// Width and height of the glyph atlas texture.
@@ -34,6 +37,11 @@ pub struct GlRasterizer {
     size: FontSize,
     font: FontKey,
 
+    // Theme replacements for built-in SVGs.
+    svg_overrides: HashMap<Svg, String>,
+    icon_style: IconStyle,
+    active_icon_style: IconStyle,
+
     // DPI scale factor.
     scale_factor: i32,
 }
@@ -60,11 +68,19 @@ impl GlRasterizer {
             font,
             size,
             metrics: Default::default(),
+            svg_overrides: Default::default(),
+            icon_style: Default::default(),
+            active_icon_style: Default::default(),
             atlas: Default::default(),
             cache: Default::default(),
         })
     }
 
+    /// Memory used by the atlas textures in bytes.
+    pub fn texture_memory(&self) -> usize {
+        self.atlas.textures.iter().map(|texture| texture.size_bytes()).sum()
+    }
+
     /// Update the DPI scale factor.
     pub fn set_scale_factor(&mut self, scale_factor: i32) {
         // Avoid clearing all caches when factor didn't change.
@@ -77,14 +93,36 @@ impl GlRasterizer {
         self.font = Self::load_font(&mut self.rasterizer, &self.font_name, self.size, scale_factor)
             .unwrap_or(self.font);
 
-        // Clear glyph cache and drop all atlas textures.
-        self.atlas = Atlas::default();
-        self.cache = HashMap::new();
+        self.clear_cache();
 
         // Clear font metrics.
         self.metrics = None;
     }
 
+    /// Switch to the font and icons of a theme.
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.font_name = theme.font.clone();
+        self.size = FontSize::new(theme.font_size);
+        let scale_factor = self.scale_factor;
+        self.font = Self::load_font(&mut self.rasterizer, &self.font_name, self.size, scale_factor)
+            .unwrap_or(self.font);
+
+        self.svg_overrides = theme.svgs.clone();
+        self.icon_style = theme.icon_style;
+        self.active_icon_style = theme.active_icon_style.unwrap_or(theme.icon_style);
+
+        self.clear_cache();
+
+        // Clear font metrics.
+        self.metrics = None;
+    }
+
+    /// Clear glyph cache and drop all atlas textures.
+    pub fn clear_cache(&mut self) {
+        self.atlas = Atlas::default();
+        self.cache = HashMap::new();
+    }
+
     /// Rasterize each glyph in a string.
     ///
     /// Returns an iterator over all glyphs. The advance stored on each glyph
@@ -92,6 +130,7 @@ impl GlRasterizer {
     ///
     /// If any of the glyphs cannot be rasterized, all glyphs up to that point
     /// will be returned.
+    #[cfg_attr(feature = "profiling", tracing::instrument(skip_all))]
     pub fn rasterize_string<'a>(
         &'a mut self,
         text: &'a str,
@@ -126,12 +165,37 @@ impl GlRasterizer {
         Ok(*entry.insert(glyph))
     }
 
-    /// Rasterize an SVG from its text.
+    /// Rasterize an SVG from its text, using the theme's icon style.
     pub fn rasterize_svg(
         &mut self,
         svg: Svg,
         target_width: impl Into<Option<u32>>,
         target_height: impl Into<Option<u32>>,
+    ) -> Result<GlSubTexture> {
+        let style = self.icon_style(svg, false);
+        self.rasterize_styled_svg(svg, style, target_width, target_height)
+    }
+
+    /// Get the icon style of an SVG, based on the state of its drawer toggle.
+    ///
+    /// This falls back to the outline style for SVGs without filled variant.
+    pub fn icon_style(&self, svg: Svg, active: bool) -> IconStyle {
+        let style = if active { self.active_icon_style } else { self.icon_style };
+        match style {
+            IconStyle::Filled if svg.filled_content().is_none() => IconStyle::Outline,
+            IconStyle::Filled if self.svg_overrides.contains_key(&svg) => IconStyle::Outline,
+            style => style,
+        }
+    }
+
+    /// Rasterize an SVG from its text.
+    #[cfg_attr(feature = "profiling", tracing::instrument(skip_all))]
+    pub fn rasterize_styled_svg(
+        &mut self,
+        svg: Svg,
+        style: IconStyle,
+        target_width: impl Into<Option<u32>>,
+        target_height: impl Into<Option<u32>>,
     ) -> Result<GlSubTexture> {
         // Calculate SVG X/Y scale factor.
         let (mut width, mut height) = svg.size();
@@ -148,22 +212,27 @@ impl GlRasterizer {
         height = (height as f32 * self.scale_factor as f32 * y_scale) as u32;
 
         // Try to lead svg from cache.
-        let entry = match self.cache.entry(CacheKey::Svg((svg, width, height))) {
+        let entry = match self.cache.entry(CacheKey::Svg((svg, style, width, height))) {
             Entry::Occupied(entry) => return Ok(*entry.get()),
             Entry::Vacant(entry) => entry,
         };
 
         // Setup target buffer.
         let mut pixmap = Pixmap::new(width, height)
-            .ok_or_else(|| format!("Invalid SVG buffer size: {width}x{height}"))?;
+            .ok_or_else(|| Error::Texture(format!("Invalid SVG buffer size: {width}x{height}")))?;
 
         // Compute transform for height.
         let transform = Transform::from_scale(1., y_scale / x_scale);
 
         // Render SVG into buffer.
-        let tree = Tree::from_str(svg.content(), &Options::default().to_ref())?;
+        let content = match (self.svg_overrides.get(&svg), style) {
+            (Some(content), _) => content.as_str(),
+            (None, IconStyle::Filled) => svg.filled_content().unwrap_or(svg.content()),
+            (None, IconStyle::Outline) => svg.content(),
+        };
+        let tree = Tree::from_str(content, &Options::default().to_ref())?;
         resvg::render(&tree, FitTo::Width(width), transform, pixmap.as_mut())
-            .ok_or_else(|| format!("Invalid SVG target size: {width}x{height}"))?;
+            .ok_or_else(|| Error::Texture(format!("Invalid SVG target size: {width}x{height}")))?;
 
         // Load SVG into atlas.
         let atlas_entry = AtlasEntry::new_svg(pixmap.take(), width, height);
@@ -225,6 +294,7 @@ impl GlRasterizer {
 ///   └─────┴─────┴─────┴───────────┘
 /// (0, 0)
 /// ```
+#[derive(Default)]
 pub struct Atlas {
     /// OpenGL texture ID.
     textures: Vec<Texture>,
@@ -236,17 +306,6 @@ pub struct Atlas {
     cursor_y: i32,
 }
 
-impl Default for Atlas {
-    fn default() -> Self {
-        Self {
-            textures: vec![Texture::new(ATLAS_SIZE, ATLAS_SIZE)],
-            row_height: Default::default(),
-            cursor_x: Default::default(),
-            cursor_y: Default::default(),
-        }
-    }
-}
-
 impl Atlas {
     /// Insert an entry into the atlas.
     fn insert<'a, E: Into<AtlasEntry<'a>>>(&mut self, entry: E) -> Result<GlSubTexture> {
@@ -254,7 +313,7 @@ impl Atlas {
 
         // Error if entry cannot fit at all.
         if entry.width > ATLAS_SIZE || entry.height > ATLAS_SIZE {
-            return Err("glyph too big for atlas".into());
+            return Err(Error::Texture("glyph too big for atlas".into()));
         }
 
         // Create new row if entry doesn't fit into current one.
@@ -264,7 +323,7 @@ impl Atlas {
         }
 
         // Create a new texture if the row's available height is too little.
-        if self.cursor_y + entry.height > ATLAS_SIZE {
+        if self.textures.is_empty() || self.cursor_y + entry.height > ATLAS_SIZE {
             self.textures.push(Texture::new(ATLAS_SIZE, ATLAS_SIZE));
             self.row_height = 0;
             self.cursor_x = 0;
@@ -386,7 +445,7 @@ impl<'a> From<&'a RasterizedGlyph> for AtlasEntry<'a> {
 #[derive(Copy, Clone, Hash, PartialEq, Eq)]
 enum CacheKey {
     Character(char),
-    Svg((Svg, u32, u32)),
+    Svg((Svg, IconStyle, u32, u32)),
 }
 
 impl From<char> for CacheKey {
@@ -396,7 +455,8 @@ impl From<char> for CacheKey {
 }
 
 /// Built-in SVGs.
-#[derive(Copy, Clone, Hash, PartialEq, Eq)]
+#[derive(Deserialize, Copy, Clone, Hash, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
 pub enum Svg {
     BatteryCharging100,
     BatteryCharging80,
@@ -408,6 +468,7 @@ pub enum Svg {
     Battery60,
     Battery40,
     Battery20,
+    BatteryFull,
     WifiConnected100,
     WifiConnected75,
     WifiConnected50,
@@ -429,6 +490,27 @@ pub enum Svg {
     FlashlightOff,
     OrientationLocked,
     OrientationUnlocked,
+    Rotation,
+    Workspace,
+    Location,
+    Cast,
+    Usb,
+    Timer,
+    Alarm,
+    Mail,
+    Updates,
+    Warning,
+    Ethernet,
+    Tethering,
+    Wireguard,
+    Overflow,
+    KillSwitch,
+    Dock,
+    DoNotDisturb,
+    Clipboard,
+    Screenshot,
+    ScreenRecorder,
+    Cpu,
 }
 
 impl Svg {
@@ -445,6 +527,7 @@ impl Svg {
             Self::Battery60 => (20, 7),
             Self::Battery40 => (20, 7),
             Self::Battery20 => (20, 7),
+            Self::BatteryFull => (20, 7),
             Self::WifiConnected100 => (20, 14),
             Self::WifiConnected75 => (20, 14),
             Self::WifiConnected50 => (20, 14),
@@ -466,6 +549,27 @@ impl Svg {
             Self::FlashlightOff => (45, 75),
             Self::OrientationLocked => (73, 65),
             Self::OrientationUnlocked => (73, 65),
+            Self::Rotation => (70, 70),
+            Self::Workspace => (20, 20),
+            Self::Location => (15, 20),
+            Self::Cast => (20, 16),
+            Self::Usb => (12, 20),
+            Self::Timer => (18, 20),
+            Self::Alarm => (20, 20),
+            Self::Mail => (20, 15),
+            Self::Updates => (20, 20),
+            Self::Warning => (22, 20),
+            Self::Ethernet => (20, 20),
+            Self::Tethering => (14, 20),
+            Self::Wireguard => (16, 20),
+            Self::Overflow => (20, 20),
+            Self::KillSwitch => (20, 16),
+            Self::Dock => (20, 18),
+            Self::DoNotDisturb => (20, 20),
+            Self::Clipboard => (16, 20),
+            Self::Screenshot => (20, 20),
+            Self::ScreenRecorder => (20, 20),
+            Self::Cpu => (20, 20),
         }
     }
 
@@ -482,6 +586,7 @@ impl Svg {
             Self::Battery60 => include_str!("../svgs/battery/battery_60.svg"),
             Self::Battery40 => include_str!("../svgs/battery/battery_40.svg"),
             Self::Battery20 => include_str!("../svgs/battery/battery_20.svg"),
+            Self::BatteryFull => include_str!("../svgs/battery/battery_full.svg"),
             Self::WifiConnected100 => include_str!("../svgs/wifi/wifi_connected_100.svg"),
             Self::WifiConnected75 => include_str!("../svgs/wifi/wifi_connected_75.svg"),
             Self::WifiConnected50 => include_str!("../svgs/wifi/wifi_connected_50.svg"),
@@ -505,6 +610,45 @@ impl Svg {
             Self::OrientationUnlocked => {
                 include_str!("../svgs/orientation/orientation_unlocked.svg")
             },
+            Self::Rotation => include_str!("../svgs/rotation/rotation.svg"),
+            Self::Workspace => include_str!("../svgs/workspace/workspace.svg"),
+            Self::Location => include_str!("../svgs/location/location.svg"),
+            Self::Cast => include_str!("../svgs/cast/cast.svg"),
+            Self::Usb => include_str!("../svgs/usb/usb.svg"),
+            Self::Timer => include_str!("../svgs/timer/timer.svg"),
+            Self::Alarm => include_str!("../svgs/alarm/alarm.svg"),
+            Self::Mail => include_str!("../svgs/mail/mail.svg"),
+            Self::Updates => include_str!("../svgs/updates/updates.svg"),
+            Self::Warning => include_str!("../svgs/warning/warning.svg"),
+            Self::Ethernet => include_str!("../svgs/ethernet/ethernet.svg"),
+            Self::Tethering => include_str!("../svgs/ethernet/tethering.svg"),
+            Self::Wireguard => include_str!("../svgs/wireguard/wireguard.svg"),
+            Self::Overflow => include_str!("../svgs/overflow/overflow.svg"),
+            Self::KillSwitch => include_str!("../svgs/privacy/kill_switch.svg"),
+            Self::Dock => include_str!("../svgs/dock/dock.svg"),
+            Self::DoNotDisturb => include_str!("../svgs/dnd/do_not_disturb.svg"),
+            Self::Clipboard => include_str!("../svgs/clipboard/clipboard.svg"),
+            Self::Screenshot => include_str!("../svgs/screenshot/screenshot.svg"),
+            Self::ScreenRecorder => include_str!("../svgs/recorder/screen_recorder.svg"),
+            Self::Cpu => include_str!("../svgs/system/cpu.svg"),
+        }
+    }
+
+    /// Get text content of the SVG's filled variant.
+    const fn filled_content(&self) -> Option<&'static str> {
+        match self {
+            Self::Rotation => Some(include_str!("../svgs/rotation/rotation_filled.svg")),
+            Self::Timer => Some(include_str!("../svgs/timer/timer_filled.svg")),
+            Self::Alarm => Some(include_str!("../svgs/alarm/alarm_filled.svg")),
+            Self::Mail => Some(include_str!("../svgs/mail/mail_filled.svg")),
+            Self::Warning => Some(include_str!("../svgs/warning/warning_filled.svg")),
+            Self::Ethernet => Some(include_str!("../svgs/ethernet/ethernet_filled.svg")),
+            Self::Tethering => Some(include_str!("../svgs/ethernet/tethering_filled.svg")),
+            Self::KillSwitch => Some(include_str!("../svgs/privacy/kill_switch_filled.svg")),
+            Self::Dock => Some(include_str!("../svgs/dock/dock_filled.svg")),
+            Self::DoNotDisturb => Some(include_str!("../svgs/dnd/do_not_disturb_filled.svg")),
+            Self::Clipboard => Some(include_str!("../svgs/clipboard/clipboard_filled.svg")),
+            _ => None,
         }
     }
 }
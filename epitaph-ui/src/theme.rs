@@ -0,0 +1,239 @@
+//! Switchable visual themes.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::result::Result as StdResult;
+
+use serde::Deserialize;
+use tiny_skia::{Pixmap, PremultipliedColorU8};
+
+use crate::error::Error;
+use crate::text::Svg;
+use crate::Result;
+
+/// Number of hue ranges compared to find the dominant wallpaper color.
+const HUE_BUCKETS: usize = 36;
+
+/// Maximum number of wallpaper pixels sampled for the accent color.
+const ACCENT_SAMPLES: usize = 10_000;
+
+/// Visual theme.
+///
+/// Themes are self-contained files, so they can be shared independently of
+/// the configuration.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Background of the panel and drawer.
+    pub background: Color,
+
+    /// Panel text and icon color.
+    pub foreground: Color,
+
+    /// Color of active drawer buttons and slider handles.
+    pub module_active: Color,
+
+    /// Color of inactive drawer buttons and slider trays.
+    pub module_inactive: Color,
+
+    /// Font family name.
+    pub font: String,
+
+    /// Font size in points.
+    pub font_size: f32,
+
+    /// Style of built-in icons, either `outline` or `filled`.
+    pub icon_style: IconStyle,
+
+    /// Style of built-in icons on enabled drawer toggles, defaults to `icon_style`.
+    pub active_icon_style: Option<IconStyle>,
+
+    /// Replacement SVG files for built-in icons, like `battery_100 = "battery.svg"`.
+    ///
+    /// Relative paths are resolved from the theme directory.
+    pub icons: HashMap<Svg, PathBuf>,
+
+    /// Content of the replacement SVGs.
+    #[serde(skip)]
+    pub svgs: HashMap<Svg, String>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            background: Color::new(26, 26, 26),
+            foreground: Color::new(255, 255, 255),
+            module_active: Color::new(85, 85, 85),
+            module_inactive: Color::new(51, 51, 51),
+            font: "Sans".into(),
+            font_size: 6.,
+            icon_style: Default::default(),
+            active_icon_style: Default::default(),
+            icons: Default::default(),
+            svgs: Default::default(),
+        }
+    }
+}
+
+impl Theme {
+    /// Load a theme by name from a theme directory.
+    pub fn load(dir: &Path, name: &str) -> Result<Self> {
+        // Prevent escaping the theme directory.
+        if name.contains('/') {
+            return Err(Error::Theme(format!("invalid theme name {name:?}")));
+        }
+
+        let path = dir.join(format!("{name}.toml"));
+        let content = fs::read_to_string(&path)
+            .map_err(|err| Error::Theme(format!("couldn't read {path:?}: {err}")))?;
+        let mut theme: Self = toml::from_str(&content)
+            .map_err(|err| Error::Theme(format!("invalid theme {path:?}: {err}")))?;
+
+        // Load icon overrides.
+        for (svg, path) in &theme.icons {
+            let path = dir.join(path);
+            let content = fs::read_to_string(&path)
+                .map_err(|err| Error::Theme(format!("couldn't read {path:?}: {err}")))?;
+            theme.svgs.insert(*svg, content);
+        }
+
+        Ok(theme)
+    }
+
+    /// Replace the active module color with an accent derived from a wallpaper PNG.
+    pub fn apply_wallpaper_accent(&mut self, path: &Path) -> Result<()> {
+        let wallpaper = Pixmap::load_png(path)
+            .map_err(|err| Error::Theme(format!("couldn't load wallpaper {path:?}: {err}")))?;
+
+        // Keep the theme's color for wallpapers without any dominant color.
+        if let Some(accent) = Color::accent(wallpaper.pixels()) {
+            self.module_active = accent;
+        }
+
+        Ok(())
+    }
+}
+
+/// Built-in icon variant.
+///
+/// Icons which are already solid don't have a separate filled variant.
+#[derive(Deserialize, Copy, Clone, Default, Hash, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum IconStyle {
+    #[default]
+    Outline,
+    Filled,
+}
+
+/// RGB color, written as `#rrggbb` hex string.
+#[derive(Deserialize, Copy, Clone, PartialEq, Eq, Debug)]
+#[serde(try_from = "String")]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Color as RGB bytes.
+    pub fn rgb(&self) -> [u8; 3] {
+        [self.r, self.g, self.b]
+    }
+
+    /// Color as opaque RGBA bytes.
+    pub fn rgba(&self) -> [u8; 4] {
+        [self.r, self.g, self.b, 255]
+    }
+
+    /// Color as normalized RGB components.
+    pub fn as_f32(&self) -> [f32; 3] {
+        [self.r as f32 / 255., self.g as f32 / 255., self.b as f32 / 255.]
+    }
+
+    /// Derive an accent color from the dominant hue of an image.
+    fn accent(pixels: &[PremultipliedColorU8]) -> Option<Self> {
+        // Accumulate saturated colors, weighted by their vibrance.
+        let mut buckets = [(0., [0.; 3]); HUE_BUCKETS];
+        let step = (pixels.len() / ACCENT_SAMPLES).max(1);
+        for pixel in pixels.iter().step_by(step).map(PremultipliedColorU8::demultiply) {
+            let color = Self::new(pixel.red(), pixel.green(), pixel.blue());
+            let (hue, saturation, value) = color.hsv();
+            if pixel.alpha() < 128 || saturation < 0.2 || value < 0.2 {
+                continue;
+            }
+
+            let weight = saturation * value;
+            let bucket = &mut buckets[(hue / 360. * HUE_BUCKETS as f32) as usize % HUE_BUCKETS];
+            bucket.0 += weight;
+            for (sum, component) in bucket.1.iter_mut().zip(color.as_f32()) {
+                *sum += component * weight;
+            }
+        }
+
+        let (weight, sum) = buckets.into_iter().max_by(|a, b| a.0.total_cmp(&b.0))?;
+        if weight == 0. {
+            return None;
+        }
+
+        // Limit brightness and saturation, to keep white icons readable.
+        let [r, g, b] = sum.map(|sum| (sum / weight * 255.).round() as u8);
+        let (hue, saturation, value) = Self::new(r, g, b).hsv();
+        Some(Self::from_hsv(hue, saturation.max(0.4), value.clamp(0.35, 0.6)))
+    }
+
+    /// Color as hue in degrees, with saturation and value from `0.0` to `1.0`.
+    fn hsv(&self) -> (f32, f32, f32) {
+        let [r, g, b] = self.as_f32();
+        let max = r.max(g).max(b);
+        let delta = max - r.min(g).min(b);
+
+        let hue = if delta == 0. {
+            0.
+        } else if max == r {
+            60. * ((g - b) / delta).rem_euclid(6.)
+        } else if max == g {
+            60. * ((b - r) / delta + 2.)
+        } else {
+            60. * ((r - g) / delta + 4.)
+        };
+        let saturation = if max == 0. { 0. } else { delta / max };
+
+        (hue, saturation, max)
+    }
+
+    /// Create a color from hue in degrees, with saturation and value from `0.0` to `1.0`.
+    fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let chroma = value * saturation;
+        let x = chroma * (1. - ((hue / 60.) % 2. - 1.).abs());
+        let (r, g, b) = match (hue / 60.) as u32 {
+            0 => (chroma, x, 0.),
+            1 => (x, chroma, 0.),
+            2 => (0., chroma, x),
+            3 => (0., x, chroma),
+            4 => (x, 0., chroma),
+            _ => (chroma, 0., x),
+        };
+
+        let offset = value - chroma;
+        let channel = |component: f32| ((component + offset) * 255.).round() as u8;
+        Self::new(channel(r), channel(g), channel(b))
+    }
+}
+
+impl TryFrom<String> for Color {
+    type Error = String;
+
+    fn try_from(hex: String) -> StdResult<Self, Self::Error> {
+        let digits = hex.strip_prefix('#').filter(|digits| digits.len() == 6);
+        let color = digits.and_then(|digits| u32::from_str_radix(digits, 16).ok());
+        match color {
+            Some(color) => Ok(Self::new((color >> 16) as u8, (color >> 8) as u8, color as u8)),
+            None => Err(format!("invalid color {hex:?}, expected #rrggbb")),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 //! OpenGL vertex batching.
 
-use std::{cmp, mem, ptr};
+use std::hash::{Hash, Hasher};
+use std::{cmp, mem, slice};
 
 use crate::gl;
 use crate::gl::types::GLuint;
@@ -21,20 +22,21 @@ const MAX_BATCH_SIZE: usize = (u16::MAX - u16::MAX % 4) as usize;
 pub struct VertexBatcher<R: RenderProgram> {
     texture_ids: Vec<GLuint>,
     vertices: Vec<R::Vertex>,
+    draw_calls: usize,
     renderer: R,
 }
 
-impl<R: RenderProgram> Default for VertexBatcher<R> {
-    fn default() -> Self {
+impl<R: RenderProgram> VertexBatcher<R> {
+    /// Create a batcher with a new render program.
+    pub fn new(srgb: bool) -> Self {
         Self {
             texture_ids: Default::default(),
             vertices: Default::default(),
-            renderer: Default::default(),
+            draw_calls: Default::default(),
+            renderer: R::new(srgb),
         }
     }
-}
 
-impl<R: RenderProgram> VertexBatcher<R> {
     /// Add a vertex to the batcher.
     pub fn push(&mut self, texture_id: GLuint, vertex: R::Vertex) {
         self.texture_ids.push(texture_id);
@@ -48,21 +50,64 @@ impl<R: RenderProgram> VertexBatcher<R> {
         VertexBatches {
             texture_ids: &mut self.texture_ids,
             vertices: &mut self.vertices,
+            draw_calls: &mut self.draw_calls,
             renderer: &self.renderer,
             offset: 0,
         }
     }
 
+    /// Get the number of batches drawn since the last call.
+    pub fn take_draw_calls(&mut self) -> usize {
+        mem::take(&mut self.draw_calls)
+    }
+
+    /// Release memory held by the vertex buffers.
+    pub fn shrink_to_fit(&mut self) {
+        self.texture_ids.shrink_to_fit();
+        self.vertices.shrink_to_fit();
+    }
+
+    /// Check if there are no pending vertices.
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    /// Discard all pending vertices.
+    pub fn clear(&mut self) {
+        self.texture_ids.clear();
+        self.vertices.clear();
+    }
+
+    /// Feed all pending vertices and their textures into a hasher.
+    pub fn hash<H: Hasher>(&self, hasher: &mut H) {
+        self.texture_ids.hash(hasher);
+
+        // Vertices are plain `repr(C)` data, so their bytes identify them.
+        let bytes = unsafe {
+            slice::from_raw_parts(
+                self.vertices.as_ptr() as *const u8,
+                mem::size_of_val(self.vertices.as_slice()),
+            )
+        };
+        bytes.hash(hasher);
+    }
+
     /// Get pending vertices.
     pub fn pending(&mut self) -> &mut [R::Vertex] {
         &mut self.vertices
     }
+
+    /// Iterate over pending vertices and their texture IDs.
+    pub fn iter_pending(&self) -> impl Iterator<Item = (GLuint, &R::Vertex)> {
+        self.texture_ids.iter().copied().zip(&self.vertices)
+    }
 }
 
 /// Iterator over batched vertex groups.
 pub struct VertexBatches<'a, R: RenderProgram> {
     texture_ids: &'a mut Vec<GLuint>,
     vertices: &'a mut Vec<R::Vertex>,
+    draw_calls: &'a mut usize,
     offset: usize,
     renderer: &'a R,
 }
@@ -92,6 +137,7 @@ impl<'a, R: RenderProgram> VertexBatches<'a, R> {
         let batch_end = self.offset + batch_size;
 
         let old_offset = mem::replace(&mut self.offset, batch_end);
+        *self.draw_calls += 1;
 
         Some(VertexBatch {
             texture_id,
@@ -113,20 +159,8 @@ impl<'a, R: RenderProgram> VertexBatch<'a, R> {
     pub fn draw(&self) {
         self.renderer.bind();
 
-        let vertex_count = self.vertices.len();
-        unsafe {
-            gl::BindTexture(gl::TEXTURE_2D, self.texture_id);
-
-            gl::BufferSubData(
-                gl::ARRAY_BUFFER,
-                0,
-                (vertex_count * mem::size_of::<R::Vertex>()) as isize,
-                self.vertices.as_ptr() as *const _,
-            );
-
-            let num_indices = (vertex_count / 4 * 6) as i32;
-            gl::DrawElements(gl::TRIANGLES, num_indices, gl::UNSIGNED_SHORT, ptr::null());
-        }
+        unsafe { gl::BindTexture(gl::TEXTURE_2D, self.texture_id) };
+        self.renderer.draw(self.vertices);
     }
 }
 
@@ -149,10 +183,24 @@ impl GlSubTexture {
             u: self.uv_left,
             v: self.uv_bot + self.uv_height,
             flags,
+            r: u8::MAX,
+            g: u8::MAX,
+            b: u8::MAX,
+            a: u8::MAX,
         };
 
         // Top-Left vertex.
-        let top_left = GlyphVertex { x, y, u: self.uv_left, v: self.uv_bot, flags };
+        let top_left = GlyphVertex {
+            x,
+            y,
+            u: self.uv_left,
+            v: self.uv_bot,
+            flags,
+            r: u8::MAX,
+            g: u8::MAX,
+            b: u8::MAX,
+            a: u8::MAX,
+        };
 
         // Top-Right vertex.
         let top_right = GlyphVertex {
@@ -161,6 +209,10 @@ impl GlSubTexture {
             u: self.uv_left + self.uv_width,
             v: self.uv_bot,
             flags,
+            r: u8::MAX,
+            g: u8::MAX,
+            b: u8::MAX,
+            a: u8::MAX,
         };
 
         // Bottom-Right vertex.
@@ -170,6 +222,10 @@ impl GlSubTexture {
             u: self.uv_left + self.uv_width,
             v: self.uv_bot + self.uv_height,
             flags,
+            r: u8::MAX,
+            g: u8::MAX,
+            b: u8::MAX,
+            a: u8::MAX,
         };
 
         Some([bottom_left, top_left, top_right, bottom_right])
@@ -190,6 +246,32 @@ pub struct GlyphVertex {
 
     // Vertex flags.
     pub flags: f32,
+
+    // Text color, or tint for colored glyphs.
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Hash for GlyphVertex {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.x, self.y).hash(state);
+        (self.u.to_bits(), self.v.to_bits(), self.flags.to_bits()).hash(state);
+        (self.r, self.g, self.b, self.a).hash(state);
+    }
+}
+
+impl GlyphVertex {
+    /// Change the vertex's text color.
+    pub fn set_color(&mut self, color: [u8; 3]) {
+        [self.r, self.g, self.b] = color;
+    }
+
+    /// Scale the vertex's opacity.
+    pub fn fade(&mut self, opacity: f32) {
+        self.a = (self.a as f32 * opacity) as u8;
+    }
 }
 
 /// Vertex for the rectangle shader.
@@ -233,6 +315,95 @@ impl RectVertex {
             RectVertex { x: x + width, y, r, g, b, a },
         ]
     }
+
+    /// Create triangles for a line connecting multiple points.
+    ///
+    /// Every segment is drawn as two triangles, extended by half the line
+    /// width at both ends to close the gaps between segments.
+    pub fn polyline(
+        window_width: i16,
+        window_height: i16,
+        points: &[(f32, f32)],
+        line_width: f32,
+        color: &[u8; 4],
+    ) -> Vec<Self> {
+        let half_width = window_width as f32 / 2.;
+        let half_height = window_height as f32 / 2.;
+        let [r, g, b, a] = *color;
+        let vertex = |x: f32, y: f32| RectVertex {
+            x: x / half_width - 1.,
+            y: -y / half_height + 1.,
+            r,
+            g,
+            b,
+            a,
+        };
+
+        let mut vertices = Vec::with_capacity(points.len().saturating_sub(1) * 6);
+        for segment in points.windows(2) {
+            let ((x1, y1), (x2, y2)) = (segment[0], segment[1]);
+            let length = (x2 - x1).hypot(y2 - y1);
+            if length == 0. {
+                continue;
+            }
+
+            // Offsets along and perpendicular to the segment.
+            let scale = line_width / 2. / length;
+            let (dx, dy) = ((x2 - x1) * scale, (y2 - y1) * scale);
+            let (start_x, start_y) = (x1 - dx, y1 - dy);
+            let (end_x, end_y) = (x2 + dx, y2 + dy);
+
+            vertices.extend([
+                vertex(start_x - dy, start_y + dx),
+                vertex(start_x + dy, start_y - dx),
+                vertex(end_x - dy, end_y + dx),
+                vertex(start_x + dy, start_y - dx),
+                vertex(end_x + dy, end_y - dx),
+                vertex(end_x - dy, end_y + dx),
+            ]);
+        }
+        vertices
+    }
+
+    /// Scale the vertex's opacity.
+    pub fn fade(&mut self, opacity: f32) {
+        self.a = (self.a as f32 * opacity) as u8;
+    }
+}
+
+/// Per-instance data for the instanced rectangle shader.
+#[repr(C)]
+pub struct RectInstance {
+    // Position of the top-left corner.
+    pub x: f32,
+    pub y: f32,
+
+    // Offset to the bottom-right corner.
+    pub width: f32,
+    pub height: f32,
+
+    // Rectangle color.
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl RectInstance {
+    /// Convert the four vertices of a rectangle to an instance.
+    pub fn from_quad(quad: &[RectVertex]) -> Self {
+        let (top_left, bottom_right) = (&quad[0], &quad[2]);
+        Self {
+            x: top_left.x,
+            y: top_left.y,
+            width: bottom_right.x - top_left.x,
+            height: bottom_right.y - top_left.y,
+            r: top_left.r,
+            g: top_left.g,
+            b: top_left.b,
+            a: top_left.a,
+        }
+    }
 }
 
 /// Insertion sort for multiple arrays.
@@ -244,7 +415,7 @@ where
     T: Ord,
 {
     let len = v1.len();
-    for i in (0..len - 1).rev() {
+    for i in (0..len.saturating_sub(1)).rev() {
         if v1[i] <= v1[i + 1] {
             continue;
         }
@@ -1,5 +0,0 @@
-varying mediump vec4 color;
-
-void main() {
-    gl_FragColor = color;
-}
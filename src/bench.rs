@@ -0,0 +1,99 @@
+//! Scripted drawer animation benchmark.
+
+use std::time::{Duration, Instant};
+
+use calloop::timer::{TimeoutAction, Timer};
+use epitaph_ui::renderer::FrameStats;
+
+use crate::{Result, State};
+
+/// Number of times the drawer is opened and closed.
+const CYCLES: usize = 10;
+
+/// Drawer animation benchmark.
+pub struct Bench {
+    frame_times: Vec<Duration>,
+    opening: bool,
+    cycles: usize,
+}
+
+impl Bench {
+    /// Start the benchmark.
+    pub fn start(state: &mut State) -> Result<()> {
+        state.show_drawer()?;
+
+        state.bench = Some(Self { opening: true, frame_times: Default::default(), cycles: 0 });
+        state.event_loop.insert_source(Timer::immediate(), step)?;
+
+        Ok(())
+    }
+
+    /// Record a rendered drawer frame.
+    pub fn record(&mut self, stats: FrameStats) {
+        self.frame_times.push(stats.frame_time);
+    }
+
+    /// Print frame time percentiles.
+    fn report(&mut self) {
+        if self.frame_times.is_empty() {
+            println!("No frames rendered");
+            return;
+        }
+
+        self.frame_times.sort_unstable();
+
+        println!("Frames: {}", self.frame_times.len());
+        for percentile in [50, 90, 99, 100] {
+            let index = (self.frame_times.len() - 1) * percentile / 100;
+            let frame_time = self.frame_times[index].as_secs_f64() * 1000.;
+            println!("p{percentile}: {frame_time:.2}ms");
+        }
+    }
+}
+
+/// Benchmark animation frame.
+fn step(now: Instant, _: &mut (), state: &mut State) -> TimeoutAction {
+    let interval = Duration::from_millis(state.drawer_animation.interval);
+
+    // Wait for the drawer window to be configured.
+    let max_offset = state.drawer().max_offset();
+    if max_offset <= 1. {
+        return TimeoutAction::ToInstant(now + interval);
+    }
+
+    // Use a fixed step per frame, to render the same frames on every run.
+    let step = max_offset * state.drawer_animation.interval as f64
+        / state.drawer_animation.duration as f64;
+
+    let bench = match &mut state.bench {
+        Some(bench) => bench,
+        None => return TimeoutAction::Drop,
+    };
+
+    let drawer = match &mut state.drawer {
+        Some(drawer) => drawer,
+        None => return TimeoutAction::Drop,
+    };
+
+    // Move the drawer back and forth between its extremes.
+    if bench.opening {
+        drawer.set_offset((drawer.offset() + step).min(max_offset));
+        bench.opening = drawer.offset() < max_offset;
+    } else {
+        drawer.set_offset((drawer.offset() - step).max(0.));
+        if drawer.offset() <= 0. {
+            bench.opening = true;
+            bench.cycles += 1;
+        }
+    }
+
+    if bench.cycles >= CYCLES {
+        bench.report();
+        state.terminated = true;
+        return TimeoutAction::Drop;
+    }
+
+    state.drawer().request_frame();
+
+    TimeoutAction::ToInstant(now + interval)
+}
@@ -0,0 +1,70 @@
+//! Non-blocking socket clients.
+
+use std::io::{ErrorKind, Read};
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+
+use calloop::generic::Generic;
+use calloop::timer::{TimeoutAction, Timer};
+use calloop::{Interest, LoopHandle, Mode, PostAction};
+
+use crate::{Result, State};
+
+/// Maximum length of a client's request line.
+const MAX_REQUEST_LEN: usize = 4096;
+
+/// Maximum time a client may take to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Wait for a client's request line, without blocking the event loop.
+///
+/// The `handler` is called with the line once it was received, after which the
+/// stream is closed. Clients which send overlong requests or don't complete
+/// them in time are disconnected.
+///
+/// The stream must be non-blocking.
+pub fn read_request<S, F>(
+    event_loop: &LoopHandle<'static, State>,
+    stream: S,
+    handler: F,
+) -> Result<()>
+where
+    S: Read + AsRawFd + 'static,
+    F: FnOnce(&mut State, &mut S, &str) + 'static,
+{
+    let mut handler = Some(handler);
+    let mut request = Vec::new();
+    let source = Generic::new(stream, Interest::READ, Mode::Level);
+    let token = event_loop.insert_source(source, move |_, stream, state| {
+        let mut data = [0; 1024];
+        let len = match stream.read(&mut data) {
+            Ok(len) if len > 0 => len,
+            Err(err) if err.kind() == ErrorKind::WouldBlock => return Ok(PostAction::Continue),
+            // Drop clients which disconnected before completing their request.
+            _ => return Ok(PostAction::Remove),
+        };
+        request.extend_from_slice(&data[..len]);
+
+        // Wait for the rest of the request line.
+        let end = match request.iter().position(|byte| *byte == b'\n') {
+            Some(end) => end,
+            None if request.len() > MAX_REQUEST_LEN => return Ok(PostAction::Remove),
+            None => return Ok(PostAction::Continue),
+        };
+
+        if let Some(handler) = handler.take() {
+            handler(state, stream, &String::from_utf8_lossy(&request[..end]));
+        }
+
+        Ok(PostAction::Remove)
+    })?;
+
+    // Disconnect clients which don't complete their request in time.
+    let timeout = Timer::from_duration(REQUEST_TIMEOUT);
+    event_loop.insert_source(timeout, move |_, _, state| {
+        state.event_loop.remove(token);
+        TimeoutAction::Drop
+    })?;
+
+    Ok(())
+}
@@ -0,0 +1,1079 @@
+//! User configuration.
+
+use std::collections::HashMap;
+use std::env;
+use std::fmt::Display;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::mem;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+use epitaph_ui::theme::{Color, Theme};
+use serde::Deserialize;
+use toml::Value;
+
+use crate::error::Error;
+use crate::layout::ModuleId;
+use crate::module::{Alignment, Section};
+use crate::Result;
+
+/// Configuration file name inside the XDG config directory.
+const CONFIG_FILE: &str = "epitaph/epitaph.toml";
+
+/// Theme directory inside the XDG config directory.
+const THEME_DIR: &str = "epitaph/themes";
+
+/// Maximum depth of nested configuration includes.
+const MAX_INCLUDE_DEPTH: usize = 5;
+
+/// Default configuration with all options documented.
+const DEFAULT_CONFIG: &str = include_str!("../epitaph.toml");
+
+/// Epitaph configuration.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub lock_screen: LockScreen,
+    pub proximity: Proximity,
+    pub panel: Panel,
+    pub modules: Modules,
+    pub drawer: Drawer,
+    pub renderer: Renderer,
+    pub haptics: Haptics,
+    pub led: Led,
+    pub sound: Sound,
+    pub metrics: Metrics,
+    pub kiosk: Kiosk,
+
+    /// Theme name, loaded from `$XDG_CONFIG_HOME/epitaph/themes/<name>.toml`.
+    pub theme: Option<String>,
+
+    /// Wallpaper PNG from which the color of active modules is derived, overriding the theme.
+    pub accent_wallpaper: Option<PathBuf>,
+
+    /// Overrides for outputs, keyed by output name like `DSI-1` or model.
+    pub outputs: HashMap<String, OutputProfile>,
+
+    /// Files merged into this configuration, relative to it.
+    pub include: Vec<PathBuf>,
+}
+
+impl Config {
+    /// Load the configuration file.
+    ///
+    /// This will fall back to the default configuration if no configuration
+    /// file exists. Invalid configuration files, including ones with unknown
+    /// keys, are rejected instead of silently replacing them with the defaults.
+    pub fn load() -> Result<Self> {
+        match config_path() {
+            Some(path) if path.exists() => Self::from_path(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Parse configuration at the specified path.
+    fn from_path(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let mut value = parse_file(path, &content)?;
+        resolve_includes(path, &mut value, 0)?;
+
+        let mut config: Self = value.try_into().map_err(|error| parse_error(path, error))?;
+
+        config.drawer.animation.clamp();
+
+        Ok(config)
+    }
+}
+
+/// Lock screen companion surface configuration.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct LockScreen {
+    /// Show essential panel modules while the session is locked.
+    pub enabled: bool,
+}
+
+/// Proximity sensor configuration.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Proximity {
+    /// Blank the panel and drawer and ignore touch input while the phone is
+    /// held to the ear during a call.
+    pub enabled: bool,
+}
+
+/// Panel configuration.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Panel {
+    /// Padding between modules and the screen edges in pixels.
+    pub edge_padding: u16,
+
+    /// Padding after each module in pixels.
+    pub module_padding: u16,
+
+    /// Text drawn between adjacent modules with the same alignment, like `│` or `·`.
+    pub separator: String,
+
+    /// Render modules with an icon without their text, leaving values to the drawer.
+    pub icons_only: bool,
+
+    /// Panel overrides for individual modules.
+    pub modules: HashMap<ModuleId, ModuleStyle>,
+
+    /// Command run when the panel is tapped twice, like locking the screen.
+    pub double_tap_command: Vec<String>,
+
+    /// Drawer pages opened by tapping individual modules.
+    pub links: HashMap<ModuleId, DrawerLink>,
+
+    /// Modules whose drawer slider is adjusted by vertical drags on their panel content.
+    pub slider_modules: Vec<ModuleId>,
+
+    /// Opacity of the panel background, from `0.0` to `1.0`.
+    pub opacity: f32,
+}
+
+impl Default for Panel {
+    fn default() -> Self {
+        Self {
+            edge_padding: 5,
+            module_padding: 5,
+            opacity: 1.,
+            separator: Default::default(),
+            icons_only: Default::default(),
+            modules: Default::default(),
+            double_tap_command: Default::default(),
+            links: HashMap::from([
+                (ModuleId::Clock, DrawerLink::Module(ModuleId::Clock)),
+                (ModuleId::DoNotDisturb, DrawerLink::Module(ModuleId::DoNotDisturb)),
+            ]),
+            slider_modules: vec![ModuleId::Brightness],
+        }
+    }
+}
+
+/// Drawer location opened by tapping a panel module.
+#[derive(Deserialize, Copy, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DrawerLink {
+    /// Page containing a drawer section.
+    Section(Section),
+    /// Page containing a module's drawer entry.
+    Module(ModuleId),
+}
+
+/// Overrides for a single output.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputProfile {
+    /// Padding between panel modules and the screen edges in pixels.
+    pub edge_padding: Option<u16>,
+
+    /// Padding after each panel module in pixels.
+    pub module_padding: Option<u16>,
+
+    /// Text drawn between adjacent panel modules with the same alignment.
+    pub separator: Option<String>,
+
+    /// Render panel modules with an icon without their text.
+    pub icons_only: Option<bool>,
+
+    /// Allow opening the drawer from the panel.
+    pub drawer: bool,
+
+    /// Rendering scale, overriding the scale factor reported by the compositor.
+    pub scale: Option<u8>,
+}
+
+impl Default for OutputProfile {
+    fn default() -> Self {
+        Self {
+            drawer: true,
+            scale: Default::default(),
+            edge_padding: Default::default(),
+            module_padding: Default::default(),
+            separator: Default::default(),
+            icons_only: Default::default(),
+        }
+    }
+}
+
+impl OutputProfile {
+    /// Find the profile for an output, preferring matches by name over model.
+    pub fn find<'a>(
+        profiles: &'a HashMap<String, Self>,
+        name: Option<&str>,
+        model: &str,
+    ) -> Option<&'a Self> {
+        name.and_then(|name| profiles.get(name)).or_else(|| profiles.get(model))
+    }
+
+    /// Apply the overrides to a panel configuration.
+    pub fn apply(&self, panel: &mut Panel) {
+        if let Some(edge_padding) = self.edge_padding {
+            panel.edge_padding = edge_padding;
+        }
+        if let Some(module_padding) = self.module_padding {
+            panel.module_padding = module_padding;
+        }
+        if let Some(separator) = &self.separator {
+            panel.separator = separator.clone();
+        }
+        if let Some(icons_only) = self.icons_only {
+            panel.icons_only = icons_only;
+        }
+    }
+}
+
+/// Panel overrides for a single module.
+#[derive(Deserialize, Default, Copy, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModuleStyle {
+    /// Padding after this module in pixels.
+    pub padding: Option<u16>,
+
+    /// Alignment group this module is placed in.
+    pub alignment: Option<Alignment>,
+
+    /// Collapse order when modules don't fit into the panel, lowest priority first.
+    pub priority: i32,
+}
+
+/// Module configuration.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Modules {
+    /// Show a panel badge while modules are disabled due to errors.
+    pub error_badge: bool,
+
+    pub brightness: Brightness,
+    pub clock: Clock,
+    pub cellular: Cellular,
+    pub wifi: Wifi,
+    pub pipe: Pipe,
+    pub cast: Cast,
+    pub usb: Usb,
+    pub timer: Timer,
+    pub mail: Mail,
+    pub updates: Updates,
+    pub journal: Journal,
+    pub wireguard: Wireguard,
+    pub dock: Dock,
+    pub do_not_disturb: DoNotDisturb,
+    pub screenshot: Screenshot,
+    pub screen_recorder: ScreenRecorder,
+    pub system: System,
+}
+
+/// Brightness module configuration.
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+#[cfg_attr(not(feature = "udev"), allow(dead_code))]
+pub struct Brightness {
+    /// Show an icon in the panel, which adjusts the brightness when dragged.
+    pub panel_icon: bool,
+}
+
+/// Clock module configuration.
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Clock {
+    /// Alarms shown in the drawer, with a panel indicator within 24 hours of ringing.
+    pub alarms: Vec<Alarm>,
+
+    /// Also show the alarms configured in GNOME Clocks.
+    pub gnome_clocks: bool,
+
+    /// Show the date next to the time in the panel.
+    pub date: bool,
+
+    /// IANA timezones shown in the drawer, like `America/New_York`.
+    pub timezones: Vec<String>,
+}
+
+/// Alarm configuration.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Alarm {
+    /// Alarm time in `HH:MM` format.
+    pub time: String,
+
+    /// Days the alarm rings on, every day if empty.
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+
+    /// Alarm description.
+    #[serde(default)]
+    pub label: String,
+}
+
+/// Day of the week.
+#[derive(Deserialize, Copy, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+/// Cellular module configuration.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Cellular {
+    /// Name prefixes of the network interfaces counted as mobile data.
+    pub interfaces: Vec<String>,
+
+    /// Monthly mobile data allowance in megabytes.
+    pub data_cap: Option<u64>,
+
+    /// Percentage of the monthly data allowance which triggers a warning notification.
+    pub data_cap_warning: u8,
+}
+
+impl Default for Cellular {
+    fn default() -> Self {
+        Self {
+            interfaces: vec!["wwan".into(), "rmnet".into(), "ccmni".into()],
+            data_cap_warning: 90,
+            data_cap: Default::default(),
+        }
+    }
+}
+
+/// WiFi module configuration.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Wifi {
+    /// Host pinged for checking internet connectivity.
+    pub ping_host: String,
+
+    /// Seconds between connectivity checks.
+    pub ping_interval: u64,
+
+    /// Round-trip time in milliseconds above which the connection is considered degraded.
+    pub degraded_latency: u64,
+
+    /// URL responding with `204 No Content` unless a captive portal intercepts it.
+    ///
+    /// Captive portal detection is disabled if this is empty.
+    pub portal_check_url: String,
+}
+
+impl Default for Wifi {
+    fn default() -> Self {
+        Self {
+            portal_check_url: "http://connectivitycheck.gstatic.com/generate_204".into(),
+            ping_host: "1.1.1.1".into(),
+            degraded_latency: 300,
+            ping_interval: 5,
+        }
+    }
+}
+
+/// Pipe module configuration.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Pipe {
+    /// Read status lines, disable to leave stdin untouched.
+    pub enabled: bool,
+
+    /// Named FIFO read instead of stdin.
+    pub path: Option<PathBuf>,
+}
+
+impl Default for Pipe {
+    fn default() -> Self {
+        Self { enabled: true, path: Default::default() }
+    }
+}
+
+/// Cast module configuration.
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Cast {
+    /// Command printing one available sink per line.
+    pub list_command: Vec<String>,
+
+    /// Command starting a stream, with the sink appended as last argument.
+    pub start_command: Vec<String>,
+
+    /// Command stopping the active stream.
+    pub stop_command: Vec<String>,
+}
+
+/// USB module configuration.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+#[cfg_attr(not(feature = "udev"), allow(dead_code))]
+pub struct Usb {
+    /// Command switching the gadget mode, with the mode appended as last argument.
+    ///
+    /// The mode is one of `charging`, `mtp` or `tethering`.
+    pub command: Vec<String>,
+}
+
+/// Timer module configuration.
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Timer {
+    /// Additional countdown durations in seconds.
+    pub custom: Vec<u64>,
+
+    /// Command run when a countdown expires, like playing a sound or vibrating.
+    pub alert_command: Vec<String>,
+}
+
+/// Mail module configuration.
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Mail {
+    /// IMAP accounts checked for unread messages.
+    pub accounts: Vec<ImapAccount>,
+
+    /// Maildir checked for unread messages.
+    pub maildir: Option<PathBuf>,
+
+    /// Command opening the mail client.
+    pub command: Vec<String>,
+}
+
+/// IMAP account configuration.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct ImapAccount {
+    /// Server URL, like `imaps://imap.example.com`.
+    pub url: String,
+
+    /// Mailbox checked for unread messages.
+    pub mailbox: String,
+
+    /// Login user name.
+    pub user: String,
+
+    /// Login password, read from `~/.netrc` if omitted.
+    pub password: Option<String>,
+}
+
+impl Default for ImapAccount {
+    fn default() -> Self {
+        Self {
+            mailbox: "INBOX".into(),
+            password: Default::default(),
+            user: Default::default(),
+            url: Default::default(),
+        }
+    }
+}
+
+/// Package updates module configuration.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Updates {
+    /// Command printing one pending update per line.
+    pub check_command: Vec<String>,
+
+    /// Command opening the updater, like a terminal running the package manager.
+    pub update_command: Vec<String>,
+}
+
+impl Default for Updates {
+    fn default() -> Self {
+        Self { check_command: vec!["checkupdates".into()], update_command: Default::default() }
+    }
+}
+
+/// Journal module configuration.
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Journal {
+    /// Systemd units whose errors raise notifications, all units if empty.
+    pub units: Vec<String>,
+}
+
+/// WireGuard module configuration.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Wireguard {
+    /// Tunnels shown in the drawer.
+    pub tunnels: Vec<String>,
+
+    /// Command printing the state of all interfaces in the `wg show all dump` format.
+    pub status_command: Vec<String>,
+
+    /// Command bringing a tunnel up or down, with `up` or `down` and the tunnel appended.
+    pub toggle_command: Vec<String>,
+}
+
+impl Default for Wireguard {
+    fn default() -> Self {
+        Self {
+            status_command: vec!["wg".into(), "show".into(), "all".into(), "dump".into()],
+            toggle_command: vec!["wg-quick".into()],
+            tunnels: Default::default(),
+        }
+    }
+}
+
+/// Dock module configuration.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Dock {
+    /// Output profile from `[outputs]` applied while docked, like `desktop`.
+    pub profile: Option<String>,
+}
+
+/// Do not disturb module configuration.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct DoNotDisturb {
+    /// Daily time range in which do not disturb is enabled automatically.
+    pub quiet_hours: Option<QuietHours>,
+
+    /// Applications whose notifications break through do not disturb.
+    pub allowed: Vec<String>,
+
+    /// Command forwarding the state to the notification daemon, with `on` or `off` appended.
+    pub command: Vec<String>,
+}
+
+impl Default for DoNotDisturb {
+    fn default() -> Self {
+        Self {
+            allowed: vec!["timer".into()],
+            quiet_hours: Default::default(),
+            command: Default::default(),
+        }
+    }
+}
+
+/// Daily quiet hours.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct QuietHours {
+    /// Start time in `HH:MM` format.
+    pub start: String,
+
+    /// End time in `HH:MM` format, which may be on the next day.
+    pub end: String,
+}
+
+/// Screenshot module configuration.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Screenshot {
+    /// Directory screenshots are saved to, defaults to `~/Pictures`.
+    pub directory: Option<PathBuf>,
+
+    /// Image editor offered in the screenshot notification, with the file path appended.
+    pub editor: Vec<String>,
+}
+
+impl Default for Screenshot {
+    fn default() -> Self {
+        Self { editor: vec!["swappy".into(), "-f".into()], directory: Default::default() }
+    }
+}
+
+/// Screen recorder module configuration.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScreenRecorder {
+    /// Directory recordings are saved to, defaults to `~/Videos`.
+    pub directory: Option<PathBuf>,
+
+    /// Recording command, with the file path appended.
+    ///
+    /// The recording is stopped by sending `SIGINT` to this process.
+    pub command: Vec<String>,
+}
+
+impl Default for ScreenRecorder {
+    fn default() -> Self {
+        Self { command: vec!["wf-recorder".into(), "-f".into()], directory: Default::default() }
+    }
+}
+
+/// System module configuration.
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct System {
+    /// Command switching the CPU governor without write access to sysfs, with the governor
+    /// appended as last argument.
+    pub governor_command: Vec<String>,
+}
+
+/// Drawer configuration.
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Drawer {
+    /// Seconds the drawer must be hidden before its GPU memory is released.
+    pub release_delay: u64,
+
+    /// Opacity of the drawer background, from `0.0` to `1.0`.
+    pub opacity: f32,
+
+    pub animation: DrawerAnimation,
+}
+
+impl Default for Drawer {
+    fn default() -> Self {
+        Self { release_delay: 30, opacity: 1., animation: Default::default() }
+    }
+}
+
+/// Drawer gesture and animation configuration.
+#[derive(Deserialize, Copy, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct DrawerAnimation {
+    /// Milliseconds it takes to fully open or close the drawer.
+    pub duration: u64,
+
+    /// Milliseconds between animation updates.
+    pub interval: u64,
+
+    /// Height percentage beyond which a released drawer keeps opening.
+    pub open_threshold: f64,
+
+    /// Height percentage beyond which a released drawer keeps closing.
+    pub close_threshold: f64,
+
+    /// Crop a single rendering of the opened drawer while animating.
+    ///
+    /// This requires `wp_viewporter` and skips the scrim and content fade.
+    pub reuse_buffer: bool,
+}
+
+impl Default for DrawerAnimation {
+    fn default() -> Self {
+        Self {
+            duration: 250,
+            interval: 1000 / 120,
+            open_threshold: 0.25,
+            close_threshold: 0.25,
+            reuse_buffer: false,
+        }
+    }
+}
+
+impl DrawerAnimation {
+    /// Clamp all options to sane bounds.
+    fn clamp(&mut self) {
+        clamp_option("drawer.animation.duration", &mut self.duration, 1, 5000);
+        clamp_option("drawer.animation.interval", &mut self.interval, 1, 100);
+        clamp_option("drawer.animation.open_threshold", &mut self.open_threshold, 0., 1.);
+        clamp_option("drawer.animation.close_threshold", &mut self.close_threshold, 0., 1.);
+    }
+}
+
+/// Renderer configuration.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Renderer {
+    /// Blend in linear color space using an sRGB framebuffer.
+    ///
+    /// This requires GLES 3 and `EGL_KHR_gl_colorspace`.
+    pub srgb: bool,
+}
+
+/// Vibration feedback configuration.
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+#[cfg_attr(not(feature = "udev"), allow(dead_code))]
+pub struct Haptics {
+    /// Vibration strength for notifications, from `0.0` to `1.0`.
+    ///
+    /// Setting this to zero disables vibration.
+    pub intensity: f32,
+
+    /// Vibration duration in milliseconds.
+    pub duration: u32,
+}
+
+impl Default for Haptics {
+    fn default() -> Self {
+        Self { intensity: 0.5, duration: 200 }
+    }
+}
+
+/// Notification sound configuration.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Sound {
+    /// Sound file played for normal notifications, silent if unset.
+    pub normal: Option<PathBuf>,
+
+    /// Sound file played for critical notifications, silent if unset.
+    pub critical: Option<PathBuf>,
+
+    /// Command playing a sound, with the file appended.
+    pub command: Vec<String>,
+
+    /// Applications whose notifications never play a sound.
+    pub muted: Vec<String>,
+}
+
+impl Default for Sound {
+    fn default() -> Self {
+        Self {
+            normal: Some("/usr/share/sounds/freedesktop/stereo/message.oga".into()),
+            critical: Some("/usr/share/sounds/freedesktop/stereo/alarm-clock-elapsed.oga".into()),
+            command: vec!["pw-play".into()],
+            muted: Default::default(),
+        }
+    }
+}
+
+/// Notification LED configuration.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+#[cfg_attr(not(feature = "udev"), allow(dead_code))]
+pub struct Led {
+    /// Drive the notification LED.
+    pub enabled: bool,
+
+    /// Pattern while normal notifications are pending.
+    pub normal: LedPattern,
+
+    /// Pattern while critical notifications are pending.
+    pub critical: LedPattern,
+
+    /// Color while charging, off if unset.
+    pub charging: Option<Color>,
+
+    /// Color while fully charged, off if unset.
+    pub full: Option<Color>,
+}
+
+impl Default for Led {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            normal: LedPattern { color: Color::new(0, 0, 255), on: 500, off: 2500 },
+            critical: LedPattern { color: Color::new(255, 0, 255), on: 250, off: 250 },
+            charging: Some(Color::new(255, 0, 0)),
+            full: Some(Color::new(0, 255, 0)),
+        }
+    }
+}
+
+/// Notification LED blink pattern.
+#[derive(Deserialize, Copy, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(not(feature = "udev"), allow(dead_code))]
+pub struct LedPattern {
+    /// LED color, in `#rrggbb` format.
+    pub color: Color,
+
+    /// Time the LED is on in milliseconds.
+    pub on: u32,
+
+    /// Time the LED is off in milliseconds, steadily lit if zero.
+    pub off: u32,
+}
+
+/// Metrics endpoint configuration.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Metrics {
+    /// Address serving Prometheus metrics over HTTP, disabled by default.
+    pub address: Option<SocketAddr>,
+}
+
+/// Kiosk mode configuration.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Kiosk {
+    /// Prevent users from changing the device configuration.
+    pub enabled: bool,
+
+    /// Numeric PIN required to open the drawer, which cannot be opened at all without one.
+    pub pin: Option<String>,
+
+    /// Modules hidden while kiosk mode is enabled, like power controls.
+    pub hidden_modules: Vec<ModuleId>,
+}
+
+/// Restrict a numeric option to an inclusive range.
+fn clamp_option<T: PartialOrd + Copy + Display>(name: &str, value: &mut T, min: T, max: T) {
+    if *value >= min && *value <= max {
+        return;
+    }
+
+    // Values which can't be compared, like NaN, fall back to the minimum.
+    let clamped = if *value > max { max } else { min };
+    eprintln!("Error: Option {name} must be between {min} and {max}, using {clamped}");
+    *value = clamped;
+}
+
+/// Location of the configuration file.
+fn config_path() -> Option<PathBuf> {
+    Some(config_home()?.join(CONFIG_FILE))
+}
+
+/// Load a theme by name from the theme directory.
+pub fn load_theme(name: &str) -> Result<Theme> {
+    let dir = config_home().ok_or(Error::MissingDirectory("config"))?.join(THEME_DIR);
+    Ok(Theme::load(&dir, name)?)
+}
+
+/// Get the XDG config directory.
+pub fn config_home() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+}
+
+/// Get the XDG state directory.
+pub fn state_home() -> Option<PathBuf> {
+    env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))
+}
+
+/// Merge all files included by a configuration into it.
+///
+/// Included files are applied in order, with later files overriding earlier
+/// ones and the including file overriding all of them.
+fn resolve_includes(path: &Path, value: &mut Value, depth: usize) -> Result<()> {
+    let includes = match value.as_table_mut().and_then(|table| table.remove("include")) {
+        Some(includes) => includes,
+        None => return Ok(()),
+    };
+
+    if depth >= MAX_INCLUDE_DEPTH {
+        return Err(Error::ConfigInclude(path.into(), "too many nested includes".into()));
+    }
+
+    // Resolve paths relative to the including file.
+    let includes: Vec<PathBuf> = includes.try_into().map_err(|error| parse_error(path, error))?;
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut merged = Value::Table(Default::default());
+    for include in includes {
+        let include = parent.join(include);
+        let content = fs::read_to_string(&include)
+            .map_err(|err| Error::ConfigInclude(include.clone(), err.to_string()))?;
+        let mut include_value = parse_file(&include, &content)?;
+        resolve_includes(&include, &mut include_value, depth + 1)?;
+        merge(&mut merged, include_value);
+    }
+
+    let own = mem::replace(value, merged);
+    merge(value, own);
+
+    Ok(())
+}
+
+/// Parse a single configuration file.
+///
+/// Every file is validated on its own before being merged with its includes,
+/// so errors can point to their position in the file.
+fn parse_file(path: &Path, content: &str) -> Result<Value> {
+    toml::from_str::<Config>(content).map_err(|error| parse_error(path, error))?;
+    toml::from_str(content).map_err(|error| parse_error(path, error))
+}
+
+/// Convert a parsing error, including the file and position it occurred at.
+fn parse_error(path: &Path, error: toml::de::Error) -> Error {
+    let mut location = path.display().to_string();
+    if let Some((line, column)) = error.line_col() {
+        location = format!("{location}:{}:{}", line + 1, column + 1);
+    }
+
+    // Strip the position, since it's already part of the location.
+    let message = error.to_string();
+    let message = message.split(" at line ").next().unwrap_or_default().to_string();
+
+    match suggestion(&error) {
+        Some(suggestion) => Error::ConfigTypo { location, message, suggestion },
+        None => Error::Config { location, message },
+    }
+}
+
+/// Recursively merge tables, replacing all other values.
+fn merge(base: &mut Value, value: Value) {
+    match (base, value) {
+        (Value::Table(base), Value::Table(table)) => {
+            for (key, value) in table {
+                match base.get_mut(&key) {
+                    Some(base) => merge(base, value),
+                    None => {
+                        base.insert(key, value);
+                    },
+                }
+            }
+        },
+        (base, value) => *base = value,
+    }
+}
+
+/// Write the documented default configuration to the configuration file location.
+///
+/// Existing configuration files are never overwritten.
+pub fn generate() -> Result<PathBuf> {
+    let path = config_path().ok_or(Error::MissingDirectory("config"))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut file = OpenOptions::new().write(true).create_new(true).open(&path)?;
+    file.write_all(DEFAULT_CONFIG.as_bytes())?;
+
+    Ok(path)
+}
+
+/// Suggest the closest known key for unknown configuration keys.
+fn suggestion(error: &toml::de::Error) -> Option<String> {
+    // Extract keys from messages like "unknown field `a`, expected one of `b`, `c`".
+    let message = error.to_string();
+    let (_, unknown) = message.split_once("unknown field `")?;
+    let (unknown, expected) = unknown.split_once('`')?;
+
+    // Ignore the parent table and position appended to the message.
+    let expected = expected.split(" for key `").next()?;
+    let expected = expected.split(" at line ").next()?;
+
+    expected
+        .split('`')
+        .skip(1)
+        .step_by(2)
+        .map(|key| (key, edit_distance(unknown, key)))
+        .filter(|(key, distance)| *distance <= key.len().max(unknown.len()) / 3 + 1)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(key, _)| key.to_string())
+}
+
+/// Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a_char != *b_char);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process;
+
+    use super::*;
+
+    #[test]
+    fn suggest_closest_key() {
+        let error = toml::from_str::<Config>("pannel = {}").unwrap_err();
+        assert_eq!(suggestion(&error).as_deref(), Some("panel"));
+    }
+
+    #[test]
+    fn suggest_closest_nested_key() {
+        let error = toml::from_str::<Config>("[panel]\nseparater = \"|\"").unwrap_err();
+        assert_eq!(suggestion(&error).as_deref(), Some("separator"));
+    }
+
+    #[test]
+    fn no_suggestion_for_distant_key() {
+        let error = toml::from_str::<Config>("qqqqqqqq = 3").unwrap_err();
+        assert_eq!(suggestion(&error), None);
+    }
+
+    #[test]
+    fn merge_nested_tables() {
+        let mut base: Value = toml::from_str("a = 1\n[table]\nb = 2\nc = 3").unwrap();
+        let value: Value = toml::from_str("d = 4\n[table]\nc = 5").unwrap();
+        merge(&mut base, value);
+
+        let expected: Value = toml::from_str("a = 1\nd = 4\n[table]\nb = 2\nc = 5").unwrap();
+        assert_eq!(base, expected);
+    }
+
+    #[test]
+    fn merge_replaces_non_tables() {
+        let mut base: Value = toml::from_str("a = [1, 2]\nb = { c = 3 }").unwrap();
+        let value: Value = toml::from_str("a = [3]\nb = 4").unwrap();
+        merge(&mut base, value);
+
+        let expected: Value = toml::from_str("a = [3]\nb = 4").unwrap();
+        assert_eq!(base, expected);
+    }
+
+    #[test]
+    fn includes_are_overridden_in_order() {
+        let dir = test_dir("includes");
+        fs::write(dir.join("first.toml"), "a = 1\nb = 1\nc = 1").unwrap();
+        fs::write(dir.join("second.toml"), "b = 2\nc = 2").unwrap();
+
+        let path = dir.join("epitaph.toml");
+        let mut value: Value =
+            toml::from_str("include = [\"first.toml\", \"second.toml\"]\nc = 3").unwrap();
+        resolve_includes(&path, &mut value, 0).unwrap();
+
+        let expected: Value = toml::from_str("a = 1\nb = 2\nc = 3").unwrap();
+        assert_eq!(value, expected);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn recursive_includes_are_limited() {
+        let dir = test_dir("recursive_includes");
+        fs::write(dir.join("loop.toml"), "include = [\"loop.toml\"]").unwrap();
+
+        let path = dir.join("epitaph.toml");
+        let mut value: Value = toml::from_str("include = [\"loop.toml\"]").unwrap();
+        let result = resolve_includes(&path, &mut value, 0);
+        assert!(matches!(result, Err(Error::ConfigInclude(..))));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn missing_include_fails() {
+        let path = test_dir("missing_include").join("epitaph.toml");
+        let mut value: Value = toml::from_str("include = [\"missing.toml\"]").unwrap();
+        let result = resolve_includes(&path, &mut value, 0);
+        assert!(matches!(result, Err(Error::ConfigInclude(..))));
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn include_errors_point_to_included_file() {
+        let dir = test_dir("include_errors");
+        let include = dir.join("include.toml");
+        fs::write(&include, "[panel]\nseparater = \"|\"").unwrap();
+
+        let path = dir.join("epitaph.toml");
+        let mut value: Value = toml::from_str("include = [\"include.toml\"]").unwrap();
+        let location = match resolve_includes(&path, &mut value, 0) {
+            Err(Error::ConfigTypo { location, .. }) => location,
+            result => panic!("unexpected result: {result:?}"),
+        };
+        assert!(location.starts_with(&format!("{}:", include.display())));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    /// Create an empty directory unique to this test.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("epitaph-{}-{name}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+}
@@ -1,38 +1,46 @@
 //! Drawer window state.
+use std::collections::HashMap;
+use std::mem;
 use std::num::NonZeroU32;
-
+use std::time::{Duration, Instant};
+
+use crossfont::Metrics;
+use epitaph_ui::renderer::{self, FrameStats, LineRenderer, RectRenderer, Renderer, TextRenderer};
+use epitaph_ui::text::{GlRasterizer, Svg};
+use epitaph_ui::theme::Theme;
+use epitaph_ui::vertex::{RectVertex, VertexBatcher};
+use epitaph_ui::{gl, Size};
 use glutin::api::egl::config::Config;
 use glutin::config::GetGlConfig;
-use glutin::context::{ContextApi, ContextAttributesBuilder, Version};
 use glutin::display::GetGlDisplay;
 use glutin::prelude::*;
 use glutin::surface::{SurfaceAttributesBuilder, WindowSurface};
+use qrcodegen::{QrCode, QrCodeEcc};
 use raw_window_handle::{RawWindowHandle, WaylandWindowHandle};
 use smithay_client_toolkit::compositor::{CompositorState, Region};
 use smithay_client_toolkit::reexports::client::protocol::wl_surface::WlSurface;
-use smithay_client_toolkit::reexports::client::{Proxy, QueueHandle};
+use smithay_client_toolkit::reexports::client::protocol::wl_touch::WlTouch;
+use smithay_client_toolkit::reexports::client::{Connection, Dispatch, Proxy, QueueHandle};
+use smithay_client_toolkit::reexports::protocols::wp::viewporter::client::wp_viewport::WpViewport;
+use smithay_client_toolkit::reexports::protocols::wp::viewporter::client::wp_viewporter::WpViewporter;
 use smithay_client_toolkit::shell::layer::{
     Anchor, Layer, LayerShell, LayerSurface, LayerSurfaceConfigure,
 };
+use tiny_skia::Pixmap;
 
-use crate::module::{DrawerModule, Module, Slider, Toggle};
-use crate::panel::PANEL_HEIGHT;
-use crate::renderer::{RectRenderer, Renderer, TextRenderer};
-use crate::text::GlRasterizer;
-use crate::vertex::{RectVertex, VertexBatcher};
-use crate::{gl, Result, Size, State};
+use crate::config::{self, DrawerLink};
+use crate::error::Error;
+use crate::layout::ModuleId;
+use crate::module::{DrawerModule, Graph, List, Module, Section, Slider, Toggle};
+use crate::panel::{Panel, PANEL_HEIGHT};
+use crate::seat::TouchId;
+use crate::{Result, State, TAP_SLOP};
 
 /// Slider module height.
 ///
 /// This should be less than `MODULE_SIZE`.
 const SLIDER_HEIGHT: i16 = MODULE_SIZE as i16 - 16;
 
-/// Color of slider handle and active buttons,
-const MODULE_COLOR_FG: [u8; 4] = [85, 85, 85, 255];
-
-/// Color of the slider tray and inactive buttons.
-const MODULE_COLOR_BG: [u8; 4] = [51, 51, 51, 255];
-
 /// Padding between drawer modules.
 const MODULE_PADDING: i16 = 16;
 
@@ -45,48 +53,175 @@ const MODULE_SIZE: u32 = 64;
 /// Drawer module icon height.
 const ICON_HEIGHT: u32 = 32;
 
+/// Scrim opacity below the drawer when it is fully opened.
+const SCRIM_ALPHA: f32 = 0.5;
+
+/// Share of the drawer opening during which a single element fades in.
+const REVEAL_DURATION: f64 = 0.5;
+
+/// Distance elements are shifted upwards before they are fully revealed.
+const REVEAL_DISTANCE: f64 = 24.;
+
+/// Drawer section header height.
+const HEADER_HEIGHT: i16 = 24;
+
+/// Size of the page indicator dots.
+const PAGE_DOT_SIZE: i16 = 6;
+
+/// Color of the active page's indicator dot.
+const PAGE_DOT_COLOR_ACTIVE: [u8; 4] = [170, 170, 170, 255];
+
+/// Color overlaid on elements below a hovering stylus.
+const HOVER_COLOR: [u8; 4] = [255, 255, 255, 24];
+
+/// Color overlaid on the currently pressed element.
+const PRESSED_COLOR: [u8; 4] = [255, 255, 255, 48];
+
+/// Color of drawer hit regions in the input debug overlay.
+const DEBUG_REGION_COLOR: [u8; 4] = [255, 0, 0, 40];
+
+/// Color of touch points in the input debug overlay.
+const DEBUG_TOUCH_COLOR: [u8; 4] = [255, 0, 0, 160];
+
+/// Size of touch points in the input debug overlay.
+const DEBUG_TOUCH_SIZE: i16 = 16;
+
+/// Duration of the transition between toggle states.
+const TOGGLE_TRANSITION: Duration = Duration::from_millis(150);
+
+/// Minimum touch duration to start moving a module.
+const LONG_PRESS_DURATION: Duration = Duration::from_millis(500);
+
+/// Vertical drag distance adjusting a toggle's level from empty to full.
+const LEVEL_DRAG_DISTANCE: f64 = 200.;
+
+/// Height of the entries of expanded lists.
+const LIST_ENTRY_HEIGHT: i16 = 40;
+
+/// Height of graph modules, including their label.
+const GRAPH_HEIGHT: i16 = 2 * MODULE_SIZE as i16;
+
+/// Width of the line plotted by graph modules.
+const GRAPH_LINE_WIDTH: f32 = 2.;
+
+/// Color of the line plotted by graph modules.
+const GRAPH_LINE_COLOR: [u8; 4] = [170, 170, 170, 255];
+
+/// Color of light QR code modules, including the surrounding quiet zone.
+const QR_LIGHT_COLOR: [u8; 4] = [255, 255, 255, 255];
+
+/// Color of dark QR code modules.
+const QR_DARK_COLOR: [u8; 4] = [0, 0, 0, 255];
+
+/// Width of the light border around QR codes in modules, as required by scanners.
+const QR_QUIET_ZONE: i16 = 4;
+
+/// Vertical drag distance adjusting a panel module's slider from empty to full.
+const PANEL_SLIDER_DISTANCE: f64 = 200.;
+
+/// Minimum horizontal distance for switching drawer pages.
+const PAGE_SWIPE_DISTANCE: f64 = 50.;
+
+/// Keys of the kiosk PIN pad, in row-major order.
+const PIN_KEYS: [char; 12] = ['1', '2', '3', '4', '5', '6', '7', '8', '9', 'C', '0', '<'];
+
+/// Number of PIN pad columns.
+const PIN_COLUMNS: usize = 3;
+
 pub struct Drawer {
+    toggle_transitions: HashMap<usize, ToggleTransition>,
+    collapsed_sections: Vec<Section>,
+    expanded_module: Option<usize>,
+    pin_lock: Option<PinLock>,
+    viewport: Option<WpViewport>,
     window: Option<LayerSurface>,
+    page_count: usize,
+    page: usize,
     queue: QueueHandle<State>,
-    touch_module: Option<usize>,
+    touch_element: Option<LayoutElement>,
+    touch_pressed: bool,
+    touch_origin: (f64, f64),
+    slider_locked: bool,
+    level_origin: Option<f64>,
     touch_position: (f64, f64),
-    touch_id: Option<i32>,
+    touch_start: Option<Instant>,
+    touch_id: Option<TouchId>,
+    gesture: Gesture,
+    offset: f64,
+    hover_position: Option<(f64, f64)>,
+    debug_touches: Option<Vec<(f64, f64)>>,
     frame_pending: bool,
+    buffer_reuse: bool,
+    buffer_cropped: bool,
     renderer: Renderer,
+    panel_config: config::Panel,
+    theme: Theme,
+    opacity: f32,
     scale_factor: i32,
     size: Size,
 }
 
 impl Drawer {
-    pub fn new(queue: QueueHandle<State>, egl_config: &Config) -> Result<Self> {
+    pub fn new(queue: QueueHandle<State>, egl_config: &Config, srgb: bool) -> Result<Self> {
         // Default to 1x1 initial size since 0x0 EGL surfaces are illegal.
         let size = Size { width: 1, height: 1 };
 
-        let context_attribules = ContextAttributesBuilder::new()
-            .with_context_api(ContextApi::Gles(Some(Version::new(2, 0))))
-            .build(None);
-
-        let egl_context =
-            unsafe { egl_config.display().create_context(egl_config, &context_attribules)? };
+        let context = renderer::create_context(egl_config, srgb)?;
 
         // Initialize the renderer.
-        let renderer = Renderer::new(egl_context, 1)?;
+        let renderer = Renderer::new(context, 1)?;
 
         Ok(Self {
             renderer,
             queue,
             size,
             scale_factor: 1,
+            theme: Default::default(),
+            panel_config: Default::default(),
+            opacity: 1.,
+            toggle_transitions: Default::default(),
+            collapsed_sections: Default::default(),
             frame_pending: Default::default(),
+            page_count: 1,
+            page: Default::default(),
             touch_position: Default::default(),
-            touch_module: Default::default(),
+            touch_start: Default::default(),
+            touch_element: Default::default(),
+            touch_pressed: Default::default(),
+            touch_origin: Default::default(),
+            slider_locked: Default::default(),
+            level_origin: Default::default(),
             touch_id: Default::default(),
+            gesture: Default::default(),
+            offset: Default::default(),
+            expanded_module: Default::default(),
+            hover_position: Default::default(),
+            debug_touches: Default::default(),
+            pin_lock: Default::default(),
+            viewport: Default::default(),
             window: Default::default(),
+            buffer_reuse: Default::default(),
+            buffer_cropped: Default::default(),
         })
     }
 
+    /// Require a PIN before showing the drawer modules.
+    pub fn set_pin(&mut self, pin: String) {
+        self.pin_lock = Some(PinLock { pin, input: String::new(), unlocked: false });
+    }
+
+    /// Check if the PIN must be entered before modules are shown.
+    pub fn pin_locked(&self) -> bool {
+        self.pin_lock.as_ref().map_or(false, |pin_lock| !pin_lock.unlocked)
+    }
+
     /// Create the window.
-    pub fn show(&mut self, compositor: &CompositorState, layer: &mut LayerShell) -> Result<()> {
+    pub fn show(
+        &mut self,
+        compositor: &CompositorState,
+        layer: &mut LayerShell,
+        viewporter: Option<&WpViewporter>,
+    ) -> Result<()> {
         // Ensure the window is not mapped yet.
         if self.window.is_some() {
             return Ok(());
@@ -101,15 +236,20 @@ impl Drawer {
 
         // Create the EGL surface.
         let config = self.renderer.egl_context().config();
-        let surface_attributes = SurfaceAttributesBuilder::<WindowSurface>::new().build(
-            raw_window_handle,
-            NonZeroU32::new(self.size.width as u32).unwrap(),
-            NonZeroU32::new(self.size.height as u32).unwrap(),
-        );
+        let surface_attributes = SurfaceAttributesBuilder::<WindowSurface>::new()
+            .with_srgb(Some(self.renderer.srgb()))
+            .build(
+                raw_window_handle,
+                NonZeroU32::new(self.size.width as u32).unwrap(),
+                NonZeroU32::new(self.size.height as u32).unwrap(),
+            );
 
         let egl_surface =
             unsafe { config.display().create_window_surface(&config, &surface_attributes)? };
 
+        self.viewport =
+            viewporter.map(|viewporter| viewporter.get_viewport(&surface, &self.queue, ()));
+
         // Create the window.
         self.window = Some(
             LayerSurface::builder()
@@ -127,30 +267,123 @@ impl Drawer {
 
     /// Destroy the window.
     pub fn hide(&mut self) {
+        if let Some(viewport) = self.viewport.take() {
+            viewport.destroy();
+        }
+        self.buffer_cropped = false;
+        self.buffer_reuse = false;
+
         self.renderer.set_surface(None);
         self.window = None;
+
+        self.expanded_module = None;
+        self.hover_position = None;
+
+        // Require the PIN again the next time the drawer is opened.
+        if let Some(pin_lock) = &mut self.pin_lock {
+            pin_lock.input.clear();
+            pin_lock.unlocked = false;
+        }
     }
 
     /// Render the panel.
+    ///
+    /// The `module_ids` identify the `modules` at the same index, to apply the
+    /// panel's module overrides to the drawer header.
     pub fn draw(
         &mut self,
         compositor: &CompositorState,
         modules: &mut [&mut dyn Module],
-        mut offset: f64,
+        module_ids: &[ModuleId],
     ) -> Result<()> {
-        offset = (offset * self.scale_factor as f64).min(self.size.height as f64);
+        let offset = (self.offset * self.scale_factor as f64).min(self.size.height as f64);
         self.frame_pending = false;
 
         // Update opaque region.
-        let region = Region::new(compositor).ok();
+        let region = Region::new(compositor).ok().filter(|_| self.opacity >= 1.);
         if let Some((window, region)) = self.window.as_ref().zip(region) {
             let logical_width = self.size.width / self.scale_factor;
             let logical_height = offset as i32 / self.scale_factor;
-            region.add(0, PANEL_HEIGHT, logical_width, logical_height);
+            region.add(0, 0, logical_width, logical_height);
             window.wl_surface().set_opaque_region(Some(region.wl_region()));
         }
 
-        self.renderer.draw(|renderer| unsafe {
+        // Crop the fully opened drawer to the offset, instead of rendering every frame.
+        //
+        // The source is rounded to fixed point precision, to stay within the buffer.
+        let logical_width = self.size.width / self.scale_factor;
+        let logical_height = (self.size.height / self.scale_factor) as f64;
+        let y = (logical_height - offset / self.scale_factor as f64) * 256.;
+        let y = y.ceil() / 256.;
+        let height = logical_height - y;
+        let viewport = self.viewport.as_ref().zip(self.window.as_ref());
+        match viewport.filter(|_| self.buffer_reuse && height > 0. && logical_width > 0) {
+            Some((viewport, window)) => {
+                viewport.set_source(0., y, logical_width as f64, height);
+                viewport.set_destination(logical_width, height.ceil() as i32);
+
+                // Render the full drawer once, then just update the viewport.
+                if self.buffer_cropped {
+                    window.wl_surface().commit();
+                    return Ok(());
+                }
+                self.buffer_cropped = true;
+                offset = self.size.height as f64;
+            },
+            // Show the entire buffer again after an animation.
+            None if mem::take(&mut self.buffer_cropped) => {
+                if let Some(viewport) = &self.viewport {
+                    viewport.set_source(-1., -1., -1., -1.);
+                    viewport.set_destination(-1, -1);
+                }
+            },
+            None => (),
+        }
+
+        self.render(modules, module_ids, offset)?;
+
+        // Keep rendering until all toggle transitions are completed.
+        if self.toggle_transitions.values().any(ToggleTransition::active) {
+            self.request_frame();
+        }
+
+        Ok(())
+    }
+
+    /// Render the drawer into an offscreen buffer.
+    pub fn snapshot(
+        &mut self,
+        size: Size,
+        scale_factor: i32,
+        modules: &mut [&mut dyn Module],
+        offset: f64,
+    ) -> Result<Pixmap> {
+        self.renderer.set_offscreen(size)?;
+        self.scale_factor = scale_factor;
+        self.resize(size);
+
+        let offset = (offset * scale_factor as f64).min(size.height as f64);
+        self.render(modules, &[], offset)?;
+
+        Ok(self.renderer.read_pixels()?)
+    }
+
+    /// Render the drawer content at the specified physical offset.
+    #[cfg_attr(feature = "profiling", tracing::instrument(skip_all))]
+    fn render(
+        &mut self,
+        modules: &mut [&mut dyn Module],
+        module_ids: &[ModuleId],
+        offset: f64,
+    ) -> Result<()> {
+        // Compute module layout.
+        let layout = self.layout(modules);
+
+        // Clamp active page, in case modules were removed.
+        self.page_count = layout.last().map_or(1, |item| item.page + 1);
+        self.page = self.page.min(self.page_count - 1);
+
+        self.renderer.draw::<Error, _>(|renderer| unsafe {
             // Transparently clear entire screen.
             gl::Disable(gl::SCISSOR_TEST);
             gl::Viewport(0, 0, self.size.width, self.size.height);
@@ -158,27 +391,138 @@ impl Drawer {
             gl::Clear(gl::COLOR_BUFFER_BIT);
 
             // Setup drawer to render at correct offset.
-            let drawer_height = self.size.height - PANEL_HEIGHT * renderer.scale_factor;
+            let panel_height = PANEL_HEIGHT * renderer.scale_factor;
             let y_offset = (self.size.height as f64 - offset) as i32;
+
+            // Dim everything below the drawer, based on how far it is opened.
+            let scrim_alpha = SCRIM_ALPHA * (offset / self.size.height as f64) as f32;
             gl::Enable(gl::SCISSOR_TEST);
-            gl::Scissor(0, y_offset, self.size.width, drawer_height);
+            gl::Scissor(0, 0, self.size.width, y_offset);
+            gl::ClearColor(0.0, 0.0, 0.0, scrim_alpha);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+
+            gl::Scissor(0, y_offset, self.size.width, self.size.height);
             gl::Viewport(0, y_offset, self.size.width, self.size.height);
 
             // Draw background for the offset viewport.
-            gl::ClearColor(0.1, 0.1, 0.1, 1.0);
+            let [r, g, b] = self.theme.background.as_f32();
+            let alpha = self.opacity;
+            gl::ClearColor(r * alpha, g * alpha, b * alpha, alpha);
             gl::Clear(gl::COLOR_BUFFER_BIT);
 
+            // Draw panel modules as drawer header, covering the panel once fully opened.
+            let header_modules: Vec<(Option<ModuleId>, &dyn Module)> = modules
+                .iter()
+                .enumerate()
+                .map(|(i, module)| (module_ids.get(i).copied(), &**module as &dyn Module))
+                .collect();
+            let header_size = Size::new(self.size.width as f32, panel_height as f32);
+            Panel::draw_modules(
+                renderer,
+                &header_modules,
+                header_size,
+                &self.panel_config,
+                &self.theme,
+            )?;
+
             // Draw module grid.
-            let mut run = DrawerRun::new(renderer);
-            for module in modules.iter_mut().filter_map(|module| module.drawer_module()) {
-                run.batch(module);
+            let fraction = offset / self.size.height as f64;
+            let mut run = DrawerRun::new(renderer, &self.theme)?;
+            for item in layout.iter().filter(|item| item.page == self.page) {
+                let vertex_start = run.vertex_counts();
+
+                match item.element {
+                    LayoutElement::Header(section) => {
+                        let collapsed = self.collapsed_sections.contains(&section);
+                        run.batch_header(item, section, collapsed);
+                    },
+                    LayoutElement::Module(index) => match modules[index].drawer_module() {
+                        Some(DrawerModule::Toggle(toggle)) => {
+                            let toggle: &dyn Toggle = toggle;
+                            let transitions = &mut self.toggle_transitions;
+                            let transition = transitions
+                                .entry(index)
+                                .and_modify(|transition| transition.update(toggle))
+                                .or_insert_with(|| ToggleTransition::new(toggle));
+                            let _ = run.batch_toggle(item, toggle, transition);
+                        },
+                        Some(module) => run.batch(item, module),
+                        None => (),
+                    },
+                    LayoutElement::PinEntry => {
+                        let input = self.pin_lock.as_ref().map_or(0, |lock| lock.input.len());
+                        let label = format!("PIN: {}", "*".repeat(input));
+                        run.batch_text(item, &label, false);
+                    },
+                    LayoutElement::ListEntry(index, entry) => {
+                        if let Some(DrawerModule::List(list)) = modules[index].drawer_module() {
+                            run.batch_list_entry(item, list, entry);
+                        }
+                    },
+                    LayoutElement::QrCode(index) => {
+                        if let Some(DrawerModule::Toggle(toggle)) = modules[index].drawer_module() {
+                            if let Some(text) = toggle.qr_code() {
+                                run.batch_qr_code(item, &text);
+                            }
+                        }
+                    },
+                    LayoutElement::Key(key) => run.batch_key(item, key),
+                }
+
+                run.reveal(item, vertex_start, fraction);
+            }
+
+            // Show feedback for the pressed element, sliders show their value instead.
+            let pressed = self.touch_element.filter(|_| self.touch_pressed).and_then(|element| {
+                if let LayoutElement::Module(index) = element {
+                    if let Some(DrawerModule::Slider(_)) = modules[index].drawer_module() {
+                        return None;
+                    }
+                }
+                layout.iter().find(|item| item.page == self.page && item.element == element)
+            });
+            if let Some(item) = pressed {
+                run.batch_highlight(item, &PRESSED_COLOR);
             }
-            run.draw();
+
+            // Highlight the element below a hovering stylus.
+            let hovered = self.hover_position.and_then(|(x, y)| {
+                let (x, y) = (x as i16, y as i16);
+                layout.iter().find(|item| item.page == self.page && item.contains(x, y))
+            });
+            if let Some(item) = hovered {
+                run.batch_highlight(item, &HOVER_COLOR);
+            }
+
+            // Visualize hit regions and touch points for input debugging.
+            if let Some(touches) = &self.debug_touches {
+                for item in layout.iter().filter(|item| item.page == self.page) {
+                    run.batch_highlight(item, &DEBUG_REGION_COLOR);
+                }
+                for (x, y) in touches {
+                    run.batch_touch_point(scale_touch((*x, *y), self.scale_factor));
+                }
+            }
+
+            run.batch_page_indicator(self.page, self.page_count);
+
+            // Reuse the last rendering while only the drawer offset changes.
+            renderer.flush_cached(self.size)?;
 
             Ok(())
         })
     }
 
+    /// Free GPU memory until the drawer is shown again.
+    pub fn release_resources(&mut self) -> Result<()> {
+        Ok(self.renderer.release_resources()?)
+    }
+
+    /// Performance statistics of the last frame.
+    pub fn frame_stats(&self) -> FrameStats {
+        self.renderer.stats()
+    }
+
     /// Check if the panel owns this surface.
     pub fn owns_surface(&self, surface: &WlSurface) -> bool {
         self.window.as_ref().map_or(false, |window| window.wl_surface() == surface)
@@ -208,6 +552,13 @@ impl Drawer {
         self.resize(size);
     }
 
+    /// Forget about pending frame callbacks.
+    ///
+    /// This allows requesting new frames when a callback might have been lost.
+    pub fn discard_frame(&mut self) {
+        self.frame_pending = false;
+    }
+
     /// Request a new frame.
     pub fn request_frame(&mut self) {
         // Ensure window is mapped without pending frame.
@@ -225,83 +576,540 @@ impl Drawer {
     /// Handle touch press events.
     pub fn touch_down(
         &mut self,
-        id: i32,
+        id: TouchId,
         position: (f64, f64),
         modules: &mut [&mut dyn Module],
     ) -> TouchStart {
         self.touch_position = scale_touch(position, self.scale_factor);
+        self.touch_start = Some(Instant::now());
         self.touch_id = Some(id);
 
-        // Find touched module.
-        let positioner = ModulePositioner::new(self.size.into(), self.scale_factor as i16);
-        let (index, x) = match positioner.module_position(modules, self.touch_position) {
-            Some((index, x, _)) => (index, x),
-            None => return TouchStart { requires_redraw: false, module_touched: false },
-        };
-        self.touch_module = Some(index);
+        // Find touched element.
+        let layout = self.layout(modules);
+        let element = match element_at(&layout, self.page, self.touch_position) {
+            Some((LayoutElement::PinEntry, ..)) | None => {
+                // Move the drawer instead, closing it when the scrim below is tapped.
+                self.start_gesture(id, position, false);
+                self.gesture.scrim_tap = position.1 >= self.offset;
 
-        // Update sliders.
-        let requires_redraw = match modules[index].drawer_module() {
-            Some(DrawerModule::Slider(slider)) => {
-                let _ = slider.set_value(x);
-                true
+                return TouchStart { requires_redraw: false, module_touched: false };
             },
-            _ => false,
+            Some((element, ..)) => element,
         };
+        self.touch_element = Some(element);
+        self.touch_origin = position;
+        self.touch_pressed = true;
+
+        // Let lists remember the pressed entry, in case it changes before a long-press.
+        if let LayoutElement::ListEntry(index, entry) = element {
+            if let Some(DrawerModule::List(list)) = modules[index].drawer_module() {
+                list.press(entry);
+            }
+        }
 
-        TouchStart { requires_redraw, module_touched: true }
+        TouchStart { requires_redraw: true, module_touched: true }
     }
 
     /// Handle touch motion events.
     pub fn touch_motion(
         &mut self,
-        id: i32,
+        id: TouchId,
         position: (f64, f64),
         modules: &mut [&mut dyn Module],
-    ) -> bool {
+    ) -> TouchMotion {
+        if self.gesture.touch == Some(id) {
+            return self.gesture_motion(position);
+        }
+
+        let mut touch_motion = TouchMotion::default();
         if Some(id) != self.touch_id {
-            return false;
+            return touch_motion;
         }
         self.touch_position = scale_touch(position, self.scale_factor);
 
+        // Only show press feedback while the touch is within the touched element.
+        let layout = self.layout(modules);
+        let element = element_at(&layout, self.page, self.touch_position);
+        let element = element.map(|(element, ..)| element);
+        let pressed = element.is_some() && element == self.touch_element;
+        touch_motion.requires_redraw = mem::replace(&mut self.touch_pressed, pressed) != pressed;
+
+        let module = match self.touch_element {
+            Some(LayoutElement::Module(index)) => modules[index].drawer_module(),
+            _ => None,
+        };
+        let slider = match module {
+            Some(DrawerModule::Slider(slider)) => slider,
+            Some(DrawerModule::Toggle(toggle)) => {
+                self.adjust_level(toggle, position, &mut touch_motion);
+                return touch_motion;
+            },
+            _ => return touch_motion,
+        };
+
+        // Lock slider gestures to an axis once the touch moved too far for a tap.
+        if !self.slider_locked {
+            let delta_x = position.0 - self.touch_origin.0;
+            let delta_y = position.1 - self.touch_origin.1;
+            if delta_x.abs().max(delta_y.abs()) <= TAP_SLOP {
+                return touch_motion;
+            }
+
+            // Hand vertical drags over to the drawer.
+            if delta_y.abs() > delta_x.abs() {
+                let origin = self.touch_origin;
+                self.reset_touch();
+
+                self.start_gesture(id, origin, false);
+                self.gesture.kind = DrawerGesture::Vertical;
+
+                let mut touch_motion = self.gesture_motion(position);
+                touch_motion.requires_redraw = true;
+                touch_motion.drawer_drag = true;
+                return touch_motion;
+            }
+
+            self.slider_locked = true;
+        }
+
         // Update slider position.
         let positioner = ModulePositioner::new(self.size.into(), self.scale_factor as i16);
-        match self.touch_module.and_then(|module| modules[module].drawer_module()) {
-            Some(DrawerModule::Slider(slider)) => {
-                let relative_x = self.touch_position.0 - positioner.edge_padding as f64;
-                let fractional_x = relative_x / positioner.slider_size.width as f64;
+        let relative_x = self.touch_position.0 - positioner.edge_padding as f64;
+        let fractional_x = relative_x / positioner.slider_size.width as f64;
+
+        let _ = slider.set_value(fractional_x);
 
-                let _ = slider.set_value(fractional_x);
+        touch_motion.requires_redraw = true;
 
-                true
+        touch_motion
+    }
+
+    /// Adjust a toggle's level with vertical drags.
+    fn adjust_level(
+        &mut self,
+        toggle: &mut dyn Toggle,
+        position: (f64, f64),
+        touch_motion: &mut TouchMotion,
+    ) {
+        let delta_x = position.0 - self.touch_origin.0;
+        let delta_y = position.1 - self.touch_origin.1;
+
+        match (self.level_origin, toggle.level()) {
+            // Dragging upwards increases the level.
+            (Some(origin), _) => {
+                let level = (origin - delta_y / LEVEL_DRAG_DISTANCE).clamp(0., 1.);
+                let _ = toggle.set_level(level);
+                touch_motion.requires_redraw = true;
+            },
+            // Start adjustment once the touch moved too far vertically for a tap.
+            (None, Some(level)) if delta_y.abs() > TAP_SLOP && delta_y.abs() > delta_x.abs() => {
+                self.level_origin = Some(level);
+                self.touch_origin = position;
             },
-            _ => false,
+            _ => (),
         }
     }
 
     /// Handle touch release events.
-    pub fn touch_up(&mut self, id: i32, modules: &mut [&mut dyn Module]) -> bool {
+    pub fn touch_up(&mut self, id: TouchId, modules: &mut [&mut dyn Module]) -> TouchEnd {
+        if self.gesture.touch == Some(id) {
+            return self.gesture_end();
+        }
+
+        let mut touch_end = TouchEnd::default();
         if Some(id) != self.touch_id {
+            return touch_end;
+        }
+
+        let long_press =
+            self.touch_start.take().map_or(false, |start| start.elapsed() >= LONG_PRESS_DURATION);
+
+        let layout = self.layout(modules);
+        let released = element_at(&layout, self.page, self.touch_position);
+        let release_x = released.map_or(0., |(_, x, _)| x);
+        let released = released.map(|(element, ..)| element);
+
+        // Clear press feedback.
+        touch_end.requires_redraw = self.touch_pressed;
+
+        match self.touch_element.zip(released) {
+            // Move toggle buttons after long-press drag.
+            Some((LayoutElement::Module(start), LayoutElement::Module(end)))
+                if long_press && self.level_origin.is_none() =>
+            {
+                let is_toggle =
+                    matches!(modules[start].drawer_module(), Some(DrawerModule::Toggle(_)));
+                if is_toggle && start != end {
+                    touch_end.reorder = Some((start, end));
+                } else if start == end {
+                    // Show or hide the toggle's QR code.
+                    let qr_code = match modules[start].drawer_module() {
+                        Some(DrawerModule::Toggle(toggle)) => toggle.qr_code(),
+                        _ => None,
+                    };
+                    let expanded = self.expanded_module == Some(start);
+                    if qr_code.is_some() || expanded {
+                        self.expanded_module = (!expanded).then_some(start);
+                        touch_end.requires_redraw = true;
+                    }
+                }
+            },
+            // Ignore releases outside of the touched element.
+            Some((start, end)) if start != end => (),
+            // Trigger destructive list actions on long-press, keeping the list expanded.
+            Some((LayoutElement::ListEntry(index, _), _)) if long_press => {
+                if let Some(DrawerModule::List(list)) = modules[index].drawer_module() {
+                    if let Err(err) = list.long_press() {
+                        eprintln!("Error: List long-press action failed: {err}");
+                    }
+                }
+                touch_end.requires_redraw = true;
+            },
+            // Handle PIN pad presses.
+            Some((LayoutElement::Key(key), _)) => {
+                self.press_key(key);
+                touch_end.requires_redraw = true;
+            },
+            // Expand or collapse sections on header tap.
+            Some((LayoutElement::Header(section), _)) => {
+                self.toggle_section(section);
+                touch_end.requires_redraw = true;
+            },
+            // Select list entries and collapse their list.
+            Some((LayoutElement::ListEntry(index, entry), _)) => {
+                if let Some(DrawerModule::List(list)) = modules[index].drawer_module() {
+                    let _ = list.select(entry);
+                }
+                self.expanded_module = None;
+                touch_end.requires_redraw = true;
+            },
+            // Hide QR codes on tap.
+            Some((LayoutElement::QrCode(_), _)) => {
+                self.expanded_module = None;
+                touch_end.requires_redraw = true;
+            },
+            // Handle button toggles on touch up.
+            Some((LayoutElement::Module(index), _)) => match modules[index].drawer_module() {
+                // Don't toggle after adjusting the level.
+                Some(DrawerModule::Toggle(toggle)) if self.level_origin.is_none() => {
+                    let _ = toggle.toggle();
+                    touch_end.dismiss = toggle.dismisses_drawer();
+                    touch_end.requires_redraw = true;
+                },
+                // Expand or collapse lists on tap.
+                Some(DrawerModule::List(_)) => {
+                    let expanded = self.expanded_module == Some(index);
+                    self.expanded_module = (!expanded).then_some(index);
+                    touch_end.requires_redraw = true;
+                },
+                // Jump to the tapped slider position.
+                Some(DrawerModule::Slider(slider)) if !self.slider_locked => {
+                    let _ = slider.set_value(release_x);
+                    touch_end.requires_redraw = true;
+                },
+                _ => (),
+            },
+            Some((LayoutElement::PinEntry, _)) | None => (),
+        }
+
+        self.reset_touch();
+
+        touch_end
+    }
+
+    /// Abort touch interactions of a touch device.
+    ///
+    /// Unlike a touch release, this never triggers the touched element.
+    ///
+    /// Returns `true` if the drawer needs to be redrawn.
+    pub fn touch_cancel(&mut self, touch: &WlTouch) -> bool {
+        if !self.touch_id.map_or(false, |id| id.is_from(touch)) {
             return false;
         }
 
-        // Handle button toggles on touch up.
-        let mut dirty = false;
-        let positioner = ModulePositioner::new(self.size.into(), self.scale_factor as i16);
-        if let Some(DrawerModule::Toggle(toggle)) = positioner
-            .module_position(modules, self.touch_position)
-            .filter(|(index, ..)| Some(*index) == self.touch_module)
-            .and_then(|(index, ..)| modules[index].drawer_module())
-        {
-            let _ = toggle.toggle();
-            dirty = true;
+        let pressed = self.touch_pressed;
+        self.reset_touch();
+        pressed
+    }
+
+    /// Abort the drawer gesture of a touch device.
+    ///
+    /// Returns `true` if a gesture was cancelled.
+    pub fn cancel_gesture(&mut self, touch: &WlTouch) -> bool {
+        if !self.gesture.touch.map_or(false, |id| id.is_from(touch)) {
+            return false;
+        }
+
+        self.gesture.touch = None;
+        self.gesture.kind = DrawerGesture::Pending;
+        self.gesture.scrim_tap = false;
+        self.gesture.panel_slider = None;
+
+        true
+    }
+
+    /// Start moving the drawer with a touch on the panel.
+    ///
+    /// The `link` is followed if the panel is tapped, while `slider` allows
+    /// adjusting a module's value with vertical drags.
+    pub fn start_panel_gesture(
+        &mut self,
+        id: TouchId,
+        position: (f64, f64),
+        link: Option<DrawerLink>,
+        slider: Option<(ModuleId, f64)>,
+    ) {
+        self.start_gesture(id, position, true);
+        self.gesture.panel_link = link;
+        self.gesture.panel_slider = slider;
+
+        // Wait for the drag direction on modules which can be adjusted from the panel.
+        if slider.is_none() {
+            self.gesture.kind = DrawerGesture::Vertical;
+        }
+    }
+
+    /// Start moving the drawer with a touch.
+    fn start_gesture(&mut self, id: TouchId, position: (f64, f64), opening: bool) {
+        self.gesture =
+            Gesture { touch: Some(id), origin: position, position, opening, ..Default::default() };
+    }
+
+    /// Handle motion of the touch moving the drawer.
+    fn gesture_motion(&mut self, position: (f64, f64)) -> TouchMotion {
+        let mut touch_motion = TouchMotion::default();
+        let gesture = &mut self.gesture;
+        let mut last_y = mem::replace(&mut gesture.position, position).1;
+
+        // Lock gesture axis once the touch moved too far for a tap.
+        if gesture.kind == DrawerGesture::Pending {
+            let delta_x = position.0 - gesture.origin.0;
+            let delta_y = position.1 - gesture.origin.1;
+            if delta_x.abs().max(delta_y.abs()) > TAP_SLOP {
+                let horizontal = delta_x.abs() > delta_y.abs();
+                gesture.kind = match gesture.panel_slider {
+                    // Only vertical drags adjust panel sliders.
+                    Some(_) if horizontal => DrawerGesture::Vertical,
+                    Some(_) => DrawerGesture::Slider,
+                    None if horizontal => DrawerGesture::Horizontal,
+                    None => DrawerGesture::Vertical,
+                };
+                gesture.scrim_tap = false;
+
+                // Include movement from before the gesture was locked.
+                last_y = gesture.origin.1;
+            }
+        }
+
+        match gesture.kind {
+            // Dragging downwards increases the panel slider's value.
+            DrawerGesture::Slider => {
+                if let Some((id, origin)) = gesture.panel_slider {
+                    let delta = (position.1 - gesture.origin.1) / PANEL_SLIDER_DISTANCE;
+                    touch_motion.panel_slider = Some((id, (origin + delta).clamp(0., 1.)));
+                }
+            },
+            DrawerGesture::Vertical => {
+                let max_offset = self.max_offset();
+                self.offset = (self.offset + position.1 - last_y).clamp(0., max_offset);
+                self.request_frame();
+            },
+            // Horizontal swipes are only handled on release.
+            DrawerGesture::Horizontal | DrawerGesture::Pending => (),
         }
 
-        // Reset touch state.
-        self.touch_module = None;
+        touch_motion
+    }
+
+    /// Handle release of the touch moving the drawer.
+    fn gesture_end(&mut self) -> TouchEnd {
+        let mut touch_end = TouchEnd::default();
+        self.gesture.touch = None;
+
+        // Switch drawer pages after horizontal swipes.
+        if self.gesture.kind == DrawerGesture::Horizontal {
+            let distance = self.gesture.position.0 - self.gesture.origin.0;
+            if distance.abs() >= PAGE_SWIPE_DISTANCE {
+                let delta = if distance < 0. { 1 } else { -1 };
+                if self.change_page(delta) {
+                    self.request_frame();
+                }
+            }
+
+            // Settle the drawer if the swipe interrupted its animation.
+            touch_end.settle = self.offset > 0. && self.offset < self.max_offset();
+
+            return touch_end;
+        }
+
+        // Close the drawer when the scrim was tapped.
+        touch_end.dismiss = mem::take(&mut self.gesture.scrim_tap);
+        touch_end.slider_released = self.gesture.panel_slider.take().is_some();
+
+        let delta_x = self.gesture.position.0 - self.gesture.origin.0;
+        let delta_y = self.gesture.position.1 - self.gesture.origin.1;
+        touch_end.panel_tap = self.gesture.opening && delta_x.abs().max(delta_y.abs()) <= TAP_SLOP;
+        touch_end.link = self.gesture.panel_link.take().filter(|_| touch_end.panel_tap);
+        touch_end.settle = true;
+
+        touch_end
+    }
+
+    /// Check if a touch is currently moving the drawer.
+    pub fn has_gesture(&self) -> bool {
+        self.gesture.touch.is_some()
+    }
+
+    /// Axis of the active drawer gesture.
+    pub fn gesture(&self) -> Option<DrawerGesture> {
+        self.gesture.touch.map(|_| self.gesture.kind)
+    }
+
+    /// Check if the last drawer gesture started on the panel.
+    pub fn opening(&self) -> bool {
+        self.gesture.opening
+    }
+
+    /// Visible height of the drawer.
+    pub fn offset(&self) -> f64 {
+        self.offset
+    }
+
+    /// Update the visible height of the drawer.
+    pub fn set_offset(&mut self, offset: f64) {
+        self.offset = offset;
+    }
+
+    /// Forget about the active touch interaction.
+    fn reset_touch(&mut self) {
+        self.touch_element = None;
+        self.touch_pressed = false;
+        self.slider_locked = false;
+        self.level_origin = None;
+        self.touch_start = None;
         self.touch_id = None;
+    }
 
-        dirty
+    /// Update the position of a hovering stylus.
+    ///
+    /// Returns `true` if the drawer needs to be redrawn.
+    pub fn set_hover(&mut self, position: Option<(f64, f64)>) -> bool {
+        let position = position.map(|position| scale_touch(position, self.scale_factor));
+        let changed = self.hover_position != position;
+        self.hover_position = position;
+        changed
+    }
+
+    /// Reuse a single rendering while the drawer is animated.
+    ///
+    /// This has no effect if the compositor doesn't support `wp_viewporter`.
+    pub fn set_buffer_reuse(&mut self, enabled: bool) {
+        self.buffer_reuse = enabled;
+    }
+
+    /// Update the panel configuration used for the drawer header.
+    pub fn set_panel_config(&mut self, config: config::Panel) {
+        self.panel_config = config;
+    }
+
+    /// Update the opacity of the drawer background.
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.opacity = opacity.clamp(0., 1.);
+    }
+
+    /// Switch to a different theme.
+    pub fn set_theme(&mut self, theme: Theme) -> Result<()> {
+        self.renderer.set_theme(&theme)?;
+        self.theme = theme;
+        Ok(())
+    }
+
+    /// Enable the input debug overlay, showing the specified touch points.
+    ///
+    /// Passing `None` disables the overlay.
+    pub fn set_debug_touches(&mut self, touches: Option<Vec<(f64, f64)>>) {
+        self.debug_touches = touches;
+    }
+
+    /// Switch to a different drawer page.
+    ///
+    /// Returns `true` if the active page was changed.
+    pub fn change_page(&mut self, delta: isize) -> bool {
+        let max_page = self.page_count as isize - 1;
+        let page = (self.page as isize + delta).clamp(0, max_page) as usize;
+
+        let changed = page != self.page;
+        self.page = page;
+        changed
+    }
+
+    /// Switch to the page containing a drawer section.
+    ///
+    /// Collapsed sections are expanded, to reveal their modules.
+    pub fn show_section(&mut self, modules: &mut [&mut dyn Module], section: Section) {
+        self.collapsed_sections.retain(|collapsed| collapsed != &section);
+        self.show_element(modules, LayoutElement::Header(section));
+    }
+
+    /// Switch to the page containing a module.
+    pub fn show_module(&mut self, modules: &mut [&mut dyn Module], index: usize) {
+        let section = match modules.get(index) {
+            Some(module) => module.section(),
+            None => return,
+        };
+
+        self.collapsed_sections.retain(|collapsed| collapsed != &section);
+        self.show_element(modules, LayoutElement::Module(index));
+    }
+
+    /// Switch to the page containing a layout element.
+    fn show_element(&mut self, modules: &mut [&mut dyn Module], element: LayoutElement) {
+        let layout = self.layout(modules);
+        if let Some(item) = layout.iter().find(|item| item.element == element) {
+            self.page_count = layout.last().map_or(1, |item| item.page + 1);
+            self.page = item.page;
+        }
+    }
+
+    /// Handle a PIN pad key press.
+    fn press_key(&mut self, key: char) {
+        let pin_lock = match &mut self.pin_lock {
+            Some(pin_lock) => pin_lock,
+            None => return,
+        };
+
+        match key {
+            'C' => pin_lock.input.clear(),
+            '<' => {
+                pin_lock.input.pop();
+            },
+            digit => pin_lock.input.push(digit),
+        }
+
+        // Check the PIN once enough digits were entered.
+        if pin_lock.input.len() >= pin_lock.pin.len() {
+            pin_lock.unlocked = pin_lock.input == pin_lock.pin;
+            pin_lock.input.clear();
+        }
+    }
+
+    /// Compute the position of all drawer elements.
+    fn layout(&self, modules: &mut [&mut dyn Module]) -> Vec<LayoutItem> {
+        let positioner = ModulePositioner::new(self.size.into(), self.scale_factor as i16);
+        match &self.pin_lock {
+            Some(pin_lock) if !pin_lock.unlocked => positioner.pin_layout(),
+            _ => positioner.layout(modules, &self.collapsed_sections, self.expanded_module),
+        }
+    }
+
+    /// Expand or collapse a drawer section.
+    fn toggle_section(&mut self, section: Section) {
+        match self.collapsed_sections.iter().position(|collapsed| collapsed == &section) {
+            Some(index) => {
+                self.collapsed_sections.swap_remove(index);
+            },
+            None => self.collapsed_sections.push(section),
+        }
     }
 
     /// Drawer offset when fully visible.
@@ -322,66 +1130,207 @@ impl Drawer {
 #[derive(Copy, Clone)]
 pub struct TouchStart {
     pub requires_redraw: bool,
+    /// Touch started on a module, instead of moving the drawer.
     pub module_touched: bool,
 }
 
+/// Drawer touch motion status.
+#[derive(Copy, Clone, Default)]
+pub struct TouchMotion {
+    pub requires_redraw: bool,
+    /// Module touch was handed over to a vertical drawer drag.
+    pub drawer_drag: bool,
+    /// New value of the panel module slider adjusted by the touch.
+    pub panel_slider: Option<(ModuleId, f64)>,
+}
+
+/// Drawer touch end status.
+#[derive(Copy, Clone, Default)]
+pub struct TouchEnd {
+    pub requires_redraw: bool,
+    /// Module index moved to a new position.
+    pub reorder: Option<(usize, usize)>,
+    /// Drawer should be closed.
+    pub dismiss: bool,
+    /// Drawer should be animated towards its resting position.
+    pub settle: bool,
+    /// Panel was tapped without moving the touch.
+    pub panel_tap: bool,
+    /// Drawer link of the tapped panel module.
+    pub link: Option<DrawerLink>,
+    /// Panel module slider adjustment has ended.
+    pub slider_released: bool,
+}
+
+/// Axis of an active drawer touch gesture.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub enum DrawerGesture {
+    /// Touch has not moved far enough to determine its direction.
+    #[default]
+    Pending,
+    /// Drawer opening or closing drag.
+    Vertical,
+    /// Drawer page swipe.
+    Horizontal,
+    /// Panel module slider adjustment.
+    Slider,
+}
+
+/// Touch moving the drawer, started on the panel or the drawer itself.
+#[derive(Default)]
+struct Gesture {
+    touch: Option<TouchId>,
+    kind: DrawerGesture,
+    origin: (f64, f64),
+    position: (f64, f64),
+    opening: bool,
+    scrim_tap: bool,
+    panel_link: Option<DrawerLink>,
+    panel_slider: Option<(ModuleId, f64)>,
+}
+
 /// Batched drawer module rendering.
 struct DrawerRun<'a> {
     text_batcher: &'a mut VertexBatcher<TextRenderer>,
     rect_batcher: &'a mut VertexBatcher<RectRenderer>,
+    line_batcher: &'a mut VertexBatcher<LineRenderer>,
     rasterizer: &'a mut GlRasterizer,
-    positioner: ModulePositioner,
-    column: i16,
-    row: i16,
+    active_color: [u8; 4],
+    inactive_color: [u8; 4],
+    scale_factor: i16,
+    metrics: Metrics,
+    size: Size<i16>,
 }
 
 impl<'a> DrawerRun<'a> {
-    fn new(renderer: &'a mut Renderer) -> Self {
-        Self {
-            positioner: ModulePositioner::new(renderer.size, renderer.scale_factor as i16),
+    fn new(renderer: &'a mut Renderer, theme: &Theme) -> Result<Self> {
+        Ok(Self {
+            active_color: theme.module_active.rgba(),
+            inactive_color: theme.module_inactive.rgba(),
+            size: Size::new(renderer.size.width as i16, renderer.size.height as i16),
+            scale_factor: renderer.scale_factor as i16,
+            metrics: renderer.rasterizer.metrics()?,
             rasterizer: &mut renderer.rasterizer,
             text_batcher: &mut renderer.text_batcher,
             rect_batcher: &mut renderer.rect_batcher,
-            column: 0,
-            row: 0,
+            line_batcher: &mut renderer.line_batcher,
+        })
+    }
+
+    /// Number of vertices batched so far, for the text, rect, and line batchers.
+    fn vertex_counts(&mut self) -> [usize; 3] {
+        [
+            self.text_batcher.pending().len(),
+            self.rect_batcher.pending().len(),
+            self.line_batcher.pending().len(),
+        ]
+    }
+
+    /// Fade and shift an element's vertices based on how far the drawer is opened.
+    ///
+    /// Elements closer to the bottom of the drawer are revealed first, since
+    /// they become visible first while the drawer slides in.
+    fn reveal(&mut self, item: &LayoutItem, vertex_start: [usize; 3], fraction: f64) {
+        let position = ((item.y + item.height) as f64 / self.size.height as f64).clamp(0., 1.);
+        let start = (1. - REVEAL_DURATION) * (1. - position);
+        let progress = ((fraction - start) / REVEAL_DURATION).clamp(0., 1.);
+        if progress >= 1. {
+            return;
+        }
+
+        let opacity = progress as f32;
+        let shift = (1. - progress) * REVEAL_DISTANCE * self.scale_factor as f64;
+
+        let [text_start, rect_start, line_start] = vertex_start;
+        for vertex in &mut self.text_batcher.pending()[text_start..] {
+            vertex.y -= shift as i16;
+            vertex.fade(opacity);
+        }
+
+        // Rectangle vertices use normalized device coordinates, with Y pointing up.
+        let ndc_shift = (2. * shift / self.size.height as f64) as f32;
+        let rect_vertices = self.rect_batcher.pending()[rect_start..].iter_mut();
+        for vertex in rect_vertices.chain(&mut self.line_batcher.pending()[line_start..]) {
+            vertex.y += ndc_shift;
+            vertex.fade(opacity);
         }
     }
 
     /// Add a drawer module to the run.
-    fn batch(&mut self, module: DrawerModule) {
+    fn batch(&mut self, item: &LayoutItem, module: DrawerModule) {
         let _ = match module {
-            DrawerModule::Toggle(toggle) => self.batch_toggle(toggle),
-            DrawerModule::Slider(slider) => self.batch_slider(slider),
+            DrawerModule::Toggle(toggle) => {
+                self.batch_toggle(item, toggle, &ToggleTransition::new(toggle))
+            },
+            DrawerModule::Slider(slider) => self.batch_slider(item, slider),
+            DrawerModule::List(list) => self.batch_list(item, list),
+            DrawerModule::Graph(graph) => self.batch_graph(item, graph),
         };
     }
 
-    /// Add a slider to the drawer.
-    fn batch_slider(&mut self, slider: &dyn Slider) -> Result<()> {
-        let window_width = self.positioner.size.width;
-        let window_height = self.positioner.size.height;
+    /// Add a section header to the drawer.
+    fn batch_header(&mut self, item: &LayoutItem, section: Section, collapsed: bool) {
+        let indicator = if collapsed { '+' } else { '-' };
+        let label = format!("{indicator} {}", section.label());
+        self.batch_text(item, &label, false);
+    }
 
-        let width = self.positioner.slider_size.width;
-        let height = self.positioner.slider_size.height;
+    /// Add a PIN pad key to the drawer.
+    fn batch_key(&mut self, item: &LayoutItem, key: char) {
+        let (window_width, window_height) = (self.size.width, self.size.height);
 
-        // Rasterize slider icon.
-        let icon = self.rasterizer.rasterize_svg(slider.svg(), ICON_HEIGHT, None)?;
+        // Batch key backdrop.
+        let (x, y, width, height) = (item.x, item.y, item.width, item.height);
+        let backdrop =
+            RectVertex::new(window_width, window_height, x, y, width, height, &self.inactive_color);
+        for vertex in backdrop {
+            self.rect_batcher.push(0, vertex);
+        }
+
+        self.batch_text(item, &key.to_string(), true);
+    }
+
+    /// Add a single line of text, vertically centered within an element.
+    fn batch_text(&mut self, item: &LayoutItem, text: &str, centered: bool) {
+        // Calculate Y to center text.
+        let line_height = self.metrics.line_height;
+        let y = item.y as f64 + (item.height as f64 - line_height) / 2.;
+        let baseline = (y + line_height + self.metrics.descent as f64) as i16;
+
+        let glyphs: Vec<_> = self.rasterizer.rasterize_string(text).collect();
 
-        // Ensure we're in an empty row.
-        if self.column != 0 {
-            self.column = 0;
-            self.row += 1;
+        // Calculate X to center text horizontally.
+        let mut x = item.x;
+        if centered {
+            let width: i16 = glyphs.iter().map(|glyph| glyph.advance.0 as i16).sum();
+            x += (item.width - width) / 2;
         }
 
-        // Calculate origin point.
-        let (x, mut y) = self.positioner.position(self.column, self.row);
-        y += (self.positioner.module_size - self.positioner.slider_size.height) / 2;
+        // Batch vertices for all glyphs.
+        for glyph in glyphs {
+            for vertex in glyph.vertices(x, baseline).into_iter().flatten() {
+                self.text_batcher.push(glyph.texture_id, vertex);
+            }
 
-        // Update active row.
-        self.row += 1;
+            x += glyph.advance.0 as i16;
+        }
+    }
+
+    /// Add a slider to the drawer.
+    fn batch_slider(&mut self, item: &LayoutItem, slider: &dyn Slider) -> Result<()> {
+        let (window_width, window_height) = (self.size.width, self.size.height);
+
+        // Rasterize slider icon.
+        let icon = self.rasterizer.rasterize_svg(slider.svg(), ICON_HEIGHT, None)?;
+
+        // Center slider vertically within its cell.
+        let height = SLIDER_HEIGHT * item.scale_factor;
+        let (x, y) = (item.x, item.y + (item.height - height) / 2);
+        let width = item.width;
 
         // Stage tray vertices.
         let tray =
-            RectVertex::new(window_width, window_height, x, y, width, height, &MODULE_COLOR_BG);
+            RectVertex::new(window_width, window_height, x, y, width, height, &self.inactive_color);
         for vertex in tray {
             self.rect_batcher.push(0, vertex);
         }
@@ -395,15 +1344,15 @@ impl<'a> DrawerRun<'a> {
             y,
             slider_width,
             height,
-            &MODULE_COLOR_FG,
+            &self.active_color,
         );
         for vertex in slider {
             self.rect_batcher.push(0, vertex);
         }
 
         // Calculate icon origin.
-        let icon_x = x + (self.positioner.slider_size.width - icon.width) / 2;
-        let icon_y = y + (self.positioner.slider_size.height - icon.height) / 2;
+        let icon_x = x + (width - icon.width) / 2;
+        let icon_y = y + (height - icon.height) / 2;
 
         for vertex in icon.vertices(icon_x, icon_y).into_iter().flatten() {
             self.text_batcher.push(icon.texture_id, vertex);
@@ -413,65 +1362,402 @@ impl<'a> DrawerRun<'a> {
     }
 
     /// Add a toggle button to the drawer.
-    fn batch_toggle(&mut self, toggle: &dyn Toggle) -> Result<()> {
-        let window_width = self.positioner.size.width;
-        let window_height = self.positioner.size.height;
-
-        let size = self.positioner.module_size;
+    fn batch_toggle(
+        &mut self,
+        item: &LayoutItem,
+        toggle: &dyn Toggle,
+        transition: &ToggleTransition,
+    ) -> Result<()> {
+        let (window_width, window_height) = (self.size.width, self.size.height);
+        let (x, y, size) = (item.x, item.y, item.width);
+        let progress = transition.progress();
+
+        // Get icon style for the current and previous state.
+        let enabled = toggle.enabled();
+        let style = self.rasterizer.icon_style(toggle.svg(), enabled);
+        let previous_enabled = enabled != transition.enabled_changed;
+        let previous_style = self.rasterizer.icon_style(transition.previous_svg, previous_enabled);
+        let icon_changed = transition.previous_svg != toggle.svg() || previous_style != style;
+
+        let svg = self.rasterizer.rasterize_styled_svg(toggle.svg(), style, None, ICON_HEIGHT)?;
+        let caption = toggle.caption();
+
+        // Calculate icon origin point, leaving room for the caption.
+        let caption_height = if caption.is_some() { self.metrics.line_height as i16 } else { 0 };
+        let icon_y = y + (size - svg.height - caption_height) / 2;
+
+        // Batch icon backdrop, blending from the previous state's color.
+        let (from, to) = if toggle.enabled() {
+            (self.inactive_color, self.active_color)
+        } else {
+            (self.active_color, self.inactive_color)
+        };
+        let color = if transition.enabled_changed { mix_colors(from, to, progress) } else { to };
+        let backdrop = RectVertex::new(window_width, window_height, x, y, size, size, &color);
+        for vertex in backdrop {
+            self.rect_batcher.push(0, vertex);
+        }
 
-        let svg = self.rasterizer.rasterize_svg(toggle.svg(), None, ICON_HEIGHT)?;
+        // Shrink the active backdrop to the toggle's level.
+        if let Some(level) = toggle.level().filter(|_| toggle.enabled()) {
+            let empty_height = (size as f64 * (1. - level)).round() as i16;
+            let color = &self.inactive_color;
+            let empty =
+                RectVertex::new(window_width, window_height, x, y, size, empty_height, color);
+            for vertex in empty {
+                self.rect_batcher.push(0, vertex);
+            }
+        }
 
-        // Calculate module origin point.
-        let (x, y) = self.positioner.position(self.column, self.row);
+        // Fade out the previous icon.
+        if progress < 1. && icon_changed {
+            let previous_svg = transition.previous_svg;
+            let previous = self.rasterizer.rasterize_styled_svg(
+                previous_svg,
+                previous_style,
+                None,
+                ICON_HEIGHT,
+            )?;
+            let previous_x = x + (size - previous.width) / 2;
+            for mut vertex in previous.vertices(previous_x, icon_y).into_iter().flatten() {
+                vertex.a = ((1. - progress) * u8::MAX as f32) as u8;
+                self.text_batcher.push(previous.texture_id, vertex);
+            }
+        }
 
-        // Calculate icon origin point.
+        // Batch icon, fading it in when it changed.
         let icon_x = x + (size - svg.width) / 2;
-        let icon_y = y + (size - svg.height) / 2;
+        let alpha = if icon_changed { progress } else { 1. };
+        for mut vertex in svg.vertices(icon_x, icon_y).into_iter().flatten() {
+            vertex.a = (alpha * u8::MAX as f32) as u8;
+            self.text_batcher.push(svg.texture_id, vertex);
+        }
 
-        // Update active column/row.
-        self.column += 1;
-        if self.column >= self.positioner.columns {
-            self.column = 0;
-            self.row += 1;
+        // Batch caption below the icon.
+        if let Some(caption) = caption {
+            let caption_item =
+                LayoutItem { y: icon_y + svg.height, height: caption_height, ..*item };
+            self.batch_text(&caption_item, &caption, true);
         }
 
-        // Batch icon backdrop.
-        let color = if toggle.enabled() { MODULE_COLOR_FG } else { MODULE_COLOR_BG };
-        let backdrop = RectVertex::new(window_width, window_height, x, y, size, size, &color);
+        Ok(())
+    }
+
+    /// Add a collapsed list to the drawer.
+    fn batch_list(&mut self, item: &LayoutItem, list: &dyn List) -> Result<()> {
+        let (window_width, window_height) = (self.size.width, self.size.height);
+        let (x, y, width, height) = (item.x, item.y, item.width, item.height);
+
+        // Batch list backdrop.
+        let backdrop =
+            RectVertex::new(window_width, window_height, x, y, width, height, &self.inactive_color);
+        for vertex in backdrop {
+            self.rect_batcher.push(0, vertex);
+        }
+
+        // Batch icon, centered in a square at the start of the row.
+        let svg = self.rasterizer.rasterize_svg(list.svg(), None, ICON_HEIGHT)?;
+        let icon_x = x + (height - svg.width) / 2;
+        let icon_y = y + (height - svg.height) / 2;
+        for vertex in svg.vertices(icon_x, icon_y).into_iter().flatten() {
+            self.text_batcher.push(svg.texture_id, vertex);
+        }
+
+        // Batch label next to the icon.
+        let label_item = LayoutItem { x: x + height, width: width - height, ..*item };
+        self.batch_text(&label_item, &list.label(), false);
+
+        Ok(())
+    }
+
+    /// Add a graph to the drawer.
+    fn batch_graph(&mut self, item: &LayoutItem, graph: &dyn Graph) -> Result<()> {
+        let (window_width, window_height) = (self.size.width, self.size.height);
+        let (x, y, width, height) = (item.x, item.y, item.width, item.height);
+
+        // Batch graph backdrop.
+        let backdrop =
+            RectVertex::new(window_width, window_height, x, y, width, height, &self.inactive_color);
         for vertex in backdrop {
             self.rect_batcher.push(0, vertex);
         }
 
-        // Batch icon.
+        // Batch icon and label in a row above the plot, like collapsed lists.
+        let row_height = MODULE_SIZE as i16 * self.scale_factor;
+        // Limit icon width, since graphs are often shown with wide icons.
+        let svg = self.rasterizer.rasterize_svg(graph.svg(), ICON_HEIGHT, None)?;
+        let icon_x = x + (row_height - svg.width) / 2;
+        let icon_y = y + (row_height - svg.height) / 2;
         for vertex in svg.vertices(icon_x, icon_y).into_iter().flatten() {
             self.text_batcher.push(svg.texture_id, vertex);
         }
 
+        let label_item = LayoutItem {
+            x: x + row_height,
+            width: width - row_height,
+            height: row_height,
+            ..*item
+        };
+        self.batch_text(&label_item, &graph.label(), false);
+
+        // Plot the samples below the label.
+        let padding = MODULE_PADDING * self.scale_factor;
+        let plot_x = (x + padding) as f64;
+        let plot_y = (y + row_height) as f64;
+        let plot_width = (width - 2 * padding) as f64;
+        let plot_height = (height - row_height - padding) as f64;
+        let points: Vec<_> = graph
+            .points()
+            .into_iter()
+            .map(|(point_x, point_y)| {
+                let point_x = plot_x + point_x.clamp(0., 1.) * plot_width;
+                let point_y = plot_y + (1. - point_y.clamp(0., 1.)) * plot_height;
+                (point_x as f32, point_y as f32)
+            })
+            .collect();
+
+        let line_width = GRAPH_LINE_WIDTH * self.scale_factor as f32;
+        let color = &GRAPH_LINE_COLOR;
+        let line = RectVertex::polyline(window_width, window_height, &points, line_width, color);
+        for vertex in line {
+            self.line_batcher.push(0, vertex);
+        }
+
         Ok(())
     }
 
-    /// Draw all modules in this run.
-    fn draw(self) {
-        let mut rect_batches = self.rect_batcher.batches();
-        while let Some(rect_batch) = rect_batches.next() {
-            rect_batch.draw();
+    /// Add an entry of an expanded list to the drawer.
+    fn batch_list_entry(&mut self, item: &LayoutItem, list: &dyn List, entry: usize) {
+        let (window_width, window_height) = (self.size.width, self.size.height);
+        let (x, y, width, height) = (item.x, item.y, item.width, item.height);
+
+        let label = match list.entries().into_iter().nth(entry) {
+            Some(label) => label,
+            None => return,
+        };
+
+        // Batch entry backdrop, highlighting the active choice.
+        let selected = list.selected() == Some(entry);
+        let color = if selected { self.active_color } else { self.inactive_color };
+        let backdrop = RectVertex::new(window_width, window_height, x, y, width, height, &color);
+        for vertex in backdrop {
+            self.rect_batcher.push(0, vertex);
+        }
+
+        // Indent labels to align them with the list's label.
+        let label_item = LayoutItem { x: x + MODULE_SIZE as i16 * self.scale_factor, ..*item };
+        self.batch_text(&label_item, &label, false);
+    }
+
+    /// Add a QR code to the drawer.
+    fn batch_qr_code(&mut self, item: &LayoutItem, text: &str) {
+        let qr_code = match QrCode::encode_text(text, QrCodeEcc::Medium) {
+            Ok(qr_code) => qr_code,
+            Err(_) => return,
+        };
+
+        let (window_width, window_height) = (self.size.width, self.size.height);
+        let (x, y, width, height) = (item.x, item.y, item.width, item.height);
+
+        // Batch light backdrop, which also forms the quiet zone.
+        let backdrop =
+            RectVertex::new(window_width, window_height, x, y, width, height, &QR_LIGHT_COLOR);
+        for vertex in backdrop {
+            self.rect_batcher.push(0, vertex);
         }
 
-        let mut text_batches = self.text_batcher.batches();
-        while let Some(text_batch) = text_batches.next() {
-            text_batch.draw();
+        // Use integer module sizes, to keep the code sharp.
+        let size = qr_code.size();
+        let module_size = width / (size as i16 + 2 * QR_QUIET_ZONE);
+        let padding = (width - module_size * size as i16) / 2;
+
+        for row in 0..size {
+            // Batch horizontal runs of dark modules as a single rectangle.
+            let mut column = 0;
+            while column < size {
+                let start = column;
+                while column < size && qr_code.get_module(column, row) {
+                    column += 1;
+                }
+
+                if column == start {
+                    column += 1;
+                    continue;
+                }
+
+                let run_x = x + padding + start as i16 * module_size;
+                let run_y = y + padding + row as i16 * module_size;
+                let run_width = (column - start) as i16 * module_size;
+                let color = &QR_DARK_COLOR;
+                let run = RectVertex::new(
+                    window_width,
+                    window_height,
+                    run_x,
+                    run_y,
+                    run_width,
+                    module_size,
+                    color,
+                );
+                for vertex in run {
+                    self.rect_batcher.push(0, vertex);
+                }
+            }
         }
     }
+
+    /// Add a translucent overlay on top of an element.
+    fn batch_highlight(&mut self, item: &LayoutItem, color: &[u8; 4]) {
+        let (window_width, window_height) = (self.size.width, self.size.height);
+        let (x, y, width, height) = (item.x, item.y, item.width, item.height);
+        let overlay = RectVertex::new(window_width, window_height, x, y, width, height, color);
+        for vertex in overlay {
+            self.rect_batcher.push(0, vertex);
+        }
+    }
+
+    /// Add a touch point marker to the drawer.
+    fn batch_touch_point(&mut self, position: (f64, f64)) {
+        let (window_width, window_height) = (self.size.width, self.size.height);
+        let size = DEBUG_TOUCH_SIZE * self.scale_factor;
+        let x = position.0 as i16 - size / 2;
+        let y = position.1 as i16 - size / 2;
+        let marker =
+            RectVertex::new(window_width, window_height, x, y, size, size, &DEBUG_TOUCH_COLOR);
+        for vertex in marker {
+            self.rect_batcher.push(0, vertex);
+        }
+    }
+
+    /// Add the page indicator dots to the drawer.
+    fn batch_page_indicator(&mut self, page: usize, page_count: usize) {
+        if page_count <= 1 {
+            return;
+        }
+
+        let (window_width, window_height) = (self.size.width, self.size.height);
+        let size = PAGE_DOT_SIZE * self.scale_factor;
+
+        // Center dots horizontally at the bottom of the drawer.
+        let total_width = size * (page_count as i16 * 2 - 1);
+        let mut x = (window_width - total_width) / 2;
+        let y = window_height - EDGE_PADDING * self.scale_factor - size;
+
+        for i in 0..page_count {
+            let color = if i == page { PAGE_DOT_COLOR_ACTIVE } else { self.active_color };
+            let dot = RectVertex::new(window_width, window_height, x, y, size, size, &color);
+            for vertex in dot {
+                self.rect_batcher.push(0, vertex);
+            }
+
+            x += size * 2;
+        }
+    }
+}
+
+/// Drawer element with its position.
+struct LayoutItem {
+    element: LayoutElement,
+    scale_factor: i16,
+    page: usize,
+    x: i16,
+    y: i16,
+    width: i16,
+    height: i16,
+}
+
+impl LayoutItem {
+    /// Check if a point is within this element.
+    fn contains(&self, x: i16, y: i16) -> bool {
+        x >= self.x && y >= self.y && x < self.x + self.width && y < self.y + self.height
+    }
+}
+
+/// Drawer layout element.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum LayoutElement {
+    Header(Section),
+    Module(usize),
+    ListEntry(usize, usize),
+    QrCode(usize),
+    PinEntry,
+    Key(char),
+}
+
+/// Animated transition between two states of a toggle module.
+struct ToggleTransition {
+    enabled: bool,
+    svg: Svg,
+    enabled_changed: bool,
+    previous_svg: Svg,
+    start: Option<Instant>,
+}
+
+impl ToggleTransition {
+    /// Create a completed transition for the toggle's current state.
+    fn new(toggle: &dyn Toggle) -> Self {
+        let svg = toggle.svg();
+        Self {
+            svg,
+            enabled: toggle.enabled(),
+            previous_svg: svg,
+            enabled_changed: Default::default(),
+            start: Default::default(),
+        }
+    }
+
+    /// Start a new transition if the toggle's state changed.
+    fn update(&mut self, toggle: &dyn Toggle) {
+        let (enabled, svg) = (toggle.enabled(), toggle.svg());
+        if enabled == self.enabled && svg == self.svg {
+            return;
+        }
+
+        self.enabled_changed = enabled != self.enabled;
+        self.previous_svg = mem::replace(&mut self.svg, svg);
+        self.enabled = enabled;
+        self.start = Some(Instant::now());
+    }
+
+    /// Transition progress, from `0.0` to `1.0`.
+    fn progress(&self) -> f32 {
+        let elapsed = self.start.map_or(TOGGLE_TRANSITION, |start| start.elapsed());
+        (elapsed.as_secs_f32() / TOGGLE_TRANSITION.as_secs_f32()).min(1.)
+    }
+
+    /// Check if the transition is still in progress.
+    fn active(&self) -> bool {
+        self.progress() < 1.
+    }
+}
+
+/// Linearly interpolate between two colors.
+fn mix_colors(from: [u8; 4], to: [u8; 4], progress: f32) -> [u8; 4] {
+    let mut color = to;
+    for (channel, from) in color.iter_mut().zip(from) {
+        *channel = (from as f32 + (*channel as f32 - from as f32) * progress) as u8;
+    }
+    color
+}
+
+/// Kiosk PIN protecting the drawer modules.
+struct PinLock {
+    pin: String,
+    input: String,
+    unlocked: bool,
 }
 
 /// Module position calculator.
 struct ModulePositioner {
     slider_size: Size<i16>,
+    list_entry_height: i16,
+    graph_height: i16,
     module_padding: i16,
+    header_height: i16,
     edge_padding: i16,
     panel_height: i16,
+    scale_factor: i16,
     module_size: i16,
-    size: Size<i16>,
+    page_bottom: i16,
     columns: i16,
 }
 
@@ -483,7 +1769,10 @@ impl ModulePositioner {
         let panel_height = PANEL_HEIGHT as i16 * scale_factor;
         let module_size = MODULE_SIZE as i16 * scale_factor;
         let module_padding = MODULE_PADDING * scale_factor;
+        let header_height = HEADER_HEIGHT * scale_factor;
         let slider_height = SLIDER_HEIGHT * scale_factor;
+        let list_entry_height = LIST_ENTRY_HEIGHT * scale_factor;
+        let graph_height = GRAPH_HEIGHT * scale_factor;
         let edge_padding = EDGE_PADDING * scale_factor;
 
         let content_width = size.width - edge_padding * 2;
@@ -494,63 +1783,250 @@ impl ModulePositioner {
         let slider_width = size.width - 2 * edge_padding;
         let slider_size = Size::new(slider_width, slider_height);
 
-        Self { module_padding, edge_padding, panel_height, slider_size, module_size, columns, size }
-    }
-
-    /// Get cell origin point.
-    fn position(&self, column: i16, row: i16) -> (i16, i16) {
-        let padded_module_size = self.module_size + self.module_padding;
-        let x = self.edge_padding + column * padded_module_size;
-        let y = self.panel_height + self.edge_padding + row * padded_module_size;
+        // Reserve space for the page indicator at the bottom.
+        let page_indicator_height = PAGE_DOT_SIZE * scale_factor * 2;
+        let page_bottom = size.height - EDGE_PADDING * scale_factor - page_indicator_height;
 
-        (x, y)
+        Self {
+            list_entry_height,
+            graph_height,
+            module_padding,
+            header_height,
+            edge_padding,
+            panel_height,
+            scale_factor,
+            slider_size,
+            module_size,
+            page_bottom,
+            columns,
+        }
     }
 
-    /// Get relative position inside a module.
-    fn module_position(
+    /// Compute the position of all drawer elements.
+    fn layout(
         &self,
         modules: &mut [&mut dyn Module],
-        position: (f64, f64),
-    ) -> Option<(usize, f64, f64)> {
-        let x = position.0 as i16;
-        let y = position.1 as i16;
-        let mut start_x = self.edge_padding;
-        let mut start_y = self.panel_height + self.edge_padding;
-
-        for (i, module) in modules.iter_mut().enumerate() {
-            // Only check drawer modules.
-            let module = match module.drawer_module() {
-                Some(module) => module,
-                None => continue,
-            };
+        collapsed_sections: &[Section],
+        expanded_module: Option<usize>,
+    ) -> Vec<LayoutItem> {
+        let padded_module_size = self.module_size + self.module_padding;
+        let top = self.panel_height + self.edge_padding;
+        let item = |element, page, x, y, width, height| LayoutItem {
+            scale_factor: self.scale_factor,
+            element,
+            page,
+            x,
+            y,
+            width,
+            height,
+        };
 
-            // Calculate module end.
-            let end_x = match module {
-                DrawerModule::Toggle(_) => start_x + self.module_size,
-                DrawerModule::Slider(_) => start_x + self.slider_size.width,
+        // Move to the next page if an element doesn't fit on the current one.
+        let fit = |y: &mut i16, page: &mut usize, height: i16| {
+            if *y + height > self.page_bottom && *y > top {
+                *page += 1;
+                *y = top;
+            }
+        };
+
+        // Add the QR code of an expanded toggle below its row.
+        let push_qr_code = |items: &mut Vec<_>, y: &mut i16, page: &mut usize, index: Option<_>| {
+            let index = match index {
+                Some(index) => index,
+                None => return,
             };
-            let end_y = start_y + self.module_size;
 
-            // Check if position is within this module.
-            if x >= start_x && y >= start_y && x < end_x && y < end_y {
-                let fractional_x = (position.0 - start_x as f64) / (end_x - start_x) as f64;
-                let fractional_y = (position.1 - start_y as f64) / (end_y - start_y) as f64;
-                return Some((i, fractional_x, fractional_y));
+            let size = self.slider_size.width.min(self.page_bottom - top);
+            fit(y, page, size);
+            let x = self.edge_padding + (self.slider_size.width - size) / 2;
+            items.push(item(LayoutElement::QrCode(index), *page, x, *y, size, size));
+            *y += size + self.module_padding;
+        };
+
+        let mut items = Vec::new();
+        let mut page = 0;
+        let mut y = top;
+        for section in Section::ALL {
+            // Skip sections without any drawer modules.
+            let indices: Vec<usize> = modules
+                .iter_mut()
+                .enumerate()
+                .filter_map(|(index, module)| {
+                    let in_section = module.section() == section;
+                    (in_section && module.drawer_module().is_some()).then_some(index)
+                })
+                .collect();
+            if indices.is_empty() {
+                continue;
             }
 
-            // Calculate next module start.
-            start_x = end_x + self.module_padding;
-            if start_x >= self.size.width - self.edge_padding {
-                start_x = self.edge_padding;
-                start_y = end_y + self.module_padding;
+            // Add section header.
+            fit(&mut y, &mut page, self.header_height);
+            let header = LayoutElement::Header(section);
+            let (x, width) = (self.edge_padding, self.slider_size.width);
+            items.push(item(header, page, x, y, width, self.header_height));
+            y += self.header_height + self.module_padding;
+
+            if collapsed_sections.contains(&section) {
+                continue;
             }
+
+            // Add the section's modules.
+            let mut qr_code = None;
+            let mut column = 0;
+            for index in indices {
+                let element = LayoutElement::Module(index);
+                match modules[index].drawer_module() {
+                    Some(module @ (DrawerModule::Slider(_) | DrawerModule::List(_))) => {
+                        // Ensure we're in an empty row.
+                        if column != 0 {
+                            column = 0;
+                            y += padded_module_size;
+                        }
+
+                        fit(&mut y, &mut page, self.module_size);
+                        let (x, width) = (self.edge_padding, self.slider_size.width);
+                        items.push(item(element, page, x, y, width, self.module_size));
+                        y += padded_module_size;
+
+                        // Add entries of the expanded list below it.
+                        let list = match module {
+                            DrawerModule::List(list) if expanded_module == Some(index) => list,
+                            _ => continue,
+                        };
+                        let height = self.list_entry_height;
+                        for entry in 0..list.entries().len() {
+                            fit(&mut y, &mut page, height);
+                            let element = LayoutElement::ListEntry(index, entry);
+                            items.push(item(element, page, x, y, width, height));
+                            y += height;
+                        }
+                        y += self.module_padding;
+                    },
+                    Some(DrawerModule::Graph(_)) => {
+                        // Ensure we're in an empty row.
+                        if column != 0 {
+                            column = 0;
+                            y += padded_module_size;
+                        }
+
+                        fit(&mut y, &mut page, self.graph_height);
+                        let (x, width) = (self.edge_padding, self.slider_size.width);
+                        items.push(item(element, page, x, y, width, self.graph_height));
+                        y += self.graph_height + self.module_padding;
+                    },
+                    Some(DrawerModule::Toggle(toggle)) => {
+                        if column == 0 {
+                            fit(&mut y, &mut page, self.module_size);
+                        }
+
+                        let x = self.edge_padding + column * padded_module_size;
+                        let size = self.module_size;
+                        items.push(item(element, page, x, y, size, size));
+
+                        if expanded_module == Some(index) && toggle.qr_code().is_some() {
+                            qr_code = Some(index);
+                        }
+
+                        // Update active column/row.
+                        column += 1;
+                        if column >= self.columns {
+                            column = 0;
+                            y += padded_module_size;
+                            push_qr_code(&mut items, &mut y, &mut page, qr_code.take());
+                        }
+                    },
+                    None => (),
+                }
+            }
+
+            // Start next section in an empty row.
+            if column != 0 {
+                y += padded_module_size;
+            }
+            push_qr_code(&mut items, &mut y, &mut page, qr_code.take());
+        }
+
+        items
+    }
+
+    /// Compute the position of the PIN pad elements.
+    fn pin_layout(&self) -> Vec<LayoutItem> {
+        let padded_module_size = self.module_size + self.module_padding;
+        let item = |element, x, y, width, height| LayoutItem {
+            scale_factor: self.scale_factor,
+            page: 0,
+            element,
+            x,
+            y,
+            width,
+            height,
+        };
+
+        // Show entered digits above the keys.
+        let mut y = self.panel_height + self.edge_padding;
+        let (x, width) = (self.edge_padding, self.slider_size.width);
+        let mut items = vec![item(LayoutElement::PinEntry, x, y, width, self.header_height)];
+        y += self.header_height + self.module_padding;
+
+        // Center keys horizontally.
+        let columns = PIN_COLUMNS as i16;
+        let keys_width = columns * padded_module_size - self.module_padding;
+        let left = self.edge_padding + (self.slider_size.width - keys_width) / 2;
+
+        for (i, key) in PIN_KEYS.into_iter().enumerate() {
+            let (column, row) = ((i % PIN_COLUMNS) as i16, (i / PIN_COLUMNS) as i16);
+            let x = left + column * padded_module_size;
+            let y = y + row * padded_module_size;
+            let size = self.module_size;
+            items.push(item(LayoutElement::Key(key), x, y, size, size));
         }
 
-        None
+        items
     }
 }
 
+/// Get element at the specified position, with the fractional position inside
+/// of it.
+fn element_at(
+    layout: &[LayoutItem],
+    page: usize,
+    position: (f64, f64),
+) -> Option<(LayoutElement, f64, f64)> {
+    let (x, y) = (position.0 as i16, position.1 as i16);
+    let item = layout.iter().find(|item| item.page == page && item.contains(x, y))?;
+
+    let fractional_x = (position.0 - item.x as f64) / item.width as f64;
+    let fractional_y = (position.1 - item.y as f64) / item.height as f64;
+
+    Some((item.element, fractional_x, fractional_y))
+}
+
 /// Scale touch position by scale factor.
 fn scale_touch(position: (f64, f64), scale_factor: i32) -> (f64, f64) {
     (position.0 * scale_factor as f64, position.1 * scale_factor as f64)
 }
+
+impl Dispatch<WpViewporter, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _viewporter: &WpViewporter,
+        _event: <WpViewporter as Proxy>::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<WpViewport, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _viewport: &WpViewport,
+        _event: <WpViewport as Proxy>::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+    }
+}
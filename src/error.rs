@@ -0,0 +1,133 @@
+//! Error types.
+
+use std::io;
+use std::path::PathBuf;
+
+use calloop::InsertError;
+use epitaph_modules::Error as ModuleError;
+use smithay_client_toolkit::error::GlobalError;
+use smithay_client_toolkit::reexports::client::backend::WaylandError;
+use smithay_client_toolkit::reexports::client::globals::BindError;
+use smithay_client_toolkit::reexports::client::ConnectError;
+use thiserror::Error;
+
+/// Epitaph error.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// Wayland connection failed.
+    #[error("Wayland connection failed: {0}")]
+    Connect(#[from] ConnectError),
+
+    /// Wayland connection was closed by the compositor.
+    #[error("{0}")]
+    ConnectionLost(Box<Error>),
+
+    /// Wayland socket communication failed.
+    #[error("Wayland error: {0}")]
+    Backend(#[from] WaylandError),
+
+    /// Wayland surface creation failed.
+    #[error("Wayland error: {0}")]
+    Wayland(#[from] GlobalError),
+
+    /// Required Wayland global is not advertised by the compositor.
+    #[error("compositor doesn't support {0}: {1}")]
+    MissingGlobal(&'static str, BindError),
+
+    /// EGL or OpenGL context failure.
+    #[error("EGL error: {0}")]
+    Egl(#[from] glutin::error::Error),
+
+    /// No EGL config supports the required framebuffer format.
+    #[error("no suitable EGL configs were found")]
+    MissingEglConfig,
+
+    /// Windows were created before the EGL display was set up.
+    #[error("EGL config accessed before initialization")]
+    EglUninitialized,
+
+    /// Udev device access failed.
+    #[error("udev {subsystem} error: {source}")]
+    Udev { subsystem: &'static str, source: io::Error },
+
+    /// Required hardware is not present.
+    #[error("no {0} device found")]
+    DeviceNotFound(&'static str),
+
+    /// Module requires configuration before it can be used.
+    #[error("{0} is not configured")]
+    NotConfigured(&'static str),
+
+    /// Configuration file could not be parsed.
+    #[error("invalid config {location}: {message}")]
+    Config { location: String, message: String },
+
+    /// Configuration file contains an unknown key.
+    #[error("invalid config {location}: {message}, did you mean `{suggestion}`?")]
+    ConfigTypo { location: String, message: String, suggestion: String },
+
+    /// Included configuration file could not be loaded.
+    #[error("couldn't include {0:?}: {1}")]
+    ConfigInclude(PathBuf, String),
+
+    /// Snapshot state description could not be parsed.
+    #[error("invalid state description: {0}")]
+    Snapshot(#[from] serde_json::Error),
+
+    /// Persistent state could not be serialized.
+    #[error("state serialization failed: {0}")]
+    Serialize(#[from] toml::ser::Error),
+
+    /// XDG base directory could not be determined.
+    #[error("unable to find {0} directory")]
+    MissingDirectory(&'static str),
+
+    /// PNG image could not be written.
+    #[error("PNG encoding failed: {0}")]
+    Png(String),
+
+    /// Rendering failed.
+    #[error(transparent)]
+    Ui(#[from] epitaph_ui::Error),
+
+    /// Module failed.
+    #[error(transparent)]
+    Module(#[from] epitaph_modules::Error),
+
+    /// Invalid epitaph IPC message.
+    #[error("{0}")]
+    Ipc(String),
+
+    /// Profiler could not be started.
+    #[cfg(feature = "profiling")]
+    #[error("profiling error: {0}")]
+    Profiling(String),
+
+    /// Event loop source registration failed.
+    #[error("event loop error: {0}")]
+    EventLoop(#[from] calloop::Error),
+
+    /// Generic I/O failure.
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+}
+
+impl<T> From<InsertError<T>> for Error {
+    fn from(err: InsertError<T>) -> Self {
+        Self::EventLoop(err.error)
+    }
+}
+
+impl From<Error> for ModuleError {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Module(err) => err,
+            Error::Udev { subsystem, source } => Self::Udev { subsystem, source },
+            Error::DeviceNotFound(device) => Self::DeviceNotFound(device),
+            Error::NotConfigured(option) => Self::NotConfigured(option),
+            Error::MissingDirectory(directory) => Self::MissingDirectory(directory),
+            Error::Io(err) => Self::Io(err),
+            err => Self::Host(Box::new(err)),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Vibration motor feedback.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+use std::{mem, ptr, slice, thread};
+
+use udev::Device;
+
+use crate::config;
+use crate::error::Error;
+use crate::module;
+use crate::Result;
+
+/// `EVIOCSFF` ioctl request, uploading a force-feedback effect.
+const EVIOCSFF: u64 =
+    (1 << 30) | ((mem::size_of::<libc::ff_effect>() as u64) << 16) | ((b'E' as u64) << 8) | 0x80;
+
+/// Vibration motor feedback.
+pub struct Haptics {
+    /// Vibration intensity in percent, disabled at zero.
+    intensity: u32,
+    /// Vibration duration in milliseconds.
+    duration: u32,
+}
+
+impl Haptics {
+    pub fn new(config: &config::Haptics) -> Self {
+        let intensity = (config.intensity.clamp(0., 1.) * 100.).round() as u32;
+        Self { intensity, duration: config.duration }
+    }
+
+    /// Vibrate once, using the configured intensity and duration.
+    pub fn vibrate(&self) -> Result<()> {
+        if self.intensity == 0 || self.duration == 0 {
+            return Ok(());
+        }
+
+        // Prefer force-feedback devices, since they support variable intensity.
+        let devices = module::devices("input")?;
+        if let Some(device) = devices.into_iter().find(is_vibrator) {
+            let devnode = device.devnode().ok_or(Error::DeviceNotFound("vibration motor"))?;
+            let file = OpenOptions::new().read(true).write(true).open(devnode)?;
+            return rumble(file, self.intensity, self.duration);
+        }
+
+        // Fall back to vibrators exposed through the LED class.
+        let mut leds = module::devices("leds")?.into_iter();
+        match leds.find(|led| led.sysname().to_string_lossy().contains("vibrator")) {
+            Some(mut led) => pulse(&mut led, self.duration),
+            None => Err(Error::DeviceNotFound("vibration motor")),
+        }
+    }
+}
+
+/// Check if an input device is a force-feedback vibration motor.
+fn is_vibrator(device: &Device) -> bool {
+    // Only event devices can play effects.
+    if !device.sysname().to_string_lossy().starts_with("event") {
+        return false;
+    }
+
+    // Avoid rumbling gamepads.
+    if device.property_value("ID_INPUT_JOYSTICK").map_or(false, |value| value == "1") {
+        return false;
+    }
+
+    // Check for the rumble bit in the force-feedback capabilities.
+    let capabilities = device.parent().and_then(|parent| {
+        Some(parent.attribute_value("capabilities/ff")?.to_string_lossy().into_owned())
+    });
+    capabilities
+        .map_or(false, |capabilities| module::has_capability(&capabilities, libc::FF_RUMBLE))
+}
+
+/// Play a rumble effect on a force-feedback device.
+fn rumble(mut file: File, intensity: u32, duration: u32) -> Result<()> {
+    let mut effect: libc::ff_effect = unsafe { mem::zeroed() };
+    effect.type_ = libc::FF_RUMBLE;
+    effect.id = -1;
+    effect.replay.length = duration.min(u16::MAX as u32) as u16;
+
+    // Rumble parameters are stored at the start of the effect union.
+    let magnitude = (intensity * u16::MAX as u32 / 100) as u16;
+    let rumble = libc::ff_rumble_effect { strong_magnitude: magnitude, weak_magnitude: magnitude };
+    unsafe { ptr::write(effect.u.as_mut_ptr() as *mut libc::ff_rumble_effect, rumble) };
+
+    // Upload the effect to the device.
+    if unsafe { libc::ioctl(file.as_raw_fd(), EVIOCSFF as _, &mut effect) } < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+
+    // Start playing the effect.
+    let mut event: libc::input_event = unsafe { mem::zeroed() };
+    event.type_ = libc::EV_FF;
+    event.code = effect.id as u16;
+    event.value = 1;
+    let event_size = mem::size_of::<libc::input_event>();
+    let bytes = unsafe { slice::from_raw_parts(&event as *const _ as *const u8, event_size) };
+    file.write_all(bytes)?;
+
+    // Effects are removed once their file is closed, so keep it open until done.
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(duration as u64));
+        drop(file);
+    });
+
+    Ok(())
+}
+
+/// Pulse a vibrator LED using the transient trigger.
+fn pulse(led: &mut Device, duration: u32) -> Result<()> {
+    let mut set = |attribute, value: &str| {
+        led.set_attribute_value(attribute, value)
+            .map_err(|source| Error::Udev { subsystem: "leds", source })
+    };
+
+    set("trigger", "transient")?;
+    set("duration", &duration.to_string())?;
+    set("state", "1")?;
+    set("activate", "1")?;
+
+    Ok(())
+}
@@ -0,0 +1,286 @@
+//! Inter-process communication.
+
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::result::Result as StdResult;
+use std::str::FromStr;
+use std::{env, fs};
+
+use calloop::generic::Generic;
+use calloop::{Interest, LoopHandle, Mode, PostAction};
+
+use crate::error::Error;
+use crate::layout::ModuleId;
+use crate::{client, config, Result, State, MAX_SCALE_FACTOR};
+
+/// Response sent for successfully handled messages.
+const RESPONSE_OK: &str = "ok";
+
+/// IPC message.
+#[derive(Debug)]
+pub enum IpcMessage {
+    /// Enable a module.
+    Enable(ModuleId),
+    /// Disable a module.
+    Disable(ModuleId),
+    /// Move a module to a different position.
+    Move(ModuleId, usize),
+    /// Switch to a different theme.
+    SetTheme(String),
+    /// Get the notification history and do not disturb state.
+    Notifications,
+    /// Stream the notification status after every change.
+    WatchNotifications,
+    /// Open or close the drawer.
+    ToggleDrawer,
+    /// Override the rendering scale, or reset it with `None`.
+    SetScale(Option<i32>),
+}
+
+/// Successful IPC response.
+enum Response {
+    /// Message was applied.
+    Ok,
+    /// Message was answered with data.
+    Data(String),
+    /// Client subscribed to updates.
+    Subscribe,
+}
+
+impl FromStr for IpcMessage {
+    type Err = String;
+
+    fn from_str(message: &str) -> StdResult<Self, Self::Err> {
+        let mut words = message.split_whitespace();
+        let command = words.next().ok_or("empty message")?;
+        let mut module = || -> StdResult<ModuleId, String> {
+            ModuleId::from_str(words.next().ok_or("missing module name")?)
+        };
+
+        let message = match command {
+            "set-theme" => Self::SetTheme(words.next().ok_or("missing theme name")?.into()),
+            "notifications" => Self::Notifications,
+            "watch-notifications" => Self::WatchNotifications,
+            "toggle-drawer" => Self::ToggleDrawer,
+            "set-scale" => match words.next().ok_or("missing scale")? {
+                "auto" => Self::SetScale(None),
+                scale => {
+                    let scale = i32::from_str(scale).ok();
+                    let scale = scale.filter(|scale| (1..=MAX_SCALE_FACTOR).contains(scale));
+                    Self::SetScale(Some(scale.ok_or("invalid scale")?))
+                },
+            },
+            "enable" => Self::Enable(module()?),
+            "disable" => Self::Disable(module()?),
+            "move" => {
+                let module = module()?;
+                let position = words
+                    .next()
+                    .and_then(|position| usize::from_str(position).ok())
+                    .ok_or("missing or invalid module position")?;
+                Self::Move(module, position)
+            },
+            _ => return Err(format!("unknown command {command:?}")),
+        };
+
+        Ok(message)
+    }
+}
+
+/// Start listening for IPC messages.
+pub fn spawn_server(event_loop: &LoopHandle<'static, State>) -> Result<()> {
+    // Remove stale sockets from previous instances, unless they're still running.
+    let path = socket_path();
+    if let Err(err) = UnixStream::connect(&path) {
+        if err.kind() == ErrorKind::ConnectionRefused {
+            let _ = fs::remove_file(&path);
+        }
+    }
+
+    let listener = UnixListener::bind(&path)?;
+    listener.set_nonblocking(true)?;
+
+    let source = Generic::new(listener, Interest::READ, Mode::Level);
+    event_loop.insert_source(source, |_, listener, state| {
+        while let Ok((stream, _)) = listener.accept() {
+            if let Err(err) = add_client(&state.event_loop, stream) {
+                eprintln!("Error: IPC client failed: {err}");
+            }
+        }
+
+        Ok(PostAction::Continue)
+    })?;
+
+    Ok(())
+}
+
+/// Send an IPC message to the running epitaph instance.
+pub fn send_message(args: &[String]) -> Result<()> {
+    let message = args.join(" ");
+
+    // Validate message before sending it to the server.
+    IpcMessage::from_str(&message).map_err(Error::Ipc)?;
+
+    let mut stream = UnixStream::connect(socket_path())?;
+    stream.write_all(message.as_bytes())?;
+    stream.write_all(b"\n")?;
+
+    let mut reader = BufReader::new(stream);
+    let mut response = String::new();
+    reader.read_line(&mut response)?;
+
+    if response.trim() != RESPONSE_OK {
+        reader.read_to_string(&mut response)?;
+        return Err(Error::Ipc(response.trim().into()));
+    }
+
+    // Print response data and streamed updates.
+    for line in reader.lines() {
+        println!("{}", line?);
+    }
+
+    Ok(())
+}
+
+/// Wait for a client's IPC message.
+fn add_client(event_loop: &LoopHandle<'static, State>, stream: UnixStream) -> Result<()> {
+    stream.set_nonblocking(true)?;
+    client::read_request(event_loop, stream, |state, stream, message| {
+        if let Err(err) = handle_client(state, stream, message) {
+            eprintln!("Error: IPC client failed: {err}");
+        }
+    })
+}
+
+/// Respond to a single IPC client message.
+fn handle_client(state: &mut State, stream: &mut UnixStream, message: &str) -> Result<()> {
+    let response = IpcMessage::from_str(message)
+        .map_err(Error::Ipc)
+        .and_then(|message| handle_message(state, message));
+
+    match response {
+        Ok(Response::Ok) => stream.write_all(RESPONSE_OK.as_bytes())?,
+        Ok(Response::Data(data)) => writeln!(stream, "{RESPONSE_OK}\n{data}")?,
+        Ok(Response::Subscribe) => {
+            writeln!(stream, "{RESPONSE_OK}")?;
+            state.notifications.subscribe(stream.try_clone()?)?;
+        },
+        Err(err) => stream.write_all(err.to_string().as_bytes())?,
+    }
+
+    Ok(())
+}
+
+/// Apply an IPC message.
+#[cfg_attr(feature = "profiling", tracing::instrument(skip(state)))]
+fn handle_message(state: &mut State, message: IpcMessage) -> Result<Response> {
+    let layout = &mut state.modules.layout;
+    match message {
+        IpcMessage::Enable(module) => layout.set_enabled(module, true),
+        IpcMessage::Disable(module) => layout.set_enabled(module, false),
+        IpcMessage::Move(module, position) => layout.move_module(module, position),
+        IpcMessage::SetTheme(name) => {
+            state.set_theme(config::load_theme(&name)?);
+            return Ok(Response::Ok);
+        },
+        IpcMessage::Notifications => return Ok(Response::Data(state.notifications.status())),
+        IpcMessage::WatchNotifications => return Ok(Response::Subscribe),
+        IpcMessage::ToggleDrawer => {
+            state.toggle_drawer()?;
+            return Ok(Response::Ok);
+        },
+        IpcMessage::SetScale(scale) => {
+            state.set_scale_override(scale);
+            return Ok(Response::Ok);
+        },
+    }
+    layout.save()?;
+
+    state.request_frame();
+
+    Ok(Response::Ok)
+}
+
+/// Path of the IPC socket.
+fn socket_path() -> PathBuf {
+    let runtime_dir =
+        env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from).unwrap_or_else(env::temp_dir);
+    let display = env::var("WAYLAND_DISPLAY").unwrap_or_else(|_| "wayland-0".into());
+    runtime_dir.join(format!("epitaph-{display}.sock"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_module_commands() {
+        let message = IpcMessage::from_str("enable battery").unwrap();
+        assert!(matches!(message, IpcMessage::Enable(ModuleId::Battery)));
+
+        let message = IpcMessage::from_str("disable  clock ").unwrap();
+        assert!(matches!(message, IpcMessage::Disable(ModuleId::Clock)));
+
+        let message = IpcMessage::from_str("move battery 3").unwrap();
+        assert!(matches!(message, IpcMessage::Move(ModuleId::Battery, 3)));
+    }
+
+    #[test]
+    fn reject_invalid_module_commands() {
+        assert!(IpcMessage::from_str("enable").is_err());
+        assert!(IpcMessage::from_str("enable unknown").is_err());
+        assert!(IpcMessage::from_str("move battery").is_err());
+        assert!(IpcMessage::from_str("move battery -1").is_err());
+    }
+
+    #[test]
+    fn reject_unknown_commands() {
+        assert!(IpcMessage::from_str("").is_err());
+        assert!(IpcMessage::from_str("explode").is_err());
+    }
+
+    #[test]
+    fn parse_set_theme() {
+        let message = IpcMessage::from_str("set-theme dark").unwrap();
+        assert!(matches!(message, IpcMessage::SetTheme(name) if name == "dark"));
+
+        assert!(IpcMessage::from_str("set-theme").is_err());
+    }
+
+    #[test]
+    fn parse_notification_commands() {
+        let message = IpcMessage::from_str("notifications").unwrap();
+        assert!(matches!(message, IpcMessage::Notifications));
+
+        let message = IpcMessage::from_str("watch-notifications").unwrap();
+        assert!(matches!(message, IpcMessage::WatchNotifications));
+    }
+
+    #[test]
+    fn parse_toggle_drawer() {
+        let message = IpcMessage::from_str("toggle-drawer").unwrap();
+        assert!(matches!(message, IpcMessage::ToggleDrawer));
+    }
+
+    #[test]
+    fn parse_set_scale() {
+        let message = IpcMessage::from_str("set-scale auto").unwrap();
+        assert!(matches!(message, IpcMessage::SetScale(None)));
+
+        let message = IpcMessage::from_str("set-scale 2").unwrap();
+        assert!(matches!(message, IpcMessage::SetScale(Some(2))));
+
+        let message = IpcMessage::from_str(&format!("set-scale {MAX_SCALE_FACTOR}")).unwrap();
+        assert!(matches!(message, IpcMessage::SetScale(Some(MAX_SCALE_FACTOR))));
+    }
+
+    #[test]
+    fn reject_invalid_scale() {
+        assert!(IpcMessage::from_str("set-scale").is_err());
+        assert!(IpcMessage::from_str("set-scale 0").is_err());
+        assert!(IpcMessage::from_str("set-scale -2").is_err());
+        assert!(IpcMessage::from_str("set-scale 1.5").is_err());
+        assert!(IpcMessage::from_str(&format!("set-scale {}", MAX_SCALE_FACTOR + 1)).is_err());
+    }
+}
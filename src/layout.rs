@@ -0,0 +1,243 @@
+//! Module arrangement.
+
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::path::PathBuf;
+use std::result::Result as StdResult;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::error::Error;
+use crate::Result;
+
+/// Layout file name inside the XDG state directory.
+const LAYOUT_FILE: &str = "epitaph/modules.toml";
+
+/// Module identifier.
+#[derive(Serialize, Deserialize, Copy, Clone, Hash, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ModuleId {
+    Brightness,
+    Clock,
+    Cellular,
+    Wifi,
+    Battery,
+    Orientation,
+    Rotation,
+    Flashlight,
+    Sway,
+    SwayTitle,
+    Hyprland,
+    River,
+    Pipe,
+    Location,
+    Cast,
+    Usb,
+    Timer,
+    Mail,
+    Updates,
+    Systemd,
+    Journal,
+    Ethernet,
+    Wireguard,
+    Privacy,
+    Dock,
+    DoNotDisturb,
+    Clipboard,
+    Screenshot,
+    ScreenRecorder,
+    System,
+}
+
+impl ModuleId {
+    /// All available modules in their default order.
+    pub const ALL: [Self; 30] = [
+        Self::Brightness,
+        Self::Clock,
+        Self::Cellular,
+        Self::Wifi,
+        Self::Battery,
+        Self::Orientation,
+        Self::Rotation,
+        Self::Flashlight,
+        Self::Sway,
+        Self::SwayTitle,
+        Self::Hyprland,
+        Self::River,
+        Self::Pipe,
+        Self::Location,
+        Self::Cast,
+        Self::Usb,
+        Self::Timer,
+        Self::Mail,
+        Self::Updates,
+        Self::Systemd,
+        Self::Journal,
+        Self::Ethernet,
+        Self::Wireguard,
+        Self::Privacy,
+        Self::Dock,
+        Self::DoNotDisturb,
+        Self::Clipboard,
+        Self::Screenshot,
+        Self::ScreenRecorder,
+        Self::System,
+    ];
+
+    /// Modules which are disabled unless explicitly enabled.
+    pub const OPTIONAL: [Self; 13] = [
+        Self::Rotation,
+        Self::Sway,
+        Self::SwayTitle,
+        Self::Hyprland,
+        Self::River,
+        Self::Pipe,
+        Self::Cast,
+        Self::Mail,
+        Self::Updates,
+        Self::Systemd,
+        Self::Journal,
+        Self::Wireguard,
+        Self::System,
+    ];
+
+    /// Module name.
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Brightness => "brightness",
+            Self::Clock => "clock",
+            Self::Cellular => "cellular",
+            Self::Wifi => "wifi",
+            Self::Battery => "battery",
+            Self::Orientation => "orientation",
+            Self::Rotation => "rotation",
+            Self::Flashlight => "flashlight",
+            Self::Sway => "sway",
+            Self::SwayTitle => "sway_title",
+            Self::Hyprland => "hyprland",
+            Self::River => "river",
+            Self::Pipe => "pipe",
+            Self::Location => "location",
+            Self::Cast => "cast",
+            Self::Usb => "usb",
+            Self::Timer => "timer",
+            Self::Mail => "mail",
+            Self::Updates => "updates",
+            Self::Systemd => "systemd",
+            Self::Journal => "journal",
+            Self::Ethernet => "ethernet",
+            Self::Wireguard => "wireguard",
+            Self::Privacy => "privacy",
+            Self::Dock => "dock",
+            Self::DoNotDisturb => "do_not_disturb",
+            Self::Clipboard => "clipboard",
+            Self::Screenshot => "screenshot",
+            Self::ScreenRecorder => "screen_recorder",
+            Self::System => "system",
+        }
+    }
+}
+
+impl FromStr for ModuleId {
+    type Err = String;
+
+    fn from_str(name: &str) -> StdResult<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|id| id.name() == name)
+            .ok_or_else(|| format!("unknown module {name:?}"))
+    }
+}
+
+impl Display for ModuleId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Order and enabled state of all modules.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct ModuleLayout {
+    order: Vec<ModuleId>,
+    disabled: Vec<ModuleId>,
+}
+
+impl Default for ModuleLayout {
+    fn default() -> Self {
+        Self { order: ModuleId::ALL.to_vec(), disabled: ModuleId::OPTIONAL.to_vec() }
+    }
+}
+
+impl ModuleLayout {
+    /// Load the persisted layout.
+    pub fn load() -> Self {
+        let mut layout = layout_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| toml::from_str::<Self>(&content).ok())
+            .unwrap_or_default();
+
+        // Ensure every module is present exactly once.
+        let mut order = Vec::with_capacity(ModuleId::ALL.len());
+        for id in layout.order.drain(..) {
+            if !order.contains(&id) {
+                order.push(id);
+            }
+        }
+        layout.order = order;
+
+        // Add new modules, keeping optional ones disabled.
+        for id in ModuleId::ALL {
+            if !layout.order.contains(&id) {
+                layout.order.push(id);
+                layout.set_enabled(id, !ModuleId::OPTIONAL.contains(&id));
+            }
+        }
+
+        layout
+    }
+
+    /// Persist the layout to the state file.
+    pub fn save(&self) -> Result<()> {
+        let path = layout_path().ok_or(Error::MissingDirectory("state"))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, toml::to_string(self)?)?;
+
+        Ok(())
+    }
+
+    /// Iterate over all enabled modules in order.
+    pub fn enabled(&self) -> impl Iterator<Item = ModuleId> + '_ {
+        self.order.iter().copied().filter(|id| !self.disabled.contains(id))
+    }
+
+    /// Enable or disable a module.
+    pub fn set_enabled(&mut self, id: ModuleId, enabled: bool) {
+        self.disabled.retain(|disabled| disabled != &id);
+        if !enabled {
+            self.disabled.push(id);
+        }
+    }
+
+    /// Move a module to a new position within the enabled modules.
+    pub fn move_module(&mut self, id: ModuleId, position: usize) {
+        // Find the module currently occupying the target position.
+        let target = self.enabled().filter(|enabled| enabled != &id).nth(position);
+
+        self.order.retain(|module| module != &id);
+        let index = target
+            .and_then(|target| self.order.iter().position(|module| module == &target))
+            .unwrap_or(self.order.len());
+        self.order.insert(index, id);
+    }
+}
+
+/// Location of the layout state file.
+fn layout_path() -> Option<PathBuf> {
+    Some(config::state_home()?.join(LAYOUT_FILE))
+}
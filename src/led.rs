@@ -0,0 +1,167 @@
+//! Notification LED.
+
+use std::str::FromStr;
+
+use epitaph_ui::theme::Color;
+use udev::Device;
+
+use crate::config::{self, LedPattern};
+use crate::error::Error;
+use crate::module::{self, Urgency};
+use crate::Result;
+
+/// Power supply state shown while no notification is pending.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PowerState {
+    Battery,
+    Charging,
+    Full,
+}
+
+/// Notification LED state.
+pub struct Led {
+    config: Option<config::Led>,
+    pending: Option<Urgency>,
+    power: PowerState,
+}
+
+impl Led {
+    pub fn new(config: &config::Led) -> Self {
+        let config = config.enabled.then(|| config.clone());
+        Self { config, pending: None, power: PowerState::Battery }
+    }
+
+    /// Signal a new notification.
+    ///
+    /// The LED keeps showing the most urgent notification until they are dismissed.
+    pub fn notify(&mut self, urgency: Urgency) -> Result<()> {
+        if self.pending.map_or(true, |pending| pending < urgency) {
+            self.pending = Some(urgency);
+            self.apply()?;
+        }
+        Ok(())
+    }
+
+    /// Clear all pending notifications.
+    pub fn dismiss(&mut self) -> Result<()> {
+        if self.pending.take().is_some() {
+            self.apply()?;
+        }
+        Ok(())
+    }
+
+    /// Update the power supply state.
+    pub fn set_power_state(&mut self, power: PowerState) -> Result<()> {
+        if self.power != power {
+            self.power = power;
+            self.apply()?;
+        }
+        Ok(())
+    }
+
+    /// Write the current state to all notification LED channels.
+    fn apply(&self) -> Result<()> {
+        let config = match &self.config {
+            Some(config) => config,
+            None => return Ok(()),
+        };
+
+        // Pending notifications take priority over the charging state.
+        let pattern = match (self.pending, self.power) {
+            (Some(Urgency::Critical), _) => Some(config.critical),
+            (Some(Urgency::Normal), _) => Some(config.normal),
+            (None, PowerState::Charging) => config.charging.map(LedPattern::steady),
+            (None, PowerState::Full) => config.full.map(LedPattern::steady),
+            (None, PowerState::Battery) => None,
+        };
+
+        for mut led in module::devices("leds")? {
+            if let Some(channels) = Channels::from_device(&led) {
+                channels.write(&mut led, pattern)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Color channels driven by an LED device.
+enum Channels {
+    /// Multicolor LED, with the channel order of its `multi_index`.
+    Multi(Vec<usize>),
+    /// Single color LED, like `red:indicator`.
+    Single(usize),
+}
+
+impl Channels {
+    /// Get the color channels of an indicator LED.
+    fn from_device(device: &Device) -> Option<Self> {
+        let channel = |name: &str| ["red", "green", "blue"].iter().position(|color| *color == name);
+
+        if let Some(index) = device.attribute_value("multi_index") {
+            let channels: Option<Vec<_>> =
+                index.to_string_lossy().split_whitespace().map(channel).collect();
+            return channels.map(Self::Multi);
+        }
+
+        // Ignore flashlights and vibrators, which use the same naming scheme.
+        let sysname = device.sysname().to_string_lossy();
+        let mut parts = sysname.split(':');
+        if !parts.clone().any(|part| part == "indicator" || part == "status") {
+            return None;
+        }
+        parts.find_map(channel).map(Self::Single)
+    }
+
+    /// Show a pattern on the LED, turning it off for `None`.
+    fn write(&self, led: &mut Device, pattern: Option<LedPattern>) -> Result<()> {
+        let max_brightness = led
+            .attribute_value("max_brightness")
+            .and_then(|max| u32::from_str(&max.to_string_lossy()).ok())
+            .unwrap_or(1);
+        let rgb = pattern.map_or([0; 3], |pattern| pattern.color.rgb());
+        let scale = |component: u8| (component as u32 * max_brightness + 254) / 255;
+
+        let mut set = |attribute, value: &str| {
+            led.set_attribute_value(attribute, value)
+                .map_err(|source| Error::Udev { subsystem: "leds", source })
+        };
+
+        // Reset any previous blinking before changing the brightness.
+        set("trigger", "none")?;
+
+        let brightness = match self {
+            Self::Multi(channels) => {
+                let intensities: Vec<_> =
+                    channels.iter().map(|channel| scale(rgb[*channel]).to_string()).collect();
+                set("multi_intensity", &intensities.join(" "))?;
+                if rgb == [0; 3] {
+                    0
+                } else {
+                    max_brightness
+                }
+            },
+            Self::Single(channel) => scale(rgb[*channel]),
+        };
+        set("brightness", &brightness.to_string())?;
+
+        // Blink using the timer trigger, which keeps the configured brightness.
+        match pattern {
+            Some(pattern) if brightness > 0 && pattern.off > 0 => {
+                set("trigger", "timer")?;
+                set("delay_on", &pattern.on.to_string())?;
+                set("delay_off", &pattern.off.to_string())?;
+            },
+            _ => (),
+        }
+
+        Ok(())
+    }
+}
+
+impl LedPattern {
+    /// Pattern permanently showing a color.
+    fn steady(color: Color) -> Self {
+        Self { color, on: 0, off: 0 }
+    }
+}
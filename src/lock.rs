@@ -0,0 +1,91 @@
+//! Session lock status.
+
+use std::env;
+use std::io::{self, ErrorKind, Read};
+use std::process::{Command, Output};
+
+use calloop::generic::Generic;
+use calloop::timer::{TimeoutAction, Timer};
+use calloop::{Interest, LoopHandle, Mode, PostAction};
+
+use crate::module::ProcessStream;
+use crate::{Result, State};
+
+/// Signals emitted by logind when a session's lock status might have changed.
+const LOCK_SIGNALS: [&str; 3] = [
+    "org.freedesktop.login1.Session.Lock",
+    "org.freedesktop.login1.Session.Unlock",
+    "'LockedHint'",
+];
+
+/// Watch logind's session lock hint.
+///
+/// Since ext-session-lock does not expose the lock status to other clients,
+/// this relies on lockers updating logind's `LockedHint`.
+pub fn watch(event_loop: &LoopHandle<'static, State>) -> Result<()> {
+    let session = env::var("XDG_SESSION_ID").unwrap_or_else(|_| "auto".into());
+
+    let mut gdbus = Command::new("gdbus");
+    gdbus.args(["monitor", "--system", "--dest", "org.freedesktop.login1"]);
+    let stream = ProcessStream::spawn(gdbus)?;
+
+    // Get the initial lock status.
+    let initial_session = session.clone();
+    event_loop.insert_source(Timer::immediate(), move |_, _, state| {
+        update_locked(state, &initial_session);
+        TimeoutAction::Drop
+    })?;
+
+    let mut buffer = Vec::new();
+    let source = Generic::new(stream, Interest::READ, Mode::Level);
+    event_loop.insert_source(source, move |_, stream, state| {
+        let mut data = [0; 1024];
+        let len = match stream.read(&mut data) {
+            Ok(len) if len > 0 => len,
+            Err(err) if err.kind() == ErrorKind::WouldBlock => return Ok(PostAction::Continue),
+            _ => {
+                eprintln!("Error: Stopped watching session lock status");
+                return Ok(PostAction::Remove);
+            },
+        };
+        buffer.extend_from_slice(&data[..len]);
+
+        // Signals don't identify our session by ID, so query its status again.
+        let mut changed = false;
+        while let Some(end) = buffer.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            changed |= LOCK_SIGNALS.iter().any(|signal| line.contains(signal));
+        }
+
+        if changed {
+            update_locked(state, &session);
+        }
+
+        Ok(PostAction::Continue)
+    })?;
+
+    Ok(())
+}
+
+/// Query logind's `LockedHint` for a session.
+fn update_locked(state: &mut State, session: &str) {
+    let mut loginctl = Command::new("loginctl");
+    loginctl.args(["show-session", session, "--property=LockedHint", "--value"]);
+    let output = state.runtime.command_output(loginctl);
+    state.runtime.spawn(async move {
+        let output = output.await;
+        move |state: &mut State| loginctl_callback(state, output)
+    });
+}
+
+/// Handle `loginctl` command completion.
+fn loginctl_callback(state: &mut State, output: io::Result<Output>) {
+    let output = match output {
+        Ok(output) if output.status.success() => output,
+        _ => return,
+    };
+
+    let locked = String::from_utf8_lossy(&output.stdout).trim() == "yes";
+    state.set_locked(locked);
+}
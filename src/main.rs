@@ -1,16 +1,25 @@
-use std::error::Error;
+use std::collections::HashMap;
 use std::ffi::CString;
-use std::ops::Mul;
-use std::process;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::result::Result as StdResult;
+use std::sync::Mutex;
 use std::time::{Duration, Instant};
+use std::{env, process, thread};
 
 use calloop::timer::{TimeoutAction, Timer};
-use calloop::{EventLoop, LoopHandle};
+use calloop::{EventLoop, LoopHandle, RegistrationToken};
+use epitaph_ui::gl;
+use epitaph_ui::theme::Theme;
+use glutin::api::egl::config::Config as EglConfig;
 use glutin::api::egl::display::Display;
 use glutin::config::ConfigTemplateBuilder;
+use glutin::display::DisplayFeatures;
 use glutin::prelude::*;
 use raw_window_handle::{RawDisplayHandle, WaylandDisplayHandle};
+use smithay_client_toolkit::activation::{
+    ActivationHandler, ActivationState, RequestData, RequestDataExt,
+};
 use smithay_client_toolkit::compositor::{CompositorHandler, CompositorState};
 use smithay_client_toolkit::event_loop::WaylandSource;
 use smithay_client_toolkit::output::{OutputHandler, OutputState};
@@ -20,6 +29,7 @@ use smithay_client_toolkit::reexports::client::protocol::wl_seat::WlSeat;
 use smithay_client_toolkit::reexports::client::protocol::wl_surface::WlSurface;
 use smithay_client_toolkit::reexports::client::protocol::wl_touch::WlTouch;
 use smithay_client_toolkit::reexports::client::{Connection, EventQueue, Proxy, QueueHandle};
+use smithay_client_toolkit::reexports::protocols::wp::viewporter::client::wp_viewporter::WpViewporter;
 use smithay_client_toolkit::registry::{ProvidesRegistryState, RegistryState};
 use smithay_client_toolkit::seat::touch::TouchHandler;
 use smithay_client_toolkit::seat::{Capability, SeatHandler, SeatState};
@@ -27,49 +37,154 @@ use smithay_client_toolkit::shell::layer::{
     LayerShell, LayerShellHandler, LayerSurface, LayerSurfaceConfigure,
 };
 use smithay_client_toolkit::{
-    delegate_compositor, delegate_layer, delegate_output, delegate_registry, delegate_seat,
-    delegate_touch, registry_handlers,
+    delegate_activation, delegate_compositor, delegate_layer, delegate_output, delegate_registry,
+    delegate_seat, delegate_touch, registry_handlers,
 };
 
+use crate::bench::Bench;
+use crate::config::{Config, DrawerLink, Kiosk, OutputProfile};
 use crate::drawer::Drawer;
+use crate::error::Error;
+#[cfg(feature = "udev")]
+use crate::haptics::Haptics;
+use crate::layout::{ModuleId, ModuleLayout};
+#[cfg(feature = "udev")]
+use crate::led::Led;
+use crate::metrics::{Metrics, Surface};
+#[cfg(feature = "udev")]
 use crate::module::battery::Battery;
+#[cfg(feature = "udev")]
 use crate::module::brightness::Brightness;
+use crate::module::cast::Cast;
 use crate::module::cellular::Cellular;
+use crate::module::clipboard::Clipboard;
 use crate::module::clock::Clock;
+use crate::module::dnd::DoNotDisturb;
+#[cfg(feature = "udev")]
+use crate::module::dock::Dock;
+use crate::module::ethernet::Ethernet;
+#[cfg(feature = "udev")]
 use crate::module::flashlight::Flashlight;
+use crate::module::hud::Hud;
+use crate::module::hyprland::Hyprland;
+use crate::module::inspector::Inspector;
+use crate::module::journal::Journal;
+use crate::module::location::Location;
+use crate::module::mail::Mail;
 use crate::module::orientation::Orientation;
+use crate::module::osd::Osd;
+use crate::module::overflow::Overflow;
+use crate::module::pipe::Pipe;
+#[cfg(feature = "udev")]
+use crate::module::privacy::Privacy;
+use crate::module::recorder::ScreenRecorder;
+use crate::module::river::River;
+use crate::module::rotation::Rotation;
+use crate::module::screenshot::Screenshot;
+use crate::module::sway::{Sway, SwayTitle};
+use crate::module::system::System;
+use crate::module::systemd::Systemd;
+use crate::module::timer::Timer;
+use crate::module::updates::Updates;
+#[cfg(feature = "udev")]
+use crate::module::usb::Usb;
 use crate::module::wifi::Wifi;
-use crate::module::Module;
-use crate::panel::Panel;
-use crate::reaper::Reaper;
-
+use crate::module::wireguard::Wireguard;
+use crate::module::{DrawerModule, ErrorBadge, Failure, Module, Signals};
+use crate::notification::Notifications;
+use crate::panel::{Panel, PanelRole};
+use crate::proximity::Proximity;
+use crate::runtime::Runtime;
+use crate::seat::{Seats, TouchId};
+use crate::sound::Sound;
+use crate::tablet::Tablets;
+
+mod bench;
+mod client;
+mod config;
 mod drawer;
+mod error;
+#[cfg(feature = "udev")]
+mod haptics;
+mod ipc;
+mod layout;
+#[cfg(feature = "udev")]
+mod led;
+mod lock;
+mod metrics;
 mod module;
+mod notification;
 mod panel;
+#[cfg(feature = "profiling")]
+mod profiling;
+mod proximity;
 mod reaper;
-mod renderer;
-mod text;
-mod vertex;
+mod runtime;
+mod seat;
+mod sleep;
+mod snapshot;
+mod sound;
+mod tablet;
+mod traffic;
 
-mod gl {
-    #![allow(clippy::all)]
-    include!(concat!(env!("OUT_DIR"), "/gl_bindings.rs"));
-}
+/// Time between drawer animation updates in power saving mode.
+const POWER_SAVING_ANIMATION_INTERVAL: Duration = Duration::from_millis(1000 / 30);
+
+/// Maximum touch movement for it to still be considered a tap.
+const TAP_SLOP: f64 = 10.;
 
-/// Time between drawer animation updates.
-const ANIMATION_INTERVAL: Duration = Duration::from_millis(1000 / 120);
+/// Maximum time between two panel taps for them to be considered a double-tap.
+const DOUBLE_TAP_INTERVAL: Duration = Duration::from_millis(300);
 
-/// Height percentage when drawer animation starts opening instead
-/// of closing.
-const ANIMATION_THRESHOLD: f64 = 0.25;
+/// Maximum rendering scale accepted as override.
+pub const MAX_SCALE_FACTOR: i32 = 4;
 
-/// Step size for drawer animation.
-const ANIMATION_STEP: f64 = 20.;
+/// Delay between attempts to reconnect to the compositor.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
 
 /// Convenience result wrapper.
-pub type Result<T> = StdResult<T, Box<dyn Error>>;
+pub type Result<T> = StdResult<T, Error>;
 
 fn main() {
+    // Send IPC messages to the running instance.
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map_or(false, |arg| arg == "msg") {
+        if let Err(err) = ipc::send_message(&args[1..]) {
+            eprintln!("Error: {err}");
+            process::exit(1);
+        }
+        return;
+    }
+
+    // Write the documented default configuration.
+    if args.first().map_or(false, |arg| arg == "generate-config") {
+        match config::generate() {
+            Ok(path) => println!("Wrote default configuration to {path:?}"),
+            Err(err) => {
+                eprintln!("Error: {err}");
+                process::exit(1);
+            },
+        }
+        return;
+    }
+
+    // Render serialized state for golden image comparisons.
+    if args.first().map_or(false, |arg| arg == "--render-to-png") {
+        let path = match args.get(1) {
+            Some(path) => Path::new(path),
+            None => {
+                eprintln!("Usage: epitaph --render-to-png <STATE_JSON>");
+                process::exit(1);
+            },
+        };
+
+        if let Err(err) = snapshot::render_to_png(path) {
+            eprintln!("Error: {err}");
+            process::exit(1);
+        }
+        return;
+    }
+
     // Initialize Wayland connection.
     let mut connection = match Connection::connect_to_env() {
         Ok(connection) => connection,
@@ -78,40 +193,136 @@ fn main() {
             process::exit(1);
         },
     };
-    let (globals, mut queue) =
-        globals::registry_queue_init(&connection).expect("initialize registry queue");
+
+    // Rebuild all state when the compositor restarts.
+    loop {
+        match run(connection, &args) {
+            Ok(()) => break,
+            Err(Error::ConnectionLost(err)) => {
+                eprintln!("Error: Lost Wayland connection: {err}");
+                connection = reconnect();
+            },
+            // Restarting won't help with errors unrelated to the compositor connection.
+            Err(err) => {
+                eprintln!("Error: {err}");
+                process::exit(1);
+            },
+        }
+    }
+}
+
+/// Run the shell until it is terminated or the Wayland connection is lost.
+fn run(mut connection: Connection, args: &[String]) -> Result<()> {
+    let (globals, mut queue) = globals::registry_queue_init(&connection)
+        .map_err(|err| Error::ConnectionLost(Box::new(err.into())))?;
 
     // Initialize calloop event loop.
-    let mut event_loop = EventLoop::try_new().expect("initialize event loop");
+    let mut event_loop = EventLoop::try_new()?;
+
+    // Load user configuration.
+    let config = Config::load()?;
+
+    // Record spans for flamegraph generation.
+    #[cfg(feature = "profiling")]
+    if let Err(err) = profiling::init(&event_loop.handle()) {
+        eprintln!("Error: Couldn't start profiler: {err}");
+    }
 
     // Setup shared state.
-    let mut state = State::new(&mut connection, &globals, &mut queue, event_loop.handle())
-        .expect("state setup");
+    let mut state = State::new(&mut connection, &globals, &mut queue, event_loop.handle(), config)?;
+
+    // Overlay renderer performance statistics.
+    if args.iter().any(|arg| arg == "--debug-hud") {
+        state.modules.hud = Some(Hud::default());
+    }
+
+    // Visualize touch input and gesture state.
+    if args.iter().any(|arg| arg == "--debug-input") {
+        state.modules.inspector = Some(Inspector::default());
+    }
+
+    // Replay drawer animation to measure frame times.
+    if args.iter().any(|arg| arg == "--bench") {
+        if let Err(err) = Bench::start(&mut state) {
+            eprintln!("Error: Couldn't start benchmark: {err}");
+            process::exit(1);
+        }
+    }
 
     // Insert wayland source into calloop loop.
-    let wayland_source = WaylandSource::new(queue).expect("wayland source creation");
-    wayland_source.insert(event_loop.handle()).expect("wayland source registration");
+    let wayland_source =
+        WaylandSource::new(queue).map_err(|err| Error::ConnectionLost(Box::new(err.into())))?;
+    wayland_source.insert(event_loop.handle())?;
 
     // Start event loop.
     while !state.terminated {
         // Dispatch Wayland & Calloop event queue.
-        event_loop.dispatch(None, &mut state).expect("event dispatch");
+        if let Err(err) = event_loop.dispatch(None, &mut state) {
+            // Only restart once the compositor is gone, not for failing event sources.
+            if connection.roundtrip().is_err() {
+                return Err(Error::ConnectionLost(Box::new(err.into())));
+            }
+            return Err(err.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Wait until the compositor accepts connections again.
+fn reconnect() -> Connection {
+    loop {
+        thread::sleep(RECONNECT_INTERVAL);
+
+        if let Ok(connection) = Connection::connect_to_env() {
+            return connection;
+        }
     }
 }
 
 /// Wayland protocol handler state.
 pub struct State {
     event_loop: LoopHandle<'static, Self>,
+    egl_config: Option<EglConfig>,
+    srgb: bool,
     protocol_states: ProtocolStates,
-    active_touch: Option<i32>,
-    drawer_opening: bool,
-    drawer_offset: f64,
-    last_touch_y: f64,
+    queue: QueueHandle<Self>,
+    input_serial: Option<(WlSeat, u32)>,
+    drawer_dismissed: bool,
+    drawer_requested: bool,
+    drawer_release_timer: Option<RegistrationToken>,
+    double_tap_timer: Option<RegistrationToken>,
+    drawer_release_delay: Duration,
+    drawer_opacity: f32,
+    drawer_animation: config::DrawerAnimation,
+    drawer_transition: Option<(Instant, f64)>,
+    drawer_animation_timer: Option<RegistrationToken>,
     modules: Modules,
     terminated: bool,
-    reaper: Reaper,
-
-    touch: Option<WlTouch>,
+    runtime: Runtime,
+    metrics: Option<Metrics>,
+    bench: Option<Bench>,
+    kiosk: Kiosk,
+    panel_config: config::Panel,
+    output_profiles: HashMap<String, OutputProfile>,
+    dock_profile: Option<String>,
+    panel_output: Option<WlOutput>,
+    scale_override: Option<i32>,
+    output_scale: i32,
+    proximity: Proximity,
+    theme: Theme,
+    accent_wallpaper: Option<PathBuf>,
+    notifications: Notifications,
+    sound: Sound,
+    power_saving: bool,
+    #[cfg(feature = "udev")]
+    haptics: Haptics,
+    #[cfg(feature = "udev")]
+    led: Led,
+
+    lock_panel: Option<Panel>,
+    seats: Seats,
+    tablets: Tablets,
     drawer: Option<Drawer>,
     panel: Option<Panel>,
 }
@@ -122,30 +333,111 @@ impl State {
         globals: &GlobalList,
         queue: &mut EventQueue<Self>,
         event_loop: LoopHandle<'static, Self>,
+        config: Config,
     ) -> Result<Self> {
         // Setup globals.
         let queue_handle = queue.handle();
-        let protocol_states = ProtocolStates::new(globals, &queue_handle);
+        let protocol_states = ProtocolStates::new(globals, &queue_handle)?;
+        let tablets = Tablets::new(globals, &queue_handle);
+
+        // Allow modules to request changes to the shell state.
+        let signals = Signals::new(&event_loop)?;
 
         // Initialize panel modules.
-        let modules = Modules::new(&event_loop)?;
+        let modules = Modules::new(&event_loop, globals, &queue_handle, &config, &signals);
+
+        // Create async runtime for module updates.
+        let runtime = Runtime::new(&event_loop)?;
+
+        // Listen for IPC messages.
+        if let Err(err) = ipc::spawn_server(&event_loop) {
+            eprintln!("Error: Couldn't start IPC server: {err}");
+        }
+
+        // Serve metrics if enabled.
+        let metrics = config.metrics.address.and_then(|address| {
+            match metrics::spawn_server(&event_loop, address) {
+                Ok(()) => Some(Metrics::default()),
+                Err(err) => {
+                    eprintln!("Error: Couldn't start metrics server: {err}");
+                    None
+                },
+            }
+        });
 
-        // Create process reaper.
-        let reaper = Reaper::new(&event_loop)?;
+        // Refresh state after resuming from suspend.
+        if let Err(err) = sleep::watch(&event_loop) {
+            eprintln!("Error: Couldn't watch for system resume: {err}");
+        }
+
+        // Watch for session lock to show companion surfaces.
+        if config.lock_screen.enabled {
+            if let Err(err) = lock::watch(&event_loop) {
+                eprintln!("Error: Couldn't watch session lock status: {err}");
+            }
+        }
+
+        // Watch the proximity sensor to blank the screen during calls.
+        if config.proximity.enabled {
+            if let Err(err) = proximity::watch(&event_loop) {
+                eprintln!("Error: Couldn't watch proximity sensor: {err}");
+            }
+        }
+
+        // Load the configured theme.
+        let mut theme = match config.theme.as_deref().map(config::load_theme) {
+            Some(Ok(theme)) => theme,
+            Some(Err(err)) => {
+                eprintln!("Error: Couldn't load theme: {err}");
+                Theme::default()
+            },
+            None => Theme::default(),
+        };
+        apply_wallpaper_accent(&mut theme, config.accent_wallpaper.as_deref());
 
         let mut state = Self {
             protocol_states,
+            theme,
+            accent_wallpaper: config.accent_wallpaper.clone(),
+            srgb: config.renderer.srgb,
+            notifications: Notifications::new(event_loop.clone()),
+            sound: Sound::new(config.sound),
+            power_saving: Default::default(),
+            #[cfg(feature = "udev")]
+            haptics: Haptics::new(&config.haptics),
+            #[cfg(feature = "udev")]
+            led: Led::new(&config.led),
             event_loop,
             modules,
-            reaper,
-            drawer_opening: Default::default(),
-            drawer_offset: Default::default(),
-            active_touch: Default::default(),
-            last_touch_y: Default::default(),
+            runtime,
+            queue: queue_handle,
+            drawer_release_delay: Duration::from_secs(config.drawer.release_delay),
+            drawer_opacity: config.drawer.opacity,
+            drawer_animation: config.drawer.animation,
+            drawer_transition: Default::default(),
+            drawer_animation_timer: Default::default(),
+            drawer_release_timer: Default::default(),
+            double_tap_timer: Default::default(),
+            drawer_dismissed: Default::default(),
+            drawer_requested: Default::default(),
+            input_serial: Default::default(),
             terminated: Default::default(),
+            bench: Default::default(),
+            kiosk: config.kiosk,
+            panel_config: config.panel,
+            output_profiles: config.outputs,
+            dock_profile: config.modules.dock.profile,
+            panel_output: Default::default(),
+            scale_override: Default::default(),
+            output_scale: 1,
+            proximity: Default::default(),
+            metrics,
+            egl_config: Default::default(),
+            lock_panel: Default::default(),
             drawer: Default::default(),
-            touch: Default::default(),
+            seats: Default::default(),
             panel: Default::default(),
+            tablets,
         };
 
         state.init_windows(connection, queue)?;
@@ -153,71 +445,675 @@ impl State {
         Ok(state)
     }
 
-    /// Initialize the panel/drawer windows and their EGL surfaces.
+    /// Initialize the panel window and its EGL surface.
     fn init_windows(
         &mut self,
         connection: &mut Connection,
         queue: &EventQueue<Self>,
     ) -> Result<()> {
-        let mut wayland_display = WaylandDisplayHandle::empty();
-        wayland_display.display = connection.display().id().as_ptr() as *mut _;
-        let raw_display_handle = RawDisplayHandle::Wayland(wayland_display);
+        let egl_config = egl_config(connection)?;
 
-        // Setup the OpenGL window.
-        let gl_display = unsafe { Display::new(raw_display_handle)? };
-
-        let template = ConfigTemplateBuilder::new()
-            .with_alpha_size(8)
-            .with_stencil_size(0)
-            .with_depth_size(0)
-            .build();
-
-        let egl_config = unsafe {
-            gl_display.find_configs(template)?.next().expect("no suitable EGL configs were found")
-        };
-
-        // Load the OpenGL symbols.
-        gl::load_with(|symbol| {
-            let symbol = CString::new(symbol).unwrap();
-            gl_display.get_proc_address(symbol.as_c_str()).cast()
-        });
+        // Fall back to regular blending without sRGB surface support.
+        let features = egl_config.display().supported_features();
+        self.srgb &= features.contains(DisplayFeatures::SRGB_FRAMEBUFFERS);
 
         // Setup panel window.
-        self.panel = Some(Panel::new(
+        let mut panel = Panel::new(
             &self.protocol_states.compositor,
             queue.handle(),
             &mut self.protocol_states.layer,
             &egl_config,
-        )?);
+            self.srgb,
+            PanelRole::Primary,
+            self.panel_config.clone(),
+        )?;
+        panel.set_theme(self.theme.clone())?;
+        self.panel = Some(panel);
+
+        self.egl_config = Some(egl_config);
+
+        Ok(())
+    }
+
+    /// Map the drawer window.
+    ///
+    /// The drawer and its modules are created the first time it is opened.
+    fn show_drawer(&mut self) -> Result<()> {
+        // Consider all notifications seen once the drawer is opened.
+        self.notifications.mark_read();
+        #[cfg(feature = "udev")]
+        if let Err(err) = self.led.dismiss() {
+            eprintln!("Error: Couldn't update notification LED: {err}");
+        }
+
+        // Keep GPU resources around while the drawer is in use.
+        if let Some(token) = self.drawer_release_timer.take() {
+            self.event_loop.remove(token);
+        }
+
+        if self.drawer.is_none() {
+            let egl_config = self.egl_config.as_ref().ok_or(Error::EglUninitialized)?;
+            let mut drawer = Drawer::new(self.queue.clone(), egl_config, self.srgb)?;
+            drawer.set_theme(self.theme.clone())?;
+            drawer.set_opacity(self.drawer_opacity);
+            drawer.set_panel_config(self.effective_panel_config());
+
+            // Require the kiosk PIN before showing any modules.
+            if let Some(pin) = self.kiosk.pin.as_ref().filter(|_| self.kiosk.enabled) {
+                drawer.set_pin(pin.clone());
+            }
+
+            // Visualize hit regions for input debugging.
+            if let Some(inspector) = &self.modules.inspector {
+                drawer.set_debug_touches(Some(inspector.touch_positions()));
+            }
+
+            self.drawer = Some(drawer);
+            self.modules.init_drawer(&self.event_loop);
+        }
+
+        let scale_factor = self.scale_factor();
+        let compositor = &self.protocol_states.compositor;
+        let layer_state = &mut self.protocol_states.layer;
+        let viewporter = self.protocol_states.viewporter.as_ref();
+        let drawer = self.drawer.as_mut().unwrap();
+        drawer.show(compositor, layer_state, viewporter)?;
+
+        // Apply scale overrides, since the compositor only reports changes.
+        drawer.set_scale_factor(scale_factor);
+
+        Ok(())
+    }
+
+    /// Unmap the drawer window.
+    ///
+    /// GPU resources are released once the drawer stayed hidden for the configured delay.
+    fn hide_drawer(&mut self) {
+        self.drawer().hide();
+
+        let timer = Timer::from_duration(self.drawer_release_delay);
+        let token = self.event_loop.insert_source(timer, |_, _, state| {
+            state.drawer_release_timer = None;
+            if let Err(err) = state.drawer().release_resources() {
+                eprintln!("Error: Couldn't release drawer resources: {err}");
+            }
+            TimeoutAction::Drop
+        });
+        self.drawer_release_timer = token.ok();
+    }
+
+    /// Recover from system suspend.
+    ///
+    /// Frame callbacks might have been lost and module state is outdated after resume.
+    fn resume(&mut self) {
+        self.modules.refresh(&self.event_loop);
+
+        // Keep the drawer within its bounds.
+        if let Some(drawer) = &mut self.drawer {
+            drawer.set_offset(drawer.offset().clamp(0., drawer.max_offset()));
+            drawer.discard_frame();
+        }
+        self.panel().discard_frame();
+        if let Some(lock_panel) = &mut self.lock_panel {
+            lock_panel.discard_frame();
+        }
+
+        self.request_frame();
+    }
+
+    /// Update proximity sensor or call state.
+    fn update_proximity<F: FnOnce(&mut Proximity)>(&mut self, update: F) {
+        let blanked = self.proximity.blanked();
+        update(&mut self.proximity);
+
+        if self.proximity.blanked() != blanked {
+            self.request_frame();
+        }
+    }
+
+    /// Abort all touch points of a touch device.
+    fn cancel_touch(&mut self, touch: &WlTouch) {
+        let drawer = match &mut self.drawer {
+            Some(drawer) => drawer,
+            None => return,
+        };
+
+        // Forget about modules touched by the device.
+        if drawer.touch_cancel(touch) {
+            drawer.request_frame();
+        }
+
+        // Let the drawer settle at its nearest resting position.
+        if drawer.cancel_gesture(touch) {
+            self.modules.osd = None;
+            self.start_drawer_animation();
+        }
+    }
+
+    /// Handle touch press events.
+    #[cfg_attr(feature = "profiling", tracing::instrument(skip_all))]
+    fn touch_down(&mut self, id: TouchId, surface: &WlSurface, position: (f64, f64)) {
+        // Ignore accidental touches while the phone is held to the ear.
+        if self.proximity.blanked() {
+            return;
+        }
+
+        self.inspect_touch(id, Some(position));
+
+        if !self.drawer_gesture_active() && self.panel().owns_surface(surface) {
+            if !self.drawer_allowed() {
+                return;
+            }
+
+            // Run the double-tap action instead of opening the drawer.
+            if let Some(token) = self.double_tap_timer.take() {
+                self.event_loop.remove(token);
+                self.double_tap();
+                return;
+            }
+
+            if let Err(err) = self.show_drawer() {
+                eprintln!("Error: Couldn't open drawer: {err}");
+                return;
+            }
+
+            // Remember the drawer link of the touched module, in case it is tapped.
+            let module = self.panel().module_at(position.0);
+            let link = module.and_then(|id| self.panel_config.links.get(&id).copied());
+
+            // Allow adjusting modules from the panel, unless the drawer is locked by the kiosk PIN.
+            let locked = self.drawer().pin_locked();
+            let slider_id =
+                module.filter(|id| !locked && self.panel_config.slider_modules.contains(id));
+            let slider = slider_id.and_then(|id| Some((id, self.modules.slider_value(id)?)));
+
+            // Take over the drawer from any running animation.
+            self.stop_drawer_animation();
+            self.drawer().start_panel_gesture(id, position, link, slider);
+            self.drawer_requested = false;
+            self.drawer_dismissed = false;
+        } else if let Some(drawer) =
+            self.drawer.as_mut().filter(|drawer| drawer.owns_surface(surface))
+        {
+            let touch_start = drawer.touch_down(id, position, &mut self.modules.as_slice_mut());
+
+            // Check drawer touch status.
+            if !touch_start.module_touched {
+                // Initiate closing drawer if no module was touched, stopping any animation.
+                self.stop_drawer_animation();
+                self.drawer_requested = false;
+                self.drawer_dismissed = false;
+            } else if touch_start.requires_redraw {
+                // Redraw to show press feedback.
+                self.request_frame();
+            }
+        }
+    }
+
+    /// Handle touch release events.
+    #[cfg_attr(feature = "profiling", tracing::instrument(skip_all))]
+    fn touch_up(&mut self, id: TouchId) {
+        self.inspect_touch(id, None);
+
+        let drawer = match &mut self.drawer {
+            Some(drawer) => drawer,
+            None => return,
+        };
+
+        let touch_end = drawer.touch_up(id, &mut self.modules.as_slice_mut());
+
+        // Move modules rearranged in the drawer, unless kiosk mode locks the layout.
+        if let Some((from, to)) = touch_end.reorder.filter(|_| !self.kiosk.enabled) {
+            self.modules.reorder(from, to);
+        }
+
+        if touch_end.requires_redraw || touch_end.reorder.is_some() {
+            self.request_frame();
+        }
+
+        // Hide the slider overlay once the panel is released.
+        if touch_end.slider_released && self.modules.osd.take().is_some() {
+            self.request_frame();
+        }
+
+        // Close the drawer after the scrim was tapped or actions which need it out of the way.
+        if touch_end.dismiss {
+            self.drawer_dismissed = true;
+        }
+
+        if let Some(link) = touch_end.link {
+            // Open the drawer at the tapped module's link target.
+            self.follow_link(link);
+        } else if touch_end.panel_tap && !self.panel_config.double_tap_command.is_empty() {
+            // Wait for a second tap on the panel.
+            let timer = Timer::from_duration(DOUBLE_TAP_INTERVAL);
+            let token = self.event_loop.insert_source(timer, |_, _, state| {
+                state.double_tap_timer = None;
+                TimeoutAction::Drop
+            });
+            self.double_tap_timer = token.ok();
+        }
+
+        // Start drawer animation.
+        if touch_end.settle || touch_end.dismiss {
+            self.start_drawer_animation();
+        }
+    }
+
+    /// Handle touch motion events.
+    #[cfg_attr(feature = "profiling", tracing::instrument(skip_all))]
+    fn touch_motion(&mut self, id: TouchId, position: (f64, f64)) {
+        self.inspect_touch(id, Some(position));
+
+        let drawer = match &mut self.drawer {
+            Some(drawer) => drawer,
+            None => return,
+        };
+
+        let touch_motion = drawer.touch_motion(id, position, &mut self.modules.as_slice_mut());
+
+        if touch_motion.requires_redraw {
+            self.request_frame();
+        }
+
+        // Continue vertical drags started on a slider as drawer gesture.
+        if touch_motion.drawer_drag {
+            self.stop_drawer_animation();
+        }
+
+        if let Some((id, value)) = touch_motion.panel_slider {
+            self.adjust_panel_slider(id, value);
+        }
+    }
+
+    /// Update the slider of the touched panel module.
+    fn adjust_panel_slider(&mut self, id: ModuleId, value: f64) {
+        let svg = match self.modules.get_mut(id).and_then(|module| module.drawer_module()) {
+            Some(DrawerModule::Slider(slider)) => {
+                let _ = slider.set_value(value);
+                slider.svg()
+            },
+            Some(DrawerModule::Toggle(toggle)) => {
+                let _ = toggle.set_level(value);
+                toggle.svg()
+            },
+            _ => return,
+        };
+
+        self.modules.osd = Some(Osd::new(svg, value));
+        self.request_frame();
+    }
+
+    /// Check if a touch is currently moving the drawer.
+    fn drawer_gesture_active(&self) -> bool {
+        self.drawer.as_ref().map_or(false, Drawer::has_gesture)
+    }
+
+    /// Visible height of the drawer.
+    fn drawer_offset(&self) -> f64 {
+        self.drawer.as_ref().map_or(0., Drawer::offset)
+    }
+
+    /// Update touch points shown by the input debug overlay.
+    fn inspect_touch(&mut self, id: TouchId, position: Option<(f64, f64)>) {
+        let inspector = match &mut self.modules.inspector {
+            Some(inspector) => inspector,
+            None => return,
+        };
+
+        inspector.set_touch(id, position);
+
+        if let Some(drawer) = &mut self.drawer {
+            drawer.set_debug_touches(Some(inspector.touch_positions()));
+        }
+
+        self.request_frame();
+    }
+
+    /// Profile of the output showing the primary panel.
+    ///
+    /// While docked, the dock profile takes precedence over the output's profile.
+    fn output_profile(&self) -> Option<&OutputProfile> {
+        #[cfg(feature = "udev")]
+        let docked = self.modules.builtin.dock.docked()
+            && !self.modules.failures.contains_key(&ModuleId::Dock);
+        #[cfg(not(feature = "udev"))]
+        let docked = false;
+        if let Some(profile) = self.dock_profile.as_ref().filter(|_| docked) {
+            return self.output_profiles.get(profile);
+        }
+
+        let info = self.protocol_states.output.info(self.panel_output.as_ref()?)?;
+        OutputProfile::find(&self.output_profiles, info.name.as_deref(), &info.model)
+    }
+
+    /// Panel configuration with the output profile applied.
+    fn effective_panel_config(&self) -> config::Panel {
+        let mut panel_config = self.panel_config.clone();
+        if let Some(profile) = self.output_profile() {
+            profile.apply(&mut panel_config);
+        }
+        panel_config
+    }
+
+    /// Update panels after the primary panel's output or the dock state changed.
+    fn apply_output_profile(&mut self) {
+        let panel_config = self.effective_panel_config();
+
+        if let Some(lock_panel) = &mut self.lock_panel {
+            lock_panel.set_config(panel_config.clone());
+        }
+        if let Some(drawer) = &mut self.drawer {
+            drawer.set_panel_config(panel_config.clone());
+        }
+        self.panel().set_config(panel_config);
+
+        self.apply_scale_factor();
+    }
+
+    /// Rendering scale, preferring overrides over the compositor's scale.
+    fn scale_factor(&self) -> i32 {
+        let profile_scale = || self.output_profile()?.scale.map(i32::from);
+        let scale = self.scale_override.or_else(profile_scale).unwrap_or(self.output_scale);
+        scale.clamp(1, MAX_SCALE_FACTOR)
+    }
+
+    /// Override the compositor's rendering scale, or reset it with `None`.
+    fn set_scale_override(&mut self, scale: Option<i32>) {
+        self.scale_override = scale;
+        self.apply_scale_factor();
+    }
+
+    /// Update the rendering scale of all windows.
+    fn apply_scale_factor(&mut self) {
+        let scale_factor = self.scale_factor();
+
+        self.panel().set_scale_factor(scale_factor);
+        if let Some(drawer) = &mut self.drawer {
+            drawer.set_scale_factor(scale_factor);
+        }
+        if let Some(lock_panel) = &mut self.lock_panel {
+            lock_panel.set_scale_factor(scale_factor);
+        }
+
+        self.request_frame();
+    }
+
+    /// Open the drawer on the page of a panel module's link target.
+    fn follow_link(&mut self, link: DrawerLink) {
+        let drawer = match &mut self.drawer {
+            Some(drawer) => drawer,
+            None => return,
+        };
+
+        match link {
+            DrawerLink::Section(section) => {
+                drawer.show_section(&mut self.modules.as_slice_mut(), section);
+            },
+            DrawerLink::Module(id) => {
+                // Modules are indexed by their position in the drawer.
+                if let Some(index) = self.modules.working().position(|working| working == id) {
+                    drawer.show_module(&mut self.modules.as_slice_mut(), index);
+                }
+            },
+        }
+
+        // Complete opening the drawer, regardless of the distance dragged.
+        self.drawer_requested = true;
+        drawer.request_frame();
+    }
+
+    /// Animate the drawer towards its resting position.
+    ///
+    /// Running animations are restarted from the current drawer offset.
+    fn start_drawer_animation(&mut self) {
+        self.stop_drawer_animation();
+
+        let token = self.event_loop.insert_source(Timer::immediate(), animate_drawer);
+        self.drawer_animation_timer = token.ok();
 
-        // Setup drawer window.
-        self.drawer = Some(Drawer::new(queue.handle(), &egl_config)?);
+        if let Some(drawer) = &mut self.drawer {
+            drawer.set_buffer_reuse(self.drawer_animation.reuse_buffer);
+        }
+    }
+
+    /// Stop the drawer animation, keeping the drawer at its current offset.
+    ///
+    /// This hands control over the drawer back to touch input.
+    fn stop_drawer_animation(&mut self) {
+        if let Some(token) = self.drawer_animation_timer.take() {
+            self.event_loop.remove(token);
+        }
+        self.drawer_transition = None;
+
+        if let Some(drawer) = &mut self.drawer {
+            drawer.set_buffer_reuse(false);
+        }
+    }
+
+    /// Check if the drawer may be opened.
+    fn drawer_allowed(&self) -> bool {
+        // Kiosk mode without PIN never opens the drawer.
+        if self.kiosk.enabled && self.kiosk.pin.is_none() {
+            return false;
+        }
+
+        // Output profiles can disable the drawer.
+        self.output_profile().map_or(true, |profile| profile.drawer)
+    }
+
+    /// Open or close the drawer without touch input.
+    fn toggle_drawer(&mut self) -> Result<()> {
+        // Leave the drawer alone while it is dragged.
+        if self.drawer_gesture_active() {
+            return Ok(());
+        }
+
+        if self.drawer_offset() > 0. && !self.drawer_dismissed {
+            self.drawer_requested = false;
+            self.drawer_dismissed = true;
+        } else if self.drawer_allowed() {
+            self.show_drawer()?;
+            self.drawer_dismissed = false;
+            self.drawer_requested = true;
+        } else {
+            return Ok(());
+        }
+
+        self.start_drawer_animation();
 
         Ok(())
     }
 
+    /// Run the panel's double-tap command.
+    fn double_tap(&mut self) {
+        let (program, args) = match self.panel_config.double_tap_command.split_first() {
+            Some(command) => command,
+            None => return,
+        };
+
+        let mut command = Command::new(program);
+        command.args(args);
+        self.launch(command);
+    }
+
+    /// Launch an application.
+    ///
+    /// The application receives an activation token for the latest touch
+    /// input, allowing it to take focus from the drawer.
+    fn launch(&mut self, command: Command) {
+        let activation = match &self.protocol_states.activation {
+            Some(activation) => activation,
+            None => return spawn_app(command),
+        };
+
+        let seat_and_serial = self.input_serial.clone();
+        let data = RequestData { app_id: None, seat_and_serial, surface: None };
+        let request = LaunchRequest { data, command: Mutex::new(Some(command)) };
+        activation.request_token_with_data(&self.queue, request);
+    }
+
+    /// Update the stylus hover position.
+    ///
+    /// Passing `None` clears the hover feedback.
+    fn hover(&mut self, surface: &WlSurface, position: Option<(f64, f64)>) {
+        if let Some(drawer) = self.drawer_for(surface) {
+            if drawer.set_hover(position) {
+                drawer.request_frame();
+            }
+        }
+    }
+
+    /// Update the session lock status.
+    ///
+    /// This creates or destroys the lock screen companion panel.
+    fn set_locked(&mut self, locked: bool) {
+        if locked == self.lock_panel.is_some() {
+            return;
+        }
+
+        if !locked {
+            self.lock_panel = None;
+            return;
+        }
+
+        let egl_config = match &self.egl_config {
+            Some(egl_config) => egl_config,
+            None => return,
+        };
+
+        match Panel::new(
+            &self.protocol_states.compositor,
+            self.queue.clone(),
+            &mut self.protocol_states.layer,
+            egl_config,
+            self.srgb,
+            PanelRole::LockScreen,
+            self.effective_panel_config(),
+        ) {
+            Ok(mut panel) => {
+                if let Err(err) = panel.set_theme(self.theme.clone()) {
+                    eprintln!("Error: Couldn't apply theme to lock screen panel: {err}");
+                }
+                panel.set_scale_factor(self.scale_factor());
+                self.lock_panel = Some(panel);
+            },
+            Err(err) => eprintln!("Error: Couldn't create lock screen panel: {err}"),
+        }
+    }
+
+    /// Switch all windows to a different theme.
+    fn set_theme(&mut self, mut theme: Theme) {
+        apply_wallpaper_accent(&mut theme, self.accent_wallpaper.as_deref());
+
+        let panels = self.panel.iter_mut().chain(self.lock_panel.iter_mut());
+        for panel in panels {
+            if let Err(err) = panel.set_theme(theme.clone()) {
+                eprintln!("Error: Couldn't apply theme to panel: {err}");
+            }
+        }
+
+        if let Some(drawer) = &mut self.drawer {
+            if let Err(err) = drawer.set_theme(theme.clone()) {
+                eprintln!("Error: Couldn't apply theme to drawer: {err}");
+            }
+        }
+
+        self.theme = theme;
+        self.request_frame();
+    }
+
     /// Draw window associated with the surface.
+    #[cfg_attr(feature = "profiling", tracing::instrument(skip_all))]
     fn draw(&mut self, surface: &WlSurface) {
-        if self.panel().owns_surface(surface) {
-            if let Err(error) = self.panel.as_mut().unwrap().draw(&self.modules.as_slice()) {
-                eprintln!("Panel rendering failed: {error:?}");
+        // Show the current gesture state in the input overlay.
+        if let Some(inspector) = &mut self.modules.inspector {
+            let drawer = self.drawer.as_ref();
+            let gesture = match drawer.and_then(Drawer::gesture) {
+                Some(gesture) => format!("{gesture:?}"),
+                None => "Idle".into(),
+            };
+            let offset = drawer.map_or(0., Drawer::offset);
+            inspector.set_gesture(format!("{gesture} {offset:.0}px"));
+        }
+
+        // Render surfaces without any modules while the screen is blanked.
+        let blanked = self.proximity.blanked();
+
+        let (window, stats) = if self.panel().owns_surface(surface) {
+            let panel = self.panel.as_mut().unwrap();
+            let modules = if blanked { Vec::new() } else { self.modules.as_slice() };
+            match panel.draw(&modules) {
+                Ok(_) if blanked => (),
+                // Update collapsed modules in the drawer.
+                Ok(collapsed) => {
+                    if self.modules.overflow.set_collapsed(collapsed) {
+                        if let Some(drawer) = &mut self.drawer {
+                            drawer.request_frame();
+                        }
+                    }
+                },
+                Err(error) => eprintln!("Panel rendering failed: {error:?}"),
             }
-        } else if self.drawer().owns_surface(surface) {
-            let drawer = self.drawer.as_mut().unwrap();
-            if let Err(error) = drawer.draw(
-                &self.protocol_states.compositor,
-                &mut self.modules.as_slice_mut(),
-                self.drawer_offset,
-            ) {
+            (Surface::Panel, panel.frame_stats())
+        } else if let Some(drawer) =
+            self.drawer.as_mut().filter(|drawer| drawer.owns_surface(surface))
+        {
+            let module_ids: Vec<_> =
+                if blanked { Vec::new() } else { self.modules.working().collect() };
+            let mut modules = if blanked { Vec::new() } else { self.modules.as_slice_mut() };
+            let compositor = &self.protocol_states.compositor;
+            if let Err(error) = drawer.draw(compositor, &mut modules, &module_ids) {
                 eprintln!("Drawer rendering failed: {error:?}");
             }
+
+            let stats = drawer.frame_stats();
+            if let Some(bench) = &mut self.bench {
+                bench.record(stats);
+            }
+            (Surface::Drawer, stats)
+        } else if let Some(lock_panel) =
+            self.lock_panel.as_mut().filter(|panel| panel.owns_surface(surface))
+        {
+            let modules = if blanked { Vec::new() } else { self.modules.lock_screen_slice() };
+            if let Err(error) = lock_panel.draw(&modules) {
+                eprintln!("Lock screen panel rendering failed: {error:?}");
+            }
+            (Surface::LockScreen, lock_panel.frame_stats())
+        } else {
+            return;
+        };
+
+        if let Some(metrics) = &mut self.metrics {
+            metrics.record_frame(window, stats);
+        }
+
+        // Show statistics for the next panel frame.
+        if let Some(hud) = &mut self.modules.hud {
+            hud.set_stats(stats);
         }
     }
 
     /// Request new frame for all windows.
     fn request_frame(&mut self) {
-        self.drawer().request_frame();
+        if let Some(drawer) = &mut self.drawer {
+            drawer.request_frame();
+        }
         self.panel().request_frame();
+
+        if let Some(lock_panel) = &mut self.lock_panel {
+            lock_panel.request_frame();
+        }
+    }
+
+    /// Get the lock screen panel if it owns the surface.
+    fn lock_panel_for(&mut self, surface: &WlSurface) -> Option<&mut Panel> {
+        self.lock_panel.as_mut().filter(|panel| panel.owns_surface(surface))
+    }
+
+    /// Get the drawer if it owns the surface.
+    fn drawer_for(&mut self, surface: &WlSurface) -> Option<&mut Drawer> {
+        self.drawer.as_mut().filter(|drawer| drawer.owns_surface(surface))
     }
 
     fn drawer(&mut self) -> &mut Drawer {
@@ -245,10 +1141,16 @@ impl CompositorHandler for State {
         surface: &WlSurface,
         factor: i32,
     ) {
+        // Keep rendering at the overridden scale.
+        self.output_scale = factor;
+        let factor = self.scale_factor();
+
         if self.panel().owns_surface(surface) {
             self.panel().set_scale_factor(factor);
-        } else if self.drawer().owns_surface(surface) {
-            self.drawer().set_scale_factor(factor);
+        } else if let Some(drawer) = self.drawer_for(surface) {
+            drawer.set_scale_factor(factor);
+        } else if let Some(lock_panel) = self.lock_panel_for(surface) {
+            lock_panel.set_scale_factor(factor);
         }
         self.draw(surface);
     }
@@ -273,24 +1175,38 @@ impl OutputHandler for State {
         &mut self,
         _connection: &Connection,
         _queue: &QueueHandle<Self>,
-        _output: WlOutput,
+        output: WlOutput,
     ) {
+        self.modules.builtin.river.add_output(&output);
+
+        // Mobile devices usually have just one output, so assume the panel is on the first.
+        if self.panel_output.is_none() {
+            self.panel_output = Some(output);
+            self.apply_output_profile();
+        }
     }
 
     fn update_output(
         &mut self,
         _connection: &Connection,
         _queue: &QueueHandle<Self>,
-        _output: WlOutput,
+        output: WlOutput,
     ) {
+        if self.panel_output.as_ref() == Some(&output) {
+            self.apply_output_profile();
+        }
     }
 
     fn output_destroyed(
         &mut self,
         _connection: &Connection,
         _queue: &QueueHandle<Self>,
-        _output: WlOutput,
+        output: WlOutput,
     ) {
+        if self.panel_output.as_ref() == Some(&output) {
+            self.panel_output = None;
+            self.apply_output_profile();
+        }
     }
 }
 
@@ -310,8 +1226,12 @@ impl LayerShellHandler for State {
         let surface = layer.wl_surface();
         if self.panel().owns_surface(surface) {
             self.panel.as_mut().unwrap().reconfigure(&self.protocol_states.compositor, configure);
-        } else if self.drawer().owns_surface(surface) {
-            self.drawer().reconfigure(configure);
+        } else if let Some(drawer) = self.drawer_for(surface) {
+            drawer.reconfigure(configure);
+        } else if let Some(lock_panel) =
+            self.lock_panel.as_mut().filter(|panel| panel.owns_surface(surface))
+        {
+            lock_panel.reconfigure(&self.protocol_states.compositor, configure);
         }
         self.draw(surface);
     }
@@ -322,7 +1242,17 @@ impl SeatHandler for State {
         &mut self.protocol_states.seat
     }
 
-    fn new_seat(&mut self, _: &Connection, _: &QueueHandle<Self>, _: WlSeat) {}
+    fn new_seat(&mut self, _: &Connection, queue: &QueueHandle<Self>, seat: WlSeat) {
+        self.seats.add_seat(seat.clone());
+
+        // Handle stylus input like touch.
+        if let Some(tablet) = self.tablets.tablet_seat(&seat, queue) {
+            self.seats.set_tablet(&seat, tablet);
+        }
+
+        self.modules.builtin.clipboard.set_seat(&seat);
+        self.modules.builtin.river.set_seat(seat);
+    }
 
     fn new_capability(
         &mut self,
@@ -331,8 +1261,13 @@ impl SeatHandler for State {
         seat: WlSeat,
         capability: Capability,
     ) {
-        if capability == Capability::Touch && self.touch.is_none() {
-            self.touch = self.protocol_states.seat.get_touch(queue, &seat).ok();
+        if capability != Capability::Touch || self.seats.has_touch(&seat) {
+            return;
+        }
+
+        match self.protocol_states.seat.get_touch(queue, &seat) {
+            Ok(touch) => self.seats.set_touch(&seat, touch),
+            Err(err) => eprintln!("Error: Couldn't get touch device: {err}"),
         }
     }
 
@@ -340,17 +1275,35 @@ impl SeatHandler for State {
         &mut self,
         _connection: &Connection,
         _queue: &QueueHandle<Self>,
-        _seat: WlSeat,
+        seat: WlSeat,
         capability: Capability,
     ) {
-        if capability != Capability::Touch {
-            if let Some(touch) = self.touch.take() {
-                touch.release();
+        if capability == Capability::Touch {
+            if let Some(touch) = self.seats.remove_touch(&seat) {
+                self.cancel_touch(&touch);
             }
         }
     }
 
-    fn remove_seat(&mut self, _: &Connection, _: &QueueHandle<Self>, _: WlSeat) {}
+    fn remove_seat(&mut self, _: &Connection, _: &QueueHandle<Self>, seat: WlSeat) {
+        if let Some(touch) = self.seats.remove_seat(&seat) {
+            self.cancel_touch(&touch);
+        }
+    }
+}
+
+impl ActivationHandler for State {
+    type RequestData = LaunchRequest;
+
+    fn new_token(&mut self, token: String, request: &LaunchRequest) {
+        let mut command = match request.command.lock().unwrap().take() {
+            Some(command) => command,
+            None => return,
+        };
+
+        command.env("XDG_ACTIVATION_TOKEN", token);
+        spawn_app(command);
+    }
 }
 
 impl TouchHandler for State {
@@ -358,97 +1311,45 @@ impl TouchHandler for State {
         &mut self,
         _connection: &Connection,
         _queue: &QueueHandle<Self>,
-        _touch: &WlTouch,
-        _serial: u32,
+        touch: &WlTouch,
+        serial: u32,
         _time: u32,
         surface: WlSurface,
         id: i32,
         position: (f64, f64),
     ) {
-        if self.active_touch.is_none() && self.panel().owns_surface(&surface) {
-            let compositor = &self.protocol_states.compositor;
-            let layer_state = &mut self.protocol_states.layer;
-            if let Err(err) = self.drawer.as_mut().unwrap().show(compositor, layer_state) {
-                eprintln!("Error: Couldn't open drawer: {err}");
-            }
-
-            self.last_touch_y = position.1;
-            self.active_touch = Some(id);
-            self.drawer_opening = true;
-        } else if self.drawer().owns_surface(&surface) {
-            let touch_start = self.drawer.as_mut().unwrap().touch_down(
-                id,
-                position,
-                &mut self.modules.as_slice_mut(),
-            );
-
-            // Check drawer touch status.
-            if !touch_start.module_touched {
-                // Initiate closing drawer if no module was touched.
-                self.last_touch_y = position.1;
-                self.active_touch = Some(id);
-                self.drawer_opening = false;
-            } else if touch_start.requires_redraw {
-                // Redraw if slider was touched.
-                self.request_frame();
-            }
-        }
+        self.input_serial = self.seats.touch_seat(touch).map(|seat| (seat.clone(), serial));
+        self.touch_down(TouchId::new(touch, id), &surface, position);
     }
 
     fn up(
         &mut self,
         _connection: &Connection,
         _queue: &QueueHandle<Self>,
-        _touch: &WlTouch,
-        _serial: u32,
+        touch: &WlTouch,
+        serial: u32,
         _time: u32,
         id: i32,
     ) {
-        if self.active_touch == Some(id) {
-            self.active_touch = None;
-
-            // Start drawer animation.
-            let _ = self.event_loop.insert_source(Timer::immediate(), animate_drawer);
-        } else {
-            let dirty =
-                self.drawer.as_mut().unwrap().touch_up(id, &mut self.modules.as_slice_mut());
-
-            if dirty {
-                self.request_frame();
-            }
-        }
+        self.input_serial = self.seats.touch_seat(touch).map(|seat| (seat.clone(), serial));
+        self.touch_up(TouchId::new(touch, id));
     }
 
     fn motion(
         &mut self,
         _connection: &Connection,
         _queue: &QueueHandle<Self>,
-        _touch: &WlTouch,
+        touch: &WlTouch,
         _time: u32,
         id: i32,
         position: (f64, f64),
     ) {
-        if self.active_touch == Some(id) {
-            let delta = position.1 - self.last_touch_y;
-            self.drawer_offset += delta;
-
-            self.last_touch_y = position.1;
-
-            self.drawer().request_frame();
-        } else {
-            let dirty = self.drawer.as_mut().unwrap().touch_motion(
-                id,
-                position,
-                &mut self.modules.as_slice_mut(),
-            );
-
-            if dirty {
-                self.request_frame();
-            }
-        }
+        self.touch_motion(TouchId::new(touch, id), position);
     }
 
-    fn cancel(&mut self, _connection: &Connection, _queue: &QueueHandle<Self>, _touch: &WlTouch) {}
+    fn cancel(&mut self, _connection: &Connection, _queue: &QueueHandle<Self>, touch: &WlTouch) {
+        self.cancel_touch(touch);
+    }
 
     fn shape(
         &mut self,
@@ -480,6 +1381,28 @@ delegate_touch!(State);
 
 delegate_registry!(State);
 
+delegate_activation!(State, LaunchRequest);
+
+/// Activation token request for an application launch.
+struct LaunchRequest {
+    data: RequestData,
+    command: Mutex<Option<Command>>,
+}
+
+impl RequestDataExt for LaunchRequest {
+    fn app_id(&self) -> Option<&str> {
+        self.data.app_id()
+    }
+
+    fn seat_and_serial(&self) -> Option<(&WlSeat, u32)> {
+        self.data.seat_and_serial()
+    }
+
+    fn surface(&self) -> Option<&WlSurface> {
+        self.data.surface()
+    }
+}
+
 #[derive(Debug)]
 struct ProtocolStates {
     compositor: CompositorState,
@@ -487,127 +1410,433 @@ struct ProtocolStates {
     output: OutputState,
     layer: LayerShell,
     seat: SeatState,
+    viewporter: Option<WpViewporter>,
+    activation: Option<ActivationState>,
 }
 
 impl ProtocolStates {
-    fn new(globals: &GlobalList, queue: &QueueHandle<State>) -> Self {
-        Self {
+    /// Bind all required globals.
+    ///
+    /// The global list is complete after the initial registry roundtrip, so
+    /// globals missing at this point are not supported by the compositor.
+    fn new(globals: &GlobalList, queue: &QueueHandle<State>) -> Result<Self> {
+        let compositor = CompositorState::bind(globals, queue)
+            .map_err(|err| Error::MissingGlobal("wl_compositor", err))?;
+        let layer = LayerShell::bind(globals, queue)
+            .map_err(|err| Error::MissingGlobal("zwlr_layer_shell_v1", err))?;
+
+        Ok(Self {
             registry: RegistryState::new(globals),
-            compositor: CompositorState::bind(globals, queue).expect("missing wl_compositor"),
-            layer: LayerShell::bind(globals, queue).expect("missing wlr_layer_shell"),
             output: OutputState::new(globals, queue),
             seat: SeatState::new(globals, queue),
-        }
+            viewporter: globals.bind(queue, 1..=1, ()).ok(),
+            activation: ActivationState::bind(globals, queue).ok(),
+            compositor,
+            layer,
+        })
+    }
+}
+
+epitaph_modules::registry! {
+    /// Modules addressable by their ID.
+    struct Builtin: ModuleId {
+        #[cfg(feature = "udev")]
+        Brightness => brightness: Brightness,
+        Clock => clock: Clock,
+        Cellular => cellular: Cellular,
+        Wifi => wifi: Wifi,
+        #[cfg(feature = "udev")]
+        Battery => battery: Battery,
+        Orientation => orientation: Orientation,
+        Rotation => rotation: Rotation,
+        #[cfg(feature = "udev")]
+        Flashlight => flashlight: Flashlight,
+        Sway => sway: Sway,
+        SwayTitle => sway_title: SwayTitle,
+        Hyprland => hyprland: Hyprland,
+        River => river: River,
+        Pipe => pipe: Pipe,
+        Location => location: Location,
+        Cast => cast: Cast,
+        #[cfg(feature = "udev")]
+        Usb => usb: Usb,
+        Timer => timer: Timer,
+        Mail => mail: Mail,
+        Updates => updates: Updates,
+        Systemd => systemd: Systemd,
+        Journal => journal: Journal,
+        Ethernet => ethernet: Ethernet,
+        Wireguard => wireguard: Wireguard,
+        #[cfg(feature = "udev")]
+        Privacy => privacy: Privacy,
+        #[cfg(feature = "udev")]
+        Dock => dock: Dock,
+        DoNotDisturb => do_not_disturb: DoNotDisturb,
+        Clipboard => clipboard: Clipboard,
+        Screenshot => screenshot: Screenshot,
+        ScreenRecorder => screen_recorder: ScreenRecorder,
+        System => system: System,
     }
 }
 
 /// Panel modules.
 struct Modules {
-    orientation: Orientation,
-    brightness: Brightness,
-    flashlight: Flashlight,
-    cellular: Cellular,
-    battery: Battery,
-    clock: Clock,
-    wifi: Wifi,
+    failures: HashMap<ModuleId, Failure>,
+    hidden: Vec<ModuleId>,
+    show_error_badge: bool,
+    hud: Option<Hud>,
+    inspector: Option<Inspector>,
+    osd: Option<Osd>,
+    layout: ModuleLayout,
+    overflow: Overflow,
+    builtin: Builtin,
 }
 
 impl Modules {
-    fn new(event_loop: &LoopHandle<'static, State>) -> Result<Self> {
-        Ok(Self {
+    fn new(
+        event_loop: &LoopHandle<'static, State>,
+        globals: &GlobalList,
+        queue: &QueueHandle<State>,
+        config: &Config,
+        signals: &Signals,
+    ) -> Self {
+        // Hide locked-down modules in kiosk mode.
+        let hidden =
+            if config.kiosk.enabled { config.kiosk.hidden_modules.clone() } else { Vec::new() };
+
+        let builtin = Builtin {
+            pipe: Pipe::new(config.modules.pipe.clone()),
+            location: Location::new(),
+            cast: Cast::new(config.modules.cast.clone()),
+            #[cfg(feature = "udev")]
+            usb: Usb::new(config.modules.usb.command.clone()),
+            timer: Timer::new(config.modules.timer.clone(), signals.clone()),
+            mail: Mail::new(config.modules.mail.clone(), signals.clone()),
+            updates: Updates::new(config.modules.updates.clone(), signals.clone()),
+            systemd: Systemd::new(),
+            journal: Journal::new(config.modules.journal.units.clone(), signals.clone()),
+            ethernet: Ethernet::new(),
+            wireguard: Wireguard::new(config.modules.wireguard.clone()),
+            #[cfg(feature = "udev")]
+            privacy: Privacy::new(),
+            #[cfg(feature = "udev")]
+            dock: Dock::new(signals.clone()),
+            do_not_disturb: DoNotDisturb::new(
+                config.modules.do_not_disturb.clone(),
+                signals.clone(),
+            ),
+            clipboard: Clipboard::new(globals, queue),
+            screenshot: Screenshot::new(config.modules.screenshot.clone(), signals.clone()),
+            screen_recorder: ScreenRecorder::new(
+                config.modules.screen_recorder.clone(),
+                signals.clone(),
+            ),
+            system: System::new(config.modules.system.clone()),
             orientation: Orientation::new(),
-            brightness: Brightness::new()?,
+            rotation: Rotation::new(globals, queue),
+            #[cfg(feature = "udev")]
+            brightness: Brightness::new(config.modules.brightness.clone()),
+            #[cfg(feature = "udev")]
             flashlight: Flashlight::new(),
-            cellular: Cellular::new(event_loop)?,
-            battery: Battery::new(event_loop)?,
-            clock: Clock::new(event_loop)?,
-            wifi: Wifi::new(event_loop)?,
+            hyprland: Hyprland::new(),
+            sway_title: SwayTitle::new(),
+            river: River::new(globals, queue),
+            cellular: Cellular::new(config.modules.cellular.clone(), signals.clone()),
+            #[cfg(feature = "udev")]
+            battery: Battery::new(signals.clone()),
+            clock: Clock::new(config.modules.clock.clone()),
+            wifi: Wifi::new(config.modules.wifi.clone(), signals.clone()),
+            sway: Sway::new(),
+        };
+
+        let mut modules = Self {
+            builtin,
+            hidden,
+            show_error_badge: config.modules.error_badge,
+            overflow: Overflow::new(),
+            layout: ModuleLayout::load(),
+            failures: Default::default(),
+            hud: Default::default(),
+            inspector: Default::default(),
+            osd: Default::default(),
+        };
+
+        // Defer drawer-only modules until the drawer is opened.
+        modules.init(event_loop, |module| !Self::drawer_only(module));
+
+        modules
+    }
+
+    /// Initialize modules which are only visible in the drawer.
+    fn init_drawer(&mut self, event_loop: &LoopHandle<'static, State>) {
+        self.init(event_loop, Self::drawer_only);
+    }
+
+    /// Check if a module is only visible in the drawer.
+    ///
+    /// Modules without any UI are not deferred, since they run in the background.
+    fn drawer_only(module: &mut dyn Module) -> bool {
+        module.panel_module().is_none() && module.drawer_module().is_some()
+    }
+
+    /// Reload the state of all working modules.
+    fn refresh(&mut self, event_loop: &LoopHandle<'static, State>) {
+        let ids: Vec<_> = self.working().collect();
+        for id in ids {
+            if let Some(Err(err)) = self.get_mut(id).map(|module| module.probe()) {
+                module::fail(event_loop, self, id, &err);
+            }
+        }
+    }
+
+    /// Probe modules and register their event sources.
+    ///
+    /// Modules which fail either step are disabled, without affecting other modules.
+    fn init<F>(&mut self, event_loop: &LoopHandle<'static, State>, filter: F)
+    where
+        F: Fn(&mut dyn Module) -> bool,
+    {
+        for id in ModuleId::ALL {
+            // Skip modules which were excluded from the build.
+            let module = match self.get_mut(id) {
+                Some(module) => module,
+                None => continue,
+            };
+
+            if !filter(module) {
+                continue;
+            }
+
+            // Disable modules which are unavailable on this device.
+            if let Err(err) = module.probe() {
+                module::fail(event_loop, self, id, &err);
+            }
+
+            // Register event sources for module updates.
+            let module = match self.get_mut(id) {
+                Some(module) => module,
+                None => continue,
+            };
+            if let Err(err) = module::register(event_loop, id, module) {
+                module::fail(event_loop, self, id, &err.into());
+
+                // Retry registration once the module recovers.
+                if let Some(failure) = self.failures.get_mut(&id) {
+                    failure.unregistered = true;
+                }
+            }
+        }
+    }
+
+    /// Get a module by its ID.
+    fn get_mut(&mut self, id: ModuleId) -> Option<&mut dyn Module> {
+        self.builtin.get_mut(id)
+    }
+
+    /// Get all enabled modules as sorted immutable slice.
+    fn as_slice(&self) -> Vec<(Option<ModuleId>, &dyn Module)> {
+        let mut builtin: HashMap<_, _> = self.builtin.all().into_iter().collect();
+        let mut modules: Vec<_> =
+            self.working().filter_map(|id| Some((Some(id), builtin.remove(&id)?))).collect();
+
+        // Indicate failures of enabled modules in the panel.
+        let failed = self.layout.enabled().any(|id| self.failures.contains_key(&id));
+        if self.show_error_badge && failed {
+            modules.push((None, &ErrorBadge));
+        }
+
+        // Show the value of sliders adjusted from the panel.
+        if let Some(osd) = &self.osd {
+            modules.push((None, osd));
+        }
+
+        // Show debug statistics overlay.
+        if let Some(hud) = &self.hud {
+            modules.push((None, hud));
+        }
+
+        // Show touch input debug overlay.
+        if let Some(inspector) = &self.inspector {
+            modules.push((None, inspector));
+        }
+
+        modules
+    }
+
+    /// Get modules shown on the lock screen as sorted immutable slice.
+    fn lock_screen_slice(&self) -> Vec<(Option<ModuleId>, &dyn Module)> {
+        let mut modules: Vec<(_, &dyn Module)> = vec![(Some(ModuleId::Clock), &self.builtin.clock)];
+        let battery = self.builtin.get(ModuleId::Battery);
+        if let Some(battery) = battery.filter(|_| !self.failures.contains_key(&ModuleId::Battery)) {
+            modules.push((Some(ModuleId::Battery), battery));
+        }
+        modules
+    }
+
+    /// Get all enabled modules as sorted mutable slice.
+    fn as_slice_mut(&mut self) -> Vec<&mut dyn Module> {
+        let mut builtin: HashMap<_, _> = self.builtin.all_mut().into_iter().collect();
+        let (failures, hidden) = (&self.failures, &self.hidden);
+        let mut modules: Vec<_> = self
+            .layout
+            .enabled()
+            .filter(|id| !failures.contains_key(id) && !hidden.contains(id))
+            .filter_map(|id| builtin.remove(&id))
+            .collect();
+
+        // Show modules which didn't fit into the panel.
+        modules.push(&mut self.overflow);
+
+        modules
+    }
+
+    /// Get the value of a visible working module's drawer slider or toggle level.
+    fn slider_value(&mut self, id: ModuleId) -> Option<f64> {
+        if self.failures.contains_key(&id) || self.hidden.contains(&id) {
+            return None;
+        }
+
+        match self.get_mut(id)?.drawer_module()? {
+            DrawerModule::Slider(slider) => Some(slider.get_value()),
+            DrawerModule::Toggle(toggle) => toggle.level(),
+            _ => None,
+        }
+    }
+
+    /// Iterate over all visible modules which have not failed.
+    fn working(&self) -> impl Iterator<Item = ModuleId> + '_ {
+        self.layout.enabled().filter(|id| {
+            !self.failures.contains_key(id)
+                && !self.hidden.contains(id)
+                && self.builtin.get(*id).is_some()
         })
     }
 
-    /// Get all modules as sorted immutable slice.
-    fn as_slice(&self) -> [&dyn Module; 7] {
-        [
-            &self.brightness,
-            &self.clock,
-            &self.cellular,
-            &self.wifi,
-            &self.battery,
-            &self.orientation,
-            &self.flashlight,
-        ]
-    }
-
-    /// Get all modules as sorted mutable slice.
-    fn as_slice_mut(&mut self) -> [&mut dyn Module; 7] {
-        [
-            &mut self.brightness,
-            &mut self.clock,
-            &mut self.cellular,
-            &mut self.wifi,
-            &mut self.battery,
-            &mut self.orientation,
-            &mut self.flashlight,
-        ]
+    /// Move a module from one position to another.
+    fn reorder(&mut self, from: usize, to: usize) {
+        let module = match self.working().nth(from) {
+            Some(module) => module,
+            None => return,
+        };
+
+        // Convert target position to include failed modules.
+        let position = self
+            .working()
+            .filter(|id| id != &module)
+            .nth(to)
+            .and_then(|target| {
+                self.layout.enabled().filter(|id| id != &module).position(|id| id == target)
+            })
+            .unwrap_or(usize::MAX);
+
+        self.layout.move_module(module, position);
+
+        if let Err(err) = self.layout.save() {
+            eprintln!("Error: Couldn't save module layout: {err}");
+        }
     }
 }
 
-#[derive(Copy, Clone, Default, Debug)]
-pub struct Size<T = i32> {
-    pub width: T,
-    pub height: T,
-}
+/// Setup OpenGL for the Wayland connection.
+fn egl_config(connection: &Connection) -> Result<EglConfig> {
+    let mut wayland_display = WaylandDisplayHandle::empty();
+    wayland_display.display = connection.display().id().as_ptr() as *mut _;
+    let raw_display_handle = RawDisplayHandle::Wayland(wayland_display);
 
-impl<T> Size<T> {
-    fn new(width: T, height: T) -> Self {
-        Self { width, height }
-    }
+    // Setup the OpenGL window.
+    let gl_display = unsafe { Display::new(raw_display_handle)? };
+
+    let template = ConfigTemplateBuilder::new()
+        .with_alpha_size(8)
+        .with_stencil_size(0)
+        .with_depth_size(0)
+        .build();
+
+    let egl_config =
+        unsafe { gl_display.find_configs(template)?.next().ok_or(Error::MissingEglConfig)? };
+
+    // Load the OpenGL symbols.
+    gl::load_with(|symbol| {
+        let symbol = CString::new(symbol).unwrap();
+        gl_display.get_proc_address(symbol.as_c_str()).cast()
+    });
+
+    Ok(egl_config)
 }
 
-impl From<Size> for Size<f32> {
-    fn from(from: Size) -> Self {
-        Self { width: from.width as f32, height: from.height as f32 }
+/// Derive the theme's accent color from the wallpaper.
+fn apply_wallpaper_accent(theme: &mut Theme, wallpaper: Option<&Path>) {
+    if let Some(Err(err)) = wallpaper.map(|path| theme.apply_wallpaper_accent(path)) {
+        eprintln!("Error: Couldn't derive accent color from wallpaper: {err}");
     }
 }
 
-impl Mul<f64> for Size {
-    type Output = Self;
-
-    fn mul(mut self, factor: f64) -> Self {
-        self.width = (self.width as f64 * factor) as i32;
-        self.height = (self.height as f64 * factor) as i32;
-        self
+/// Spawn an application launched by the user.
+fn spawn_app(command: Command) {
+    let program = command.get_program().to_owned();
+    if let Err(err) = reaper::detach(command) {
+        eprintln!("Error: Couldn't launch {program:?}: {err}");
     }
 }
 
 /// Drawer animation frame.
-fn animate_drawer(now: Instant, _: &mut (), state: &mut State) -> TimeoutAction {
+#[cfg_attr(feature = "profiling", tracing::instrument(skip_all))]
+fn animate_drawer(_: Instant, _: &mut (), state: &mut State) -> TimeoutAction {
     // Compute threshold beyond which motion will automatically be completed.
+    let animation = state.drawer_animation;
     let max_offset = state.drawer().max_offset();
-    let threshold = if state.drawer_opening {
-        max_offset * ANIMATION_THRESHOLD
+    let threshold = if state.drawer_dismissed {
+        // Always close the drawer after the scrim was tapped or a module dismissed it.
+        f64::INFINITY
+    } else if state.drawer_requested {
+        // Always open the drawer after a panel link or shortcut requested it.
+        0.
+    } else if state.drawer().opening() {
+        max_offset * animation.open_threshold
     } else {
-        max_offset - max_offset * ANIMATION_THRESHOLD
+        max_offset - max_offset * animation.close_threshold
     };
 
+    // Reduce frame rate in power saving mode.
+    let mut interval = Duration::from_millis(animation.interval);
+    if state.power_saving {
+        interval = interval.max(POWER_SAVING_ANIMATION_INTERVAL);
+    }
+
+    // Move based on elapsed time, so the speed is independent of timer precision.
+    let (start, start_offset) =
+        *state.drawer_transition.get_or_insert_with(|| (Instant::now(), state.drawer_offset()));
+    let duration = Duration::from_millis(animation.duration).as_secs_f64();
+    let distance = max_offset * start.elapsed().as_secs_f64() / duration;
+
     // Update drawer position.
-    if state.drawer_offset >= threshold {
-        state.drawer_offset += ANIMATION_STEP;
+    let drawer = state.drawer();
+    if drawer.offset() >= threshold {
+        drawer.set_offset(start_offset + distance);
     } else {
-        state.drawer_offset -= ANIMATION_STEP;
+        drawer.set_offset(start_offset - distance);
     }
 
-    if state.drawer_offset <= 0. {
-        state.drawer().hide();
+    if drawer.offset() <= 0. {
+        state.drawer_animation_timer = None;
+        state.drawer_transition = None;
+        state.drawer_dismissed = false;
+        state.hide_drawer();
 
         TimeoutAction::Drop
-    } else if state.drawer_offset >= state.drawer().max_offset() {
+    } else if drawer.offset() >= max_offset {
+        drawer.set_offset(max_offset);
+        state.drawer_animation_timer = None;
+        state.drawer_transition = None;
+        state.drawer_requested = false;
+        state.drawer().set_buffer_reuse(false);
         state.drawer().request_frame();
 
         TimeoutAction::Drop
     } else {
         state.drawer().request_frame();
 
-        TimeoutAction::ToInstant(now + ANIMATION_INTERVAL)
+        // Schedule relative to the current time, to avoid catching up on delayed frames.
+        TimeoutAction::ToDuration(interval)
     }
 }
@@ -0,0 +1,158 @@
+//! Prometheus metrics endpoint.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::Write;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::time::Duration;
+
+use calloop::generic::Generic;
+use calloop::{Interest, LoopHandle, Mode, PostAction};
+use epitaph_ui::renderer::FrameStats;
+
+use crate::layout::ModuleId;
+use crate::{client, Result, State};
+
+/// Rendered window with separately tracked frame statistics.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Surface {
+    Panel,
+    Drawer,
+    LockScreen,
+}
+
+impl Surface {
+    /// Metric label value.
+    const fn name(&self) -> &'static str {
+        match self {
+            Self::Panel => "panel",
+            Self::Drawer => "drawer",
+            Self::LockScreen => "lock_screen",
+        }
+    }
+}
+
+/// Accumulated runtime metrics.
+#[derive(Default)]
+pub struct Metrics {
+    frames: HashMap<Surface, Summary>,
+    updates: HashMap<ModuleId, Summary>,
+}
+
+impl Metrics {
+    /// Record a rendered frame.
+    pub fn record_frame(&mut self, surface: Surface, stats: FrameStats) {
+        self.frames.entry(surface).or_default().record(stats.frame_time);
+    }
+
+    /// Record the time spent handling a module update.
+    pub fn record_update(&mut self, id: ModuleId, duration: Duration) {
+        self.updates.entry(id).or_default().record(duration);
+    }
+
+    /// Prometheus text exposition of all metrics.
+    fn render(&self, battery: Option<u8>) -> String {
+        let mut text = String::new();
+
+        let _ = writeln!(text, "# HELP epitaph_frame_time_seconds Time spent rendering frames.");
+        let _ = writeln!(text, "# TYPE epitaph_frame_time_seconds summary");
+        for (surface, summary) in &self.frames {
+            summary.render(&mut text, "epitaph_frame_time_seconds", "surface", surface.name());
+        }
+
+        let _ = writeln!(text, "# HELP epitaph_module_update_seconds Time spent updating modules.");
+        let _ = writeln!(text, "# TYPE epitaph_module_update_seconds summary");
+        for (id, summary) in &self.updates {
+            summary.render(&mut text, "epitaph_module_update_seconds", "module", id.name());
+        }
+
+        if let Some(battery) = battery {
+            let _ = writeln!(text, "# HELP epitaph_battery_level_percent Battery capacity.");
+            let _ = writeln!(text, "# TYPE epitaph_battery_level_percent gauge");
+            let _ = writeln!(text, "epitaph_battery_level_percent {battery}");
+        }
+
+        text
+    }
+}
+
+/// Count and total duration of an event.
+#[derive(Default)]
+struct Summary {
+    count: u64,
+    sum: Duration,
+}
+
+impl Summary {
+    fn record(&mut self, duration: Duration) {
+        self.count += 1;
+        self.sum += duration;
+    }
+
+    /// Write the summary's samples.
+    fn render(&self, text: &mut String, name: &str, label: &str, value: &str) {
+        let sum = self.sum.as_secs_f64();
+        let _ = writeln!(text, "{name}_sum{{{label}=\"{value}\"}} {sum}");
+        let _ = writeln!(text, "{name}_count{{{label}=\"{value}\"}} {}", self.count);
+    }
+}
+
+/// Start serving metrics over HTTP.
+pub fn spawn_server(event_loop: &LoopHandle<'static, State>, address: SocketAddr) -> Result<()> {
+    let listener = TcpListener::bind(address)?;
+    listener.set_nonblocking(true)?;
+
+    let source = Generic::new(listener, Interest::READ, Mode::Level);
+    event_loop.insert_source(source, |_, listener, state| {
+        while let Ok((stream, _)) = listener.accept() {
+            if let Err(err) = add_client(&state.event_loop, stream) {
+                eprintln!("Error: Metrics client failed: {err}");
+            }
+        }
+
+        Ok(PostAction::Continue)
+    })?;
+
+    Ok(())
+}
+
+/// Wait for a client's HTTP request.
+fn add_client(event_loop: &LoopHandle<'static, State>, stream: TcpStream) -> Result<()> {
+    stream.set_nonblocking(true)?;
+    client::read_request(event_loop, stream, |state, stream, request| {
+        if let Err(err) = respond(state, stream, request) {
+            eprintln!("Error: Metrics client failed: {err}");
+        }
+    })
+}
+
+/// Answer a single HTTP request.
+fn respond(state: &State, stream: &mut TcpStream, request: &str) -> Result<()> {
+    // Headers are ignored, since only the request path matters.
+    let path = request.split_whitespace().nth(1).unwrap_or_default();
+
+    let metrics = match &state.metrics {
+        Some(metrics) if path == "/metrics" => metrics,
+        _ => {
+            stream.write_all(b"HTTP/1.1 404 Not Found\r\nConnection: close\r\n\r\n")?;
+            return Ok(());
+        },
+    };
+
+    // Omit battery level on devices without battery.
+    #[cfg(feature = "udev")]
+    let battery = Some(state.modules.builtin.battery.capacity())
+        .filter(|_| !state.modules.failures.contains_key(&ModuleId::Battery));
+    #[cfg(not(feature = "udev"))]
+    let battery = None;
+    let body = metrics.render(battery);
+
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+
+    Ok(())
+}
@@ -1,82 +1,146 @@
 //! Battery status and capacity.
 
+use std::path::PathBuf;
+use std::process::Command;
 use std::str::FromStr;
-use std::time::Duration;
+use std::time::{Duration, UNIX_EPOCH};
+use std::{fs, mem};
 
-use calloop::generic::Generic;
-use calloop::timer::{TimeoutAction, Timer};
-use calloop::{Interest, LoopHandle, Mode, PostAction};
-use udev::{Enumerator, MonitorBuilder};
+use epitaph_modules::{Error, Result};
+use epitaph_ui::text::Svg;
 
-use crate::module::{Alignment, Module, PanelModule, PanelModuleContent};
-use crate::text::Svg;
-use crate::{Result, State};
+use crate::config;
+use crate::led::PowerState;
+use crate::module::{
+    self, Alignment, DrawerModule, Event, Graph, Module, PanelModule, PanelModuleContent, Signal,
+    Signals, Source,
+};
+
+/// Capacity history file name inside the XDG state directory.
+const HISTORY_FILE: &str = "epitaph/battery_history";
+
+/// Time range of the capacity history graph.
+const HISTORY_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Maximum time between capacity history samples while the capacity is unchanged.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(10 * 60);
 
 /// Refresh interval for capacity updates.
 const UPDATE_INTERVAL: Duration = Duration::from_secs(60);
 
-pub struct Battery {
-    charging: bool,
-    capacity: u8,
-}
-
-impl Battery {
-    pub fn new(event_loop: &LoopHandle<'static, State>) -> Result<Self> {
-        // Create Udev device enumerator.
-        let mut socket_enumerator = Enumerator::new()?;
-        socket_enumerator.match_subsystem("power_supply")?;
-        let mut timer_enumerator = Enumerator::new()?;
-        timer_enumerator.match_subsystem("power_supply")?;
+/// Time between charging animation frames.
+const ANIMATION_INTERVAL: Duration = Duration::from_millis(500);
 
-        // Create udev socket event source.
-        let udev_socket = MonitorBuilder::new()?.match_subsystem("power_supply")?.listen()?;
-        let udev_source = Generic::new(udev_socket, Interest::READ, Mode::Edge);
+/// Refresh interval for the power profile.
+const PROFILE_INTERVAL: Duration = Duration::from_secs(30);
 
-        // Register udev socket for charging status changes.
-        event_loop.insert_source(udev_source, move |_, _, state| {
-            Self::update(&mut socket_enumerator, state);
+/// Capacity in percent below which power saving mode is enabled while discharging.
+const LOW_CAPACITY: u8 = 20;
 
-            // Request new frame.
-            state.request_frame();
+/// Index of the charging animation timer in the module's sources.
+const ANIMATION_SOURCE: usize = 2;
 
-            Ok(PostAction::Continue)
-        })?;
+/// Index of the power profile command in the module's sources.
+const PROFILE_SOURCE: usize = 3;
 
-        // Register timer for battery capacity updates.
-        event_loop.insert_source(Timer::immediate(), move |now, _, state| {
-            Self::update(&mut timer_enumerator, state);
+/// Charging SVGs in ascending capacity.
+const CHARGING_SVGS: [Svg; 5] = [
+    Svg::BatteryCharging20,
+    Svg::BatteryCharging40,
+    Svg::BatteryCharging60,
+    Svg::BatteryCharging80,
+    Svg::BatteryCharging100,
+];
 
-            // NOTE: Clock takes care of redraw here, to avoid redrawing twice per minute.
+pub struct Battery {
+    history: History,
+    power_saver: bool,
+    status: Status,
+    capacity: u8,
+    signals: Signals,
+}
 
-            TimeoutAction::ToInstant(now + UPDATE_INTERVAL)
-        })?;
+impl Battery {
+    pub fn new(signals: Signals) -> Self {
+        Self {
+            signals,
+            status: Status::Discharging,
+            history: History::load(),
+            capacity: 100,
+            power_saver: false,
+        }
+    }
 
-        Ok(Self { charging: false, capacity: 100 })
+    /// Battery capacity in percent.
+    pub fn capacity(&self) -> u8 {
+        self.capacity
     }
 
-    /// Update battery status from udev attributes.
-    fn update(enumerator: &mut Enumerator, state: &mut State) {
+    /// Get battery capacity and charging status from udev attributes.
+    fn status() -> Result<(u8, Status)> {
         // Get all `power_supply` devices.
-        let devices = match enumerator.scan_devices() {
-            Ok(devices) => devices,
-            Err(_) => return,
-        };
+        let devices = module::devices("power_supply")?;
 
         // Find first device with `capacity` and `status` attributes.
         let battery = devices.into_iter().find_map(|device| {
-            let new_capacity = device
+            let capacity = device
                 .attribute_value("capacity")
                 .and_then(|capacity| u8::from_str(&capacity.to_string_lossy()).ok());
 
-            let new_charging = device.attribute_value("status").map(|status| status == "Charging");
+            let status = device.attribute_value("status").map(|status| match status.to_str() {
+                Some("Charging") => Status::Charging,
+                Some("Full") => Status::Full,
+                _ => Status::Discharging,
+            });
 
-            new_capacity.zip(new_charging)
+            capacity.zip(status)
         });
 
-        // Update charging status.
-        if let Some((new_capacity, new_charging)) = battery {
-            state.modules.battery.capacity = new_capacity;
-            state.modules.battery.charging = new_charging;
+        battery.ok_or(Error::DeviceNotFound("battery"))
+    }
+
+    /// Enable power saving mode on battery with low capacity or the power saver profile.
+    fn update_power_saving(&self) {
+        let low_power = self.power_saver || self.capacity <= LOW_CAPACITY;
+        let enabled = self.status == Status::Discharging && low_power;
+        self.signals.send(Signal::PowerSaving(enabled));
+    }
+
+    /// Show the charging status on the notification LED.
+    fn update_led(&self) {
+        let power = match self.status {
+            Status::Discharging => PowerState::Battery,
+            Status::Charging => PowerState::Charging,
+            Status::Full => PowerState::Full,
+        };
+
+        self.signals.send(Signal::PowerState(power));
+    }
+
+    /// Add the current capacity to the persisted history.
+    fn record_history(&mut self) {
+        if let Err(err) = self.history.record(self.capacity) {
+            eprintln!("Error: Couldn't save battery history: {err}");
+        }
+    }
+
+    /// Get the SVG for the current capacity and charging status.
+    fn icon(&self) -> Svg {
+        match (self.status, self.capacity) {
+            (Status::Full, _) | (Status::Charging, 100..) => Svg::BatteryFull,
+            (Status::Charging, capacity) => {
+                // Fill segments from the current capacity up to full.
+                let level = (capacity as usize / 20).min(CHARGING_SVGS.len() - 1);
+                let frames = CHARGING_SVGS.len() - level;
+                let frame = UNIX_EPOCH.elapsed().unwrap().as_millis() as usize
+                    / ANIMATION_INTERVAL.as_millis() as usize;
+                CHARGING_SVGS[level + frame % frames]
+            },
+            (Status::Discharging, 80..) => Svg::Battery100,
+            (Status::Discharging, 60..=79) => Svg::Battery80,
+            (Status::Discharging, 40..=59) => Svg::Battery60,
+            (Status::Discharging, 20..=39) => Svg::Battery40,
+            (Status::Discharging, 0..=19) => Svg::Battery20,
         }
     }
 }
@@ -85,6 +149,60 @@ impl Module for Battery {
     fn panel_module(&self) -> Option<&dyn PanelModule> {
         Some(self)
     }
+
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::Graph(self))
+    }
+
+    fn sources(&self) -> Vec<Source> {
+        // Listen for charging status changes and poll for capacity updates.
+        vec![
+            Source::Udev("power_supply"),
+            Source::Interval(UPDATE_INTERVAL),
+            Source::Interval(ANIMATION_INTERVAL),
+            Source::Command(Box::new(power_profile), PROFILE_INTERVAL),
+        ]
+    }
+
+    fn probe(&mut self) -> Result<()> {
+        (self.capacity, self.status) = Self::status()?;
+        self.update_power_saving();
+        self.update_led();
+        self.record_history();
+        Ok(())
+    }
+
+    fn update(&mut self, index: usize, event: Event) -> Result<bool> {
+        match (index, event) {
+            // Redraw for the next charging animation frame.
+            //
+            // Since the frame is derived from the time of drawing, the animation stays paused
+            // while the compositor withholds frame callbacks, like when the screen is off.
+            (ANIMATION_SOURCE, _) => {
+                return Ok(self.status == Status::Charging && self.capacity < 80);
+            },
+            // Ignore errors, since power profiles are not available everywhere.
+            (PROFILE_SOURCE, Event::Output(output)) => {
+                self.power_saver = output.map_or(false, |output| {
+                    String::from_utf8_lossy(&output.stdout).trim() == "power-saver"
+                });
+                self.update_power_saving();
+                return Ok(false);
+            },
+            _ => (),
+        }
+
+        let (capacity, status) = Self::status()?;
+
+        let old_capacity = mem::replace(&mut self.capacity, capacity);
+        let old_status = mem::replace(&mut self.status, status);
+        self.update_power_saving();
+        self.update_led();
+        self.record_history();
+
+        // Redraw if value changed.
+        Ok(capacity != old_capacity || status != old_status)
+    }
 }
 
 impl PanelModule for Battery {
@@ -93,17 +211,105 @@ impl PanelModule for Battery {
     }
 
     fn content(&self) -> PanelModuleContent {
-        PanelModuleContent::Svg(match (self.charging, self.capacity) {
-            (true, 80..) => Svg::BatteryCharging100,
-            (true, 60..=79) => Svg::BatteryCharging80,
-            (true, 40..=59) => Svg::BatteryCharging60,
-            (true, 20..=39) => Svg::BatteryCharging40,
-            (true, 0..=19) => Svg::BatteryCharging20,
-            (false, 80..) => Svg::Battery100,
-            (false, 60..=79) => Svg::Battery80,
-            (false, 40..=59) => Svg::Battery60,
-            (false, 20..=39) => Svg::Battery40,
-            (false, 0..=19) => Svg::Battery20,
-        })
+        PanelModuleContent::Svg(self.icon())
+    }
+}
+
+impl Graph for Battery {
+    fn points(&self) -> Vec<(f64, f64)> {
+        self.history.points()
+    }
+
+    fn label(&self) -> String {
+        match self.status {
+            Status::Discharging => format!("{}%", self.capacity),
+            Status::Charging => format!("{}%, charging", self.capacity),
+            Status::Full => format!("{}%, full", self.capacity),
+        }
+    }
+
+    fn svg(&self) -> Svg {
+        self.icon()
+    }
+}
+
+/// Battery capacity samples, as UNIX timestamp and capacity in percent.
+struct History {
+    samples: Vec<(u64, u8)>,
+}
+
+impl History {
+    /// Load the persisted history.
+    fn load() -> Self {
+        let content = history_path().and_then(|path| fs::read_to_string(path).ok());
+        let samples = content
+            .iter()
+            .flat_map(|content| content.lines())
+            .filter_map(|line| {
+                let (time, capacity) = line.split_once(' ')?;
+                Some((u64::from_str(time).ok()?, u8::from_str(capacity).ok()?))
+            })
+            .collect();
+        Self { samples }
     }
+
+    /// Add a new capacity sample and persist the history.
+    fn record(&mut self, capacity: u8) -> Result<()> {
+        let now = UNIX_EPOCH.elapsed().unwrap().as_secs();
+
+        // Skip unchanged samples, unless the graph would lose track of the capacity.
+        if let Some((time, last_capacity)) = self.samples.last() {
+            let elapsed = now.saturating_sub(*time);
+            if *last_capacity == capacity && elapsed < SAMPLE_INTERVAL.as_secs() {
+                return Ok(());
+            }
+        }
+
+        // Drop samples which are no longer shown.
+        let start = now.saturating_sub(HISTORY_DURATION.as_secs());
+        self.samples.retain(|(time, _)| *time >= start);
+        self.samples.push((now, capacity));
+
+        let path = history_path().ok_or(Error::MissingDirectory("state"))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let content: String =
+            self.samples.iter().map(|(time, capacity)| format!("{time} {capacity}\n")).collect();
+        fs::write(path, content)?;
+
+        Ok(())
+    }
+
+    /// Get samples of the graphed time range, as fraction of the range and capacity.
+    fn points(&self) -> Vec<(f64, f64)> {
+        let now = UNIX_EPOCH.elapsed().unwrap().as_secs_f64();
+        let duration = HISTORY_DURATION.as_secs_f64();
+        self.samples
+            .iter()
+            .map(|(time, capacity)| (1. - (now - *time as f64) / duration, *capacity as f64 / 100.))
+            .filter(|(x, _)| *x >= 0.)
+            .collect()
+    }
+}
+
+/// Location of the capacity history file.
+fn history_path() -> Option<PathBuf> {
+    Some(config::state_home()?.join(HISTORY_FILE))
+}
+
+/// Command printing the active power profile.
+fn power_profile() -> Command {
+    let mut powerprofilesctl = Command::new("powerprofilesctl");
+    powerprofilesctl.arg("get");
+    powerprofilesctl
+}
+
+/// Battery charging status.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Status {
+    Discharging,
+    Charging,
+    Full,
 }
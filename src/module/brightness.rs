@@ -1,28 +1,31 @@
 //! Screen brightness.
 
+use std::mem;
 use std::str::FromStr;
 
-use udev::Enumerator;
+use epitaph_modules::{Error, Result};
+use epitaph_ui::text::Svg;
 
-use crate::module::{DrawerModule, Module, Slider};
-use crate::text::Svg;
-use crate::Result;
+use crate::config::Brightness as Config;
+use crate::module::{
+    self, Alignment, DrawerModule, Event, Module, PanelModule, PanelModuleContent, Section, Slider,
+    Source,
+};
 
 pub struct Brightness {
     brightness: f64,
+    config: Config,
 }
 
 impl Brightness {
-    pub fn new() -> Result<Self> {
-        Ok(Self { brightness: Self::get_brightness()? })
+    pub fn new(config: Config) -> Self {
+        Self { config, brightness: 1. }
     }
 
-    /// Set device backlight brightness.
+    /// Get device backlight brightness.
     fn get_brightness() -> Result<f64> {
         // Get all backlight devices.
-        let mut enumerator = Enumerator::new()?;
-        enumerator.match_subsystem("backlight")?;
-        let devices = enumerator.scan_devices()?;
+        let devices = module::devices("backlight")?;
 
         // Find first device with `actual_brightness` and `max_brightness` attributes.
         let brightness = devices.into_iter().find_map(|device| {
@@ -37,16 +40,56 @@ impl Brightness {
             brightness.zip(max_brightness)
         });
 
-        Ok(brightness
+        brightness
             .map(|(brightness, max_brightness)| brightness as f64 / max_brightness as f64)
-            .unwrap_or(1.))
+            .ok_or(Error::DeviceNotFound("backlight"))
     }
 }
 
 impl Module for Brightness {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        if self.config.panel_icon {
+            Some(self)
+        } else {
+            None
+        }
+    }
+
+    fn probe(&mut self) -> Result<()> {
+        self.brightness = Self::get_brightness()?;
+        Ok(())
+    }
+
+    fn sources(&self) -> Vec<Source> {
+        // Listen for backlight devices being added or removed.
+        vec![Source::Udev("backlight")]
+    }
+
+    fn update(&mut self, _index: usize, _event: Event) -> Result<bool> {
+        let brightness = Self::get_brightness()?;
+        let old_brightness = mem::replace(&mut self.brightness, brightness);
+
+        // Redraw if value changed.
+        Ok(brightness != old_brightness)
+    }
+
     fn drawer_module(&mut self) -> Option<DrawerModule> {
         Some(DrawerModule::Slider(self))
     }
+
+    fn section(&self) -> Section {
+        Section::Display
+    }
+}
+
+impl PanelModule for Brightness {
+    fn alignment(&self) -> Alignment {
+        Alignment::Right
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        PanelModuleContent::Svg(Svg::Brightness)
+    }
 }
 
 impl Slider for Brightness {
@@ -56,11 +99,9 @@ impl Slider for Brightness {
         let brightness = value.clamp(0., 1.);
 
         // Get all backlight devices.
-        let mut enumerator = Enumerator::new()?;
-        enumerator.match_subsystem("backlight")?;
-        let mut devices = enumerator.scan_devices()?;
+        let devices = module::devices("backlight")?;
 
-        for mut device in &mut devices {
+        for mut device in devices {
             let max_brightness = match device
                 .attribute_value("max_brightness")
                 .and_then(|max_brightness| u32::from_str(&max_brightness.to_string_lossy()).ok())
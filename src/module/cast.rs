@@ -0,0 +1,120 @@
+//! Wireless display streaming.
+
+use std::time::Duration;
+
+use epitaph_modules::{Error, Result};
+use epitaph_ui::text::Svg;
+
+use crate::config;
+use crate::module::{self, DrawerModule, Event, List, Module, Section, Source};
+use crate::reaper;
+
+/// Refresh interval for sink discovery.
+const UPDATE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Label of the list entry stopping the active stream.
+const STOP_LABEL: &str = "Off";
+
+/// Wireless display sinks and streaming status.
+pub struct Cast {
+    config: config::Cast,
+    active: Option<String>,
+    sinks: Vec<String>,
+}
+
+impl Cast {
+    pub fn new(config: config::Cast) -> Self {
+        Self { config, active: Default::default(), sinks: Default::default() }
+    }
+}
+
+impl Module for Cast {
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::List(self))
+    }
+
+    fn section(&self) -> Section {
+        Section::Connectivity
+    }
+
+    fn probe(&mut self) -> Result<()> {
+        if self.config.list_command.is_empty() || self.config.start_command.is_empty() {
+            return Err(Error::NotConfigured("cast"));
+        }
+        Ok(())
+    }
+
+    fn sources(&self) -> Vec<Source> {
+        let list_command = self.config.list_command.clone();
+        vec![Source::Command(Box::new(move || module::command(&list_command)), UPDATE_INTERVAL)]
+    }
+
+    fn update(&mut self, _index: usize, event: Event) -> Result<bool> {
+        let output = match event {
+            Event::Output(output) => output?,
+            _ => return Ok(false),
+        };
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let sinks: Vec<String> = stdout
+            .lines()
+            .map(str::trim)
+            .filter(|sink| !sink.is_empty())
+            .map(String::from)
+            .collect();
+
+        let changed = sinks != self.sinks;
+        self.sinks = sinks;
+
+        Ok(changed)
+    }
+}
+
+impl List for Cast {
+    fn select(&mut self, index: usize) -> Result<()> {
+        // Stop the active stream before switching sinks.
+        if self.active.take().is_some() {
+            if let Some((program, args)) = self.config.stop_command.split_first() {
+                reaper::daemon(program.as_str(), args.iter().map(String::as_str))?;
+            }
+        }
+
+        // Index zero is the stop entry.
+        let sink = match index.checked_sub(1).and_then(|index| self.sinks.get(index)) {
+            Some(sink) => sink,
+            None => return Ok(()),
+        };
+
+        if let Some((program, args)) = self.config.start_command.split_first() {
+            let args = args.iter().map(String::as_str).chain([sink.as_str()]);
+            reaper::daemon(program.as_str(), args)?;
+            self.active = Some(sink.clone());
+        }
+
+        Ok(())
+    }
+
+    fn entries(&self) -> Vec<String> {
+        let mut entries = vec![STOP_LABEL.to_owned()];
+        entries.extend(self.sinks.iter().cloned());
+        entries
+    }
+
+    fn selected(&self) -> Option<usize> {
+        match &self.active {
+            Some(active) => self.sinks.iter().position(|sink| sink == active).map(|i| i + 1),
+            None => Some(0),
+        }
+    }
+
+    fn label(&self) -> String {
+        match &self.active {
+            Some(active) => active.clone(),
+            None => "Cast".into(),
+        }
+    }
+
+    fn svg(&self) -> Svg {
+        Svg::Cast
+    }
+}
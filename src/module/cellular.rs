@@ -1,85 +1,123 @@
 //! Cellular status and signal strength.
 
+use std::io::{self, ErrorKind};
 use std::mem;
 use std::process::{Command, Output};
 use std::str::FromStr;
 use std::time::{Duration, UNIX_EPOCH};
 
-use calloop::timer::{TimeoutAction, Timer};
-use calloop::LoopHandle;
+use chrono::offset::Local;
+use epitaph_modules::{Error, Result};
+use epitaph_ui::text::Svg;
 
-use crate::module::{Alignment, DrawerModule, Module, PanelModule, PanelModuleContent, Toggle};
-use crate::text::Svg;
-use crate::{reaper, Result, State};
+use crate::config::Cellular as Config;
+use crate::module::{
+    self, Alignment, DrawerModule, Event, Module, PanelModule, PanelModuleContent, Section,
+    Signals, Source, Toggle, Urgency,
+};
+use crate::reaper;
+use crate::traffic::TrafficHistory;
 
 /// Refresh interval for this module.
 const UPDATE_INTERVAL: Duration = Duration::from_secs(5);
 
+/// Refresh interval for traffic accounting.
+const TRAFFIC_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Index of the traffic accounting timer in the module's sources.
+const TRAFFIC_SOURCE: usize = 1;
+
 /// Seconds after toggling status until updates are resumed.
 const TOGGLE_COOLDOWN: u64 = 10;
 
 pub struct Cellular {
+    traffic: TrafficHistory,
     signal_strength: i32,
+    today_traffic: u64,
     last_toggle: u64,
     disabled: bool,
+    config: Config,
+    signals: Signals,
 }
 
 impl Cellular {
-    pub fn new(event_loop: &LoopHandle<'static, State>) -> Result<Self> {
-        // Schedule module updates.
-        event_loop.insert_source(Timer::immediate(), move |now, _, state| {
-            // Temporarily suspend updates after toggling status.
-            let secs_since_toggle = unix_secs() - state.modules.cellular.last_toggle;
-            if let Some(remaining) =
-                TOGGLE_COOLDOWN.checked_sub(secs_since_toggle).filter(|x| *x != 0)
-            {
-                return TimeoutAction::ToDuration(Duration::from_secs(remaining + 1));
+    pub fn new(config: Config, signals: Signals) -> Self {
+        let traffic = TrafficHistory::load();
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        let today_traffic = traffic.traffic(&config.interfaces, &today).total();
+
+        Self {
+            today_traffic,
+            traffic,
+            config,
+            signals,
+            signal_strength: 0,
+            last_toggle: 0,
+            disabled: false,
+        }
+    }
+
+    /// Account traffic since the last update.
+    fn update_traffic(&mut self) -> Result<bool> {
+        let now = Local::now();
+        let interfaces = &self.config.interfaces;
+
+        if self.traffic.update()? {
+            // Warn once per month when approaching the data cap.
+            let month = now.format("%Y-%m").to_string();
+            if let Some(data_cap) = self.config.data_cap.map(|data_cap| data_cap * 1_000_000) {
+                let monthly = self.traffic.traffic(interfaces, &month).total();
+                let threshold = data_cap * self.config.data_cap_warning as u64 / 100;
+                if monthly >= threshold && self.traffic.cap_warning.as_ref() != Some(&month) {
+                    let used = module::format_bytes(monthly);
+                    let data_cap = module::format_bytes(data_cap);
+                    let body = format!("{used} of {data_cap} used this month");
+                    self.signals.notify(Urgency::Normal, "cellular", "Mobile data", &body);
+                    self.traffic.cap_warning = Some(month);
+                }
             }
 
-            // Setup signal strength updates.
-            let mut mmcli = Command::new("mmcli");
-            mmcli.args(["-m", "0", "--signal-get"]);
-            state.reaper.watch(mmcli, Box::new(Self::mmcli_callback));
+            self.traffic.save()?;
+        }
 
-            TimeoutAction::ToInstant(now + UPDATE_INTERVAL)
-        })?;
+        // Always refresh, since the day might have changed.
+        let today = now.format("%Y-%m-%d").to_string();
+        let today_traffic = self.traffic.traffic(interfaces, &today).total();
 
-        Ok(Self { signal_strength: 0, last_toggle: 0, disabled: false })
+        // Redraw if value changed.
+        Ok(mem::replace(&mut self.today_traffic, today_traffic) != today_traffic)
     }
 
     /// Handle `mmcli` command completion.
-    fn mmcli_callback(state: &mut State, output: Output) {
+    fn mmcli_update(&mut self, output: Output) -> bool {
         let output = String::from_utf8_lossy(&output.stdout);
 
         let start_offset = match output.find("rssi: ") {
             Some(start) => start + "rssi: ".len(),
             None => {
                 // Mark cellular as disabled when there is no active connection.
-                let old_disabled = mem::replace(&mut state.modules.cellular.disabled, true);
+                let old_disabled = mem::replace(&mut self.disabled, true);
 
                 // Redraw if value changed.
-                if !old_disabled {
-                    state.request_frame();
-                }
-
-                return;
+                return !old_disabled;
             },
         };
         let end_offset = match output[start_offset..].find(' ') {
             Some(end) => start_offset + end,
-            None => return,
+            None => return false,
         };
 
-        if let Ok(strength) = f32::from_str(&output[start_offset..end_offset]) {
-            let old_svg = state.modules.cellular.svg();
+        match f32::from_str(&output[start_offset..end_offset]) {
+            Ok(strength) => {
+                let old_svg = self.svg();
 
-            state.modules.cellular.signal_strength = strength as i32;
-            state.modules.cellular.disabled = false;
+                self.signal_strength = strength as i32;
+                self.disabled = false;
 
-            // Redraw if value changed.
-            if state.modules.cellular.svg() != old_svg {
-                state.request_frame();
-            }
+                // Redraw if value changed.
+                self.svg() != old_svg
+            },
+            Err(_) => false,
         }
     }
 }
@@ -92,6 +130,41 @@ impl Module for Cellular {
     fn drawer_module(&mut self) -> Option<DrawerModule> {
         Some(DrawerModule::Toggle(self))
     }
+
+    fn section(&self) -> Section {
+        Section::Connectivity
+    }
+
+    fn sources(&self) -> Vec<Source> {
+        // Setup signal strength and traffic updates.
+        vec![Source::Command(Box::new(mmcli), UPDATE_INTERVAL), Source::Interval(TRAFFIC_INTERVAL)]
+    }
+
+    fn update(&mut self, index: usize, event: Event) -> Result<bool> {
+        if index == TRAFFIC_SOURCE {
+            return self.update_traffic();
+        }
+
+        // Temporarily suspend updates after toggling status.
+        if unix_secs() - self.last_toggle < TOGGLE_COOLDOWN {
+            return Ok(false);
+        }
+
+        match event {
+            Event::Output(output) => {
+                let output = output.map_err(service_error)?;
+
+                // Disable the module while ModemManager isn't running.
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                if stderr.contains("ModemManager process") {
+                    return Err(Error::ServiceNotFound("ModemManager"));
+                }
+
+                Ok(self.mmcli_update(output))
+            },
+            _ => Ok(false),
+        }
+    }
 }
 
 impl PanelModule for Cellular {
@@ -138,6 +211,26 @@ impl Toggle for Cellular {
     fn enabled(&self) -> bool {
         !self.disabled
     }
+
+    /// Mobile data used today.
+    fn caption(&self) -> Option<String> {
+        Some(module::format_bytes(self.today_traffic))
+    }
+}
+
+/// Command for signal strength updates.
+fn mmcli() -> Command {
+    let mut mmcli = Command::new("mmcli");
+    mmcli.args(["-m", "0", "--signal-get"]);
+    mmcli
+}
+
+/// Treat a missing `mmcli` binary as missing ModemManager.
+fn service_error(err: io::Error) -> Error {
+    match err.kind() {
+        ErrorKind::NotFound => Error::ServiceNotFound("ModemManager"),
+        _ => err.into(),
+    }
 }
 
 /// Seconds since unix epoch.
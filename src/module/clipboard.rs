@@ -0,0 +1,240 @@
+//! Clipboard status and clearing.
+
+use std::mem;
+
+use epitaph_modules::{Error, Result};
+use epitaph_ui::text::Svg;
+use smithay_client_toolkit::reexports::client::globals::GlobalList;
+use smithay_client_toolkit::reexports::client::protocol::wl_seat::WlSeat;
+use smithay_client_toolkit::reexports::client::{
+    event_created_child, Connection, Dispatch, Proxy, QueueHandle,
+};
+
+use self::data_control::zwlr_data_control_device_v1::{self, ZwlrDataControlDeviceV1};
+use self::data_control::zwlr_data_control_manager_v1::ZwlrDataControlManagerV1;
+use self::data_control::zwlr_data_control_offer_v1::{self, ZwlrDataControlOfferV1};
+use crate::module::{
+    Alignment, DrawerModule, List, Module, PanelModule, PanelModuleContent, Section,
+};
+use crate::State;
+
+#[allow(clippy::all)]
+mod data_control {
+    use smithay_client_toolkit::reexports::client as wayland_client;
+    use smithay_client_toolkit::reexports::client::protocol::*;
+
+    pub mod __interfaces {
+        use smithay_client_toolkit::reexports::client::protocol::__interfaces::*;
+        wayland_scanner::generate_interfaces!("protocols/wlr-data-control-unstable-v1.xml");
+    }
+    use self::__interfaces::*;
+
+    wayland_scanner::generate_client_code!("protocols/wlr-data-control-unstable-v1.xml");
+}
+
+/// Clipboard indicator through wlr-data-control.
+pub struct Clipboard {
+    manager: Option<ZwlrDataControlManagerV1>,
+    device: Option<ZwlrDataControlDeviceV1>,
+    queue: QueueHandle<State>,
+    /// Offers announced before their selection event.
+    offers: Vec<Offer>,
+    selection: Option<Offer>,
+}
+
+impl Clipboard {
+    pub fn new(globals: &GlobalList, queue: &QueueHandle<State>) -> Self {
+        Self {
+            manager: globals.bind(queue, 1..=2, ()).ok(),
+            queue: queue.clone(),
+            device: Default::default(),
+            offers: Default::default(),
+            selection: Default::default(),
+        }
+    }
+
+    /// Watch the selection of a seat.
+    ///
+    /// Only the first seat is tracked, since mobile devices usually have just one.
+    pub fn set_seat(&mut self, seat: &WlSeat) {
+        if let (Some(manager), None) = (&self.manager, &self.device) {
+            self.device = Some(manager.get_data_device(seat, &self.queue, ()));
+        }
+    }
+
+    /// Stop tracking an offer which was announced earlier.
+    fn take_offer(&mut self, offer: &ZwlrDataControlOfferV1) -> Option<Offer> {
+        let index = self.offers.iter().position(|tracked| &tracked.offer == offer)?;
+        Some(self.offers.swap_remove(index))
+    }
+
+    /// Replace the current selection.
+    fn set_selection(&mut self, offer: Option<ZwlrDataControlOfferV1>) {
+        let offer = offer.and_then(|offer| self.take_offer(&offer));
+        if let Some(old_selection) = mem::replace(&mut self.selection, offer) {
+            old_selection.offer.destroy();
+        }
+    }
+
+    /// MIME types of the current selection.
+    fn mime_types(&self) -> &[String] {
+        self.selection.as_ref().map_or(&[], |selection| &selection.mime_types)
+    }
+}
+
+impl Module for Clipboard {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        Some(self)
+    }
+
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::List(self))
+    }
+
+    fn section(&self) -> Section {
+        Section::Device
+    }
+
+    fn probe(&mut self) -> Result<()> {
+        match self.manager {
+            Some(_) => Ok(()),
+            None => Err(Error::CompositorNotFound("wlr data control")),
+        }
+    }
+}
+
+impl PanelModule for Clipboard {
+    fn alignment(&self) -> Alignment {
+        Alignment::Right
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        // Only show an indicator while the clipboard holds content.
+        if self.selection.is_some() {
+            PanelModuleContent::Svg(Svg::Clipboard)
+        } else {
+            PanelModuleContent::Text(String::new())
+        }
+    }
+}
+
+impl List for Clipboard {
+    fn select(&mut self, index: usize) -> Result<()> {
+        // Only the trailing entry is an action, the others just list MIME types.
+        if index != self.mime_types().len() {
+            return Ok(());
+        }
+
+        let device = self.device.as_ref().ok_or(Error::CompositorNotFound("wlr data control"))?;
+        device.set_selection(None);
+
+        // Immediately clear the selection for better UX.
+        self.set_selection(None);
+
+        Ok(())
+    }
+
+    fn entries(&self) -> Vec<String> {
+        let mut entries = self.mime_types().to_vec();
+        entries.push("Clear clipboard".into());
+        entries
+    }
+
+    fn selected(&self) -> Option<usize> {
+        None
+    }
+
+    fn label(&self) -> String {
+        match self.mime_types().len() {
+            0 => "Clipboard: Empty".into(),
+            1 => "Clipboard: 1 type".into(),
+            count => format!("Clipboard: {count} types"),
+        }
+    }
+
+    fn svg(&self) -> Svg {
+        Svg::Clipboard
+    }
+}
+
+/// Data offer with its MIME types.
+struct Offer {
+    offer: ZwlrDataControlOfferV1,
+    mime_types: Vec<String>,
+}
+
+impl Offer {
+    fn new(offer: ZwlrDataControlOfferV1) -> Self {
+        Self { offer, mime_types: Default::default() }
+    }
+}
+
+impl Dispatch<ZwlrDataControlManagerV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _manager: &ZwlrDataControlManagerV1,
+        _event: <ZwlrDataControlManagerV1 as Proxy>::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrDataControlDeviceV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        device: &ZwlrDataControlDeviceV1,
+        event: zwlr_data_control_device_v1::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+        let clipboard = &mut state.modules.builtin.clipboard;
+        match event {
+            zwlr_data_control_device_v1::Event::DataOffer { id } => {
+                clipboard.offers.push(Offer::new(id));
+            },
+            zwlr_data_control_device_v1::Event::Selection { id } => {
+                clipboard.set_selection(id);
+                state.request_frame();
+            },
+            // Ignore the primary selection, since it's not a clipboard.
+            zwlr_data_control_device_v1::Event::PrimarySelection { id } => {
+                if let Some(offer) = id.and_then(|id| clipboard.take_offer(&id)) {
+                    offer.offer.destroy();
+                }
+            },
+            zwlr_data_control_device_v1::Event::Finished => {
+                clipboard.set_selection(None);
+                clipboard.device = None;
+                device.destroy();
+                state.request_frame();
+            },
+            _ => (),
+        }
+    }
+
+    event_created_child!(State, ZwlrDataControlDeviceV1, [
+        zwlr_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ZwlrDataControlOfferV1, ()),
+    ]);
+}
+
+impl Dispatch<ZwlrDataControlOfferV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        offer: &ZwlrDataControlOfferV1,
+        event: zwlr_data_control_offer_v1::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+        if let zwlr_data_control_offer_v1::Event::Offer { mime_type } = event {
+            let clipboard = &mut state.modules.builtin.clipboard;
+            let tracked = clipboard.offers.iter_mut().find(|tracked| &tracked.offer == offer);
+            if let Some(tracked) = tracked {
+                tracked.mime_types.push(mime_type);
+            }
+        }
+    }
+}
@@ -1,31 +1,109 @@
 //! Nice clock.
 
-use std::time::{Duration, UNIX_EPOCH};
+use std::mem;
+use std::process::Command;
+use std::time::Duration;
 
-use calloop::timer::{TimeoutAction, Timer};
-use calloop::LoopHandle;
 use chrono::offset::Local;
+use chrono::{Datelike, NaiveDateTime, NaiveTime, Weekday};
+use chrono_tz::Tz;
+use epitaph_modules::Result;
+use epitaph_ui::text::Svg;
 
-use crate::module::{Alignment, Module, PanelModule, PanelModuleContent};
-use crate::{Result, State};
+use crate::config::{self, Alarm as AlarmConfig};
+use crate::module::{
+    Alignment, DrawerModule, Event, List, Module, PanelModule, PanelModuleContent, Source,
+};
+use crate::panel::TEXT_COLOR;
+
+/// Refresh interval for GNOME Clocks alarms.
+const ALARM_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Index of the GNOME Clocks command in the module's sources.
+const ALARM_SOURCE: usize = 2;
+
+/// Panel date format, used when the date is enabled.
+const DATE_FORMAT: &str = "%a %-d";
+
+/// Weekdays indexed by their number of days from Monday.
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
 
 pub struct Clock {
-    _new: (),
+    gnome_alarms: Vec<Alarm>,
+    timezones: Vec<Tz>,
+    gnome_clocks: bool,
+    alarms: Vec<Alarm>,
+    date: bool,
 }
 
 impl Clock {
-    pub fn new(event_loop: &LoopHandle<'static, State>) -> Result<Self> {
-        event_loop.insert_source(Timer::immediate(), move |now, _, state| {
-            state.request_frame();
+    pub fn new(config: config::Clock) -> Self {
+        let alarms = config
+            .alarms
+            .iter()
+            .filter_map(|alarm| match Alarm::from_config(alarm) {
+                Some(alarm) => Some(alarm),
+                None => {
+                    eprintln!("Error: Ignoring alarm with invalid time {:?}", alarm.time);
+                    None
+                },
+            })
+            .collect();
 
-            // Calculate seconds until next minute. We add one second just to be sure.
-            let total_secs = UNIX_EPOCH.elapsed().unwrap().as_secs();
-            let remaining = Duration::from_secs(60 - (total_secs % 60) + 1);
+        let timezones = config
+            .timezones
+            .iter()
+            .filter_map(|name| match name.parse() {
+                Ok(timezone) => Some(timezone),
+                Err(err) => {
+                    eprintln!("Error: Ignoring invalid timezone {name:?}: {err}");
+                    None
+                },
+            })
+            .collect();
 
-            TimeoutAction::ToInstant(now + remaining)
-        })?;
+        Self {
+            alarms,
+            timezones,
+            gnome_clocks: config.gnome_clocks,
+            date: config.date,
+            gnome_alarms: Default::default(),
+        }
+    }
+
+    /// Get the panel content, optionally including the date.
+    fn panel_content(&self, date: bool) -> PanelModuleContent {
+        let now = Local::now().naive_local();
+        let text = if date {
+            format!("{} {}", now.format(DATE_FORMAT), now.format("%H:%M"))
+        } else {
+            now.format("%H:%M").to_string()
+        };
 
-        Ok(Self { _new: () })
+        // Indicate alarms ringing within the next 24 hours.
+        match self.upcoming(now).first() {
+            Some((time, _)) if *time - now <= chrono::Duration::days(1) => {
+                PanelModuleContent::Status { text, svg: Some(Svg::Alarm), color: TEXT_COLOR }
+            },
+            _ => PanelModuleContent::Text(text),
+        }
+    }
+
+    /// Get all alarms with their next ring time, in the order they will ring.
+    fn upcoming(&self, now: NaiveDateTime) -> Vec<(NaiveDateTime, &Alarm)> {
+        let alarms = self.alarms.iter().chain(&self.gnome_alarms);
+        let mut upcoming: Vec<_> =
+            alarms.filter_map(|alarm| Some((alarm.next(now)?, alarm))).collect();
+        upcoming.sort_by_key(|(time, _)| *time);
+        upcoming
     }
 }
 
@@ -33,6 +111,47 @@ impl Module for Clock {
     fn panel_module(&self) -> Option<&dyn PanelModule> {
         Some(self)
     }
+
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::List(self))
+    }
+
+    fn sources(&self) -> Vec<Source> {
+        // Update the date as soon as the day changes.
+        let mut sources = vec![Source::Minute, Source::Midnight];
+
+        if self.gnome_clocks {
+            let command = || {
+                let mut command = Command::new("gsettings");
+                command.args(["get", "org.gnome.clocks", "alarms"]);
+                command
+            };
+            sources.push(Source::Command(Box::new(command), ALARM_INTERVAL));
+        }
+
+        sources
+    }
+
+    fn update(&mut self, index: usize, event: Event) -> Result<bool> {
+        let output = match event {
+            Event::Output(output) if index == ALARM_SOURCE => output,
+            _ => return Ok(true),
+        };
+
+        // Keep the clock working when GNOME Clocks is unavailable.
+        let stdout = match output {
+            Ok(output) => output.stdout,
+            Err(err) => {
+                eprintln!("Error: Couldn't read GNOME Clocks alarms: {err}");
+                return Ok(false);
+            },
+        };
+
+        let alarms = parse_gnome_alarms(&String::from_utf8_lossy(&stdout));
+        let old_alarms = mem::replace(&mut self.gnome_alarms, alarms);
+
+        Ok(old_alarms != self.gnome_alarms)
+    }
 }
 
 impl PanelModule for Clock {
@@ -41,6 +160,123 @@ impl PanelModule for Clock {
     }
 
     fn content(&self) -> PanelModuleContent {
-        PanelModuleContent::Text(Local::now().format("%H:%M").to_string())
+        self.panel_content(self.date)
     }
+
+    fn condensed_content(&self) -> PanelModuleContent {
+        // Always show the time, since there is no other place for it.
+        self.panel_content(false)
+    }
+}
+
+impl List for Clock {
+    fn select(&mut self, _index: usize) -> Result<()> {
+        Ok(())
+    }
+
+    fn entries(&self) -> Vec<String> {
+        let now = Local::now();
+        let local_date = now.naive_local().date();
+
+        // Show world clocks with their day offset from the local date.
+        let mut entries: Vec<String> = self
+            .timezones
+            .iter()
+            .map(|timezone| {
+                let time = now.with_timezone(timezone).naive_local();
+                let city = timezone.name().rsplit('/').next().unwrap_or_default().replace('_', " ");
+                let text = format!("{city} {}", time.format("%H:%M"));
+                match (time.date() - local_date).num_days() {
+                    0 => text,
+                    days => format!("{text} ({days:+})"),
+                }
+            })
+            .collect();
+
+        let upcoming = self.upcoming(now.naive_local());
+        entries.extend(upcoming.into_iter().map(|(time, alarm)| {
+            let time = time.format("%a %H:%M").to_string();
+            if alarm.label.is_empty() {
+                time
+            } else {
+                format!("{time} {}", alarm.label)
+            }
+        }));
+
+        entries
+    }
+
+    fn selected(&self) -> Option<usize> {
+        None
+    }
+
+    fn label(&self) -> String {
+        Local::now().format("%A, %B %-d").to_string()
+    }
+
+    fn svg(&self) -> Svg {
+        Svg::Alarm
+    }
+}
+
+/// Recurring alarm.
+#[derive(PartialEq, Eq)]
+struct Alarm {
+    time: NaiveTime,
+    days: Vec<Weekday>,
+    label: String,
+}
+
+impl Alarm {
+    /// Convert a configured alarm, returning `None` if its time is invalid.
+    fn from_config(alarm: &AlarmConfig) -> Option<Self> {
+        let time = NaiveTime::parse_from_str(&alarm.time, "%H:%M").ok()?;
+        let days = alarm.days.iter().map(|day| WEEKDAYS[*day as usize]).collect();
+        Some(Self { time, days, label: alarm.label.clone() })
+    }
+
+    /// Get the next time this alarm will ring.
+    fn next(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        (0..=7)
+            .map(|offset| (now.date() + chrono::Duration::days(offset)).and_time(self.time))
+            .filter(|time| *time > now)
+            .find(|time| self.days.is_empty() || self.days.contains(&time.weekday()))
+    }
+}
+
+/// Parse active alarms from the GVariant text of the GNOME Clocks `alarms` setting.
+///
+/// Each alarm is a dictionary like `{'name': <'Work'>, 'hour': <7>, 'minute': <30>, ...}`, with
+/// `days` holding the numbers of days from Monday.
+fn parse_gnome_alarms(text: &str) -> Vec<Alarm> {
+    text.split('{')
+        .skip(1)
+        .filter_map(|entry| {
+            // Extract the content of a dictionary value's variant.
+            let field = |name: &str| {
+                let key = format!("'{name}': <");
+                let value = &entry[entry.find(&key)? + key.len()..];
+                Some(&value[..value.find('>')?])
+            };
+
+            if field("active") == Some("false") {
+                return None;
+            }
+
+            let hour = field("hour")?.parse().ok()?;
+            let minute = field("minute")?.parse().ok()?;
+            let time = NaiveTime::from_hms_opt(hour, minute, 0)?;
+
+            let days = field("days").unwrap_or_default().trim_start_matches("@ai ");
+            let days = days
+                .trim_matches(|c| c == '[' || c == ']')
+                .split(',')
+                .filter_map(|day| WEEKDAYS.get(day.trim().parse::<usize>().ok()?).copied())
+                .collect();
+
+            let label = field("name").unwrap_or_default().trim_matches('\'').to_owned();
+
+            Some(Alarm { time, days, label })
+        })
+        .collect()
 }
@@ -0,0 +1,152 @@
+//! Do not disturb mode.
+
+use std::mem;
+
+use chrono::offset::Local;
+use chrono::NaiveTime;
+use epitaph_modules::Result;
+use epitaph_ui::text::Svg;
+
+use crate::module::{
+    Alignment, DrawerModule, Event, Module, PanelModule, PanelModuleContent, Signal, Signals,
+    Source, Toggle,
+};
+use crate::{config, reaper};
+
+/// Do not disturb toggle with scheduled quiet hours.
+pub struct DoNotDisturb {
+    command: Vec<String>,
+    quiet_hours: Option<(NaiveTime, NaiveTime)>,
+    /// Applications whose notifications break through do not disturb.
+    allowed: Vec<String>,
+    /// Manual override, reset at the next start or end of the quiet hours.
+    manual: Option<bool>,
+    scheduled: bool,
+    /// Whether do not disturb was applied to notifications.
+    active: bool,
+    signals: Signals,
+}
+
+impl DoNotDisturb {
+    pub fn new(config: config::DoNotDisturb, signals: Signals) -> Self {
+        // Ignore quiet hours with invalid times.
+        let quiet_hours = config.quiet_hours.and_then(|quiet_hours| {
+            let start = NaiveTime::parse_from_str(&quiet_hours.start, "%H:%M").ok()?;
+            let end = NaiveTime::parse_from_str(&quiet_hours.end, "%H:%M").ok()?;
+            Some((start, end))
+        });
+
+        Self {
+            quiet_hours,
+            signals,
+            command: config.command,
+            allowed: config.allowed,
+            scheduled: false,
+            active: false,
+            manual: None,
+        }
+    }
+
+    /// Check if notifications from an application should be silenced.
+    pub fn silenced(&self, app: &str) -> bool {
+        self.active && !self.allowed.iter().any(|allowed| allowed == app)
+    }
+
+    /// Check if the current time is within the quiet hours.
+    fn in_quiet_hours(&self) -> bool {
+        let (start, end) = match self.quiet_hours {
+            Some(quiet_hours) => quiet_hours,
+            None => return false,
+        };
+
+        // Quiet hours usually wrap around midnight.
+        let now = Local::now().naive_local().time();
+        if start <= end {
+            now >= start && now < end
+        } else {
+            now >= start || now < end
+        }
+    }
+
+    /// Apply the current state to notifications.
+    fn apply(&mut self) -> Result<()> {
+        let active = self.enabled();
+        if mem::replace(&mut self.active, active) == active {
+            return Ok(());
+        }
+
+        self.signals.send(Signal::DoNotDisturb(active));
+
+        // Forward the state to the notification daemon.
+        if let Some((program, args)) = self.command.split_first() {
+            let action = if active { "on" } else { "off" };
+            let args = args.iter().map(String::as_str).chain([action]);
+            reaper::daemon(program.as_str(), args)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Module for DoNotDisturb {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        Some(self)
+    }
+
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::Toggle(self))
+    }
+
+    fn sources(&self) -> Vec<Source> {
+        vec![Source::Minute]
+    }
+
+    fn probe(&mut self) -> Result<()> {
+        self.scheduled = self.in_quiet_hours();
+        self.apply()
+    }
+
+    fn update(&mut self, _index: usize, _event: Event) -> Result<bool> {
+        let was_enabled = self.enabled();
+
+        // Drop manual overrides once the quiet hours start or end.
+        let scheduled = self.in_quiet_hours();
+        if scheduled != self.scheduled {
+            self.scheduled = scheduled;
+            self.manual = None;
+        }
+
+        self.apply()?;
+
+        Ok(was_enabled != self.enabled())
+    }
+}
+
+impl PanelModule for DoNotDisturb {
+    fn alignment(&self) -> Alignment {
+        Alignment::Right
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        if self.enabled() {
+            PanelModuleContent::Svg(Svg::DoNotDisturb)
+        } else {
+            PanelModuleContent::Text(String::new())
+        }
+    }
+}
+
+impl Toggle for DoNotDisturb {
+    fn toggle(&mut self) -> Result<()> {
+        self.manual = Some(!self.enabled());
+        self.apply()
+    }
+
+    fn svg(&self) -> Svg {
+        Svg::DoNotDisturb
+    }
+
+    fn enabled(&self) -> bool {
+        self.manual.unwrap_or(self.scheduled)
+    }
+}
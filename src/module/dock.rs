@@ -0,0 +1,142 @@
+//! USB-C dock detection.
+
+use std::mem;
+
+use epitaph_modules::Result;
+use epitaph_ui::text::Svg;
+use udev::Device;
+
+use crate::module::ethernet::TETHERING_DRIVERS;
+use crate::module::{
+    self, Alignment, Event, Module, PanelModule, PanelModuleContent, Signal, Signals, Source,
+};
+
+/// DRM connector types of built-in displays.
+const INTERNAL_CONNECTORS: [&str; 4] = ["eDP", "DSI", "LVDS", "DPI"];
+
+/// Dock connection indicator.
+pub struct Dock {
+    docked: bool,
+    signals: Signals,
+}
+
+impl Dock {
+    pub fn new(signals: Signals) -> Self {
+        Self { signals, docked: false }
+    }
+
+    /// Check if the device is currently docked.
+    pub fn docked(&self) -> bool {
+        self.docked
+    }
+
+    /// Detect an attached dock.
+    ///
+    /// Docks are recognized by an external display, or a USB-C partner which has the device
+    /// act as USB host while either powering it or providing ethernet.
+    fn detect() -> Result<bool> {
+        if module::devices("drm")?.iter().any(is_external_display) {
+            return Ok(true);
+        }
+
+        let typec = module::devices("typec")?;
+        let mut host_ports = typec.iter().filter(|port| is_host_port(port, &typec)).peekable();
+        if host_ports.peek().is_none() {
+            return Ok(false);
+        }
+
+        if host_ports.any(|port| has_role(port, "power_role", "sink")) {
+            return Ok(true);
+        }
+
+        has_usb_ethernet()
+    }
+}
+
+impl Module for Dock {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        Some(self)
+    }
+
+    fn sources(&self) -> Vec<Source> {
+        vec![Source::Udev("drm"), Source::Udev("typec"), Source::Udev("net")]
+    }
+
+    fn probe(&mut self) -> Result<()> {
+        self.docked = Self::detect()?;
+        Ok(())
+    }
+
+    fn update(&mut self, _index: usize, _event: Event) -> Result<bool> {
+        let old_docked = mem::replace(&mut self.docked, Self::detect()?);
+        if old_docked == self.docked {
+            return Ok(false);
+        }
+
+        self.signals.send(Signal::DockChanged);
+
+        Ok(true)
+    }
+}
+
+impl PanelModule for Dock {
+    fn alignment(&self) -> Alignment {
+        Alignment::Right
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        if self.docked {
+            PanelModuleContent::Svg(Svg::Dock)
+        } else {
+            PanelModuleContent::Text(String::new())
+        }
+    }
+}
+
+/// Check if a DRM device is a connected external display.
+fn is_external_display(device: &Device) -> bool {
+    // Connectors are named like `card0-HDMI-A-1`.
+    let sysname = device.sysname().to_string_lossy();
+    let connector = match sysname.split_once('-') {
+        Some((_, connector)) => connector,
+        None => return false,
+    };
+
+    let internal = INTERNAL_CONNECTORS.iter().any(|internal| connector.starts_with(internal));
+    !internal && device.attribute_value("status").map_or(false, |status| status == "connected")
+}
+
+/// Check if a USB-C port has a partner attached and acts as USB host.
+fn is_host_port(port: &Device, typec: &[Device]) -> bool {
+    // Ports are named `port0`, with their partners named `port0-partner`.
+    let sysname = port.sysname().to_string_lossy();
+    if sysname.contains('-') {
+        return false;
+    }
+
+    let partner = format!("{sysname}-partner");
+    let attached = typec.iter().any(|device| device.sysname().to_string_lossy() == partner);
+
+    attached && has_role(port, "data_role", "host")
+}
+
+/// Check the active role of a USB-C port, which is enclosed in brackets like `[host] device`.
+fn has_role(port: &Device, attribute: &str, role: &str) -> bool {
+    let active = format!("[{role}]");
+    port.attribute_value(attribute)
+        .map_or(false, |roles| roles.to_string_lossy().split_whitespace().any(|r| r == active))
+}
+
+/// Check for wired network adapters connected over USB.
+fn has_usb_ethernet() -> Result<bool> {
+    Ok(module::devices("net")?.iter().any(|device| {
+        // Ignore wireless adapters and phones providing USB tethering.
+        let wireless = device.devtype().map_or(false, |devtype| devtype == "wlan");
+        let usb = device.parent_with_subsystem("usb").ok().flatten().is_some();
+        let driver = device
+            .parent()
+            .and_then(|parent| Some(parent.driver()?.to_string_lossy().into_owned()));
+        let tethering = driver.map_or(false, |driver| TETHERING_DRIVERS.contains(&driver.as_str()));
+        usb && !wireless && !tethering
+    }))
+}
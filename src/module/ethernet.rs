@@ -0,0 +1,129 @@
+//! Wired and USB tethering connection status.
+
+use std::fs;
+use std::mem;
+use std::path::Path;
+
+use epitaph_modules::Result;
+use epitaph_ui::text::Svg;
+
+use crate::module::{Alignment, Event, Module, PanelModule, PanelModuleContent, Source};
+
+/// Drivers used for USB tethering by phones and docks.
+pub const TETHERING_DRIVERS: [&str; 4] = ["rndis_host", "cdc_ncm", "cdc_ether", "ipheth"];
+
+/// Interface type carrying the default route.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+enum Link {
+    #[default]
+    Other,
+    Ethernet,
+    Tethering,
+}
+
+impl Link {
+    /// Get the link type of the interface carrying the default route.
+    fn current() -> Self {
+        match default_interface() {
+            Some(interface) => Self::from_interface(&interface),
+            None => Self::Other,
+        }
+    }
+
+    /// Determine the link type of a network interface.
+    fn from_interface(interface: &str) -> Self {
+        let path = Path::new("/sys/class/net").join(interface);
+
+        // Ignore virtual and wireless interfaces.
+        if !path.join("device").exists() || path.join("wireless").exists() {
+            return Self::Other;
+        }
+
+        let driver = fs::read_link(path.join("device/driver")).unwrap_or_default();
+        let driver = driver.file_name().and_then(|driver| driver.to_str()).unwrap_or_default();
+        if TETHERING_DRIVERS.contains(&driver) {
+            Self::Tethering
+        } else {
+            Self::Ethernet
+        }
+    }
+}
+
+/// Wired network indicator.
+pub struct Ethernet {
+    link: Link,
+}
+
+impl Ethernet {
+    pub fn new() -> Self {
+        Self { link: Default::default() }
+    }
+}
+
+impl Module for Ethernet {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        Some(self)
+    }
+
+    fn sources(&self) -> Vec<Source> {
+        let groups = libc::RTMGRP_IPV4_ROUTE | libc::RTMGRP_IPV6_ROUTE | libc::RTMGRP_LINK;
+        vec![Source::Netlink(groups as u32)]
+    }
+
+    fn update(&mut self, _index: usize, event: Event) -> Result<bool> {
+        // Re-read the default route after connecting and on every route change.
+        if let Event::Disconnected = event {
+            return Ok(false);
+        }
+
+        let old_link = mem::replace(&mut self.link, Link::current());
+
+        Ok(old_link != self.link)
+    }
+}
+
+impl PanelModule for Ethernet {
+    fn alignment(&self) -> Alignment {
+        Alignment::Right
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        match self.link {
+            Link::Ethernet => PanelModuleContent::Svg(Svg::Ethernet),
+            Link::Tethering => PanelModuleContent::Svg(Svg::Tethering),
+            Link::Other => PanelModuleContent::Text(String::new()),
+        }
+    }
+}
+
+/// Get the interface with the lowest metric default route.
+fn default_interface() -> Option<String> {
+    // Columns are interface, destination, gateway, flags, refcnt, use, metric and mask.
+    let ipv4 = fs::read_to_string("/proc/net/route").unwrap_or_default();
+    let ipv4_routes = ipv4.lines().skip(1).filter_map(|line| {
+        let columns: Vec<&str> = line.split_whitespace().collect();
+        match columns.as_slice() {
+            [interface, "00000000", _, _, _, _, metric, "00000000", ..] => {
+                Some((u32::from_str_radix(metric, 16).ok()?, interface.to_string()))
+            },
+            _ => None,
+        }
+    });
+
+    // Columns are destination, prefix length, source, source prefix length, gateway, metric,
+    // refcnt, use, flags and interface.
+    let ipv6 = fs::read_to_string("/proc/net/ipv6_route").unwrap_or_default();
+    let ipv6_routes = ipv6.lines().filter_map(|line| {
+        let columns: Vec<&str> = line.split_whitespace().collect();
+        match columns.as_slice() {
+            [destination, "00", _, _, _, metric, _, _, _, interface]
+                if destination.bytes().all(|byte| byte == b'0') && *interface != "lo" =>
+            {
+                Some((u32::from_str_radix(metric, 16).ok()?, interface.to_string()))
+            },
+            _ => None,
+        }
+    });
+
+    ipv4_routes.chain(ipv6_routes).min_by_key(|(metric, _)| *metric).map(|(_, interface)| interface)
+}
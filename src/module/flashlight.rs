@@ -1,17 +1,42 @@
 //! Screen brightness.
 
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::mem;
 use std::ops::{Deref, DerefMut};
+use std::os::unix::io::AsRawFd;
 use std::str::FromStr;
 
-use udev::{Device, Enumerator};
+use epitaph_modules::{Error, Result};
+use epitaph_ui::text::Svg;
+use udev::Device;
 
-use crate::module::{DrawerModule, Module, Toggle};
-use crate::text::Svg;
-use crate::Result;
+use crate::module::{self, DrawerModule, Module, Toggle};
+
+/// `VIDIOC_G_CTRL` ioctl request, reading a V4L2 control.
+const VIDIOC_G_CTRL: u64 =
+    (3 << 30) | ((mem::size_of::<V4l2Control>() as u64) << 16) | ((b'V' as u64) << 8) | 27;
+
+/// `VIDIOC_S_CTRL` ioctl request, writing a V4L2 control.
+const VIDIOC_S_CTRL: u64 =
+    (3 << 30) | ((mem::size_of::<V4l2Control>() as u64) << 16) | ((b'V' as u64) << 8) | 28;
+
+/// V4L2 flash LED mode control ID.
+const V4L2_CID_FLASH_LED_MODE: u32 = 0x009c0901;
+
+/// Flash LED modes for [`V4L2_CID_FLASH_LED_MODE`].
+const V4L2_FLASH_LED_MODE_NONE: i32 = 0;
+const V4L2_FLASH_LED_MODE_TORCH: i32 = 2;
 
 #[derive(Default)]
 pub struct Flashlight {
     enabled: bool,
+
+    /// Torch intensity, if the LED supports multiple brightness steps.
+    level: Option<f64>,
+
+    /// Active V4L2 torch, which is turned off once its subdevice is closed.
+    v4l2_torch: Option<File>,
 }
 
 impl Flashlight {
@@ -21,6 +46,22 @@ impl Flashlight {
 }
 
 impl Module for Flashlight {
+    fn probe(&mut self) -> Result<()> {
+        let flash = Flash::find()?.ok_or(Error::DeviceNotFound("flashlight"))?;
+        self.enabled = flash.enabled();
+
+        // Keep the last intensity while the flashlight is off.
+        self.level = match flash {
+            Flash::Led(led) if led.max_brightness > 1 && led.enabled() => {
+                Some(led.brightness as f64 / led.max_brightness as f64)
+            },
+            Flash::Led(led) if led.max_brightness > 1 => Some(self.level.unwrap_or(1.)),
+            _ => None,
+        };
+
+        Ok(())
+    }
+
     fn drawer_module(&mut self) -> Option<DrawerModule> {
         Some(DrawerModule::Toggle(self))
     }
@@ -30,20 +71,28 @@ impl Toggle for Flashlight {
     fn toggle(&mut self) -> Result<()> {
         self.enabled = !self.enabled;
 
-        // Get all LED devices.
-        let mut enumerator = Enumerator::new()?;
-        enumerator.match_subsystem("leds")?;
-        let devices = enumerator.scan_devices()?;
-
         // Find any flashlight device.
-        let mut flash = match devices.into_iter().find_map(Flash::from_device) {
+        let flash = match Flash::find()? {
             Some(flash) => flash,
             None => return Ok(()),
         };
 
-        // Toggle flashlight brightness.
-        let new_value = if flash.enabled() { 0 } else { flash.max_brightness };
-        flash.set_attribute_value("brightness", new_value.to_string())?;
+        match flash {
+            // Toggle flashlight brightness.
+            Flash::Led(mut led) => {
+                let new_value = if led.enabled() { 0 } else { led.scaled_brightness(self.level) };
+                led.set_attribute_value("brightness", new_value.to_string())
+                    .map_err(|source| Error::Udev { subsystem: "leds", source })?;
+            },
+            // Toggle torch mode, keeping the subdevice open while it's active.
+            Flash::V4l2(file) => {
+                let enable = !v4l2_torch_enabled(&file)?;
+                let mode =
+                    if enable { V4L2_FLASH_LED_MODE_TORCH } else { V4L2_FLASH_LED_MODE_NONE };
+                set_v4l2_control(&file, V4L2_CID_FLASH_LED_MODE, mode)?;
+                self.v4l2_torch = enable.then_some(file);
+            },
+        }
 
         Ok(())
     }
@@ -59,23 +108,87 @@ impl Toggle for Flashlight {
     fn enabled(&self) -> bool {
         self.enabled
     }
+
+    fn level(&self) -> Option<f64> {
+        self.level
+    }
+
+    fn set_level(&mut self, level: f64) -> Result<()> {
+        // Intensity can only be controlled for LED class flashlights.
+        let mut led = match Flash::find()? {
+            Some(Flash::Led(led)) if self.level.is_some() => led,
+            _ => return Ok(()),
+        };
+
+        let brightness = led.scaled_brightness(Some(level));
+        led.set_attribute_value("brightness", brightness.to_string())
+            .map_err(|source| Error::Udev { subsystem: "leds", source })?;
+
+        self.level = Some(level);
+        self.enabled = true;
+
+        Ok(())
+    }
+}
+
+/// Flashlight device.
+enum Flash {
+    /// LED class device.
+    Led(LedFlash),
+    /// V4L2 flash subdevice.
+    V4l2(File),
+}
+
+impl Flash {
+    /// Find the first flashlight device.
+    ///
+    /// V4L2 flash controls are only used if no LED class flashlight exists.
+    fn find() -> Result<Option<Flash>> {
+        // Get all LED devices.
+        let devices = module::devices("leds")?;
+        if let Some(led) = devices.into_iter().find_map(LedFlash::from_device) {
+            return Ok(Some(Flash::Led(led)));
+        }
+
+        // Fall back to V4L2 subdevices with flash controls.
+        let devices = module::devices("video4linux")?;
+        Ok(devices.into_iter().find_map(open_v4l2_flash).map(Flash::V4l2))
+    }
+
+    /// Check if flashlight is on.
+    fn enabled(&self) -> bool {
+        match self {
+            Self::Led(led) => led.enabled(),
+            Self::V4l2(file) => v4l2_torch_enabled(file).unwrap_or(false),
+        }
+    }
 }
 
-/// Flashlight udev device.
-struct Flash {
+/// Flashlight LED class udev device.
+struct LedFlash {
     max_brightness: usize,
     brightness: usize,
     device: Device,
 }
 
-impl Flash {
+impl LedFlash {
     /// Check if flashlight is on.
     fn enabled(&self) -> bool {
         self.brightness > 0
     }
 
+    /// Brightness for a torch intensity, using full brightness without intensity.
+    ///
+    /// The minimum is the lowest brightness which still keeps the LED on.
+    fn scaled_brightness(&self, level: Option<f64>) -> usize {
+        match level {
+            Some(level) => ((self.max_brightness as f64 * level).round() as usize).max(1),
+            None => self.max_brightness,
+        }
+    }
+
     /// Convert udev device to flashlight.
-    fn from_device(device: Device) -> Option<Flash> {
+    fn from_device(device: Device) -> Option<LedFlash> {
         // Ignore non-flashlight LEDs.
         if device.sysname() != "white:flash" {
             return None;
@@ -90,7 +203,7 @@ impl Flash {
     }
 }
 
-impl Deref for Flash {
+impl Deref for LedFlash {
     type Target = Device;
 
     fn deref(&self) -> &Self::Target {
@@ -98,8 +211,50 @@ impl Deref for Flash {
     }
 }
 
-impl DerefMut for Flash {
+impl DerefMut for LedFlash {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.device
     }
 }
+
+/// V4L2 control value, for `VIDIOC_G_CTRL` and `VIDIOC_S_CTRL`.
+#[repr(C)]
+struct V4l2Control {
+    id: u32,
+    value: i32,
+}
+
+/// Open a V4L2 subdevice if it has flash controls.
+fn open_v4l2_flash(device: Device) -> Option<File> {
+    // Flash controls are only exposed on subdevices.
+    if !device.sysname().to_string_lossy().starts_with("v4l-subdev") {
+        return None;
+    }
+
+    let file = OpenOptions::new().read(true).write(true).open(device.devnode()?).ok()?;
+    v4l2_control(&file, V4L2_CID_FLASH_LED_MODE).ok()?;
+    Some(file)
+}
+
+/// Check if a V4L2 flash is in torch mode.
+fn v4l2_torch_enabled(file: &File) -> Result<bool> {
+    Ok(v4l2_control(file, V4L2_CID_FLASH_LED_MODE)? == V4L2_FLASH_LED_MODE_TORCH)
+}
+
+/// Read a V4L2 control value.
+fn v4l2_control(file: &File, id: u32) -> Result<i32> {
+    let mut control = V4l2Control { id, value: 0 };
+    if unsafe { libc::ioctl(file.as_raw_fd(), VIDIOC_G_CTRL as _, &mut control) } < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    Ok(control.value)
+}
+
+/// Write a V4L2 control value.
+fn set_v4l2_control(file: &File, id: u32, value: i32) -> Result<()> {
+    let mut control = V4l2Control { id, value };
+    if unsafe { libc::ioctl(file.as_raw_fd(), VIDIOC_S_CTRL as _, &mut control) } < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    Ok(())
+}
@@ -0,0 +1,37 @@
+//! Frame statistics overlay.
+
+use epitaph_ui::renderer::FrameStats;
+
+use crate::module::{Alignment, Module, PanelModule, PanelModuleContent};
+
+/// Debug overlay for renderer performance.
+#[derive(Default)]
+pub struct Hud {
+    stats: FrameStats,
+}
+
+impl Hud {
+    /// Update the displayed statistics.
+    pub fn set_stats(&mut self, stats: FrameStats) {
+        self.stats = stats;
+    }
+}
+
+impl Module for Hud {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        Some(self)
+    }
+}
+
+impl PanelModule for Hud {
+    fn alignment(&self) -> Alignment {
+        Alignment::Center
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        let frame_time = self.stats.frame_time.as_secs_f64() * 1000.;
+        let texture_memory = self.stats.texture_memory / 1024 / 1024;
+        let draw_calls = self.stats.draw_calls;
+        PanelModuleContent::Text(format!("{frame_time:.1}ms {draw_calls}dc {texture_memory}MiB"))
+    }
+}
@@ -0,0 +1,232 @@
+//! Hyprland workspace, window title and submap.
+
+use std::env;
+use std::io::{Read, Write};
+use std::mem;
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use epitaph_modules::{Error, Result};
+use epitaph_ui::text::Svg;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::module::{
+    self, Alignment, DrawerModule, Event, Module, PanelModule, PanelModuleContent, Section, Slider,
+    Source,
+};
+
+/// Number of workspaces selectable from the drawer.
+const WORKSPACE_COUNT: u32 = 10;
+
+/// Maximum number of characters shown for the window title.
+const MAX_TITLE_LEN: usize = 32;
+
+/// Maximum time to wait for Hyprland to accept or answer a request.
+const IPC_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Hyprland status and workspace switcher.
+pub struct Hyprland {
+    workspace: Workspace,
+    buffer: Vec<u8>,
+    submap: String,
+    title: String,
+}
+
+impl Hyprland {
+    pub fn new() -> Self {
+        Self {
+            workspace: Workspace { id: 1, name: String::new() },
+            buffer: Default::default(),
+            submap: Default::default(),
+            title: Default::default(),
+        }
+    }
+
+    /// Reload the active workspace and window.
+    fn load(&mut self) -> Result<bool> {
+        let workspace = request::<Workspace>("j/activeworkspace")?;
+        let window = request::<Window>("j/activewindow")?;
+
+        let old_workspace = mem::replace(&mut self.workspace, workspace);
+        let old_title = mem::replace(&mut self.title, window.title.unwrap_or_default());
+
+        Ok(old_workspace != self.workspace || old_title != self.title)
+    }
+
+    /// Apply a socket2 event.
+    fn handle_event(&mut self, event: &str) -> Result<bool> {
+        let (name, data) = match event.split_once(">>") {
+            Some(event) => event,
+            None => return Ok(false),
+        };
+
+        match name {
+            "workspace" | "focusedmon" => self.load(),
+            "activewindow" => {
+                // Window class and title are separated by the first comma.
+                let title = data.split_once(',').map_or("", |(_, title)| title).to_owned();
+                let old_title = mem::replace(&mut self.title, title);
+                Ok(old_title != self.title)
+            },
+            "submap" => {
+                let old_submap = mem::replace(&mut self.submap, data.to_owned());
+                Ok(old_submap != self.submap)
+            },
+            _ => Ok(false),
+        }
+    }
+}
+
+impl Module for Hyprland {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        Some(self)
+    }
+
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::Slider(self))
+    }
+
+    fn section(&self) -> Section {
+        Section::Display
+    }
+
+    fn probe(&mut self) -> Result<()> {
+        self.load()?;
+        Ok(())
+    }
+
+    fn sources(&self) -> Vec<Source> {
+        vec![Source::Socket(events)]
+    }
+
+    fn update(&mut self, _index: usize, event: Event) -> Result<bool> {
+        let data = match event {
+            Event::Connected => {
+                self.buffer.clear();
+                return self.load();
+            },
+            Event::Disconnected => {
+                self.submap.clear();
+                return Ok(!mem::take(&mut self.title).is_empty());
+            },
+            Event::Data(data) => data,
+            _ => return Ok(false),
+        };
+
+        // Handle all complete event lines.
+        self.buffer.extend_from_slice(&data);
+        let mut dirty = false;
+        while let Some(end) = self.buffer.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            dirty |= self.handle_event(String::from_utf8_lossy(&line).trim_end())?;
+        }
+
+        Ok(dirty)
+    }
+}
+
+impl PanelModule for Hyprland {
+    fn alignment(&self) -> Alignment {
+        Alignment::Left
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        let mut text = self.workspace.name.clone();
+
+        if !self.submap.is_empty() {
+            text.push_str(&format!(" ({})", self.submap));
+        }
+
+        if !self.title.is_empty() {
+            text.push(' ');
+            text.push_str(&module::truncate(&self.title, MAX_TITLE_LEN));
+        }
+
+        PanelModuleContent::Text(text)
+    }
+}
+
+impl Slider for Hyprland {
+    fn set_value(&mut self, value: f64) -> Result<()> {
+        let id = (value.clamp(0., 1.) * (WORKSPACE_COUNT - 1) as f64).round() as i32 + 1;
+        if id == self.workspace.id {
+            return Ok(());
+        }
+
+        let reply = request_raw(&format!("dispatch workspace {id}"))?;
+        if reply != "ok" {
+            return Err(Error::Compositor(reply));
+        }
+
+        self.workspace.id = id;
+
+        Ok(())
+    }
+
+    fn get_value(&self) -> f64 {
+        let id = self.workspace.id.clamp(1, WORKSPACE_COUNT as i32);
+        (id - 1) as f64 / (WORKSPACE_COUNT - 1) as f64
+    }
+
+    fn svg(&self) -> Svg {
+        Svg::Workspace
+    }
+}
+
+/// Hyprland workspace.
+#[derive(Deserialize, PartialEq, Eq)]
+struct Workspace {
+    id: i32,
+    name: String,
+}
+
+/// Hyprland window.
+///
+/// All fields are missing when no window is focused.
+#[derive(Deserialize)]
+struct Window {
+    title: Option<String>,
+}
+
+/// Directory containing the Hyprland IPC sockets.
+fn socket_dir() -> Result<PathBuf> {
+    let signature =
+        env::var_os("HYPRLAND_INSTANCE_SIGNATURE").ok_or(Error::CompositorNotFound("hyprland"))?;
+
+    // Older Hyprland versions place their sockets in `/tmp`.
+    let runtime_dir = env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from);
+    let dir = runtime_dir.map(|dir| dir.join("hypr").join(&signature));
+    match dir.filter(|dir| dir.exists()) {
+        Some(dir) => Ok(dir),
+        None => Ok(PathBuf::from("/tmp/hypr").join(signature)),
+    }
+}
+
+/// Connect to the event socket.
+fn events() -> Result<UnixStream> {
+    Ok(UnixStream::connect(socket_dir()?.join(".socket2.sock"))?)
+}
+
+/// Send a request and return its reply.
+fn request_raw(command: &str) -> Result<String> {
+    let mut stream = UnixStream::connect(socket_dir()?.join(".socket.sock"))?;
+
+    // Avoid blocking the event loop forever if Hyprland stops responding.
+    stream.set_read_timeout(Some(IPC_TIMEOUT))?;
+    stream.set_write_timeout(Some(IPC_TIMEOUT))?;
+
+    stream.write_all(command.as_bytes())?;
+
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply)?;
+
+    Ok(reply)
+}
+
+/// Send a JSON request and parse its reply.
+fn request<T: DeserializeOwned>(command: &str) -> Result<T> {
+    let reply = request_raw(command)?;
+    serde_json::from_str(&reply).map_err(|err| Error::Compositor(err.to_string()))
+}
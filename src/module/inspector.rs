@@ -0,0 +1,50 @@
+//! Touch input overlay.
+
+use crate::module::{Alignment, Module, PanelModule, PanelModuleContent};
+use crate::seat::TouchId;
+
+/// Debug overlay for touch input.
+#[derive(Default)]
+pub struct Inspector {
+    touches: Vec<(TouchId, (f64, f64))>,
+    gesture: String,
+}
+
+impl Inspector {
+    /// Update the position of a touch point.
+    ///
+    /// Passing `None` removes the touch point.
+    pub fn set_touch(&mut self, id: TouchId, position: Option<(f64, f64)>) {
+        self.touches.retain(|(touch, _)| touch != &id);
+        if let Some(position) = position {
+            self.touches.push((id, position));
+        }
+    }
+
+    /// Update the displayed gesture state.
+    pub fn set_gesture(&mut self, gesture: String) {
+        self.gesture = gesture;
+    }
+
+    /// Positions of all active touch points.
+    pub fn touch_positions(&self) -> Vec<(f64, f64)> {
+        self.touches.iter().map(|(_, position)| *position).collect()
+    }
+}
+
+impl Module for Inspector {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        Some(self)
+    }
+}
+
+impl PanelModule for Inspector {
+    fn alignment(&self) -> Alignment {
+        Alignment::Center
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        let touches = self.touches.len();
+        PanelModuleContent::Text(format!("{touches}t {}", self.gesture))
+    }
+}
@@ -0,0 +1,77 @@
+//! Notifications for systemd journal errors.
+
+use std::process::Command;
+
+use epitaph_modules::Result;
+use serde::Deserialize;
+
+use crate::module::{self, Event, Module, Signals, Source, Urgency};
+
+/// Maximum number of characters shown for an error message.
+const MAX_MESSAGE_LEN: usize = 256;
+
+/// Journal watcher raising notifications for errors.
+pub struct Journal {
+    units: Vec<String>,
+    buffer: Vec<u8>,
+    signals: Signals,
+}
+
+impl Journal {
+    pub fn new(units: Vec<String>, signals: Signals) -> Self {
+        Self { units, signals, buffer: Default::default() }
+    }
+}
+
+impl Module for Journal {
+    fn sources(&self) -> Vec<Source> {
+        let units = self.units.clone();
+        vec![Source::Process(Box::new(move || {
+            // Follow new error messages, without replaying old ones.
+            let mut journalctl = Command::new("journalctl");
+            journalctl.args(["--follow", "--lines=0", "--priority=err", "--output=json"]);
+            for unit in &units {
+                journalctl.arg("--unit").arg(unit);
+            }
+            journalctl
+        }))]
+    }
+
+    fn update(&mut self, _index: usize, event: Event) -> Result<bool> {
+        let data = match event {
+            Event::Connected => {
+                self.buffer.clear();
+                return Ok(false);
+            },
+            Event::Data(data) => data,
+            _ => return Ok(false),
+        };
+
+        // Notify about every complete journal entry.
+        self.buffer.extend_from_slice(&data);
+        while let Some(end) = self.buffer.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let entry = match serde_json::from_slice::<Entry>(&line) {
+                Ok(entry) => entry,
+                Err(_) => continue,
+            };
+
+            let unit = entry.unit.or(entry.identifier).unwrap_or_else(|| "journal".into());
+            let message = module::truncate(&entry.message, MAX_MESSAGE_LEN);
+            self.signals.notify(Urgency::Normal, "journal", &unit, &message);
+        }
+
+        Ok(false)
+    }
+}
+
+/// Journal entry in JSON output format.
+#[derive(Deserialize)]
+struct Entry {
+    #[serde(rename = "MESSAGE")]
+    message: String,
+    #[serde(rename = "_SYSTEMD_UNIT")]
+    unit: Option<String>,
+    #[serde(rename = "SYSLOG_IDENTIFIER")]
+    identifier: Option<String>,
+}
@@ -0,0 +1,158 @@
+//! Location services status and toggle.
+
+use std::mem;
+use std::process::{Command, Output};
+use std::time::{Duration, Instant};
+
+use epitaph_modules::Result;
+use epitaph_ui::text::Svg;
+
+use crate::module::{
+    Alignment, DrawerModule, Event, Module, PanelModule, PanelModuleContent, Section, Source,
+    Toggle,
+};
+use crate::reaper;
+
+/// Refresh interval for this module.
+const UPDATE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Time after toggling the service until updates are resumed.
+const TOGGLE_COOLDOWN: Duration = Duration::from_secs(10);
+
+/// Systemd unit of the location service.
+const SERVICE: &str = "geoclue";
+
+/// Index of the `systemctl` command in the module's sources.
+const SERVICE_SOURCE: usize = 0;
+
+/// Geoclue location service.
+pub struct Location {
+    last_toggle: Option<Instant>,
+    enabled: bool,
+    in_use: bool,
+}
+
+impl Location {
+    pub fn new() -> Self {
+        Self { last_toggle: None, enabled: false, in_use: false }
+    }
+
+    /// Handle `systemctl` command completion.
+    fn service_update(&mut self, output: Output) -> bool {
+        let enabled = String::from_utf8_lossy(&output.stdout).trim() == "active";
+        let old_enabled = mem::replace(&mut self.enabled, enabled);
+
+        // Stopped services cannot have any clients.
+        let old_in_use = mem::replace(&mut self.in_use, self.in_use && enabled);
+
+        old_enabled != self.enabled || old_in_use != self.in_use
+    }
+
+    /// Handle `busctl` command completion.
+    fn clients_update(&mut self, output: Output) -> bool {
+        let in_use = String::from_utf8_lossy(&output.stdout).trim() == "b true";
+        let old_in_use = mem::replace(&mut self.in_use, in_use);
+        old_in_use != self.in_use
+    }
+}
+
+impl Module for Location {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        Some(self)
+    }
+
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::Toggle(self))
+    }
+
+    fn section(&self) -> Section {
+        Section::Connectivity
+    }
+
+    fn sources(&self) -> Vec<Source> {
+        vec![
+            Source::Command(Box::new(systemctl), UPDATE_INTERVAL),
+            Source::Command(Box::new(busctl), UPDATE_INTERVAL),
+        ]
+    }
+
+    fn update(&mut self, index: usize, event: Event) -> Result<bool> {
+        // Temporarily suspend updates after toggling the service.
+        if self.last_toggle.map_or(false, |toggle| toggle.elapsed() < TOGGLE_COOLDOWN) {
+            return Ok(false);
+        }
+
+        let output = match event {
+            Event::Output(output) => output?,
+            _ => return Ok(false),
+        };
+
+        match index {
+            SERVICE_SOURCE => Ok(self.service_update(output)),
+            _ => Ok(self.clients_update(output)),
+        }
+    }
+}
+
+impl PanelModule for Location {
+    fn alignment(&self) -> Alignment {
+        Alignment::Right
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        // Only show an indicator while an application is using the location.
+        if self.in_use {
+            PanelModuleContent::Svg(Svg::Location)
+        } else {
+            PanelModuleContent::Text(String::new())
+        }
+    }
+}
+
+impl Toggle for Location {
+    fn toggle(&mut self) -> Result<()> {
+        // Temporarily block updates after toggling.
+        self.last_toggle = Some(Instant::now());
+
+        // Immediately change status for better UX.
+        self.enabled = !self.enabled;
+        self.in_use &= self.enabled;
+
+        let action = if self.enabled { "start" } else { "stop" };
+        let _ = reaper::daemon("systemctl", [action, SERVICE]);
+
+        Ok(())
+    }
+
+    fn svg(&self) -> Svg {
+        Svg::Location
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+/// Command for location service status updates.
+fn systemctl() -> Command {
+    let mut systemctl = Command::new("systemctl");
+    systemctl.args(["is-active", SERVICE]);
+    systemctl
+}
+
+/// Command for location client updates.
+///
+/// This does not auto-start geoclue, to avoid enabling the service while checking its status.
+fn busctl() -> Command {
+    let mut busctl = Command::new("busctl");
+    busctl.args([
+        "--system",
+        "--auto-start=false",
+        "get-property",
+        "org.freedesktop.GeoClue2",
+        "/org/freedesktop/GeoClue2/Manager",
+        "org.freedesktop.GeoClue2.Manager",
+        "InUse",
+    ]);
+    busctl
+}
@@ -0,0 +1,212 @@
+//! Unread mail count.
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::mem;
+use std::os::unix::io::FromRawFd;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use epitaph_modules::{Error, Result};
+use epitaph_ui::text::Svg;
+
+use crate::config::{self, ImapAccount};
+use crate::module::{
+    Alignment, DrawerModule, Event, Module, PanelModule, PanelModuleContent, Signals, Source,
+    Toggle,
+};
+use crate::panel::TEXT_COLOR;
+
+/// Refresh interval for IMAP accounts.
+const IMAP_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Refresh interval for the maildir.
+const MAILDIR_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Unread messages in IMAP accounts and a maildir.
+pub struct Mail {
+    config: config::Mail,
+    unread: Vec<usize>,
+    signals: Signals,
+}
+
+impl Mail {
+    pub fn new(config: config::Mail, signals: Signals) -> Self {
+        let sources = config.accounts.len() + config.maildir.iter().len();
+        Self { config, signals, unread: vec![0; sources] }
+    }
+
+    /// Total number of unread messages.
+    fn total(&self) -> usize {
+        self.unread.iter().sum()
+    }
+}
+
+impl Module for Mail {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        Some(self)
+    }
+
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::Toggle(self))
+    }
+
+    fn probe(&mut self) -> Result<()> {
+        if self.unread.is_empty() {
+            return Err(Error::NotConfigured("mail"));
+        }
+        Ok(())
+    }
+
+    fn sources(&self) -> Vec<Source> {
+        let mut sources = Vec::new();
+
+        for account in &self.config.accounts {
+            let account = account.clone();
+            sources.push(Source::Command(Box::new(move || imap_command(&account)), IMAP_INTERVAL));
+        }
+
+        if self.config.maildir.is_some() {
+            sources.push(Source::Interval(MAILDIR_INTERVAL));
+        }
+
+        sources
+    }
+
+    fn update(&mut self, index: usize, event: Event) -> Result<bool> {
+        let unread = match (event, &self.config.maildir) {
+            (Event::Output(output), _) => {
+                match parse_unseen(&String::from_utf8_lossy(&output?.stdout)) {
+                    Some(unread) => unread,
+                    // Keep the last count while the server is unreachable.
+                    None => return Ok(false),
+                }
+            },
+            (Event::Timer, Some(maildir)) => maildir_unread(maildir)?,
+            _ => return Ok(false),
+        };
+
+        let old_unread = self.unread.get_mut(index).map(|old| mem::replace(old, unread));
+
+        Ok(old_unread != Some(unread))
+    }
+}
+
+impl PanelModule for Mail {
+    fn alignment(&self) -> Alignment {
+        Alignment::Right
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        // Only show the badge while there are unread messages.
+        match self.total() {
+            0 => PanelModuleContent::Text(String::new()),
+            total => PanelModuleContent::Status {
+                text: total.to_string(),
+                svg: Some(Svg::Mail),
+                color: TEXT_COLOR,
+            },
+        }
+    }
+}
+
+impl Toggle for Mail {
+    fn toggle(&mut self) -> Result<()> {
+        let (program, args) =
+            self.config.command.split_first().ok_or(Error::NotConfigured("mail command"))?;
+        let mut command = Command::new(program);
+        command.args(args);
+        self.signals.launch(command);
+        Ok(())
+    }
+
+    fn enabled(&self) -> bool {
+        self.total() > 0
+    }
+
+    fn svg(&self) -> Svg {
+        Svg::Mail
+    }
+}
+
+/// Create a `curl` command requesting the unread count of an IMAP mailbox.
+fn imap_command(account: &ImapAccount) -> Command {
+    let mut command = Command::new("curl");
+    command.args(["--silent", "--show-error"]);
+
+    // Pass passwords through stdin, since arguments are visible to all users.
+    let password = account.password.as_ref();
+    match password.map(|password| credentials_pipe(&account.user, password)) {
+        Some(Ok(stdin)) => command.arg("--config").arg("-").stdin(stdin),
+        Some(Err(err)) => {
+            eprintln!("Error: Couldn't pass IMAP credentials to curl: {err}");
+            command.arg("--netrc").arg("--user").arg(&account.user)
+        },
+        None => command.arg("--netrc").arg("--user").arg(&account.user),
+    };
+
+    command.arg("--request").arg(format!("STATUS \"{}\" (UNSEEN)", account.mailbox));
+    command.arg(&account.url);
+
+    command
+}
+
+/// Create a pipe containing a curl config with the login credentials.
+fn credentials_pipe(user: &str, password: &str) -> io::Result<Stdio> {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let (reader, mut writer) = unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) };
+
+    // Quote the credentials, escaping characters with special meaning in curl configs.
+    let credentials = format!("{user}:{password}").replace('\\', "\\\\").replace('"', "\\\"");
+
+    // The config is much smaller than the pipe buffer, so this never blocks.
+    writeln!(writer, "user = \"{credentials}\"")?;
+
+    Ok(Stdio::from(reader))
+}
+
+/// Parse the unread count from an IMAP `STATUS` reply.
+fn parse_unseen(reply: &str) -> Option<usize> {
+    let count = &reply[reply.find("UNSEEN ")? + "UNSEEN ".len()..];
+    let end = count.find(|c: char| !c.is_ascii_digit()).unwrap_or(count.len());
+    count[..end].parse().ok()
+}
+
+/// Count unread messages in a maildir.
+fn maildir_unread(maildir: &Path) -> Result<usize> {
+    let new = fs::read_dir(maildir.join("new"))?.count();
+
+    // Messages in `cur` are unread unless their info contains the seen flag.
+    let cur = fs::read_dir(maildir.join("cur"))?
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            name.rsplit_once(":2,").map_or(true, |(_, flags)| !flags.contains('S'))
+        })
+        .count();
+
+    Ok(new + cur)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_status_reply() {
+        assert_eq!(parse_unseen("* STATUS INBOX (UNSEEN 12)\r\n"), Some(12));
+        assert_eq!(parse_unseen("* STATUS \"INBOX\" (MESSAGES 20 UNSEEN 0 RECENT 1)"), Some(0));
+    }
+
+    #[test]
+    fn reject_reply_without_count() {
+        assert_eq!(parse_unseen("* STATUS INBOX (MESSAGES 20)"), None);
+        assert_eq!(parse_unseen("* STATUS INBOX (UNSEEN )"), None);
+        assert_eq!(parse_unseen(""), None);
+    }
+}
@@ -1,77 +1,879 @@
 //! Panel modules.
 
-use crate::text::Svg;
-use crate::Result;
+use std::cell::Cell;
+use std::fs::{File, OpenOptions};
+use std::io::{self, ErrorKind, Read};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::{AsFd, AsRawFd, FromRawFd, RawFd};
+use std::path::Path;
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use std::{mem, ptr};
 
+use calloop::generic::Generic;
+use calloop::timer::{TimeoutAction, Timer};
+use calloop::{channel, Interest, LoopHandle, Mode, PostAction, RegistrationToken};
+use chrono::offset::Local;
+use epitaph_modules::Error as ModuleError;
+use serde::Serialize;
+#[cfg(feature = "udev")]
+use udev::{Device, Enumerator, MonitorBuilder};
+
+use crate::error::Error;
+use crate::layout::ModuleId;
+#[cfg(feature = "udev")]
+use crate::led::PowerState;
+use crate::{reaper, Modules, Result, State};
+
+pub use epitaph_modules::{
+    Alignment, DrawerModule, Event, Graph, List, Module, PanelModule, PanelModuleContent, Section,
+    Slider, Source, Toggle,
+};
+
+#[cfg(feature = "udev")]
 pub mod battery;
+#[cfg(feature = "udev")]
 pub mod brightness;
+pub mod cast;
 pub mod cellular;
+pub mod clipboard;
 pub mod clock;
+pub mod dnd;
+#[cfg(feature = "udev")]
+pub mod dock;
+pub mod ethernet;
+#[cfg(feature = "udev")]
 pub mod flashlight;
+pub mod hud;
+pub mod hyprland;
+pub mod inspector;
+pub mod journal;
+pub mod location;
+pub mod mail;
 pub mod orientation;
+pub mod osd;
+pub mod overflow;
+pub mod pipe;
+#[cfg(feature = "udev")]
+pub mod privacy;
+pub mod recorder;
+pub mod river;
+pub mod rotation;
+pub mod screenshot;
+pub mod sway;
+pub mod system;
+pub mod systemd;
+pub mod timer;
+pub mod updates;
+#[cfg(feature = "udev")]
+pub mod usb;
 pub mod wifi;
+pub mod wireguard;
 
-/// Panel module.
-pub trait Module {
-    /// Panel module implementation.
-    fn panel_module(&self) -> Option<&dyn PanelModule> {
-        None
+/// Delay before the first retry of a failed module.
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Maximum delay between retries of a failed module.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
+
+/// Delay between attempts to connect a socket source.
+const SOCKET_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Delay before running a watched command after its monitor reported changes.
+const WATCH_DELAY: Duration = Duration::from_secs(1);
+
+/// Size of the buffer for reading from socket sources.
+const SOCKET_READ_SIZE: usize = 4096;
+
+/// Delay before updating after a timezone change.
+///
+/// Chrono reuses its cached local timezone for up to a second.
+const TIMEZONE_UPDATE_DELAY: Duration = Duration::from_secs(1);
+
+/// File name of the system timezone in `/etc`.
+const LOCALTIME_NAME: &[u8] = b"localtime";
+
+/// Path of the system timezone.
+const LOCALTIME_PATH: &[u8] = b"/etc/localtime\0";
+
+/// Factor by which polling intervals are lengthened in power saving mode.
+const POWER_SAVING_FACTOR: u32 = 4;
+
+/// Whether stdin was already claimed by a pipe source.
+static STDIN_OPENED: AtomicBool = AtomicBool::new(false);
+
+/// Register a module's event sources with the event loop.
+///
+/// If any source fails to register, all of the module's sources are removed again.
+pub fn register(
+    event_loop: &LoopHandle<'static, State>,
+    id: ModuleId,
+    module: &dyn Module,
+) -> Result<()> {
+    let mut tokens = Vec::new();
+    let result = register_sources(event_loop, id, module, &mut tokens);
+
+    if result.is_err() {
+        for token in tokens {
+            event_loop.remove(token);
+        }
     }
 
-    /// Drawer module implementation.
-    fn drawer_module(&mut self) -> Option<DrawerModule> {
-        None
+    result
+}
+
+/// Register event sources, collecting their tokens.
+fn register_sources(
+    event_loop: &LoopHandle<'static, State>,
+    id: ModuleId,
+    module: &dyn Module,
+    tokens: &mut Vec<RegistrationToken>,
+) -> Result<()> {
+    for (index, source) in module.sources().into_iter().enumerate() {
+        match source {
+            Source::Interval(interval) => {
+                let timer = Timer::immediate();
+                let token = event_loop.insert_source(timer, move |now, _, state| {
+                    dispatch(state, id, index, Event::Timer);
+                    TimeoutAction::ToInstant(now + poll_interval(state, interval))
+                })?;
+                tokens.push(token);
+            },
+            Source::Minute => {
+                let timer = Timer::immediate();
+                let token = event_loop.insert_source(timer, move |now, _, state| {
+                    dispatch(state, id, index, Event::Timer);
+
+                    // Calculate time until next minute. We add one second just to be sure.
+                    let total_secs = UNIX_EPOCH.elapsed().unwrap().as_secs();
+                    let remaining = Duration::from_secs(60 - (total_secs % 60) + 1);
+
+                    TimeoutAction::ToInstant(now + remaining)
+                })?;
+                tokens.push(token);
+            },
+            Source::Midnight => {
+                let timer = midnight_timer()?;
+                let timezone_timer = Rc::new(timer.try_clone()?);
+
+                // Update at midnight and whenever the system clock is set.
+                let source = Generic::new(timer, Interest::READ, Mode::Level);
+                let token = event_loop.insert_source(source, move |_, timer, state| {
+                    // Acknowledge expiration, which fails with `ECANCELED` if the clock was set.
+                    let _ = timer.read(&mut [0; 8]);
+                    arm_midnight_timer(timer)?;
+
+                    dispatch(state, id, index, Event::Timer);
+
+                    Ok(PostAction::Continue)
+                })?;
+                tokens.push(token);
+
+                // Update once the timezone changed, since that also moves local midnight.
+                let source = Generic::new(localtime_watch()?, Interest::READ, Mode::Level);
+                let token = event_loop.insert_source(source, move |_, watch, state| {
+                    if !localtime_changed(watch) {
+                        return Ok(PostAction::Continue);
+                    }
+
+                    let timer = timezone_timer.clone();
+                    let delay = Timer::from_duration(TIMEZONE_UPDATE_DELAY);
+                    let result = state.event_loop.insert_source(delay, move |_, _, state| {
+                        if let Err(err) = arm_midnight_timer(&timer) {
+                            eprintln!("Error: Couldn't rearm {id} midnight timer: {err}");
+                        }
+                        dispatch(state, id, index, Event::Timer);
+                        TimeoutAction::Drop
+                    });
+                    if let Err(err) = result {
+                        eprintln!("Error: Couldn't schedule {id} module timezone update: {err}");
+                    }
+
+                    Ok(PostAction::Continue)
+                })?;
+                tokens.push(token);
+            },
+            #[cfg(feature = "udev")]
+            Source::Udev(subsystem) => {
+                let socket = MonitorBuilder::new()
+                    .and_then(|builder| builder.match_subsystem(subsystem))
+                    .and_then(|builder| builder.listen())
+                    .map_err(|source| Error::Udev { subsystem, source })?;
+                let source = Generic::new(socket, Interest::READ, Mode::Level);
+                let token = event_loop.insert_source(source, move |_, socket, state| {
+                    // Drain all pending device events.
+                    if socket.iter().count() > 0 {
+                        dispatch(state, id, index, Event::Udev);
+                    }
+
+                    Ok(PostAction::Continue)
+                })?;
+                tokens.push(token);
+            },
+            // Device changes can't be monitored without udev support.
+            #[cfg(not(feature = "udev"))]
+            Source::Udev(_) => (),
+            Source::Command(command, interval) => {
+                let timer = Timer::immediate();
+                let token = event_loop.insert_source(timer, move |now, _, state| {
+                    spawn_command(state, id, index, command());
+                    TimeoutAction::ToInstant(now + poll_interval(state, interval))
+                })?;
+                tokens.push(token);
+            },
+            Source::Socket(connect) => {
+                let connect = move || {
+                    let stream = connect()?;
+                    stream.set_nonblocking(true)?;
+                    Ok(stream)
+                };
+                tokens.push(connect_stream(event_loop, id, connect, forward(id, index))?);
+            },
+            Source::Pipe(path) => {
+                let connect = move || open_pipe(path.as_deref());
+                tokens.push(connect_stream(event_loop, id, connect, forward(id, index))?);
+            },
+            Source::Process(command) => {
+                let command: Rc<dyn Fn() -> Command> = Rc::from(command);
+                let connect = move || ProcessStream::spawn(command());
+                tokens.push(connect_stream(event_loop, id, connect, forward(id, index))?);
+            },
+            Source::Watch(command, monitor) => {
+                let command: Rc<dyn Fn() -> Command> = Rc::from(command);
+                let monitor: Rc<dyn Fn() -> Command> = Rc::from(monitor);
+                let connect = move || ProcessStream::spawn(monitor());
+
+                // Coalesce bursts of monitor output into a single command run.
+                let pending = Rc::new(Cell::new(false));
+                let handler = move |state: &mut State, event: Event| {
+                    if !matches!(event, Event::Connected | Event::Data(_)) || pending.get() {
+                        return;
+                    }
+
+                    let command = command.clone();
+                    let pending = pending.clone();
+                    let delay = Timer::from_duration(WATCH_DELAY);
+                    let result = state.event_loop.insert_source(delay, move |_, _, state| {
+                        pending.set(false);
+                        spawn_command(state, id, index, command());
+                        TimeoutAction::Drop
+                    });
+
+                    match result {
+                        Ok(_) => pending.set(true),
+                        Err(err) => eprintln!("Error: Couldn't schedule {id} module update: {err}"),
+                    }
+                };
+                tokens.push(connect_stream(event_loop, id, connect, handler)?);
+            },
+            Source::Netlink(groups) => {
+                let connect = move || netlink_socket(groups);
+                tokens.push(connect_stream(event_loop, id, connect, forward(id, index))?);
+            },
+        }
     }
+
+    Ok(())
 }
 
-/// Module alignment.
-#[derive(Copy, Clone, PartialEq, Eq)]
-pub enum Alignment {
-    Center,
-    Right,
+/// Run a module's command in the background and forward its output.
+fn spawn_command(state: &mut State, id: ModuleId, index: usize, command: Command) {
+    let output = state.runtime.command_output(command);
+    state.runtime.spawn(async move {
+        let output = output.await;
+        move |state: &mut State| dispatch(state, id, index, Event::Output(output))
+    });
 }
 
-/// Module in the panel.
-pub trait PanelModule {
-    /// Module alignment.
-    fn alignment(&self) -> Alignment;
+/// Stream event handler forwarding all events to a module.
+fn forward(id: ModuleId, index: usize) -> impl Fn(&mut State, Event) + Clone + 'static {
+    move |state, event| dispatch(state, id, index, event)
+}
+
+/// Connect a streaming source, retrying until the connection succeeds.
+fn connect_stream<F, S, H>(
+    event_loop: &LoopHandle<'static, State>,
+    id: ModuleId,
+    connect: F,
+    handler: H,
+) -> Result<RegistrationToken>
+where
+    F: Fn() -> Result<S> + Clone + 'static,
+    S: Read + AsRawFd + 'static,
+    H: Fn(&mut State, Event) + Clone + 'static,
+{
+    let token = event_loop.insert_source(Timer::immediate(), move |_, _, state| {
+        // Retry until the stream is available.
+        let stream = match connect() {
+            Ok(stream) => stream,
+            Err(_) => return TimeoutAction::ToDuration(SOCKET_RETRY_DELAY),
+        };
+
+        let connect = connect.clone();
+        let stream_handler = handler.clone();
+        let source = Generic::new(stream, Interest::READ, Mode::Level);
+        let result = state.event_loop.insert_source(source, move |_, stream, state| {
+            let mut buffer = vec![0; SOCKET_READ_SIZE];
+            match stream.read(&mut buffer) {
+                Ok(len) if len > 0 => {
+                    buffer.truncate(len);
+                    stream_handler(state, Event::Data(buffer));
+                    Ok(PostAction::Continue)
+                },
+                Err(err) if err.kind() == ErrorKind::WouldBlock => Ok(PostAction::Continue),
+                // Reconnect once the stream was closed.
+                _ => {
+                    stream_handler(state, Event::Disconnected);
+                    let (connect, handler) = (connect.clone(), stream_handler.clone());
+                    if let Err(err) = connect_stream(&state.event_loop, id, connect, handler) {
+                        eprintln!("Error: Couldn't reconnect {id} module: {err}");
+                    }
+                    Ok(PostAction::Remove)
+                },
+            }
+        });
 
-    /// Renderable panel content.
-    fn content(&self) -> PanelModuleContent;
+        match result {
+            Ok(_) => handler(state, Event::Connected),
+            Err(err) => eprintln!("Error: Couldn't register {id} module stream: {err}"),
+        }
+
+        TimeoutAction::Drop
+    })?;
+
+    Ok(token)
 }
 
-/// Panel module renderable.
-pub enum PanelModuleContent {
-    Text(String),
-    Svg(Svg),
+/// Open a named FIFO, or stdin if no path is specified.
+fn open_pipe(path: Option<&Path>) -> Result<File> {
+    let path = match path {
+        Some(path) => path,
+        // Stdin is only read once, since it cannot be reopened after it was closed.
+        None if STDIN_OPENED.swap(true, Ordering::Relaxed) => {
+            return Err(io::Error::from(ErrorKind::UnexpectedEof).into());
+        },
+        None => return Ok(File::from(io::stdin().as_fd().try_clone_to_owned()?)),
+    };
+
+    // Open for writing too, so the FIFO stays open while no writer is connected.
+    let pipe =
+        OpenOptions::new().read(true).write(true).custom_flags(libc::O_NONBLOCK).open(path)?;
+
+    Ok(pipe)
 }
 
-/// Module in the drawer.
-pub enum DrawerModule<'a> {
-    Toggle(&'a mut dyn Toggle),
-    Slider(&'a mut dyn Slider),
+/// Open a non-blocking route netlink socket subscribed to multicast groups.
+fn netlink_socket(groups: u32) -> Result<File> {
+    let flags = libc::SOCK_RAW | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC;
+    let fd = unsafe { libc::socket(libc::AF_NETLINK, flags, libc::NETLINK_ROUTE) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    let socket = unsafe { File::from_raw_fd(fd) };
+
+    let mut address: libc::sockaddr_nl = unsafe { mem::zeroed() };
+    address.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+    address.nl_groups = groups;
+
+    let address_ptr = &address as *const libc::sockaddr_nl as *const libc::sockaddr;
+    let address_len = mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t;
+    if unsafe { libc::bind(fd, address_ptr, address_len) } < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+
+    Ok(socket)
 }
 
-/// Drawer slider module.
-pub trait Slider {
-    /// Handle slider updates.
-    fn set_value(&mut self, value: f64) -> Result<()>;
+/// Create a realtime timer firing at the start of the next local day.
+///
+/// Unlike the event loop's monotonic timers, this fires right away when
+/// midnight passed while the device was suspended. It is also cancelled
+/// whenever the system clock is set, like after NTP synchronization.
+fn midnight_timer() -> Result<File> {
+    let flags = libc::TFD_NONBLOCK | libc::TFD_CLOEXEC;
+    let fd = unsafe { libc::timerfd_create(libc::CLOCK_REALTIME, flags) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    let timer = unsafe { File::from_raw_fd(fd) };
 
-    /// Get current slider value.
-    fn get_value(&self) -> f64;
+    arm_midnight_timer(&timer)?;
 
-    /// Get symbol for this slider.
-    fn svg(&self) -> Svg;
+    Ok(timer)
 }
 
-/// Drawer toggle button module.
-pub trait Toggle {
-    /// Toggle button status.
-    fn toggle(&mut self) -> Result<()>;
+/// Schedule a midnight timer for the start of the next local day.
+fn arm_midnight_timer(timer: &File) -> io::Result<()> {
+    let now = Local::now();
+    let tomorrow = now.naive_local().date().succ_opt().and_then(|date| date.and_hms_opt(0, 0, 0));
+
+    // Fall back to an hour from now if midnight is skipped by a DST transition.
+    let midnight = tomorrow.and_then(|time| time.and_local_timezone(Local).earliest());
+    let timestamp = midnight.map_or(now.timestamp() + 3600, |midnight| midnight.timestamp());
 
-    /// Get button status.
-    fn enabled(&self) -> bool;
+    let spec = libc::itimerspec {
+        it_interval: libc::timespec { tv_sec: 0, tv_nsec: 0 },
+        it_value: libc::timespec { tv_sec: timestamp as libc::time_t, tv_nsec: 0 },
+    };
+    let flags = libc::TFD_TIMER_ABSTIME | libc::TFD_TIMER_CANCEL_ON_SET;
+    if unsafe { libc::timerfd_settime(timer.as_raw_fd(), flags, &spec, ptr::null_mut()) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Watch `/etc/localtime` for changes to the system timezone.
+///
+/// The symlink is usually replaced rather than modified, so its parent
+/// directory is watched for new entries too.
+fn localtime_watch() -> Result<File> {
+    let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    let watch = unsafe { File::from_raw_fd(fd) };
+
+    let mask = libc::IN_ONLYDIR | libc::IN_CREATE | libc::IN_MOVED_TO;
+    let path = b"/etc\0".as_ptr() as *const libc::c_char;
+    if unsafe { libc::inotify_add_watch(fd, path, mask) } < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+
+    // Systems without a configured timezone use UTC until the file is created.
+    let _ = watch_localtime(&watch);
+
+    Ok(watch)
+}
+
+/// Watch the current `/etc/localtime` itself, without following the symlink.
+fn watch_localtime(watch: &File) -> io::Result<()> {
+    let mask = libc::IN_DONT_FOLLOW
+        | libc::IN_CLOSE_WRITE
+        | libc::IN_ATTRIB
+        | libc::IN_DELETE_SELF
+        | libc::IN_MOVE_SELF;
+    let path = LOCALTIME_PATH.as_ptr() as *const libc::c_char;
+    if unsafe { libc::inotify_add_watch(watch.as_raw_fd(), path, mask) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Drain all pending timezone changes, returning whether the timezone was changed.
+fn localtime_changed(watch: &mut File) -> bool {
+    let header_len = mem::size_of::<libc::inotify_event>();
+    let mut buffer = [0; SOCKET_READ_SIZE];
+    let mut changed = false;
+
+    loop {
+        let len = match watch.read(&mut buffer) {
+            Ok(len) if len > 0 => len,
+            _ => break,
+        };
+
+        // Events are a header followed by the NUL-padded file name.
+        let mut offset = 0;
+        while offset + header_len <= len {
+            let header = buffer[offset..].as_ptr() as *const libc::inotify_event;
+            let event = unsafe { ptr::read_unaligned(header) };
+
+            let name_start = offset + header_len;
+            let name_end = (name_start + event.len as usize).min(len);
+            let name = buffer[name_start..name_end].split(|byte| *byte == 0).next();
+            // Events without a name are for the `/etc/localtime` watch itself.
+            changed |= event.len == 0 || name == Some(LOCALTIME_NAME);
+
+            offset = name_end;
+        }
+    }
+
+    // Follow the replacement file, the old watch is removed with its inode.
+    if changed {
+        let _ = watch_localtime(watch);
+    }
+
+    changed
+}
+
+/// Stdout of a child process, which is killed once the stream is dropped.
+pub struct ProcessStream {
+    stdout: ChildStdout,
+    child: Child,
+}
+
+impl ProcessStream {
+    /// Spawn a process with non-blocking stdout.
+    pub fn spawn(mut command: Command) -> Result<Self> {
+        command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::null());
+
+        let mut child = command.spawn()?;
+        let stdout = child.stdout.take().ok_or_else(|| io::Error::from(ErrorKind::BrokenPipe))?;
+
+        let fd = stdout.as_raw_fd();
+        unsafe {
+            let flags = libc::fcntl(fd, libc::F_GETFL);
+            libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK);
+        }
+
+        Ok(Self { stdout, child })
+    }
+}
+
+impl Read for ProcessStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stdout.read(buf)
+    }
+}
+
+impl AsRawFd for ProcessStream {
+    fn as_raw_fd(&self) -> RawFd {
+        self.stdout.as_raw_fd()
+    }
+}
+
+impl Drop for ProcessStream {
+    fn drop(&mut self) {
+        // Reap the process to avoid leaving a zombie behind.
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Forward an event to its module.
+#[cfg_attr(feature = "profiling", tracing::instrument(skip(state, event)))]
+fn dispatch(state: &mut State, id: ModuleId, index: usize, event: Event) {
+    // Ignore updates for failed modules, but retry immediately on hotplug.
+    if state.modules.failures.contains_key(&id) {
+        if let Event::Udev = event {
+            if let Some(retry_timer) = recover(state, id) {
+                state.event_loop.remove(retry_timer);
+            }
+        }
+        return;
+    }
 
-    /// Get renderable SVG.
-    fn svg(&self) -> Svg;
+    let start = Instant::now();
+    let result = match state.modules.get_mut(id) {
+        Some(module) => module.update(index, event),
+        None => return,
+    };
+    if let Some(metrics) = &mut state.metrics {
+        metrics.record_update(id, start.elapsed());
+    }
+
+    match result {
+        Ok(true) => state.request_frame(),
+        Ok(false) => (),
+        Err(err) => {
+            fail(&state.event_loop, &mut state.modules, id, &err);
+            state.request_frame();
+        },
+    }
+}
+
+/// Failed module awaiting recovery.
+pub struct Failure {
+    retry_timer: RegistrationToken,
+    /// Event sources must be registered before the module can recover.
+    pub unregistered: bool,
+}
+
+/// Disable a failed module and periodically try to recover it.
+pub fn fail(
+    event_loop: &LoopHandle<'static, State>,
+    modules: &mut Modules,
+    id: ModuleId,
+    err: &ModuleError,
+) {
+    if modules.failures.contains_key(&id) {
+        return;
+    }
+
+    // Missing hardware is expected, since not every device has every module.
+    if err.is_unavailable() {
+        eprintln!("Disabling {id} module: {err}");
+    } else {
+        eprintln!("Error: Disabling {id} module: {err}");
+    }
+
+    // Retry with exponential backoff.
+    let mut delay = RETRY_DELAY;
+    let timer = Timer::from_duration(delay);
+    let retry_timer = event_loop.insert_source(timer, move |_, _, state| {
+        if !state.modules.failures.contains_key(&id) || recover(state, id).is_some() {
+            return TimeoutAction::Drop;
+        }
+
+        delay = (delay * 2).min(MAX_RETRY_DELAY);
+        TimeoutAction::ToDuration(delay)
+    });
+
+    match retry_timer {
+        Ok(retry_timer) => {
+            modules.failures.insert(id, Failure { retry_timer, unregistered: false });
+        },
+        Err(err) => eprintln!("Error: Couldn't schedule {id} module retry: {err}"),
+    }
+}
+
+/// Try to recover a failed module.
+///
+/// Returns the retry timer of the module if it was recovered successfully.
+fn recover(state: &mut State, id: ModuleId) -> Option<RegistrationToken> {
+    state.modules.get_mut(id)?.probe().ok()?;
+
+    // Register event sources which couldn't be registered before.
+    if state.modules.failures.get(&id)?.unregistered {
+        let module = state.modules.get_mut(id)?;
+        if let Err(err) = register(&state.event_loop, id, module) {
+            eprintln!("Error: Couldn't register {id} module: {err}");
+            return None;
+        }
+    }
+
+    let failure = state.modules.failures.remove(&id)?;
+    state.request_frame();
+
+    Some(failure.retry_timer)
+}
+
+/// Lengthen a polling interval while power saving mode is active.
+fn poll_interval(state: &State, interval: Duration) -> Duration {
+    if state.power_saving {
+        interval * POWER_SAVING_FACTOR
+    } else {
+        interval
+    }
+}
+
+/// Get all udev devices in a subsystem.
+#[cfg(feature = "udev")]
+pub fn devices(subsystem: &'static str) -> Result<Vec<Device>> {
+    let scan = || -> io::Result<Vec<Device>> {
+        let mut enumerator = Enumerator::new()?;
+        enumerator.match_subsystem(subsystem)?;
+        Ok(enumerator.scan_devices()?.collect())
+    };
+    scan().map_err(|source| Error::Udev { subsystem, source })
+}
+
+/// Check if a bit is set in a sysfs capability bitmask.
+///
+/// Bitmasks are whitespace separated hex words, starting with the highest bits.
+pub fn has_capability(bitmask: &str, bit: u16) -> bool {
+    let word_bits = mem::size_of::<libc::c_ulong>() * 8;
+    let (index, bit) = (bit as usize / word_bits, bit as usize % word_bits);
+    bitmask
+        .split_whitespace()
+        .rev()
+        .nth(index)
+        .and_then(|word| u64::from_str_radix(word, 16).ok())
+        .map_or(false, |word| word & (1 << bit) != 0)
+}
+
+/// Notification urgency level.
+#[derive(Serialize, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Urgency {
+    Normal,
+    Critical,
+}
+
+impl Urgency {
+    /// Urgency argument for `notify-send`.
+    fn as_arg(&self) -> &'static str {
+        match self {
+            Self::Normal => "--urgency=normal",
+            Self::Critical => "--urgency=critical",
+        }
+    }
+}
+
+/// Desktop notification raised by a module.
+pub struct Notification {
+    pub urgency: Urgency,
+    pub app: &'static str,
+    pub summary: String,
+    pub body: String,
+}
+
+/// Shell state change requested by a module.
+pub enum Signal {
+    /// Show a desktop notification.
+    Notify(Notification),
+    /// Do not disturb was enabled or disabled.
+    DoNotDisturb(bool),
+    /// Launch an application.
+    Launch(Command),
+    /// Power saving mode was enabled or disabled.
+    #[cfg(feature = "udev")]
+    PowerSaving(bool),
+    /// Power supply state shown on the notification LED changed.
+    #[cfg(feature = "udev")]
+    PowerState(PowerState),
+    /// Device was docked or undocked.
+    #[cfg(feature = "udev")]
+    DockChanged,
+}
+
+/// Sender for signals from modules to the shell.
+#[derive(Clone)]
+pub struct Signals {
+    sender: channel::Sender<Signal>,
+}
+
+impl Signals {
+    /// Create a signal channel handled by the event loop.
+    pub fn new(event_loop: &LoopHandle<'static, State>) -> Result<Self> {
+        let (sender, receiver) = channel::channel();
+        event_loop.insert_source(receiver, |event, _, state| {
+            if let channel::Event::Msg(signal) = event {
+                handle_signal(state, signal);
+            }
+        })?;
+
+        Ok(Self { sender })
+    }
+
+    /// Show a desktop notification.
+    pub fn notify(&self, urgency: Urgency, app: &'static str, summary: &str, body: &str) {
+        let notification =
+            Notification { urgency, app, summary: summary.into(), body: body.into() };
+        self.send(Signal::Notify(notification));
+    }
+
+    /// Launch an application, allowing it to take focus.
+    pub fn launch(&self, command: Command) {
+        self.send(Signal::Launch(command));
+    }
+
+    /// Send a signal to the shell.
+    pub fn send(&self, signal: Signal) {
+        // The receiver is only dropped while the shell is shutting down.
+        let _ = self.sender.send(signal);
+    }
+}
+
+/// Apply a signal sent by a module.
+fn handle_signal(state: &mut State, signal: Signal) {
+    match signal {
+        Signal::Notify(notification) => notify(state, notification),
+        Signal::DoNotDisturb(active) => state.notifications.set_do_not_disturb(active),
+        Signal::Launch(command) => state.launch(command),
+        #[cfg(feature = "udev")]
+        Signal::PowerSaving(enabled) => state.power_saving = enabled,
+        #[cfg(feature = "udev")]
+        Signal::PowerState(power) => {
+            if let Err(err) = state.led.set_power_state(power) {
+                eprintln!("Error: Couldn't update notification LED: {err}");
+            }
+        },
+        // Switch panel profiles when docking or undocking.
+        #[cfg(feature = "udev")]
+        Signal::DockChanged => state.apply_output_profile(),
+    }
+}
+
+/// Show a desktop notification.
+///
+/// This also plays a sound, vibrates the device, and signals the notification on the
+/// notification LED, to ensure the notification is noticed. All of them are skipped while do
+/// not disturb silences the application.
+///
+/// Failures of the individual outputs are logged, so one of them missing doesn't prevent the
+/// others from alerting the user.
+fn notify(state: &mut State, notification: Notification) {
+    let Notification { urgency, app, summary, body } = notification;
+    state.notifications.push(urgency, app, &summary, &body);
+
+    let silenced = state.modules.builtin.do_not_disturb.silenced(app);
+
+    let app_name = format!("--app-name={app}");
+    let mut args = vec![urgency.as_arg(), &app_name];
+    if silenced {
+        args.push("--hint=boolean:suppress-sound:true");
+    }
+    args.extend([summary.as_str(), body.as_str()]);
+    if let Err(err) = reaper::daemon("notify-send", args) {
+        eprintln!("Error: Couldn't send notification: {err}");
+    }
+
+    if silenced {
+        return;
+    }
+
+    if let Err(err) = state.sound.play(urgency, app) {
+        eprintln!("Error: Couldn't play notification sound: {err}");
+    }
+
+    #[cfg(feature = "udev")]
+    {
+        if let Err(err) = state.haptics.vibrate() {
+            eprintln!("Error: Couldn't vibrate: {err}");
+        }
+
+        if let Err(err) = state.led.notify(urgency) {
+            eprintln!("Error: Couldn't update notification LED: {err}");
+        }
+    }
+}
+
+/// Create a command from its configured program and arguments.
+pub fn command(command: &[String]) -> Command {
+    let mut args = command.iter();
+    let mut command = Command::new(args.next().map_or("", String::as_str));
+    command.args(args);
+    command
+}
+
+/// Shorten text to a maximum number of characters.
+pub fn truncate(text: &str, max_len: usize) -> String {
+    match text.char_indices().nth(max_len) {
+        Some((index, _)) => format!("{}…", &text[..index]),
+        None => text.into(),
+    }
+}
+
+/// Format a byte count with a decimal unit, like `12.3 MB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["kB", "MB", "GB", "TB"];
+
+    if bytes < 1000 {
+        return format!("{bytes} B");
+    }
+
+    let mut value = bytes as f64 / 1000.;
+    let mut unit = UNITS[0];
+    for next_unit in &UNITS[1..] {
+        if value < 1000. {
+            break;
+        }
+        value /= 1000.;
+        unit = next_unit;
+    }
+
+    format!("{value:.1} {unit}")
+}
+
+/// Placeholder indicating that some modules have failed.
+pub struct ErrorBadge;
+
+impl Module for ErrorBadge {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        Some(self)
+    }
+}
+
+impl PanelModule for ErrorBadge {
+    fn alignment(&self) -> Alignment {
+        Alignment::Right
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        PanelModuleContent::Text("!".into())
+    }
 }
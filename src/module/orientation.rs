@@ -1,10 +1,10 @@
 //! Display orientation lock.
 
 use catacomb_ipc::{self, IpcMessage};
+use epitaph_modules::{Error, Result};
+use epitaph_ui::text::Svg;
 
-use crate::module::{DrawerModule, Module, Toggle};
-use crate::text::Svg;
-use crate::Result;
+use crate::module::{DrawerModule, Module, Section, Toggle};
 
 pub struct Orientation {
     locked: bool,
@@ -20,6 +20,10 @@ impl Module for Orientation {
     fn drawer_module(&mut self) -> Option<DrawerModule> {
         Some(DrawerModule::Toggle(self))
     }
+
+    fn section(&self) -> Section {
+        Section::Display
+    }
 }
 
 impl Toggle for Orientation {
@@ -27,7 +31,7 @@ impl Toggle for Orientation {
         self.locked = !self.locked;
 
         let msg = IpcMessage::Orientation { lock: None, unlock: !self.locked };
-        catacomb_ipc::send_message(msg)?;
+        catacomb_ipc::send_message(msg).map_err(|err| Error::Compositor(err.to_string()))?;
 
         Ok(())
     }
@@ -0,0 +1,35 @@
+//! Slider value overlay.
+
+use epitaph_ui::text::Svg;
+
+use crate::module::{Alignment, Module, PanelModule, PanelModuleContent};
+use crate::panel::TEXT_COLOR;
+
+/// Panel overlay showing a slider's value while it is dragged from the panel.
+pub struct Osd {
+    svg: Svg,
+    value: f64,
+}
+
+impl Osd {
+    pub fn new(svg: Svg, value: f64) -> Self {
+        Self { svg, value }
+    }
+}
+
+impl Module for Osd {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        Some(self)
+    }
+}
+
+impl PanelModule for Osd {
+    fn alignment(&self) -> Alignment {
+        Alignment::Center
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        let text = format!("{:.0}%", self.value * 100.);
+        PanelModuleContent::Status { text, svg: Some(self.svg), color: TEXT_COLOR }
+    }
+}
@@ -0,0 +1,85 @@
+//! Panel modules collapsed due to missing space.
+
+use std::mem;
+
+use epitaph_modules::Result;
+use epitaph_ui::text::Svg;
+
+use crate::layout::ModuleId;
+use crate::module::{DrawerModule, List, Module, PanelModuleContent};
+
+/// Drawer list of collapsed panel modules.
+pub struct Overflow {
+    entries: Vec<String>,
+}
+
+impl Overflow {
+    pub fn new() -> Self {
+        Self { entries: Default::default() }
+    }
+
+    /// Update the collapsed modules.
+    ///
+    /// Returns `true` if the collapsed modules changed.
+    pub fn set_collapsed(
+        &mut self,
+        collapsed: Vec<(Option<ModuleId>, PanelModuleContent)>,
+    ) -> bool {
+        let entries = collapsed
+            .into_iter()
+            .map(|(id, content)| {
+                let text = match content {
+                    PanelModuleContent::Text(text) | PanelModuleContent::Status { text, .. } => {
+                        text
+                    },
+                    PanelModuleContent::Svg(_) => String::new(),
+                };
+
+                match id {
+                    Some(id) if text.is_empty() => id.to_string(),
+                    Some(id) => format!("{id}: {text}"),
+                    None => text,
+                }
+            })
+            .collect();
+        let old_entries = mem::replace(&mut self.entries, entries);
+
+        old_entries != self.entries
+    }
+}
+
+impl Module for Overflow {
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        // Only show the list while modules are collapsed.
+        if self.entries.is_empty() {
+            None
+        } else {
+            Some(DrawerModule::List(self))
+        }
+    }
+}
+
+impl List for Overflow {
+    fn select(&mut self, _index: usize) -> Result<()> {
+        Ok(())
+    }
+
+    fn entries(&self) -> Vec<String> {
+        self.entries.clone()
+    }
+
+    fn selected(&self) -> Option<usize> {
+        None
+    }
+
+    fn label(&self) -> String {
+        match self.entries.len() {
+            1 => "1 hidden module".into(),
+            hidden => format!("{hidden} hidden modules"),
+        }
+    }
+
+    fn svg(&self) -> Svg {
+        Svg::Overflow
+    }
+}
@@ -0,0 +1,154 @@
+//! Custom status read from stdin or a named FIFO.
+
+use std::mem;
+
+use epitaph_modules::Result;
+use epitaph_ui::text::Svg;
+use serde::Deserialize;
+
+use crate::config;
+use crate::module::{self, Alignment, Event, Module, PanelModule, PanelModuleContent, Source};
+
+/// Maximum number of characters shown for the status text.
+const MAX_TEXT_LEN: usize = 64;
+
+/// Default status text color.
+const DEFAULT_COLOR: [u8; 3] = [255, 255, 255];
+
+/// Latest status line written by an external program.
+pub struct Pipe {
+    config: config::Pipe,
+    buffer: Vec<u8>,
+    status: Status,
+}
+
+impl Pipe {
+    pub fn new(config: config::Pipe) -> Self {
+        Self { config, buffer: Default::default(), status: Default::default() }
+    }
+}
+
+impl Module for Pipe {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        if self.config.enabled {
+            Some(self)
+        } else {
+            None
+        }
+    }
+
+    fn sources(&self) -> Vec<Source> {
+        if !self.config.enabled {
+            return Vec::new();
+        }
+
+        vec![Source::Pipe(self.config.path.clone())]
+    }
+
+    fn update(&mut self, _index: usize, event: Event) -> Result<bool> {
+        let data = match event {
+            Event::Connected => {
+                self.buffer.clear();
+                return Ok(false);
+            },
+            Event::Data(data) => data,
+            // Keep showing the last status after the writer is gone.
+            _ => return Ok(false),
+        };
+
+        // Only the last complete line is relevant.
+        self.buffer.extend_from_slice(&data);
+        let end = match self.buffer.iter().rposition(|byte| *byte == b'\n') {
+            Some(end) => end,
+            None => return Ok(false),
+        };
+        let lines: Vec<u8> = self.buffer.drain(..=end).collect();
+        let line = lines[..end].rsplit(|byte| *byte == b'\n').next().unwrap_or_default();
+
+        let status = Status::parse(String::from_utf8_lossy(line).trim_end());
+        let old_status = mem::replace(&mut self.status, status);
+
+        Ok(old_status != self.status)
+    }
+}
+
+impl PanelModule for Pipe {
+    fn alignment(&self) -> Alignment {
+        Alignment::Right
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        let color = self.status.color.as_deref().and_then(parse_color);
+        PanelModuleContent::Status {
+            text: module::truncate(&self.status.text, MAX_TEXT_LEN),
+            color: color.unwrap_or(DEFAULT_COLOR),
+            svg: self.status.icon,
+        }
+    }
+}
+
+/// Status line content.
+#[derive(Deserialize, Default, PartialEq, Eq)]
+#[serde(default)]
+struct Status {
+    text: String,
+    icon: Option<Svg>,
+    color: Option<String>,
+}
+
+impl Status {
+    /// Parse a JSON status line, falling back to plain text.
+    fn parse(line: &str) -> Self {
+        match serde_json::from_str(line) {
+            Ok(status) if line.starts_with('{') => status,
+            _ => Self { text: line.into(), ..Default::default() },
+        }
+    }
+}
+
+/// Parse a `#rrggbb` hex color.
+fn parse_color(color: &str) -> Option<[u8; 3]> {
+    let hex = color.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+    let channel = |index: usize| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_plain_text() {
+        let status = Status::parse("hello world");
+        assert_eq!(status.text, "hello world");
+        assert!(status.icon.is_none());
+        assert_eq!(status.color, None);
+    }
+
+    #[test]
+    fn parse_json_status() {
+        let status =
+            Status::parse(r##"{"text": "50%", "icon": "brightness", "color": "#ff0000"}"##);
+        assert_eq!(status.text, "50%");
+        assert!(matches!(status.icon, Some(Svg::Brightness)));
+        assert_eq!(status.color.as_deref(), Some("#ff0000"));
+    }
+
+    #[test]
+    fn invalid_json_is_plain_text() {
+        let status = Status::parse(r#"{"text": "unterminated"#);
+        assert_eq!(status.text, r#"{"text": "unterminated"#);
+        assert!(status.icon.is_none());
+
+        let status = Status::parse("42");
+        assert_eq!(status.text, "42");
+    }
+
+    #[test]
+    fn parse_hex_color() {
+        assert_eq!(parse_color("#ff8000"), Some([255, 128, 0]));
+        assert_eq!(parse_color("ff8000"), None);
+        assert_eq!(parse_color("#ff80"), None);
+        assert_eq!(parse_color("#gg8000"), None);
+    }
+}
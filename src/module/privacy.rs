@@ -0,0 +1,144 @@
+//! Hardware privacy switch status.
+
+use std::fs::File;
+use std::mem;
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+
+use epitaph_modules::{Error, Result};
+use epitaph_ui::text::Svg;
+use udev::Device;
+
+use crate::module::{self, Alignment, Event, Module, PanelModule, PanelModuleContent, Source};
+
+/// Refresh interval for input switches, since they don't emit udev events.
+const UPDATE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Input switch covering the camera lens.
+const SW_CAMERA_LENS_COVER: u16 = 0x09;
+
+/// Input switch muting the microphone.
+const SW_MUTE_DEVICE: u16 = 0x0e;
+
+/// Size of the input switch state bitmask in bytes.
+const SWITCH_BYTES: usize = 8;
+
+/// `EVIOCGSW` ioctl request, reading the state of all input switches.
+const EVIOCGSW: u64 = (2 << 30) | ((SWITCH_BYTES as u64) << 16) | ((b'E' as u64) << 8) | 0x1b;
+
+/// Devices disabled by hardware switches.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+struct Switches {
+    camera: bool,
+    microphone: bool,
+    radio: bool,
+}
+
+impl Switches {
+    /// Read the state of all hardware privacy switches.
+    fn read() -> Result<Self> {
+        let mut switches = Self::default();
+        let mut found = false;
+
+        // Check camera and microphone switches exposed as input devices, like `gpio-keys`.
+        for device in module::devices("input")? {
+            let state = match switch_state(&device) {
+                Some(state) => state,
+                None => continue,
+            };
+            found = true;
+
+            let is_set = |bit: u16| state[bit as usize / 8] & (1 << (bit % 8)) != 0;
+            switches.camera |= is_set(SW_CAMERA_LENS_COVER);
+            switches.microphone |= is_set(SW_MUTE_DEVICE);
+        }
+
+        // Check radios disabled by a hardware kill switch.
+        for device in module::devices("rfkill")? {
+            found = true;
+            switches.radio |= device.attribute_value("hard").map_or(false, |hard| hard == "1");
+        }
+
+        if !found {
+            return Err(Error::DeviceNotFound("privacy switch"));
+        }
+
+        Ok(switches)
+    }
+
+    /// Check if any device is disabled.
+    fn any(&self) -> bool {
+        self.camera || self.microphone || self.radio
+    }
+}
+
+/// Camera, microphone, and radio kill switch indicator.
+pub struct Privacy {
+    switches: Switches,
+}
+
+impl Privacy {
+    pub fn new() -> Self {
+        Self { switches: Default::default() }
+    }
+}
+
+impl Module for Privacy {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        Some(self)
+    }
+
+    fn sources(&self) -> Vec<Source> {
+        vec![Source::Udev("rfkill"), Source::Interval(UPDATE_INTERVAL)]
+    }
+
+    fn probe(&mut self) -> Result<()> {
+        self.switches = Switches::read()?;
+        Ok(())
+    }
+
+    fn update(&mut self, _index: usize, _event: Event) -> Result<bool> {
+        let old_switches = mem::replace(&mut self.switches, Switches::read()?);
+        Ok(old_switches != self.switches)
+    }
+}
+
+impl PanelModule for Privacy {
+    fn alignment(&self) -> Alignment {
+        Alignment::Right
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        // Only show an indicator while a device is disabled.
+        if self.switches.any() {
+            PanelModuleContent::Svg(Svg::KillSwitch)
+        } else {
+            PanelModuleContent::Text(String::new())
+        }
+    }
+}
+
+/// Read the switch state of an input device with privacy switches.
+fn switch_state(device: &Device) -> Option<[u8; SWITCH_BYTES]> {
+    // Only event devices can be queried.
+    if !device.sysname().to_string_lossy().starts_with("event") {
+        return None;
+    }
+
+    // Ignore devices without camera or microphone switches.
+    let capabilities = device.parent()?.attribute_value("capabilities/sw")?.to_owned();
+    let capabilities = capabilities.to_string_lossy();
+    if !module::has_capability(&capabilities, SW_CAMERA_LENS_COVER)
+        && !module::has_capability(&capabilities, SW_MUTE_DEVICE)
+    {
+        return None;
+    }
+
+    let file = File::open(device.devnode()?).ok()?;
+    let mut state = [0; SWITCH_BYTES];
+    if unsafe { libc::ioctl(file.as_raw_fd(), EVIOCGSW as _, state.as_mut_ptr()) } < 0 {
+        return None;
+    }
+
+    Some(state)
+}
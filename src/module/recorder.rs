@@ -0,0 +1,180 @@
+//! Screen recording.
+
+use std::env;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use chrono::offset::Local;
+use epitaph_modules::{Error, Result};
+use epitaph_ui::text::Svg;
+
+use crate::config;
+use crate::module::timer::format_time;
+use crate::module::{
+    Alignment, DrawerModule, Event, Module, PanelModule, PanelModuleContent, Section, Signals,
+    Source, Toggle, Urgency,
+};
+use crate::panel::TEXT_COLOR;
+
+/// Refresh interval for the elapsed time.
+const UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Screen recorder.
+pub struct ScreenRecorder {
+    config: config::ScreenRecorder,
+    recording: Option<Recording>,
+    signals: Signals,
+}
+
+impl ScreenRecorder {
+    pub fn new(config: config::ScreenRecorder, signals: Signals) -> Self {
+        Self { config, signals, recording: None }
+    }
+
+    /// Directory recordings are saved to.
+    fn directory(&self) -> Option<PathBuf> {
+        self.config
+            .directory
+            .clone()
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join("Videos")))
+    }
+
+    /// Start a new recording.
+    fn start(&mut self) -> Result<()> {
+        let (program, args) = self
+            .config
+            .command
+            .split_first()
+            .ok_or(Error::NotConfigured("screen recorder command"))?;
+
+        let directory = self.directory().ok_or(Error::MissingDirectory("videos"))?;
+        let file_name = Local::now().format("recording-%Y%m%d-%H%M%S.mp4").to_string();
+        let path = directory.join(file_name);
+
+        let mut command = Command::new(program);
+        command.args(args).arg(&path);
+        command.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+        let child = command.spawn()?;
+
+        self.recording = Some(Recording { child, path, start: Instant::now() });
+
+        Ok(())
+    }
+
+    /// Ask the recorder to finish writing the recording.
+    fn stop(&mut self) {
+        if let Some(recording) = &self.recording {
+            // Recorders finalize the file on SIGINT, like after Ctrl+C.
+            unsafe { libc::kill(recording.child.id() as libc::pid_t, libc::SIGINT) };
+        }
+    }
+
+    /// Handle exit of the recorder process.
+    fn finish(&mut self) {
+        let recording = match self.recording.take() {
+            Some(recording) => recording,
+            None => return,
+        };
+
+        let summary = if recording.path.exists() {
+            "Screen recording saved"
+        } else {
+            "Screen recording failed"
+        };
+        let body = recording.path.to_string_lossy();
+
+        self.signals.notify(Urgency::Normal, "screen_recorder", summary, &body);
+    }
+}
+
+impl Drop for ScreenRecorder {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+impl Module for ScreenRecorder {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        Some(self)
+    }
+
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::Toggle(self))
+    }
+
+    fn section(&self) -> Section {
+        Section::Display
+    }
+
+    fn sources(&self) -> Vec<Source> {
+        vec![Source::Interval(UPDATE_INTERVAL)]
+    }
+
+    fn update(&mut self, _index: usize, _event: Event) -> Result<bool> {
+        let recording = match &mut self.recording {
+            Some(recording) => recording,
+            None => return Ok(false),
+        };
+
+        // Reap the recorder once it has exited.
+        if recording.child.try_wait()?.is_some() {
+            self.finish();
+        }
+
+        Ok(true)
+    }
+}
+
+impl PanelModule for ScreenRecorder {
+    fn alignment(&self) -> Alignment {
+        Alignment::Right
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        // Only show the elapsed time while recording.
+        match &self.recording {
+            Some(recording) => PanelModuleContent::Status {
+                text: format_time(recording.start.elapsed().as_secs()),
+                svg: Some(Svg::ScreenRecorder),
+                color: TEXT_COLOR,
+            },
+            None => PanelModuleContent::Text(String::new()),
+        }
+    }
+}
+
+impl Toggle for ScreenRecorder {
+    fn toggle(&mut self) -> Result<()> {
+        match self.recording {
+            Some(_) => self.stop(),
+            None => self.start()?,
+        }
+        Ok(())
+    }
+
+    fn enabled(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    fn svg(&self) -> Svg {
+        Svg::ScreenRecorder
+    }
+
+    fn caption(&self) -> Option<String> {
+        let recording = self.recording.as_ref()?;
+        Some(format_time(recording.start.elapsed().as_secs()))
+    }
+
+    fn dismisses_drawer(&self) -> bool {
+        // Keep the drawer out of new recordings.
+        self.recording.is_some()
+    }
+}
+
+/// Active screen recording.
+struct Recording {
+    child: Child,
+    path: PathBuf,
+    start: Instant,
+}
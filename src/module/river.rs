@@ -0,0 +1,244 @@
+//! River tag status and switching.
+
+use std::mem;
+
+use epitaph_modules::{Error, Result};
+use epitaph_ui::text::Svg;
+use smithay_client_toolkit::reexports::client::globals::GlobalList;
+use smithay_client_toolkit::reexports::client::protocol::wl_output::WlOutput;
+use smithay_client_toolkit::reexports::client::protocol::wl_seat::WlSeat;
+use smithay_client_toolkit::reexports::client::{Connection, Dispatch, Proxy, QueueHandle};
+
+use self::control::zriver_command_callback_v1::{self, ZriverCommandCallbackV1};
+use self::control::zriver_control_v1::ZriverControlV1;
+use self::status::zriver_output_status_v1::{self, ZriverOutputStatusV1};
+use self::status::zriver_status_manager_v1::ZriverStatusManagerV1;
+use crate::module::{
+    Alignment, DrawerModule, Module, PanelModule, PanelModuleContent, Section, Slider,
+};
+use crate::State;
+
+/// Number of tags shown in the panel and selectable from the drawer.
+const TAG_COUNT: u32 = 9;
+
+#[allow(clippy::all)]
+mod status {
+    use smithay_client_toolkit::reexports::client as wayland_client;
+    use smithay_client_toolkit::reexports::client::protocol::*;
+
+    pub mod __interfaces {
+        use smithay_client_toolkit::reexports::client::protocol::__interfaces::*;
+        wayland_scanner::generate_interfaces!("protocols/river-status-unstable-v1.xml");
+    }
+    use self::__interfaces::*;
+
+    wayland_scanner::generate_client_code!("protocols/river-status-unstable-v1.xml");
+}
+
+#[allow(clippy::all)]
+mod control {
+    use smithay_client_toolkit::reexports::client as wayland_client;
+    use smithay_client_toolkit::reexports::client::protocol::*;
+
+    pub mod __interfaces {
+        use smithay_client_toolkit::reexports::client::protocol::__interfaces::*;
+        wayland_scanner::generate_interfaces!("protocols/river-control-unstable-v1.xml");
+    }
+    use self::__interfaces::*;
+
+    wayland_scanner::generate_client_code!("protocols/river-control-unstable-v1.xml");
+}
+
+/// River tags.
+pub struct River {
+    status_manager: Option<ZriverStatusManagerV1>,
+    output_status: Option<ZriverOutputStatusV1>,
+    control: Option<ZriverControlV1>,
+    queue: QueueHandle<State>,
+    seat: Option<WlSeat>,
+    occupied_tags: u32,
+    focused_tags: u32,
+    urgent_tags: u32,
+}
+
+impl River {
+    pub fn new(globals: &GlobalList, queue: &QueueHandle<State>) -> Self {
+        Self {
+            status_manager: globals.bind(queue, 2..=4, ()).ok(),
+            control: globals.bind(queue, 1..=1, ()).ok(),
+            queue: queue.clone(),
+            output_status: Default::default(),
+            occupied_tags: Default::default(),
+            focused_tags: Default::default(),
+            urgent_tags: Default::default(),
+            seat: Default::default(),
+        }
+    }
+
+    /// Track the tags of an output.
+    ///
+    /// Only the first output is tracked, since mobile devices usually have just one.
+    pub fn add_output(&mut self, output: &WlOutput) {
+        if let (Some(manager), None) = (&self.status_manager, &self.output_status) {
+            let status = manager.get_river_output_status(output, &self.queue, ());
+            self.output_status = Some(status);
+        }
+    }
+
+    /// Set the seat used for running commands.
+    pub fn set_seat(&mut self, seat: WlSeat) {
+        self.seat.get_or_insert(seat);
+    }
+}
+
+impl Module for River {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        Some(self)
+    }
+
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::Slider(self))
+    }
+
+    fn section(&self) -> Section {
+        Section::Display
+    }
+
+    fn probe(&mut self) -> Result<()> {
+        match self.status_manager {
+            Some(_) => Ok(()),
+            None => Err(Error::CompositorNotFound("river")),
+        }
+    }
+}
+
+impl PanelModule for River {
+    fn alignment(&self) -> Alignment {
+        Alignment::Left
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        let text = (0..TAG_COUNT)
+            .filter_map(|tag| {
+                let mask = 1 << tag;
+                if self.focused_tags & mask != 0 {
+                    Some(format!("[{}]", tag + 1))
+                } else if self.urgent_tags & mask != 0 {
+                    Some(format!("{}!", tag + 1))
+                } else if self.occupied_tags & mask != 0 {
+                    Some((tag + 1).to_string())
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        PanelModuleContent::Text(text)
+    }
+}
+
+impl Slider for River {
+    fn set_value(&mut self, value: f64) -> Result<()> {
+        let tag = (value.clamp(0., 1.) * (TAG_COUNT - 1) as f64).round() as u32;
+        let tags = 1 << tag;
+        if tags == self.focused_tags {
+            return Ok(());
+        }
+
+        let (control, seat) = match (&self.control, &self.seat) {
+            (Some(control), Some(seat)) => (control, seat),
+            _ => return Err(Error::CompositorNotFound("river control")),
+        };
+
+        control.add_argument("set-focused-tags".into());
+        control.add_argument(tags.to_string());
+        control.run_command(seat, &self.queue, ());
+
+        self.focused_tags = tags;
+
+        Ok(())
+    }
+
+    fn get_value(&self) -> f64 {
+        let tag = self.focused_tags.trailing_zeros().min(TAG_COUNT - 1);
+        tag as f64 / (TAG_COUNT - 1) as f64
+    }
+
+    fn svg(&self) -> Svg {
+        Svg::Workspace
+    }
+}
+
+impl Dispatch<ZriverStatusManagerV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _manager: &ZriverStatusManagerV1,
+        _event: <ZriverStatusManagerV1 as Proxy>::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZriverOutputStatusV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _status: &ZriverOutputStatusV1,
+        event: zriver_output_status_v1::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+        let river = &mut state.modules.builtin.river;
+        let changed = match event {
+            zriver_output_status_v1::Event::FocusedTags { tags } => {
+                mem::replace(&mut river.focused_tags, tags) != tags
+            },
+            zriver_output_status_v1::Event::UrgentTags { tags } => {
+                mem::replace(&mut river.urgent_tags, tags) != tags
+            },
+            zriver_output_status_v1::Event::ViewTags { tags } => {
+                // Combine the tags of all views into a single bitfield.
+                let occupied = tags
+                    .chunks_exact(4)
+                    .map(|view| u32::from_ne_bytes([view[0], view[1], view[2], view[3]]))
+                    .fold(0, |occupied, view| occupied | view);
+                mem::replace(&mut river.occupied_tags, occupied) != occupied
+            },
+            _ => false,
+        };
+
+        if changed {
+            state.request_frame();
+        }
+    }
+}
+
+impl Dispatch<ZriverControlV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _control: &ZriverControlV1,
+        _event: <ZriverControlV1 as Proxy>::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZriverCommandCallbackV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _callback: &ZriverCommandCallbackV1,
+        event: zriver_command_callback_v1::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+        if let zriver_command_callback_v1::Event::Failure { failure_message } = event {
+            eprintln!("Error: River command failed: {failure_message}");
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! Manual display rotation.
+
+use epitaph_modules::{Error, Result};
+use epitaph_ui::text::Svg;
+use smithay_client_toolkit::reexports::client::globals::GlobalList;
+use smithay_client_toolkit::reexports::client::protocol::wl_output::Transform;
+use smithay_client_toolkit::reexports::client::{
+    event_created_child, Connection, Dispatch, Proxy, QueueHandle,
+};
+
+use self::output_management::zwlr_output_configuration_head_v1::ZwlrOutputConfigurationHeadV1;
+use self::output_management::zwlr_output_configuration_v1::{self, ZwlrOutputConfigurationV1};
+use self::output_management::zwlr_output_head_v1::{self, ZwlrOutputHeadV1};
+use self::output_management::zwlr_output_manager_v1::{self, ZwlrOutputManagerV1};
+use self::output_management::zwlr_output_mode_v1::ZwlrOutputModeV1;
+use crate::module::{DrawerModule, Module, Section, Toggle};
+use crate::State;
+
+#[allow(clippy::all)]
+mod output_management {
+    use smithay_client_toolkit::reexports::client as wayland_client;
+    use smithay_client_toolkit::reexports::client::protocol::*;
+
+    pub mod __interfaces {
+        use smithay_client_toolkit::reexports::client::protocol::__interfaces::*;
+        wayland_scanner::generate_interfaces!("protocols/wlr-output-management-unstable-v1.xml");
+    }
+    use self::__interfaces::*;
+
+    wayland_scanner::generate_client_code!("protocols/wlr-output-management-unstable-v1.xml");
+}
+
+/// Display rotation through wlr-output-management.
+pub struct Rotation {
+    manager: Option<ZwlrOutputManagerV1>,
+    queue: QueueHandle<State>,
+    serial: Option<u32>,
+    heads: Vec<Head>,
+}
+
+impl Rotation {
+    pub fn new(globals: &GlobalList, queue: &QueueHandle<State>) -> Self {
+        Self {
+            manager: globals.bind(queue, 1..=2, ()).ok(),
+            queue: queue.clone(),
+            serial: Default::default(),
+            heads: Default::default(),
+        }
+    }
+
+    /// Get the rotated head.
+    ///
+    /// Only the first enabled head is rotated, since mobile devices usually have just one.
+    fn head(&self) -> Option<&Head> {
+        self.heads.iter().find(|head| head.enabled)
+    }
+
+    /// Get the tracked state of a head.
+    fn head_mut(&mut self, head: &ZwlrOutputHeadV1) -> Option<&mut Head> {
+        self.heads.iter_mut().find(|tracked| &tracked.head == head)
+    }
+}
+
+impl Module for Rotation {
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::Toggle(self))
+    }
+
+    fn section(&self) -> Section {
+        Section::Display
+    }
+
+    fn probe(&mut self) -> Result<()> {
+        match self.manager {
+            Some(_) => Ok(()),
+            None => Err(Error::CompositorNotFound("wlr output management")),
+        }
+    }
+}
+
+impl Toggle for Rotation {
+    fn toggle(&mut self) -> Result<()> {
+        let (manager, serial, target) = match (&self.manager, self.serial, self.head()) {
+            (Some(manager), Some(serial), Some(head)) => (manager, serial, head),
+            _ => return Err(Error::CompositorNotFound("wlr output management")),
+        };
+        let transform = next_transform(target.transform);
+
+        // Every head must be part of the configuration, so keep all others unchanged.
+        let configuration = manager.create_configuration(serial, &self.queue, ());
+        for head in &self.heads {
+            if !head.enabled {
+                configuration.disable_head(&head.head);
+                continue;
+            }
+
+            let head_configuration = configuration.enable_head(&head.head, &self.queue, ());
+            if head.head == target.head {
+                head_configuration.set_transform(transform);
+            }
+        }
+        configuration.apply();
+
+        Ok(())
+    }
+
+    fn enabled(&self) -> bool {
+        self.head().map_or(false, |head| head.transform != Transform::Normal)
+    }
+
+    fn svg(&self) -> Svg {
+        Svg::Rotation
+    }
+}
+
+/// Output device state.
+struct Head {
+    head: ZwlrOutputHeadV1,
+    transform: Transform,
+    enabled: bool,
+}
+
+impl Head {
+    fn new(head: ZwlrOutputHeadV1) -> Self {
+        Self { head, transform: Transform::Normal, enabled: Default::default() }
+    }
+}
+
+/// Get the transform after a clockwise rotation by 90 degrees.
+fn next_transform(transform: Transform) -> Transform {
+    match transform {
+        Transform::Normal => Transform::_90,
+        Transform::_90 => Transform::_180,
+        Transform::_180 => Transform::_270,
+        _ => Transform::Normal,
+    }
+}
+
+impl Dispatch<ZwlrOutputManagerV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _manager: &ZwlrOutputManagerV1,
+        event: zwlr_output_manager_v1::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+        let rotation = &mut state.modules.builtin.rotation;
+        match event {
+            zwlr_output_manager_v1::Event::Head { head } => rotation.heads.push(Head::new(head)),
+            zwlr_output_manager_v1::Event::Done { serial } => {
+                rotation.serial = Some(serial);
+                state.request_frame();
+            },
+            zwlr_output_manager_v1::Event::Finished => {
+                rotation.manager = None;
+                rotation.serial = None;
+                rotation.heads.clear();
+            },
+            _ => (),
+        }
+    }
+
+    event_created_child!(State, ZwlrOutputManagerV1, [
+        zwlr_output_manager_v1::EVT_HEAD_OPCODE => (ZwlrOutputHeadV1, ()),
+    ]);
+}
+
+impl Dispatch<ZwlrOutputHeadV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        head: &ZwlrOutputHeadV1,
+        event: zwlr_output_head_v1::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+        let rotation = &mut state.modules.builtin.rotation;
+        match event {
+            zwlr_output_head_v1::Event::Enabled { enabled } => {
+                if let Some(head) = rotation.head_mut(head) {
+                    head.enabled = enabled != 0;
+                }
+            },
+            zwlr_output_head_v1::Event::Transform { transform } => {
+                if let (Some(head), Ok(transform)) =
+                    (rotation.head_mut(head), transform.into_result())
+                {
+                    head.transform = transform;
+                }
+            },
+            zwlr_output_head_v1::Event::Finished => {
+                rotation.heads.retain(|tracked| &tracked.head != head)
+            },
+            _ => (),
+        }
+    }
+
+    event_created_child!(State, ZwlrOutputHeadV1, [
+        zwlr_output_head_v1::EVT_MODE_OPCODE => (ZwlrOutputModeV1, ()),
+    ]);
+}
+
+impl Dispatch<ZwlrOutputModeV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _mode: &ZwlrOutputModeV1,
+        _event: <ZwlrOutputModeV1 as Proxy>::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrOutputConfigurationV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        configuration: &ZwlrOutputConfigurationV1,
+        event: zwlr_output_configuration_v1::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_output_configuration_v1::Event::Failed => {
+                eprintln!("Error: Output rotation was rejected by the compositor");
+            },
+            zwlr_output_configuration_v1::Event::Cancelled => {
+                eprintln!("Error: Output rotation was cancelled by the compositor");
+            },
+            _ => (),
+        }
+
+        configuration.destroy();
+    }
+}
+
+impl Dispatch<ZwlrOutputConfigurationHeadV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _head: &ZwlrOutputConfigurationHeadV1,
+        _event: <ZwlrOutputConfigurationHeadV1 as Proxy>::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+    }
+}
@@ -0,0 +1,82 @@
+//! Screenshot capture.
+
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+use chrono::offset::Local;
+use epitaph_modules::{Error, Result};
+use epitaph_ui::text::Svg;
+
+use crate::config;
+use crate::module::{DrawerModule, Module, Section, Signals, Toggle};
+
+/// Shell script capturing a screenshot to `$0`, with the editor command as remaining arguments.
+///
+/// The delay gives the drawer time to close before the screen is captured.
+const SCREENSHOT_SCRIPT: &str = r#"sleep 1
+grim "$0" || exit
+action=$(notify-send --wait --action=copy=Copy ${1:+--action=edit=Edit} \
+    "Screenshot saved" "$0")
+case "$action" in
+    copy) wl-copy --type image/png < "$0" ;;
+    edit) "$@" "$0" ;;
+esac"#;
+
+/// Screenshot button.
+pub struct Screenshot {
+    config: config::Screenshot,
+    signals: Signals,
+}
+
+impl Screenshot {
+    pub fn new(config: config::Screenshot, signals: Signals) -> Self {
+        Self { config, signals }
+    }
+
+    /// Directory screenshots are saved to.
+    fn directory(&self) -> Option<PathBuf> {
+        self.config
+            .directory
+            .clone()
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join("Pictures")))
+    }
+}
+
+impl Module for Screenshot {
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::Toggle(self))
+    }
+
+    fn section(&self) -> Section {
+        Section::Display
+    }
+}
+
+impl Toggle for Screenshot {
+    fn toggle(&mut self) -> Result<()> {
+        let directory = self.directory().ok_or(Error::MissingDirectory("pictures"))?;
+        let file_name = Local::now().format("screenshot-%Y%m%d-%H%M%S.png").to_string();
+        let path = directory.join(file_name);
+
+        // Launch through the shell, so the editor inherits the activation token.
+        let mut command = Command::new("sh");
+        command.args(["-c".into(), SCREENSHOT_SCRIPT.into(), path.into_os_string()]);
+        command.args(&self.config.editor);
+        self.signals.launch(command);
+
+        Ok(())
+    }
+
+    fn svg(&self) -> Svg {
+        Svg::Screenshot
+    }
+
+    fn enabled(&self) -> bool {
+        false
+    }
+
+    fn dismisses_drawer(&self) -> bool {
+        true
+    }
+}
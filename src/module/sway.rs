@@ -0,0 +1,376 @@
+//! Sway/i3 workspaces, binding mode and window title.
+
+use std::env;
+use std::io::{Read, Write};
+use std::mem;
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+use epitaph_modules::{Error, Result};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::module::{self, Alignment, Event, Module, PanelModule, PanelModuleContent, Source};
+
+/// Magic string starting every IPC message.
+const MAGIC: &[u8] = b"i3-ipc";
+
+/// Length of the IPC message header.
+const HEADER_LEN: usize = MAGIC.len() + 8;
+
+/// IPC message types.
+const GET_WORKSPACES: u32 = 1;
+const SUBSCRIBE: u32 = 2;
+const GET_TREE: u32 = 4;
+const GET_BINDING_STATE: u32 = 12;
+
+/// IPC event types.
+const EVENT_WORKSPACE: u32 = 0x8000_0000;
+const EVENT_MODE: u32 = 0x8000_0002;
+
+/// Binding mode which is not shown in the panel.
+const DEFAULT_MODE: &str = "default";
+
+/// Maximum number of characters shown for the window title.
+const MAX_TITLE_LEN: usize = 32;
+
+/// Maximum time to wait for sway to accept or answer a request.
+const IPC_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Workspaces and binding mode.
+pub struct Sway {
+    workspaces: Vec<Workspace>,
+    decoder: Decoder,
+    mode: String,
+}
+
+impl Sway {
+    pub fn new() -> Self {
+        Self {
+            workspaces: Default::default(),
+            decoder: Default::default(),
+            mode: DEFAULT_MODE.into(),
+        }
+    }
+
+    /// Reload all workspaces.
+    fn load_workspaces(&mut self) -> Result<bool> {
+        let workspaces = request(GET_WORKSPACES)?;
+        let old_workspaces = mem::replace(&mut self.workspaces, workspaces);
+        Ok(old_workspaces != self.workspaces)
+    }
+}
+
+impl Module for Sway {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        Some(self)
+    }
+
+    fn probe(&mut self) -> Result<()> {
+        self.load_workspaces()?;
+        self.mode = request::<BindingState>(GET_BINDING_STATE)?.name;
+        Ok(())
+    }
+
+    fn sources(&self) -> Vec<Source> {
+        vec![Source::Socket(|| subscribe(r#"["workspace","mode"]"#))]
+    }
+
+    fn update(&mut self, _index: usize, event: Event) -> Result<bool> {
+        let data = match event {
+            Event::Connected => {
+                self.decoder = Decoder::default();
+                return self.load_workspaces();
+            },
+            Event::Disconnected => {
+                self.mode = DEFAULT_MODE.into();
+                return Ok(!mem::take(&mut self.workspaces).is_empty());
+            },
+            Event::Data(data) => data,
+            _ => return Ok(false),
+        };
+
+        let mut dirty = false;
+        for (kind, payload) in self.decoder.decode(&data) {
+            match kind {
+                EVENT_WORKSPACE => dirty |= self.load_workspaces()?,
+                EVENT_MODE => {
+                    let mode = parse::<ModeEvent>(&payload)?.change;
+                    let old_mode = mem::replace(&mut self.mode, mode);
+                    dirty |= old_mode != self.mode;
+                },
+                _ => (),
+            }
+        }
+
+        Ok(dirty)
+    }
+}
+
+impl PanelModule for Sway {
+    fn alignment(&self) -> Alignment {
+        Alignment::Left
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        let mut text = self
+            .workspaces
+            .iter()
+            .map(|workspace| {
+                if workspace.focused {
+                    format!("[{}]", workspace.name)
+                } else {
+                    workspace.name.clone()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        if self.mode != DEFAULT_MODE {
+            text.push_str(&format!(" ({})", self.mode));
+        }
+
+        PanelModuleContent::Text(text)
+    }
+}
+
+/// Title of the focused window.
+pub struct SwayTitle {
+    decoder: Decoder,
+    title: String,
+}
+
+impl SwayTitle {
+    pub fn new() -> Self {
+        Self { decoder: Default::default(), title: Default::default() }
+    }
+
+    /// Reload the focused window's title.
+    fn load_title(&mut self) -> Result<bool> {
+        let tree = request::<Node>(GET_TREE)?;
+        let title = tree.focused().map_or_else(String::new, Node::title);
+        let old_title = mem::replace(&mut self.title, title);
+        Ok(old_title != self.title)
+    }
+}
+
+impl Module for SwayTitle {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        Some(self)
+    }
+
+    fn probe(&mut self) -> Result<()> {
+        self.load_title()?;
+        Ok(())
+    }
+
+    fn sources(&self) -> Vec<Source> {
+        vec![Source::Socket(|| subscribe(r#"["workspace","window"]"#))]
+    }
+
+    fn update(&mut self, _index: usize, event: Event) -> Result<bool> {
+        match event {
+            Event::Connected => {
+                self.decoder = Decoder::default();
+                self.load_title()
+            },
+            Event::Data(data) => {
+                if self.decoder.decode(&data).is_empty() {
+                    return Ok(false);
+                }
+
+                // Focus changes are not always reported with the new window, so reload the tree.
+                self.load_title()
+            },
+            Event::Disconnected => Ok(!mem::take(&mut self.title).is_empty()),
+            _ => Ok(false),
+        }
+    }
+}
+
+impl PanelModule for SwayTitle {
+    fn alignment(&self) -> Alignment {
+        Alignment::Left
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        PanelModuleContent::Text(self.title.clone())
+    }
+}
+
+/// Sway workspace.
+#[derive(Deserialize, PartialEq, Eq)]
+struct Workspace {
+    name: String,
+    focused: bool,
+}
+
+/// Reply to `GET_BINDING_STATE`.
+#[derive(Deserialize)]
+struct BindingState {
+    name: String,
+}
+
+/// Binding mode change event.
+#[derive(Deserialize)]
+struct ModeEvent {
+    change: String,
+}
+
+/// Sway layout tree node.
+#[derive(Deserialize)]
+struct Node {
+    #[serde(rename = "type")]
+    kind: String,
+    name: Option<String>,
+    focused: bool,
+    #[serde(default)]
+    nodes: Vec<Node>,
+    #[serde(default)]
+    floating_nodes: Vec<Node>,
+}
+
+impl Node {
+    /// Find the focused node in this subtree.
+    fn focused(&self) -> Option<&Node> {
+        if self.focused {
+            return Some(self);
+        }
+
+        self.nodes.iter().chain(&self.floating_nodes).find_map(Node::focused)
+    }
+
+    /// Truncated window title.
+    ///
+    /// Returns an empty title for nodes which are not windows, like empty workspaces.
+    fn title(&self) -> String {
+        match &self.name {
+            Some(name) if self.kind == "con" || self.kind == "floating_con" => {
+                module::truncate(name, MAX_TITLE_LEN)
+            },
+            _ => String::new(),
+        }
+    }
+}
+
+/// Buffer for reassembling IPC messages from a socket stream.
+#[derive(Default)]
+struct Decoder {
+    buffer: Vec<u8>,
+}
+
+impl Decoder {
+    /// Get all messages completed by new data.
+    fn decode(&mut self, data: &[u8]) -> Vec<(u32, Vec<u8>)> {
+        self.buffer.extend_from_slice(data);
+
+        // Discard data which doesn't start with a valid header.
+        if self.buffer.len() >= HEADER_LEN && parse_header(&self.buffer).is_none() {
+            self.buffer.clear();
+        }
+
+        let mut messages = Vec::new();
+        while let Some((kind, len)) = parse_header(&self.buffer) {
+            if self.buffer.len() < HEADER_LEN + len {
+                break;
+            }
+
+            let payload = self.buffer[HEADER_LEN..HEADER_LEN + len].to_vec();
+            self.buffer.drain(..HEADER_LEN + len);
+            messages.push((kind, payload));
+        }
+
+        messages
+    }
+}
+
+/// Connect to the sway IPC socket.
+fn connect() -> Result<UnixStream> {
+    let path = env::var_os("SWAYSOCK")
+        .or_else(|| env::var_os("I3SOCK"))
+        .ok_or(Error::CompositorNotFound("sway"))?;
+    let stream = UnixStream::connect(path)?;
+
+    // Avoid blocking the event loop forever if sway stops responding.
+    stream.set_read_timeout(Some(IPC_TIMEOUT))?;
+    stream.set_write_timeout(Some(IPC_TIMEOUT))?;
+
+    Ok(stream)
+}
+
+/// Open a socket receiving the specified events.
+fn subscribe(events: &str) -> Result<UnixStream> {
+    let mut stream = connect()?;
+    send(&mut stream, SUBSCRIBE, events)?;
+    receive(&mut stream)?;
+    Ok(stream)
+}
+
+/// Send a payload-free IPC request and parse its reply.
+fn request<T: DeserializeOwned>(kind: u32) -> Result<T> {
+    let mut stream = connect()?;
+    send(&mut stream, kind, "")?;
+    parse(&receive(&mut stream)?)
+}
+
+/// Write an IPC message.
+fn send(stream: &mut UnixStream, kind: u32, payload: &str) -> Result<()> {
+    let mut message = Vec::with_capacity(HEADER_LEN + payload.len());
+    message.extend_from_slice(MAGIC);
+    message.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+    message.extend_from_slice(&kind.to_ne_bytes());
+    message.extend_from_slice(payload.as_bytes());
+    stream.write_all(&message)?;
+    Ok(())
+}
+
+/// Read the payload of the next IPC message.
+fn receive(stream: &mut UnixStream) -> Result<Vec<u8>> {
+    let mut header = [0; HEADER_LEN];
+    stream.read_exact(&mut header)?;
+    let (_, len) =
+        parse_header(&header).ok_or_else(|| Error::Compositor("invalid sway IPC header".into()))?;
+
+    let mut payload = vec![0; len];
+    stream.read_exact(&mut payload)?;
+    Ok(payload)
+}
+
+/// Parse an IPC message header into its type and payload length.
+fn parse_header(buffer: &[u8]) -> Option<(u32, usize)> {
+    let header = buffer.get(..HEADER_LEN)?.strip_prefix(MAGIC)?;
+    let len = u32::from_ne_bytes(header[..4].try_into().ok()?);
+    let kind = u32::from_ne_bytes(header[4..].try_into().ok()?);
+    Some((kind, len as usize))
+}
+
+/// Deserialize an IPC payload.
+fn parse<T: DeserializeOwned>(payload: &[u8]) -> Result<T> {
+    serde_json::from_slice(payload).map_err(|err| Error::Compositor(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_valid_header() {
+        let mut buffer = MAGIC.to_vec();
+        buffer.extend_from_slice(&42u32.to_ne_bytes());
+        buffer.extend_from_slice(&GET_TREE.to_ne_bytes());
+        buffer.extend_from_slice(b"trailing payload");
+
+        assert_eq!(parse_header(&buffer), Some((GET_TREE, 42)));
+    }
+
+    #[test]
+    fn reject_invalid_header() {
+        let mut buffer = b"i3-ipx".to_vec();
+        buffer.extend_from_slice(&[0; 8]);
+        assert_eq!(parse_header(&buffer), None);
+
+        // Incomplete headers can't be parsed yet.
+        assert_eq!(parse_header(&MAGIC[..4]), None);
+        assert_eq!(parse_header(&buffer[..HEADER_LEN - 1]), None);
+    }
+}
@@ -0,0 +1,316 @@
+//! CPU frequency, governor and top processes.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use epitaph_modules::{Error, Result};
+use epitaph_ui::text::Svg;
+
+use crate::config::System as Config;
+use crate::module::{self, DrawerModule, Event, List, Module, Source};
+use crate::reaper;
+
+/// Refresh interval for this module.
+const UPDATE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Directory containing the cpufreq policies of all CPU clusters.
+const CPUFREQ_DIR: &str = "/sys/devices/system/cpu/cpufreq";
+
+/// Governors offered in the drawer, if supported by the kernel.
+const GOVERNORS: [&str; 3] = ["schedutil", "performance", "powersave"];
+
+/// Number of processes listed for both CPU and memory usage.
+const TOP_PROCESSES: usize = 3;
+
+pub struct System {
+    cpu_times: HashMap<i32, u64>,
+    governors: Vec<&'static str>,
+    governor: Option<String>,
+    processes: Vec<(Process, Usage)>,
+    pressed_pid: Option<i32>,
+    total_cpu_time: u64,
+    frequency: u64,
+    config: Config,
+}
+
+impl System {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            total_cpu_time: Default::default(),
+            cpu_times: Default::default(),
+            governors: Default::default(),
+            processes: Default::default(),
+            pressed_pid: Default::default(),
+            governor: Default::default(),
+            frequency: Default::default(),
+        }
+    }
+
+    /// Find the processes with the highest CPU and memory usage.
+    ///
+    /// CPU usage is measured as share of the total CPU time since the last update.
+    fn sample_processes(&mut self) -> Result<()> {
+        let total_cpu_time = match total_cpu_time() {
+            Some(total_cpu_time) => total_cpu_time,
+            None => return Ok(()),
+        };
+        let elapsed = total_cpu_time.saturating_sub(self.total_cpu_time).max(1);
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) }.max(0) as u64;
+
+        let mut processes = Vec::new();
+        let mut cpu_times = HashMap::new();
+        for entry in fs::read_dir("/proc")? {
+            let pid = match entry?.file_name().to_str().and_then(|name| i32::from_str(name).ok()) {
+                Some(pid) => pid,
+                None => continue,
+            };
+
+            // Ignore processes which exited while sampling.
+            let (name, cpu_time, pages) = match read_process(pid) {
+                Some(process) => process,
+                None => continue,
+            };
+
+            // Processes without previous sample haven't used any CPU time yet.
+            let last_cpu_time = self.cpu_times.get(&pid).copied().unwrap_or(cpu_time);
+            cpu_times.insert(pid, cpu_time);
+
+            let cpu = cpu_time.saturating_sub(last_cpu_time) as f64 / elapsed as f64;
+            processes.push((Process { pid, name }, cpu, pages * page_size));
+        }
+
+        self.total_cpu_time = total_cpu_time;
+        self.cpu_times = cpu_times;
+
+        self.processes.clear();
+        processes.sort_by(|a, b| b.1.total_cmp(&a.1));
+        for (process, cpu, _) in processes.iter().take(TOP_PROCESSES) {
+            self.processes.push((process.clone(), Usage::Cpu(*cpu)));
+        }
+        processes.sort_by(|a, b| b.2.cmp(&a.2));
+        for (process, _, memory) in processes.into_iter().take(TOP_PROCESSES) {
+            self.processes.push((process, Usage::Memory(memory)));
+        }
+
+        Ok(())
+    }
+
+    /// Read the current frequency and governor.
+    ///
+    /// Returns `true` if either of them changed.
+    fn refresh(&mut self) -> Result<bool> {
+        let policies = policies()?;
+
+        // Show the frequency of the fastest cluster.
+        let frequency = policies
+            .iter()
+            .filter_map(|policy| read_attribute(policy, "scaling_cur_freq"))
+            .filter_map(|frequency| u64::from_str(&frequency).ok())
+            .max()
+            .unwrap_or(0);
+
+        let governor =
+            policies.first().and_then(|policy| read_attribute(policy, "scaling_governor"));
+
+        let changed = frequency != self.frequency || governor != self.governor;
+        self.frequency = frequency;
+        self.governor = governor;
+
+        Ok(changed)
+    }
+
+    /// Switch all CPU clusters to a new governor.
+    fn set_governor(&mut self, governor: &str) -> Result<()> {
+        for policy in policies()? {
+            match fs::write(policy.join("scaling_governor"), governor) {
+                Ok(()) => (),
+                // Fall back to the privileged helper without write access to sysfs.
+                Err(err) if err.kind() == ErrorKind::PermissionDenied => {
+                    let (program, args) = self
+                        .config
+                        .governor_command
+                        .split_first()
+                        .ok_or(Error::NotConfigured("CPU governor command"))?;
+                    let args = args.iter().map(String::as_str).chain([governor]);
+                    reaper::daemon(program.as_str(), args)?;
+                    break;
+                },
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        self.governor = Some(governor.into());
+
+        Ok(())
+    }
+}
+
+impl Module for System {
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::List(self))
+    }
+
+    fn sources(&self) -> Vec<Source> {
+        vec![Source::Interval(UPDATE_INTERVAL)]
+    }
+
+    fn probe(&mut self) -> Result<()> {
+        // Only offer governors supported by the first cluster.
+        let policies = policies()?;
+        let available = policies
+            .first()
+            .and_then(|policy| read_attribute(policy, "scaling_available_governors"))
+            .ok_or(Error::DeviceNotFound("cpufreq"))?;
+        let available: Vec<_> = available.split_whitespace().collect();
+        self.governors =
+            GOVERNORS.into_iter().filter(|governor| available.contains(governor)).collect();
+
+        self.refresh()?;
+
+        Ok(())
+    }
+
+    fn update(&mut self, _index: usize, _event: Event) -> Result<bool> {
+        self.sample_processes()?;
+        self.refresh()?;
+
+        // Process usage changes constantly.
+        Ok(true)
+    }
+}
+
+impl List for System {
+    fn select(&mut self, index: usize) -> Result<()> {
+        match self.governors.get(index) {
+            Some(governor) => self.set_governor(governor),
+            None => Ok(()),
+        }
+    }
+
+    /// Remember the process of a pressed process entry.
+    ///
+    /// The process list is re-sorted during updates, so the entry's index
+    /// might belong to a different process once the long-press completes.
+    fn press(&mut self, index: usize) {
+        let index = index.checked_sub(self.governors.len());
+        let process = index.and_then(|index| self.processes.get(index));
+        self.pressed_pid = process.map(|(process, _)| process.pid);
+    }
+
+    /// Terminate the process of a long-pressed process entry.
+    fn long_press(&mut self) -> Result<()> {
+        let pid = match self.pressed_pid.take() {
+            Some(pid) => pid,
+            None => return Ok(()),
+        };
+
+        if unsafe { libc::kill(pid, libc::SIGTERM) } != 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+
+        // Hide all entries of the terminated process.
+        self.processes.retain(|(process, _)| process.pid != pid);
+
+        Ok(())
+    }
+
+    fn entries(&self) -> Vec<String> {
+        let governors = self.governors.iter().map(|governor| governor.to_string());
+        let processes = self.processes.iter().map(|(process, usage)| {
+            let usage = match usage {
+                Usage::Cpu(cpu) => format!("{:.0}% CPU", cpu * 100.),
+                Usage::Memory(memory) => module::format_bytes(*memory),
+            };
+            format!("{usage}: {} ({})", process.name, process.pid)
+        });
+        governors.chain(processes).collect()
+    }
+
+    fn selected(&self) -> Option<usize> {
+        let governor = self.governor.as_deref()?;
+        self.governors.iter().position(|available| *available == governor)
+    }
+
+    fn label(&self) -> String {
+        let frequency = format!("{:.2} GHz", self.frequency as f64 / 1_000_000.);
+        match &self.governor {
+            Some(governor) => format!("{frequency}, {governor}"),
+            None => frequency,
+        }
+    }
+
+    fn svg(&self) -> Svg {
+        Svg::Cpu
+    }
+}
+
+/// Process listed in the drawer.
+#[derive(Clone)]
+struct Process {
+    name: String,
+    pid: i32,
+}
+
+/// Resource usage of a listed process.
+#[derive(Copy, Clone)]
+enum Usage {
+    /// Share of the total CPU time, from `0.0` to `1.0`.
+    Cpu(f64),
+    /// Resident memory in bytes.
+    Memory(u64),
+}
+
+/// Get the total CPU time of all CPUs in clock ticks.
+fn total_cpu_time() -> Option<u64> {
+    let stat = fs::read_to_string("/proc/stat").ok()?;
+    let cpu = stat.lines().next()?.strip_prefix("cpu ")?;
+
+    // Skip guest time, which is already included in user time.
+    Some(cpu.split_whitespace().take(8).filter_map(|time| u64::from_str(time).ok()).sum())
+}
+
+/// Get name, CPU time in clock ticks and resident memory pages of a process.
+fn read_process(pid: i32) -> Option<(String, u64, u64)> {
+    let stat = fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+
+    // Process names can contain whitespace and parentheses.
+    let (name, fields) = stat.split_once(" (")?.1.rsplit_once(") ")?;
+    let fields: Vec<_> = fields.split_whitespace().collect();
+
+    // Fields are offset by the PID and name, which are the first two.
+    let field = |index: usize| u64::from_str(fields.get(index - 3)?).ok();
+    let cpu_time = field(14)? + field(15)?;
+    let pages = field(24)?;
+
+    Some((name.into(), cpu_time, pages))
+}
+
+/// Get the cpufreq policy directories of all CPU clusters.
+fn policies() -> Result<Vec<PathBuf>> {
+    let mut policies = Vec::new();
+    for entry in fs::read_dir(CPUFREQ_DIR)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with("policy") {
+            policies.push(entry.path());
+        }
+    }
+
+    if policies.is_empty() {
+        return Err(Error::DeviceNotFound("cpufreq"));
+    }
+
+    policies.sort();
+
+    Ok(policies)
+}
+
+/// Read a cpufreq policy attribute.
+fn read_attribute(policy: &Path, attribute: &str) -> Option<String> {
+    let value = fs::read_to_string(policy.join(attribute)).ok()?;
+    Some(value.trim().into())
+}
@@ -0,0 +1,158 @@
+//! Failed systemd units.
+
+use std::mem;
+use std::process::Command;
+
+use epitaph_modules::Result;
+use epitaph_ui::text::Svg;
+
+use crate::module::{
+    Alignment, DrawerModule, Event, List, Module, PanelModule, PanelModuleContent, Source,
+};
+use crate::panel::TEXT_COLOR;
+use crate::reaper;
+
+/// Index of the user manager's command in the module's sources.
+const USER_SOURCE: usize = 1;
+
+/// Failed units of the system and user service managers.
+pub struct Systemd {
+    system: Vec<String>,
+    user: Vec<String>,
+}
+
+impl Systemd {
+    pub fn new() -> Self {
+        Self { system: Default::default(), user: Default::default() }
+    }
+
+    /// Get all failed units with their service manager.
+    fn units(&self) -> impl Iterator<Item = (&str, bool)> {
+        let system = self.system.iter().map(|unit| (unit.as_str(), false));
+        system.chain(self.user.iter().map(|unit| (unit.as_str(), true)))
+    }
+
+    /// Total number of failed units.
+    fn failed(&self) -> usize {
+        self.system.len() + self.user.len()
+    }
+}
+
+impl Module for Systemd {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        Some(self)
+    }
+
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::List(self))
+    }
+
+    fn sources(&self) -> Vec<Source> {
+        vec![
+            Source::Watch(Box::new(|| failed_units(false)), Box::new(|| unit_signals(false))),
+            Source::Watch(Box::new(|| failed_units(true)), Box::new(|| unit_signals(true))),
+        ]
+    }
+
+    fn update(&mut self, index: usize, event: Event) -> Result<bool> {
+        let output = match event {
+            Event::Output(output) => output?,
+            _ => return Ok(false),
+        };
+
+        // The unit name is the first column.
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let units: Vec<String> = stdout
+            .lines()
+            .filter_map(|line| line.split_whitespace().next())
+            .map(String::from)
+            .collect();
+
+        let failed = match index {
+            USER_SOURCE => &mut self.user,
+            _ => &mut self.system,
+        };
+        let old_units = mem::replace(failed, units);
+
+        Ok(old_units != *failed)
+    }
+}
+
+impl PanelModule for Systemd {
+    fn alignment(&self) -> Alignment {
+        Alignment::Right
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        // Only show the warning while units have failed.
+        match self.failed() {
+            0 => PanelModuleContent::Text(String::new()),
+            failed => PanelModuleContent::Status {
+                text: failed.to_string(),
+                svg: Some(Svg::Warning),
+                color: TEXT_COLOR,
+            },
+        }
+    }
+}
+
+impl List for Systemd {
+    fn select(&mut self, index: usize) -> Result<()> {
+        let (unit, user) = match self.units().nth(index) {
+            Some(unit) => unit,
+            None => return Ok(()),
+        };
+
+        // Restart the unit, which might prompt for authentication.
+        let mut args = vec!["restart", unit];
+        if user {
+            args.insert(0, "--user");
+        }
+        reaper::daemon("systemctl", args)?;
+
+        Ok(())
+    }
+
+    fn entries(&self) -> Vec<String> {
+        self.units()
+            .map(|(unit, user)| if user { format!("{unit} (user)") } else { unit.into() })
+            .collect()
+    }
+
+    fn selected(&self) -> Option<usize> {
+        None
+    }
+
+    fn label(&self) -> String {
+        match self.failed() {
+            0 => "No failed units".into(),
+            1 => "1 failed unit".into(),
+            failed => format!("{failed} failed units"),
+        }
+    }
+
+    fn svg(&self) -> Svg {
+        Svg::Warning
+    }
+}
+
+/// Command listing the failed units of a service manager.
+fn failed_units(user: bool) -> Command {
+    let mut systemctl = Command::new("systemctl");
+    if user {
+        systemctl.arg("--user");
+    }
+    systemctl.args(["list-units", "--state=failed", "--plain", "--no-legend", "--full"]);
+    systemctl
+}
+
+/// Command monitoring a service manager's signals.
+///
+/// The manager only broadcasts unit changes while a client is subscribed to
+/// it, which logind always is for the system manager.
+fn unit_signals(user: bool) -> Command {
+    let mut gdbus = Command::new("gdbus");
+    gdbus.args(["monitor", if user { "--session" } else { "--system" }]);
+    gdbus.args(["--dest", "org.freedesktop.systemd1"]);
+    gdbus
+}
@@ -0,0 +1,188 @@
+//! Countdown timer and stopwatch.
+
+use std::time::{Duration, Instant};
+
+use epitaph_modules::Result;
+use epitaph_ui::text::Svg;
+
+use crate::config;
+use crate::module::{
+    Alignment, DrawerModule, Event, List, Module, PanelModule, PanelModuleContent, Signals, Source,
+    Urgency,
+};
+use crate::panel::TEXT_COLOR;
+use crate::reaper;
+
+/// Refresh interval for the remaining time.
+const UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Countdown durations in seconds which are always available.
+const PRESETS: [u64; 4] = [60, 300, 600, 1800];
+
+/// Label of the list entry stopping the timer.
+const STOP_LABEL: &str = "Off";
+
+/// Label of the list entry starting the stopwatch.
+const STOPWATCH_LABEL: &str = "Stopwatch";
+
+/// Number of list entries before the first countdown duration.
+const DURATION_OFFSET: usize = 2;
+
+/// Countdown timer and stopwatch.
+pub struct Timer {
+    alert_command: Vec<String>,
+    durations: Vec<u64>,
+    selected: usize,
+    mode: Mode,
+    signals: Signals,
+}
+
+impl Timer {
+    pub fn new(config: config::Timer, signals: Signals) -> Self {
+        let mut durations = PRESETS.to_vec();
+        for duration in config.custom {
+            if duration > 0 && !durations.contains(&duration) {
+                durations.push(duration);
+            }
+        }
+
+        Self {
+            alert_command: config.alert_command,
+            durations,
+            signals,
+            selected: Default::default(),
+            mode: Mode::Stopped,
+        }
+    }
+
+    /// Notify the user about an expired countdown.
+    fn alert(&self) -> Result<()> {
+        self.signals.notify(Urgency::Critical, "timer", "Timer", "Time is up");
+
+        if let Some((program, args)) = self.alert_command.split_first() {
+            reaper::daemon(program.as_str(), args.iter().map(String::as_str))?;
+        }
+
+        Ok(())
+    }
+
+    /// Remaining countdown or elapsed stopwatch time in seconds.
+    fn seconds(&self) -> Option<u64> {
+        match self.mode {
+            Mode::Stopped => None,
+            // Round up, so the countdown expires when it reaches zero.
+            Mode::Countdown(end) => {
+                let remaining = end.saturating_duration_since(Instant::now());
+                Some((remaining.as_millis() as u64 + 999) / 1000)
+            },
+            Mode::Stopwatch(start) => Some(start.elapsed().as_secs()),
+        }
+    }
+}
+
+impl Module for Timer {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        Some(self)
+    }
+
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::List(self))
+    }
+
+    fn sources(&self) -> Vec<Source> {
+        vec![Source::Interval(UPDATE_INTERVAL)]
+    }
+
+    fn update(&mut self, _index: usize, _event: Event) -> Result<bool> {
+        match self.mode {
+            Mode::Stopped => Ok(false),
+            Mode::Countdown(end) if Instant::now() >= end => {
+                self.mode = Mode::Stopped;
+                self.selected = 0;
+
+                if let Err(err) = self.alert() {
+                    eprintln!("Error: Couldn't send timer alert: {err}");
+                }
+
+                Ok(true)
+            },
+            _ => Ok(true),
+        }
+    }
+}
+
+impl PanelModule for Timer {
+    fn alignment(&self) -> Alignment {
+        Alignment::Right
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        // Only show the time while the timer is running.
+        match self.seconds() {
+            Some(seconds) => PanelModuleContent::Status {
+                text: format_time(seconds),
+                svg: Some(Svg::Timer),
+                color: TEXT_COLOR,
+            },
+            None => PanelModuleContent::Text(String::new()),
+        }
+    }
+}
+
+impl List for Timer {
+    fn select(&mut self, index: usize) -> Result<()> {
+        self.mode = match index {
+            0 => Mode::Stopped,
+            1 => Mode::Stopwatch(Instant::now()),
+            _ => match self.durations.get(index - DURATION_OFFSET) {
+                Some(duration) => Mode::Countdown(Instant::now() + Duration::from_secs(*duration)),
+                None => return Ok(()),
+            },
+        };
+        self.selected = index;
+
+        Ok(())
+    }
+
+    fn entries(&self) -> Vec<String> {
+        let mut entries = vec![STOP_LABEL.to_owned(), STOPWATCH_LABEL.to_owned()];
+        entries.extend(self.durations.iter().map(|duration| match duration % 60 {
+            0 => format!("{} min", duration / 60),
+            _ => format_time(*duration),
+        }));
+        entries
+    }
+
+    fn selected(&self) -> Option<usize> {
+        Some(self.selected)
+    }
+
+    fn label(&self) -> String {
+        match self.seconds() {
+            Some(seconds) => format_time(seconds),
+            None => "Timer".into(),
+        }
+    }
+
+    fn svg(&self) -> Svg {
+        Svg::Timer
+    }
+}
+
+/// Timer state.
+#[derive(Copy, Clone)]
+enum Mode {
+    Stopped,
+    Countdown(Instant),
+    Stopwatch(Instant),
+}
+
+/// Format seconds as `h:mm:ss`, omitting hours when possible.
+pub fn format_time(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
@@ -0,0 +1,103 @@
+//! Pending package updates.
+
+use std::mem;
+use std::process::Command;
+use std::time::Duration;
+
+use epitaph_modules::{Error, Result};
+use epitaph_ui::text::Svg;
+
+use crate::config;
+use crate::module::{
+    self, Alignment, DrawerModule, Event, Module, PanelModule, PanelModuleContent, Signals, Source,
+    Toggle,
+};
+use crate::panel::TEXT_COLOR;
+
+/// Refresh interval for this module.
+const UPDATE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Number of pending package updates.
+pub struct Updates {
+    config: config::Updates,
+    pending: usize,
+    signals: Signals,
+}
+
+impl Updates {
+    pub fn new(config: config::Updates, signals: Signals) -> Self {
+        Self { config, signals, pending: Default::default() }
+    }
+}
+
+impl Module for Updates {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        Some(self)
+    }
+
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::Toggle(self))
+    }
+
+    fn probe(&mut self) -> Result<()> {
+        if self.config.check_command.is_empty() {
+            return Err(Error::NotConfigured("updates"));
+        }
+        Ok(())
+    }
+
+    fn sources(&self) -> Vec<Source> {
+        let check_command = self.config.check_command.clone();
+        vec![Source::Command(Box::new(move || module::command(&check_command)), UPDATE_INTERVAL)]
+    }
+
+    fn update(&mut self, _index: usize, event: Event) -> Result<bool> {
+        let output = match event {
+            Event::Output(output) => output?,
+            _ => return Ok(false),
+        };
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let pending = stdout.lines().filter(|line| !line.trim().is_empty()).count();
+        let old_pending = mem::replace(&mut self.pending, pending);
+
+        Ok(old_pending != self.pending)
+    }
+}
+
+impl PanelModule for Updates {
+    fn alignment(&self) -> Alignment {
+        Alignment::Right
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        // Only show the badge while updates are pending.
+        match self.pending {
+            0 => PanelModuleContent::Text(String::new()),
+            pending => PanelModuleContent::Status {
+                text: pending.to_string(),
+                svg: Some(Svg::Updates),
+                color: TEXT_COLOR,
+            },
+        }
+    }
+}
+
+impl Toggle for Updates {
+    fn toggle(&mut self) -> Result<()> {
+        let (program, args) =
+            self.config.update_command.split_first().ok_or(Error::NotConfigured("updater"))?;
+        let mut command = Command::new(program);
+        command.args(args);
+        self.signals.launch(command);
+        Ok(())
+    }
+
+    fn enabled(&self) -> bool {
+        self.pending > 0
+    }
+
+    fn svg(&self) -> Svg {
+        Svg::Updates
+    }
+}
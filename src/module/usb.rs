@@ -0,0 +1,196 @@
+//! USB gadget mode.
+
+use std::fs;
+use std::mem;
+use std::path::Path;
+
+use epitaph_modules::{Error, Result};
+use epitaph_ui::text::Svg;
+
+use crate::module::{
+    self, Alignment, DrawerModule, Event, List, Module, PanelModule, PanelModuleContent, Section,
+    Source,
+};
+use crate::panel::TEXT_COLOR;
+use crate::reaper;
+
+/// Configfs directory containing all USB gadgets.
+const GADGET_DIR: &str = "/sys/kernel/config/usb_gadget";
+
+/// USB gadget mode and cable status.
+pub struct Usb {
+    command: Vec<String>,
+    attached: bool,
+    mode: Mode,
+}
+
+impl Usb {
+    pub fn new(command: Vec<String>) -> Self {
+        Self { command, attached: false, mode: Mode::Charging }
+    }
+
+    /// Check if a USB host is connected to any device controller.
+    fn attached() -> Result<bool> {
+        let devices = module::devices("udc")?;
+        if devices.is_empty() {
+            return Err(Error::DeviceNotFound("usb gadget"));
+        }
+
+        Ok(devices.iter().any(|device| {
+            device.attribute_value("state").map_or(false, |state| state != "not attached")
+        }))
+    }
+}
+
+impl Module for Usb {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        Some(self)
+    }
+
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::List(self))
+    }
+
+    fn section(&self) -> Section {
+        Section::Connectivity
+    }
+
+    fn probe(&mut self) -> Result<()> {
+        self.attached = Self::attached()?;
+        self.mode = Mode::current();
+        Ok(())
+    }
+
+    fn sources(&self) -> Vec<Source> {
+        vec![Source::Udev("udc")]
+    }
+
+    fn update(&mut self, _index: usize, _event: Event) -> Result<bool> {
+        let attached = Self::attached()?;
+        let old_attached = mem::replace(&mut self.attached, attached);
+        let old_mode = mem::replace(&mut self.mode, Mode::current());
+
+        // Redraw if value changed.
+        Ok(old_attached != self.attached || old_mode != self.mode)
+    }
+}
+
+impl PanelModule for Usb {
+    fn alignment(&self) -> Alignment {
+        Alignment::Right
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        // Only show the mode while a cable is attached.
+        if !self.attached {
+            return PanelModuleContent::Text(String::new());
+        }
+
+        PanelModuleContent::Status {
+            text: self.mode.label().into(),
+            svg: Some(Svg::Usb),
+            color: TEXT_COLOR,
+        }
+    }
+}
+
+impl List for Usb {
+    fn select(&mut self, index: usize) -> Result<()> {
+        let mode = match Mode::ALL.get(index) {
+            Some(mode) => *mode,
+            None => return Ok(()),
+        };
+
+        // Switch modes using the configured script.
+        let (program, args) = self.command.split_first().ok_or(Error::NotConfigured("usb"))?;
+        let args = args.iter().map(String::as_str).chain([mode.name()]);
+        reaper::daemon(program.as_str(), args)?;
+
+        // Immediately change mode for better UX.
+        self.mode = mode;
+
+        Ok(())
+    }
+
+    fn entries(&self) -> Vec<String> {
+        Mode::ALL.iter().map(|mode| mode.label().into()).collect()
+    }
+
+    fn selected(&self) -> Option<usize> {
+        Mode::ALL.iter().position(|mode| mode == &self.mode)
+    }
+
+    fn label(&self) -> String {
+        format!("USB: {}", self.mode.label())
+    }
+
+    fn svg(&self) -> Svg {
+        Svg::Usb
+    }
+}
+
+/// USB gadget mode.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Mode {
+    Charging,
+    Mtp,
+    Tethering,
+}
+
+impl Mode {
+    /// All modes in drawer order.
+    const ALL: [Self; 3] = [Self::Charging, Self::Mtp, Self::Tethering];
+
+    /// Mode description.
+    const fn label(&self) -> &'static str {
+        match self {
+            Self::Charging => "Charging",
+            Self::Mtp => "MTP",
+            Self::Tethering => "Tethering",
+        }
+    }
+
+    /// Mode argument passed to the mode switching script.
+    const fn name(&self) -> &'static str {
+        match self {
+            Self::Charging => "charging",
+            Self::Mtp => "mtp",
+            Self::Tethering => "tethering",
+        }
+    }
+
+    /// Get the mode of the active configfs gadget.
+    ///
+    /// Gadgets without any known functions are considered to be charging only.
+    fn current() -> Self {
+        let gadgets = fs::read_dir(GADGET_DIR).into_iter().flatten().flatten();
+        let active_gadget = gadgets.map(|gadget| gadget.path()).find(|gadget| {
+            let udc = fs::read_to_string(gadget.join("UDC")).unwrap_or_default();
+            !udc.trim().is_empty()
+        });
+
+        match active_gadget {
+            Some(gadget) => Self::from_gadget(&gadget),
+            None => Self::Charging,
+        }
+    }
+
+    /// Get the mode of a configfs gadget from its linked functions.
+    fn from_gadget(gadget: &Path) -> Self {
+        let configs = fs::read_dir(gadget.join("configs")).into_iter().flatten().flatten();
+        let links = configs.flat_map(|config| fs::read_dir(config.path()).into_iter().flatten());
+
+        // Function directories are named `<type>.<instance>`.
+        let mut mode = Self::Charging;
+        for link in links.flatten().filter_map(|link| fs::read_link(link.path()).ok()) {
+            let function = link.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
+            match function.split('.').next() {
+                Some("rndis" | "ecm" | "ncm" | "eem") => return Self::Tethering,
+                _ if function.contains("mtp") => mode = Self::Mtp,
+                _ => (),
+            }
+        }
+
+        mode
+    }
+}
@@ -1,16 +1,21 @@
 //! WiFi status and signal strength.
 
+use std::io::{self, ErrorKind};
 use std::mem;
 use std::process::{Command, Output};
 use std::str::FromStr;
 use std::time::{Duration, UNIX_EPOCH};
 
-use calloop::timer::{TimeoutAction, Timer};
-use calloop::LoopHandle;
+use epitaph_modules::{Error, Result};
+use epitaph_ui::text::Svg;
 
-use crate::module::{Alignment, DrawerModule, Module, PanelModule, PanelModuleContent, Toggle};
-use crate::text::Svg;
-use crate::{reaper, Result, State};
+use crate::config;
+use crate::module::{
+    Alignment, DrawerModule, Event, Module, PanelModule, PanelModuleContent, Section, Signals,
+    Source, Toggle,
+};
+use crate::panel::TEXT_COLOR;
+use crate::reaper;
 
 /// Refresh interval for this module.
 const UPDATE_INTERVAL: Duration = Duration::from_secs(5);
@@ -18,89 +23,160 @@ const UPDATE_INTERVAL: Duration = Duration::from_secs(5);
 /// Seconds after toggling status until updates are resumed.
 const TOGGLE_COOLDOWN: u64 = 10;
 
-/// IP to ping for checking network connectivity.
-const PING_IP: &str = "1.1.1.1";
+/// Panel icon color while the connection is degraded.
+const DEGRADED_COLOR: [u8; 3] = [255, 190, 0];
+
+/// Panel icon color while there is no internet connectivity.
+const OFFLINE_COLOR: [u8; 3] = [255, 85, 85];
+
+/// Refresh interval for captive portal detection.
+const PORTAL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Shell script showing a captive portal notification, with the portal URL as `$0`.
+const PORTAL_SCRIPT: &str = r#"action=$(notify-send --wait --action=open=Log\ in \
+    "WiFi login required" "This network requires signing in")
+[ "$action" = open ] && xdg-open "$0""#;
+
+/// Index of the `iw` command in the module's sources.
+const IW_SOURCE: usize = 0;
+
+/// Index of the network credentials query in the module's sources.
+const CREDENTIALS_SOURCE: usize = 2;
+
+/// Index of the captive portal check in the module's sources.
+const PORTAL_SOURCE: usize = 3;
+
+/// Refresh interval for the credentials of the active network.
+const CREDENTIALS_INTERVAL: Duration = Duration::from_secs(30);
 
-#[derive(Debug)]
 pub struct Wifi {
+    config: config::Wifi,
+    credentials: Option<Credentials>,
+    ssid: Option<String>,
+    portal: Option<String>,
+    latency: Option<u64>,
     signal_strength: i32,
     last_toggle: u64,
     connected: bool,
     disabled: bool,
+    signals: Signals,
 }
 
 impl Wifi {
-    pub fn new(event_loop: &LoopHandle<'static, State>) -> Result<Self> {
-        // Schedule module updates.
-        event_loop.insert_source(Timer::immediate(), move |now, _, state| {
-            // Temporarily suspend updates after toggling status.
-            let secs_since_toggle = unix_secs() - state.modules.wifi.last_toggle;
-            if let Some(remaining) =
-                TOGGLE_COOLDOWN.checked_sub(secs_since_toggle).filter(|x| *x != 0)
-            {
-                return TimeoutAction::ToDuration(Duration::from_secs(remaining + 1));
-            }
-
-            // Setup signal strength updates.
-            let mut iw = Command::new("iw");
-            iw.args(["dev", "wlan0", "link"]);
-            state.reaper.watch(iw, Box::new(Self::iw_callback));
-
-            // Setup internet connectivity updates.
-            let mut ping = Command::new("ping");
-            ping.args(["-c", "1", PING_IP]);
-            state.reaper.watch(ping, Box::new(Self::ping_callback));
-
-            TimeoutAction::ToInstant(now + UPDATE_INTERVAL)
-        })?;
-
-        Ok(Self { signal_strength: 0, last_toggle: 0, connected: false, disabled: false })
+    pub fn new(config: config::Wifi, signals: Signals) -> Self {
+        Self {
+            config,
+            signals,
+            signal_strength: 0,
+            last_toggle: 0,
+            connected: false,
+            disabled: false,
+            credentials: None,
+            latency: None,
+            portal: None,
+            ssid: None,
+        }
     }
 
     /// Handle `ping` command completion.
-    fn ping_callback(state: &mut State, output: Output) {
+    fn ping_update(&mut self, output: Output) -> bool {
         let new_connected = output.status.success();
-        let old_connected = mem::replace(&mut state.modules.wifi.connected, new_connected);
+        let old_connected = mem::replace(&mut self.connected, new_connected);
+
+        // Extract the round-trip time in milliseconds from `time=12.3 ms`.
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let new_latency = stdout
+            .split_once("time=")
+            .and_then(|(_, time)| time.split_whitespace().next()?.parse::<f64>().ok())
+            .filter(|_| new_connected)
+            .map(|time| time.round() as u64);
+        let old_latency = mem::replace(&mut self.latency, new_latency);
 
         // Redraw if value changed.
-        if new_connected != old_connected {
-            state.request_frame();
+        new_connected != old_connected || new_latency != old_latency
+    }
+
+    /// Handle captive portal check completion.
+    fn portal_update(&mut self, output: Output) {
+        // Output is the HTTP status code followed by the redirect URL.
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let (status, redirect) = stdout.trim().split_once(' ').unwrap_or((stdout.trim(), ""));
+
+        // Any response other than the expected empty one indicates a captive portal.
+        let portal = match status {
+            "204" | "000" | "" => None,
+            _ if redirect.is_empty() => Some(self.config.portal_check_url.clone()),
+            _ => Some(redirect.to_owned()),
+        };
+
+        // Only notify once after connecting to a new portal.
+        let old_portal = mem::replace(&mut self.portal, portal);
+        if let (Some(portal), None, false) = (&self.portal, old_portal, self.disabled) {
+            // Launch through the shell, so the browser inherits the activation token.
+            let mut command = Command::new("sh");
+            command.args(["-c", PORTAL_SCRIPT, portal.as_str()]);
+            self.signals.launch(command);
         }
     }
 
+    /// Panel icon color indicating connection quality.
+    fn color(&self) -> [u8; 3] {
+        match self.latency {
+            _ if self.disabled => TEXT_COLOR,
+            _ if !self.connected => OFFLINE_COLOR,
+            Some(latency) if latency > self.config.degraded_latency => DEGRADED_COLOR,
+            _ => TEXT_COLOR,
+        }
+    }
+
+    /// Handle `nmcli` credentials query completion.
+    fn credentials_update(&mut self, output: Output) {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let field = |name: &str| stdout.lines().find_map(|line| line.strip_prefix(name));
+
+        self.credentials = match (field("SSID: "), field("Security: ")) {
+            (Some(ssid), Some(security)) => Some(Credentials {
+                password: field("Password: ").unwrap_or_default().into(),
+                security: security.into(),
+                ssid: ssid.into(),
+            }),
+            _ => None,
+        };
+    }
+
     /// Handle `iw` command completion.
-    fn iw_callback(state: &mut State, output: Output) {
+    fn iw_update(&mut self, output: Output) -> bool {
         let output = String::from_utf8_lossy(&output.stdout);
 
+        self.ssid =
+            output.lines().find_map(|line| Some(line.trim().strip_prefix("SSID: ")?.into()));
+
         let start_offset = match output.find("signal: ") {
             Some(start) => start + "signal: ".len(),
             None => {
                 // Mark wifi as disabled when there is no active connection.
-                let old_disabled = mem::replace(&mut state.modules.wifi.disabled, true);
+                let old_disabled = mem::replace(&mut self.disabled, true);
 
                 // Redraw if value changed.
-                if !old_disabled {
-                    state.request_frame();
-                }
-
-                return;
+                return !old_disabled;
             },
         };
         let end_offset = match output[start_offset..].find(' ') {
             Some(end) => start_offset + end,
-            None => return,
+            None => return false,
         };
 
-        if let Ok(new_strength) = i32::from_str(&output[start_offset..end_offset]) {
-            let old_svg = state.modules.wifi.svg();
+        match i32::from_str(&output[start_offset..end_offset]) {
+            Ok(new_strength) => {
+                let old_svg = self.svg();
 
-            state.modules.wifi.signal_strength = new_strength;
-            state.modules.wifi.disabled = false;
+                self.signal_strength = new_strength;
+                self.disabled = false;
 
-            // Redraw if value changed.
-            if state.modules.wifi.svg() != old_svg {
-                state.request_frame();
-            }
+                // Redraw if value changed.
+                self.svg() != old_svg
+            },
+            Err(_) => false,
         }
     }
 }
@@ -113,6 +189,63 @@ impl Module for Wifi {
     fn drawer_module(&mut self) -> Option<DrawerModule> {
         Some(DrawerModule::Toggle(self))
     }
+
+    fn section(&self) -> Section {
+        Section::Connectivity
+    }
+
+    fn sources(&self) -> Vec<Source> {
+        // Setup signal strength and internet connectivity updates.
+        let host = self.config.ping_host.clone();
+        let ping_interval = Duration::from_secs(self.config.ping_interval.max(1));
+        let mut sources = vec![
+            Source::Command(Box::new(iw), UPDATE_INTERVAL),
+            Source::Command(Box::new(move || ping(&host)), ping_interval),
+            Source::Command(Box::new(show_password), CREDENTIALS_INTERVAL),
+        ];
+
+        // Setup captive portal detection.
+        if !self.config.portal_check_url.is_empty() {
+            let url = self.config.portal_check_url.clone();
+            sources.push(Source::Command(Box::new(move || portal_check(&url)), PORTAL_INTERVAL));
+        }
+
+        sources
+    }
+
+    fn update(&mut self, index: usize, event: Event) -> Result<bool> {
+        // Temporarily suspend updates after toggling status.
+        if unix_secs() - self.last_toggle < TOGGLE_COOLDOWN {
+            return Ok(false);
+        }
+
+        let output = match event {
+            Event::Output(output) if index == CREDENTIALS_SOURCE => {
+                output.map_err(service_error)?
+            },
+            Event::Output(output) => output?,
+            _ => return Ok(false),
+        };
+
+        match index {
+            IW_SOURCE => Ok(self.iw_update(output)),
+            CREDENTIALS_SOURCE => {
+                // Disable the module while NetworkManager isn't running.
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                if stderr.contains("NetworkManager is not running") {
+                    return Err(Error::ServiceNotFound("NetworkManager"));
+                }
+
+                self.credentials_update(output);
+                Ok(false)
+            },
+            PORTAL_SOURCE => {
+                self.portal_update(output);
+                Ok(false)
+            },
+            _ => Ok(self.ping_update(output)),
+        }
+    }
 }
 
 impl PanelModule for Wifi {
@@ -121,7 +254,11 @@ impl PanelModule for Wifi {
     }
 
     fn content(&self) -> PanelModuleContent {
-        PanelModuleContent::Svg(self.svg())
+        PanelModuleContent::Status {
+            text: String::new(),
+            svg: Some(self.svg()),
+            color: self.color(),
+        }
     }
 }
 
@@ -161,6 +298,86 @@ impl Toggle for Wifi {
     fn enabled(&self) -> bool {
         !self.disabled
     }
+
+    fn caption(&self) -> Option<String> {
+        let latency = self.latency.filter(|_| !self.disabled)?;
+        Some(format!("{latency} ms"))
+    }
+
+    /// Network sharing QR code for the active network.
+    fn qr_code(&self) -> Option<String> {
+        // Ignore credentials which haven't been updated after switching networks.
+        let credentials = self.credentials.as_ref().filter(|_| !self.disabled)?;
+        if self.ssid.as_ref() != Some(&credentials.ssid) {
+            return None;
+        }
+
+        let ssid = escape_qr_field(&credentials.ssid);
+        Some(match credentials.security.as_str() {
+            "None" => format!("WIFI:T:nopass;S:{ssid};;"),
+            security => {
+                let password = escape_qr_field(&credentials.password);
+                format!("WIFI:T:{security};S:{ssid};P:{password};;")
+            },
+        })
+    }
+}
+
+/// Credentials of the active network.
+#[derive(Debug)]
+struct Credentials {
+    ssid: String,
+    security: String,
+    password: String,
+}
+
+/// Command for signal strength updates.
+fn iw() -> Command {
+    let mut iw = Command::new("iw");
+    iw.args(["dev", "wlan0", "link"]);
+    iw
+}
+
+/// Command for internet connectivity updates.
+fn ping(host: &str) -> Command {
+    let mut ping = Command::new("ping");
+    ping.args(["-c", "1", host]);
+    ping
+}
+
+/// Command for active network credentials.
+fn show_password() -> Command {
+    let mut nmcli = Command::new("nmcli");
+    nmcli.args(["device", "wifi", "show-password"]);
+    nmcli
+}
+
+/// Treat a missing `nmcli` binary as missing NetworkManager.
+fn service_error(err: io::Error) -> Error {
+    match err.kind() {
+        ErrorKind::NotFound => Error::ServiceNotFound("NetworkManager"),
+        _ => err.into(),
+    }
+}
+
+/// Command for captive portal detection.
+fn portal_check(url: &str) -> Command {
+    let mut curl = Command::new("curl");
+    curl.args(["--silent", "--max-time", "10", "--output", "/dev/null"]);
+    curl.args(["--write-out", "%{http_code} %{redirect_url}", url]);
+    curl
+}
+
+/// Escape special characters in a WiFi QR code field.
+fn escape_qr_field(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for c in field.chars() {
+        if matches!(c, '\\' | ';' | ',' | ':' | '"') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
 }
 
 /// Seconds since unix epoch.
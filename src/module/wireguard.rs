@@ -0,0 +1,231 @@
+//! WireGuard tunnel status.
+
+use std::collections::HashMap;
+use std::mem;
+use std::time::{Duration, UNIX_EPOCH};
+
+use epitaph_modules::{Error, Result};
+use epitaph_ui::text::Svg;
+
+use crate::config;
+use crate::module::{
+    self, Alignment, DrawerModule, Event, List, Module, PanelModule, PanelModuleContent, Section,
+    Source,
+};
+use crate::panel::TEXT_COLOR;
+use crate::reaper;
+
+/// Refresh interval for this module.
+const UPDATE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Seconds after the last handshake until a tunnel is considered stale.
+///
+/// WireGuard rejects sessions without a new handshake after 180 seconds.
+const STALE_HANDSHAKE: u64 = 180;
+
+/// Panel icon color while a tunnel is stale.
+const STALE_COLOR: [u8; 3] = [255, 190, 0];
+
+/// Configured WireGuard tunnels.
+pub struct Wireguard {
+    config: config::Wireguard,
+    interfaces: HashMap<String, Interface>,
+}
+
+impl Wireguard {
+    pub fn new(config: config::Wireguard) -> Self {
+        Self { config, interfaces: Default::default() }
+    }
+
+    /// Get the state of all active configured tunnels.
+    fn active(&self) -> impl Iterator<Item = &Interface> {
+        self.config.tunnels.iter().filter_map(|tunnel| self.interfaces.get(tunnel))
+    }
+}
+
+impl Module for Wireguard {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        Some(self)
+    }
+
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        Some(DrawerModule::List(self))
+    }
+
+    fn section(&self) -> Section {
+        Section::Connectivity
+    }
+
+    fn probe(&mut self) -> Result<()> {
+        if self.config.tunnels.is_empty() {
+            return Err(Error::NotConfigured("wireguard"));
+        }
+        Ok(())
+    }
+
+    fn sources(&self) -> Vec<Source> {
+        let status_command = self.config.status_command.clone();
+        vec![Source::Command(Box::new(move || module::command(&status_command)), UPDATE_INTERVAL)]
+    }
+
+    fn update(&mut self, _index: usize, event: Event) -> Result<bool> {
+        let output = match event {
+            Event::Output(output) => output?,
+            _ => return Ok(false),
+        };
+
+        let interfaces = parse_dump(&String::from_utf8_lossy(&output.stdout));
+        let old_interfaces = mem::replace(&mut self.interfaces, interfaces);
+
+        Ok(old_interfaces != self.interfaces)
+    }
+}
+
+impl PanelModule for Wireguard {
+    fn alignment(&self) -> Alignment {
+        Alignment::Right
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        let now = unix_secs();
+        let mut active = self.active().peekable();
+
+        // Only show the icon while a tunnel is up.
+        if active.peek().is_none() {
+            return PanelModuleContent::Text(String::new());
+        }
+
+        let color =
+            if active.all(|interface| interface.fresh(now)) { TEXT_COLOR } else { STALE_COLOR };
+        PanelModuleContent::Status { text: String::new(), svg: Some(Svg::Wireguard), color }
+    }
+}
+
+impl List for Wireguard {
+    fn select(&mut self, index: usize) -> Result<()> {
+        let tunnel = match self.config.tunnels.get(index) {
+            Some(tunnel) => tunnel,
+            None => return Ok(()),
+        };
+
+        let (program, args) = self
+            .config
+            .toggle_command
+            .split_first()
+            .ok_or(Error::NotConfigured("wireguard toggle command"))?;
+        let action = if self.interfaces.contains_key(tunnel) { "down" } else { "up" };
+        let args = args.iter().map(String::as_str).chain([action, tunnel.as_str()]);
+        reaper::daemon(program.as_str(), args)?;
+
+        Ok(())
+    }
+
+    fn entries(&self) -> Vec<String> {
+        let now = unix_secs();
+        self.config
+            .tunnels
+            .iter()
+            .map(|tunnel| match self.interfaces.get(tunnel) {
+                Some(interface) => format!("{tunnel} {}", interface.status(now)),
+                None => format!("{tunnel} down"),
+            })
+            .collect()
+    }
+
+    fn selected(&self) -> Option<usize> {
+        self.config.tunnels.iter().position(|tunnel| self.interfaces.contains_key(tunnel))
+    }
+
+    fn label(&self) -> String {
+        match self.active().count() {
+            0 => "WireGuard".into(),
+            1 => "1 tunnel up".into(),
+            active => format!("{active} tunnels up"),
+        }
+    }
+
+    fn svg(&self) -> Svg {
+        Svg::Wireguard
+    }
+}
+
+/// WireGuard interface state.
+#[derive(PartialEq, Eq, Default, Debug)]
+struct Interface {
+    /// Unix time of the most recent handshake with any peer.
+    handshake: u64,
+    rx: u64,
+    tx: u64,
+}
+
+impl Interface {
+    /// Check if a handshake has happened recently.
+    fn fresh(&self, now: u64) -> bool {
+        now.saturating_sub(self.handshake) < STALE_HANDSHAKE
+    }
+
+    /// Get the handshake age and transfer counters.
+    fn status(&self, now: u64) -> String {
+        let handshake = match self.handshake {
+            0 => "no handshake".into(),
+            handshake => format!("{} ago", format_age(now.saturating_sub(handshake))),
+        };
+        format!("{handshake} ↓{} ↑{}", format_bytes(self.rx), format_bytes(self.tx))
+    }
+}
+
+/// Parse interface states from `wg show all dump` output.
+///
+/// Interface lines have 5 tab-separated columns, while peer lines have 9 columns with the latest
+/// handshake, received and transmitted bytes in columns 6 to 8.
+fn parse_dump(dump: &str) -> HashMap<String, Interface> {
+    let mut interfaces: HashMap<String, Interface> = HashMap::new();
+
+    for line in dump.lines() {
+        let columns: Vec<&str> = line.split('\t').collect();
+        match columns.as_slice() {
+            [name, _, _, _, _] => {
+                interfaces.entry(name.to_string()).or_default();
+            },
+            [name, _, _, _, _, handshake, rx, tx, _] => {
+                let interface = interfaces.entry(name.to_string()).or_default();
+                interface.handshake = interface.handshake.max(handshake.parse().unwrap_or(0));
+                interface.rx += rx.parse().unwrap_or(0);
+                interface.tx += tx.parse().unwrap_or(0);
+            },
+            _ => (),
+        }
+    }
+
+    interfaces
+}
+
+/// Format a duration in seconds with its largest unit.
+fn format_age(seconds: u64) -> String {
+    match seconds {
+        0..=59 => format!("{seconds}s"),
+        60..=3599 => format!("{}m", seconds / 60),
+        _ => format!("{}h", seconds / 3600),
+    }
+}
+
+/// Format a byte count with a binary unit prefix.
+fn format_bytes(bytes: u64) -> String {
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut value = bytes as f64 / 1024.;
+    for unit in ["KiB", "MiB", "GiB"] {
+        if value < 1024. {
+            return format!("{value:.1} {unit}");
+        }
+        value /= 1024.;
+    }
+    format!("{value:.1} TiB")
+}
+
+/// Seconds since unix epoch.
+fn unix_secs() -> u64 {
+    UNIX_EPOCH.elapsed().unwrap().as_secs()
+}
@@ -0,0 +1,205 @@
+//! Notification history shared over IPC.
+
+use std::collections::VecDeque;
+use std::io::{self, ErrorKind, Write};
+use std::os::unix::net::UnixStream;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use calloop::generic::Generic;
+use calloop::{Interest, LoopHandle, Mode, PostAction, RegistrationToken};
+use serde::Serialize;
+
+use crate::module::Urgency;
+use crate::{Result, State};
+
+/// Maximum number of notifications kept in the history.
+const MAX_HISTORY: usize = 50;
+
+/// Maximum number of status lines buffered for a subscriber before it is disconnected.
+const MAX_QUEUED_STATUSES: usize = 16;
+
+/// Notification history and its IPC subscribers.
+pub struct Notifications {
+    event_loop: LoopHandle<'static, State>,
+    entries: Vec<Notification>,
+    unread: usize,
+    do_not_disturb: bool,
+    /// IPC clients receiving a status line after every change.
+    subscribers: Vec<Subscriber>,
+    next_subscriber_id: u64,
+}
+
+impl Notifications {
+    pub fn new(event_loop: LoopHandle<'static, State>) -> Self {
+        Self {
+            event_loop,
+            entries: Default::default(),
+            unread: Default::default(),
+            do_not_disturb: Default::default(),
+            subscribers: Default::default(),
+            next_subscriber_id: Default::default(),
+        }
+    }
+
+    /// Add a notification to the history.
+    pub fn push(&mut self, urgency: Urgency, app: &str, summary: &str, body: &str) {
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let notification = Notification {
+            app: app.into(),
+            urgency,
+            summary: summary.into(),
+            body: body.into(),
+            time,
+        };
+
+        if self.entries.len() >= MAX_HISTORY {
+            self.entries.remove(0);
+        }
+        self.entries.push(notification);
+        self.unread += 1;
+        self.broadcast();
+    }
+
+    /// Mark all notifications as read.
+    pub fn mark_read(&mut self) {
+        if self.unread > 0 {
+            self.unread = 0;
+            self.broadcast();
+        }
+    }
+
+    /// Update the do not disturb state reported to subscribers.
+    pub fn set_do_not_disturb(&mut self, active: bool) {
+        self.do_not_disturb = active;
+        self.broadcast();
+    }
+
+    /// Serialize the current status as a single JSON line.
+    pub fn status(&self) -> String {
+        let status = Status {
+            unread: self.unread,
+            do_not_disturb: self.do_not_disturb,
+            history: &self.entries,
+        };
+        serde_json::to_string(&status).expect("notification status serialization")
+    }
+
+    /// Send the status to an IPC client after every change.
+    pub fn subscribe(&mut self, stream: UnixStream) -> Result<()> {
+        stream.set_nonblocking(true)?;
+
+        // Flush pending status lines whenever the client can accept more data.
+        let id = self.next_subscriber_id;
+        let source = Generic::new(stream.try_clone()?, Interest::WRITE, Mode::Edge);
+        let token = self.event_loop.insert_source(source, move |readiness, _, state| {
+            let notifications = &mut state.notifications;
+            let index = match notifications.subscribers.iter().position(|sub| sub.id == id) {
+                Some(index) => index,
+                None => return Ok(PostAction::Remove),
+            };
+
+            // Drop clients which disconnected or can't be written to anymore.
+            if readiness.error || notifications.subscribers[index].flush().is_err() {
+                notifications.subscribers.remove(index);
+                return Ok(PostAction::Remove);
+            }
+
+            Ok(PostAction::Continue)
+        })?;
+        self.next_subscriber_id += 1;
+
+        // Send the initial status, the rest is written once the client is ready for it.
+        let mut subscriber =
+            Subscriber { id, stream, token, pending: Vec::new(), queue: VecDeque::new() };
+        if let Err(err) = subscriber.queue(self.status()).and_then(|_| subscriber.flush()) {
+            self.event_loop.remove(token);
+            return Err(err.into());
+        }
+        self.subscribers.push(subscriber);
+
+        Ok(())
+    }
+
+    /// Send the current status to all subscribers, dropping disconnected ones.
+    fn broadcast(&mut self) {
+        let status = self.status();
+        let event_loop = &self.event_loop;
+        self.subscribers.retain_mut(|subscriber| {
+            match subscriber.queue(status.clone()).and_then(|_| subscriber.flush()) {
+                Ok(()) => true,
+                Err(_) => {
+                    event_loop.remove(subscriber.token);
+                    false
+                },
+            }
+        });
+    }
+}
+
+/// IPC client watching the notification status.
+///
+/// Since clients might not read their socket as fast as the status changes, writes are
+/// buffered. Clients which fall too far behind are disconnected.
+struct Subscriber {
+    id: u64,
+    stream: UnixStream,
+    token: RegistrationToken,
+    /// Unsent end of the status line currently being written.
+    pending: Vec<u8>,
+    /// Statuses waiting for the pending line to complete.
+    queue: VecDeque<String>,
+}
+
+impl Subscriber {
+    /// Queue a status line, failing if the client stopped reading.
+    fn queue(&mut self, status: String) -> io::Result<()> {
+        if self.queue.len() >= MAX_QUEUED_STATUSES {
+            return Err(io::Error::new(ErrorKind::Other, "subscriber stopped reading"));
+        }
+
+        self.queue.push_back(status);
+        Ok(())
+    }
+
+    /// Write as much of the pending output as possible without blocking.
+    fn flush(&mut self) -> io::Result<()> {
+        loop {
+            // Only start a new line once the previous one was written completely.
+            if self.pending.is_empty() {
+                match self.queue.pop_front() {
+                    Some(status) => self.pending = format!("{status}\n").into_bytes(),
+                    None => return Ok(()),
+                }
+            }
+
+            match self.stream.write(&self.pending) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(len) => {
+                    self.pending.drain(..len);
+                },
+                Err(err) if err.kind() == ErrorKind::WouldBlock => return Ok(()),
+                Err(err) if err.kind() == ErrorKind::Interrupted => (),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+/// Notification raised by epitaph.
+#[derive(Serialize)]
+struct Notification {
+    app: String,
+    urgency: Urgency,
+    summary: String,
+    body: String,
+    /// Unix timestamp in seconds.
+    time: u64,
+}
+
+/// Notification status sent to IPC clients.
+#[derive(Serialize)]
+struct Status<'a> {
+    unread: usize,
+    do_not_disturb: bool,
+    history: &'a [Notification],
+}
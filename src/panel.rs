@@ -1,9 +1,18 @@
 //! Panel window state.
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::mem;
 use std::num::NonZeroU32;
+use std::ops::Range;
 
 use crossfont::Metrics;
+use epitaph_ui::renderer::{self, FrameStats, Renderer, TextRenderer};
+use epitaph_ui::text::{GlRasterizer, Svg};
+use epitaph_ui::theme::Theme;
+use epitaph_ui::vertex::VertexBatcher;
+use epitaph_ui::{gl, Rect, Size};
 use glutin::api::egl::config::Config;
-use glutin::context::{ContextApi, ContextAttributesBuilder, Version};
 use glutin::display::GetGlDisplay;
 use glutin::prelude::*;
 use glutin::surface::{SurfaceAttributesBuilder, WindowSurface};
@@ -14,12 +23,13 @@ use smithay_client_toolkit::reexports::client::{Proxy, QueueHandle};
 use smithay_client_toolkit::shell::layer::{
     Anchor, Layer, LayerShell, LayerSurface, LayerSurfaceConfigure,
 };
+use tiny_skia::Pixmap;
 
+use crate::config::{self, ModuleStyle};
+use crate::error::Error;
+use crate::layout::ModuleId;
 use crate::module::{Alignment, Module, PanelModuleContent};
-use crate::renderer::{Renderer, TextRenderer};
-use crate::text::{GlRasterizer, Svg};
-use crate::vertex::VertexBatcher;
-use crate::{gl, Result, Size, State};
+use crate::{Result, State};
 
 /// Panel height in pixels with a scale factor of 1.
 pub const PANEL_HEIGHT: i32 = 20;
@@ -27,17 +37,65 @@ pub const PANEL_HEIGHT: i32 = 20;
 /// Panel SVG width.
 const MODULE_WIDTH: u32 = 20;
 
-/// Panel padding to the screen edges.
-const EDGE_PADDING: i16 = 5;
+/// Text indicating modules collapsed due to missing space.
+const OVERFLOW_TEXT: &str = "…";
 
-/// Padding between panel modules.
-const MODULE_PADDING: i16 = 5;
+/// Default panel text color.
+pub const TEXT_COLOR: [u8; 3] = [255, 255, 255];
+
+/// Horizontal region in physical pixels occupied by a panel module.
+type ModuleRegion = (ModuleId, Range<i16>);
+
+/// Hash and horizontal extent of a rendered glyph.
+type GlyphExtent = (u64, Range<i16>);
+
+/// Panel surface role.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum PanelRole {
+    /// Regular panel at the top of the screen.
+    Primary,
+    /// Companion panel shown above the lock screen.
+    LockScreen,
+}
+
+impl PanelRole {
+    /// Layer shell layer for this panel.
+    fn layer(&self) -> Layer {
+        match self {
+            Self::Primary => Layer::Bottom,
+            Self::LockScreen => Layer::Overlay,
+        }
+    }
+
+    /// Layer shell namespace for this panel.
+    fn namespace(&self) -> &'static str {
+        match self {
+            Self::Primary => "panel",
+            Self::LockScreen => "lockscreen",
+        }
+    }
+
+    /// Exclusive zone reserved by this panel.
+    fn exclusive_zone(&self) -> i32 {
+        match self {
+            Self::Primary => PANEL_HEIGHT,
+            Self::LockScreen => -1,
+        }
+    }
+}
 
 pub struct Panel {
+    // NOTE: The renderer must be dropped before the window, to ensure the EGL
+    // surface is destroyed before its Wayland surface.
+    renderer: Renderer,
+    regions: Vec<ModuleRegion>,
+    /// Content of the last frame, `None` to redraw the entire panel.
+    last_frame: Option<FrameContent>,
+    config: config::Panel,
+    theme: Theme,
     queue: QueueHandle<State>,
     window: LayerSurface,
     frame_pending: bool,
-    renderer: Renderer,
     scale_factor: i32,
     size: Size,
 }
@@ -48,17 +106,15 @@ impl Panel {
         queue: QueueHandle<State>,
         layer: &mut LayerShell,
         egl_config: &Config,
+        srgb: bool,
+        role: PanelRole,
+        config: config::Panel,
     ) -> Result<Self> {
         // Default to 1x1 initial size since 0x0 EGL surfaces are illegal.
         let size = Size { width: 1, height: 1 };
 
         // Initialize EGL context.
-        let context_attribules = ContextAttributesBuilder::new()
-            .with_context_api(ContextApi::Gles(Some(Version::new(2, 0))))
-            .build(None);
-
-        let egl_display = egl_config.display();
-        let egl_context = unsafe { egl_display.create_context(egl_config, &context_attribules)? };
+        let context = renderer::create_context(egl_config, srgb)?;
 
         // Create the Wayland surface.
         let surface = compositor.create_surface(&queue);
@@ -68,11 +124,12 @@ impl Panel {
         let raw_window_handle = RawWindowHandle::Wayland(wayland_window_handle);
 
         // Create the EGL surface.
-        let surface_attributes = SurfaceAttributesBuilder::<WindowSurface>::new().build(
-            raw_window_handle,
-            NonZeroU32::new(size.width as u32).unwrap(),
-            NonZeroU32::new(size.height as u32).unwrap(),
-        );
+        let surface_attributes =
+            SurfaceAttributesBuilder::<WindowSurface>::new().with_srgb(Some(context.srgb)).build(
+                raw_window_handle,
+                NonZeroU32::new(size.width as u32).unwrap(),
+                NonZeroU32::new(size.height as u32).unwrap(),
+            );
 
         // Create the EGL surface.
         let egl_surface =
@@ -81,47 +138,170 @@ impl Panel {
         // Create the window.
         let window = LayerSurface::builder()
             .anchor(Anchor::LEFT | Anchor::TOP | Anchor::RIGHT)
-            .exclusive_zone(PANEL_HEIGHT)
+            .exclusive_zone(role.exclusive_zone())
             .size((0, PANEL_HEIGHT as u32))
-            .namespace("panel")
-            .map(&queue, layer, surface, Layer::Bottom)?;
+            .namespace(role.namespace())
+            .map(&queue, layer, surface, role.layer())?;
 
         // Initialize the renderer.
-        let mut renderer = Renderer::new(egl_context, 1)?;
+        let mut renderer = Renderer::new(context, 1)?;
         renderer.set_surface(Some(egl_surface));
 
-        Ok(Self { renderer, window, queue, size, frame_pending: false, scale_factor: 1 })
+        Ok(Self {
+            renderer,
+            config,
+            window,
+            queue,
+            size,
+            theme: Default::default(),
+            regions: Default::default(),
+            last_frame: Default::default(),
+            frame_pending: false,
+            scale_factor: 1,
+        })
     }
 
     /// Render the panel.
-    pub fn draw(&mut self, modules: &[&dyn Module]) -> Result<()> {
+    ///
+    /// Returns the modules which were collapsed due to missing space.
+    #[cfg_attr(feature = "profiling", tracing::instrument(skip_all))]
+    pub fn draw(
+        &mut self,
+        modules: &[(Option<ModuleId>, &dyn Module)],
+    ) -> Result<Vec<(Option<ModuleId>, PanelModuleContent)>> {
         self.frame_pending = false;
 
-        self.renderer.draw(|renderer| unsafe {
+        let mut collapsed = Vec::new();
+        let (config, theme) = (&self.config, &self.theme);
+        let (regions, last_frame) = (&mut self.regions, &mut self.last_frame);
+        self.renderer.draw::<Error, _>(|renderer| unsafe {
+            (collapsed, *regions) =
+                Self::draw_modules(renderer, modules, renderer.size, config, theme)?;
+
+            let [r, g, b] = theme.background.as_f32();
+            let alpha = config.opacity.clamp(0., 1.);
+            let background = [r * alpha, g * alpha, b * alpha, alpha];
+
+            // Only redraw the glyphs which changed since the last frame.
+            //
+            // Rectangles and lines aren't tracked, so they always redraw the entire panel.
+            let frame = FrameContent { background, glyphs: glyph_extents(renderer) };
+            let tracked = renderer.rect_batcher.is_empty() && renderer.line_batcher.is_empty();
+            let previous =
+                last_frame.take().filter(|last| tracked && last.background == background);
+            if let Some(previous) = previous {
+                let columns = damaged_columns(&previous.glyphs, &frame.glyphs);
+
+                // Keep the last frame if nothing changed.
+                if columns.is_empty() {
+                    renderer.discard_frame();
+                    *last_frame = Some(frame);
+                    return Ok(());
+                }
+
+                let width = columns.end as i32 - columns.start as i32;
+                let height = renderer.size.height as i32;
+                renderer.set_damage(Rect::new(columns.start as i32, 0, width, height));
+            }
+            *last_frame = Some(frame).filter(|_| tracked);
+
+            gl::ClearColor(background[0], background[1], background[2], background[3]);
             gl::Clear(gl::COLOR_BUFFER_BIT);
 
-            Self::draw_modules(renderer, modules, renderer.size)
-        })
+            Ok(())
+        })?;
+
+        Ok(collapsed)
+    }
+
+    /// Render the panel into an offscreen buffer.
+    pub fn snapshot(
+        egl_config: &Config,
+        width: i32,
+        scale_factor: i32,
+        modules: &[(Option<ModuleId>, &dyn Module)],
+    ) -> Result<Pixmap> {
+        let context = renderer::create_context(egl_config, false)?;
+
+        let size = Size::new(width, PANEL_HEIGHT) * scale_factor as f64;
+        let mut renderer = Renderer::new(context, scale_factor)?;
+        renderer.set_offscreen(size)?;
+        renderer.resize(size, scale_factor)?;
+
+        renderer.draw::<Error, _>(|renderer| unsafe {
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+
+            let (config, theme) = (config::Panel::default(), Theme::default());
+            Self::draw_modules(renderer, modules, renderer.size, &config, &theme)?;
+
+            Ok(())
+        })?;
+
+        Ok(renderer.read_pixels()?)
     }
 
     /// Render just the panel modules.
+    ///
+    /// Returns the modules which were collapsed due to missing space, and the
+    /// horizontal region in physical pixels occupied by each visible module.
     pub fn draw_modules(
         renderer: &mut Renderer,
-        modules: &[&dyn Module],
+        modules: &[(Option<ModuleId>, &dyn Module)],
         size: Size<f32>,
-    ) -> Result<()> {
-        for alignment in [Alignment::Center, Alignment::Right] {
-            let mut run = PanelRun::new(renderer, size, alignment)?;
-            for module in modules
-                .iter()
-                .filter_map(|module| module.panel_module())
-                .filter(|module| module.alignment() == alignment)
-            {
-                run.batch(module.content());
+        config: &config::Panel,
+        theme: &Theme,
+    ) -> Result<(Vec<(Option<ModuleId>, PanelModuleContent)>, Vec<ModuleRegion>)> {
+        let mut items: Vec<_> = modules
+            .iter()
+            .filter_map(|(id, module)| {
+                let module = module.panel_module()?;
+                let content =
+                    if config.icons_only { module.condensed_content() } else { module.content() };
+                if content.is_empty() {
+                    return None;
+                }
+
+                // Apply user overrides to the module's placement.
+                let style = id.and_then(|id| config.modules.get(&id).copied()).unwrap_or_default();
+                let alignment = style.alignment.unwrap_or_else(|| module.alignment());
+
+                Some(PanelItem { id: *id, alignment, style, content })
+            })
+            .collect();
+
+        // Collapse modules which don't fit into the panel.
+        let foreground = theme.foreground.rgb();
+        let mut run = PanelRun::new(renderer, size, Alignment::Left, config, foreground)?;
+        let collapsed = run.collapse(&mut items)?;
+
+        let mut regions = Vec::new();
+        for alignment in [Alignment::Left, Alignment::Center, Alignment::Right] {
+            let mut run = PanelRun::new(renderer, size, alignment, config, foreground)?;
+            for item in items.iter().filter(|item| item.alignment == alignment) {
+                run.batch(item.id, &item.content, item.style);
             }
-            run.draw();
+
+            // Indicate collapsed modules at the end of the panel.
+            if alignment == Alignment::Right && !collapsed.is_empty() {
+                let overflow = PanelModuleContent::Text(OVERFLOW_TEXT.into());
+                run.batch(None, &overflow, ModuleStyle::default());
+            }
+
+            regions.append(&mut run.finish());
         }
-        Ok(())
+
+        Ok((collapsed, regions))
+    }
+
+    /// Performance statistics of the last frame.
+    pub fn frame_stats(&self) -> FrameStats {
+        self.renderer.stats()
+    }
+
+    /// Get the module at a horizontal position in logical pixels.
+    pub fn module_at(&self, x: f64) -> Option<ModuleId> {
+        let x = (x * self.scale_factor as f64) as i16;
+        self.regions.iter().find(|(_, region)| region.contains(&x)).map(|(id, _)| *id)
     }
 
     /// Check if the panel owns this surface.
@@ -139,6 +319,20 @@ impl Panel {
         self.resize(self.size * factor_change);
     }
 
+    /// Switch to a different theme.
+    pub fn set_theme(&mut self, theme: Theme) -> Result<()> {
+        self.renderer.set_theme(&theme)?;
+        self.theme = theme;
+        self.last_frame = None;
+        Ok(())
+    }
+
+    /// Update the panel configuration.
+    pub fn set_config(&mut self, config: config::Panel) {
+        self.config = config;
+        self.last_frame = None;
+    }
+
     /// Reconfigure the window.
     pub fn reconfigure(&mut self, compositor: &CompositorState, configure: LayerSurfaceConfigure) {
         // Update size.
@@ -147,12 +341,21 @@ impl Panel {
         self.resize(size);
 
         // Set opaque region.
-        if let Ok(region) = Region::new(compositor) {
+        if self.config.opacity < 1. {
+            self.window.wl_surface().set_opaque_region(None);
+        } else if let Ok(region) = Region::new(compositor) {
             region.add(0, 0, new_width, PANEL_HEIGHT);
             self.window.wl_surface().set_opaque_region(Some(region.wl_region()));
         }
     }
 
+    /// Forget about pending frame callbacks.
+    ///
+    /// This allows requesting new frames when a callback might have been lost.
+    pub fn discard_frame(&mut self) {
+        self.frame_pending = false;
+    }
+
     /// Request a new frame.
     pub fn request_frame(&mut self) {
         if self.frame_pending {
@@ -168,111 +371,319 @@ impl Panel {
     /// Resize the window.
     fn resize(&mut self, size: Size) {
         self.size = size;
+        self.last_frame = None;
 
         let scale_factor = self.scale_factor;
         let _ = self.renderer.resize(size, scale_factor);
     }
 }
 
+/// Rendered content of a panel frame.
+struct FrameContent {
+    background: [f32; 4],
+    glyphs: Vec<GlyphExtent>,
+}
+
+/// Panel module content with its placement.
+struct PanelItem {
+    id: Option<ModuleId>,
+    alignment: Alignment,
+    style: ModuleStyle,
+    content: PanelModuleContent,
+}
+
 /// Run of multiple panel modules.
 struct PanelRun<'a> {
     batcher: &'a mut VertexBatcher<TextRenderer>,
     rasterizer: &'a mut GlRasterizer,
+    config: &'a config::Panel,
+    regions: Vec<ModuleRegion>,
     alignment: Alignment,
+    foreground: [u8; 3],
     scale_factor: i16,
     metrics: Metrics,
     size: Size<f32>,
+    last_padding: i16,
+    vertex_start: usize,
     width: i16,
 }
 
 impl<'a> PanelRun<'a> {
-    fn new(renderer: &'a mut Renderer, size: Size<f32>, alignment: Alignment) -> Result<Self> {
+    fn new(
+        renderer: &'a mut Renderer,
+        size: Size<f32>,
+        alignment: Alignment,
+        config: &'a config::Panel,
+        foreground: [u8; 3],
+    ) -> Result<Self> {
         Ok(Self {
             alignment,
+            config,
+            foreground,
             size,
             scale_factor: renderer.scale_factor as i16,
             metrics: renderer.rasterizer.metrics()?,
+            vertex_start: renderer.text_batcher.pending().len(),
             rasterizer: &mut renderer.rasterizer,
             batcher: &mut renderer.text_batcher,
+            regions: Vec::new(),
+            last_padding: 0,
             width: 0,
         })
     }
 
-    /// Draw all modules in this run.
-    fn draw(mut self) {
+    /// Move all modules in this run to their final position.
+    ///
+    /// Returns the horizontal region occupied by each module.
+    fn finish(mut self) -> Vec<ModuleRegion> {
         // Trim last module padding.
-        self.width = self.width.saturating_sub(self.module_padding());
+        self.width = self.width.saturating_sub(self.last_padding);
 
         // Determine vertex offset from left screen edge.
         let x_offset = match self.alignment {
+            Alignment::Left => self.edge_padding(),
             Alignment::Center => (self.size.width as i16 - self.width) / 2,
             Alignment::Right => self.size.width as i16 - self.width - self.edge_padding(),
         };
 
         // Update vertex position based on text alignment.
-        for vertex in self.batcher.pending() {
+        for vertex in &mut self.batcher.pending()[self.vertex_start..] {
             vertex.x += x_offset;
         }
 
-        // Draw all batched vertices.
-        let mut batches = self.batcher.batches();
-        while let Some(batch) = batches.next() {
-            batch.draw();
+        for (_, region) in &mut self.regions {
+            *region = region.start + x_offset..region.end + x_offset;
+        }
+        self.regions
+    }
+
+    /// Remove the lowest priority modules until all modules fit into the panel.
+    ///
+    /// Returns all removed modules, in their original order.
+    fn collapse(
+        &mut self,
+        items: &mut Vec<PanelItem>,
+    ) -> Result<Vec<(Option<ModuleId>, PanelModuleContent)>> {
+        let mut widths = Vec::with_capacity(items.len());
+        for item in items.iter() {
+            widths.push(self.measure(&item.content, item.style)?);
+        }
+
+        let overflow = PanelModuleContent::Text(OVERFLOW_TEXT.into());
+        let overflow_width = self.measure(&overflow, ModuleStyle::default())?;
+        let available = self.size.width as i16 - 2 * self.edge_padding();
+
+        // Keep all modules if they fit without an overflow indicator.
+        let mut width: i16 = widths.iter().sum();
+        if width <= available {
+            return Ok(Vec::new());
+        }
+
+        let mut collapsed = vec![false; items.len()];
+        while width + overflow_width > available {
+            // Collapse the last module with the lowest priority first.
+            let index = (0..items.len())
+                .rev()
+                .filter(|index| !collapsed[*index])
+                .min_by_key(|index| items[*index].style.priority);
+            match index {
+                Some(index) => {
+                    collapsed[index] = true;
+                    width -= widths[index];
+                },
+                None => break,
+            }
         }
+
+        let mut collapsed = collapsed.into_iter();
+        let (hidden, visible): (Vec<_>, Vec<_>) =
+            mem::take(items).into_iter().partition(|_| collapsed.next().unwrap_or_default());
+        *items = visible;
+
+        Ok(hidden.into_iter().map(|item| (item.id, item.content)).collect())
+    }
+
+    /// Calculate the width of a panel module, including its padding and separator.
+    fn measure(&mut self, module: &PanelModuleContent, style: ModuleStyle) -> Result<i16> {
+        let mut width = self.padding(style);
+
+        let config = self.config;
+        if !config.separator.is_empty() {
+            width += self.text_width(&config.separator) + self.padding(style);
+        }
+
+        match module {
+            PanelModuleContent::Text(text) => width += self.text_width(text),
+            PanelModuleContent::Svg(svg) => width += self.svg_width(*svg)?,
+            PanelModuleContent::Status { text, svg, .. } => {
+                if let Some(svg) = svg {
+                    width += self.svg_width(*svg)?;
+
+                    if !text.is_empty() {
+                        width += self.module_padding();
+                    }
+                }
+                width += self.text_width(text);
+            },
+        }
+
+        Ok(width)
+    }
+
+    /// Width of a text without padding.
+    fn text_width(&mut self, text: &str) -> i16 {
+        self.rasterizer.rasterize_string(text).map(|glyph| glyph.advance.0 as i16).sum()
+    }
+
+    /// Width of an SVG without padding.
+    fn svg_width(&mut self, svg: Svg) -> Result<i16> {
+        Ok(self.rasterizer.rasterize_svg(svg, MODULE_WIDTH, None)?.advance.0 as i16)
     }
 
     /// Add a panel module to the run.
-    fn batch(&mut self, module: PanelModuleContent) {
+    fn batch(&mut self, id: Option<ModuleId>, module: &PanelModuleContent, style: ModuleStyle) {
+        // Skip padding for modules without any content.
+        if module.is_empty() {
+            return;
+        }
+
+        // Separate this module from the previous one.
+        let config = self.config;
+        let separator = &config.separator;
+        if self.width > 0 && !separator.is_empty() {
+            self.batch_string(separator, self.foreground);
+            self.width += self.last_padding;
+        }
+
+        let start = self.width;
+
         match module {
-            PanelModuleContent::Text(text) => self.batch_string(&text),
+            PanelModuleContent::Text(text) => self.batch_string(text, self.foreground),
             PanelModuleContent::Svg(svg) => {
-                let _ = self.batch_svg(svg);
+                let _ = self.batch_svg(*svg, self.foreground);
+            },
+            PanelModuleContent::Status { text, svg, color } => {
+                if let Some(svg) = svg {
+                    let _ = self.batch_svg(*svg, *color);
+
+                    if !text.is_empty() {
+                        self.width += self.module_padding();
+                    }
+                }
+                self.batch_string(text, *color);
             },
         }
+
+        self.last_padding = self.padding(style);
+        self.width += self.last_padding;
+
+        // Include padding, to make small modules easier to tap.
+        if let Some(id) = id {
+            self.regions.push((id, start..self.width));
+        }
     }
 
-    /// Add text module to this run.
-    fn batch_string(&mut self, text: &str) {
+    /// Add text to this run.
+    fn batch_string(&mut self, text: &str, color: [u8; 3]) {
         // Calculate Y to center text.
         let y = ((self.size.height as f64 - self.metrics.line_height) / 2.
             + (self.metrics.line_height + self.metrics.descent as f64)) as i16;
 
         // Batch vertices for all glyphs.
         for glyph in self.rasterizer.rasterize_string(text) {
-            for vertex in glyph.vertices(self.width, y).into_iter().flatten() {
+            for mut vertex in glyph.vertices(self.width, y).into_iter().flatten() {
+                vertex.set_color(color);
                 self.batcher.push(glyph.texture_id, vertex);
             }
 
             self.width += glyph.advance.0 as i16;
         }
-
-        self.width += self.module_padding();
     }
 
-    /// Add SVG module to this run.
-    fn batch_svg(&mut self, svg: Svg) -> Result<()> {
+    /// Add SVG to this run.
+    fn batch_svg(&mut self, svg: Svg, color: [u8; 3]) -> Result<()> {
         let svg = self.rasterizer.rasterize_svg(svg, MODULE_WIDTH, None)?;
 
         // Calculate Y to center SVG.
         let y = (self.size.height as i16 - svg.height) / 2;
 
-        for vertex in svg.vertices(self.width, y).into_iter().flatten() {
+        for mut vertex in svg.vertices(self.width, y).into_iter().flatten() {
+            vertex.set_color(color);
             self.batcher.push(svg.texture_id, vertex);
         }
         self.width += svg.advance.0 as i16;
 
-        self.width += self.module_padding();
-
         Ok(())
     }
 
+    /// Padding after a module with scale factor applied.
+    fn padding(&self, style: ModuleStyle) -> i16 {
+        let padding = style.padding.map(|padding| padding as i16 * self.scale_factor);
+        padding.unwrap_or_else(|| self.module_padding())
+    }
+
     /// Module padding with scale factor applied.
     fn module_padding(&self) -> i16 {
-        MODULE_PADDING * self.scale_factor
+        self.config.module_padding as i16 * self.scale_factor
     }
 
     /// Edge padding with scale factor applied.
     fn edge_padding(&self) -> i16 {
-        EDGE_PADDING * self.scale_factor
+        self.config.edge_padding as i16 * self.scale_factor
+    }
+}
+
+/// Hash and horizontal extent of every batched glyph.
+fn glyph_extents(renderer: &Renderer) -> Vec<GlyphExtent> {
+    let vertices: Vec<_> = renderer.text_batcher.iter_pending().collect();
+    vertices
+        .chunks(4)
+        .map(|quad| {
+            let mut hasher = DefaultHasher::new();
+            quad.hash(&mut hasher);
+
+            let start = quad.iter().map(|(_, vertex)| vertex.x).min().unwrap_or_default();
+            let end = quad.iter().map(|(_, vertex)| vertex.x).max().unwrap_or_default();
+            (hasher.finish(), start..end)
+        })
+        .collect()
+}
+
+/// Horizontal region covering all glyphs which were added or removed.
+fn damaged_columns(old_glyphs: &[GlyphExtent], new_glyphs: &[GlyphExtent]) -> Range<i16> {
+    let old_hashes: HashSet<_> = old_glyphs.iter().map(|(hash, _)| hash).collect();
+    let new_hashes: HashSet<_> = new_glyphs.iter().map(|(hash, _)| hash).collect();
+
+    let removed = old_glyphs.iter().filter(|(hash, _)| !new_hashes.contains(hash));
+    let added = new_glyphs.iter().filter(|(hash, _)| !old_hashes.contains(hash));
+    removed
+        .chain(added)
+        .map(|(_, columns)| columns.clone())
+        .reduce(|damage, columns| damage.start.min(columns.start)..damage.end.max(columns.end))
+        .unwrap_or(0..0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unchanged_glyphs_are_not_damaged() {
+        let glyphs = [(1, 0..10), (2, 10..20)];
+        assert!(damaged_columns(&glyphs, &glyphs).is_empty());
+    }
+
+    #[test]
+    fn changed_glyphs_are_damaged() {
+        let old_glyphs = [(1, 0..10), (2, 10..20), (3, 20..30), (4, 50..60)];
+        let new_glyphs = [(1, 0..10), (5, 10..18), (3, 20..30), (6, 40..45)];
+        assert_eq!(damaged_columns(&old_glyphs, &new_glyphs), 10..60);
+    }
+
+    #[test]
+    fn moved_glyphs_are_damaged() {
+        let old_glyphs = [(1, 0..10)];
+        let new_glyphs = [(2, 5..15)];
+        assert_eq!(damaged_columns(&old_glyphs, &new_glyphs), 0..15);
     }
 }
@@ -0,0 +1,41 @@
+//! Flamegraph profiling of rendering and event handling.
+
+use std::env;
+use std::time::Duration;
+
+use calloop::timer::{TimeoutAction, Timer};
+use calloop::LoopHandle;
+use tracing_flame::FlameLayer;
+use tracing_subscriber::prelude::*;
+
+use crate::error::Error;
+use crate::{Result, State};
+
+/// Default file for recorded samples.
+const DEFAULT_PATH: &str = "epitaph.folded";
+
+/// Interval between writes of recorded samples.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Start recording spans.
+///
+/// Samples are written to `$EPITAPH_PROFILE` in the folded stack format, which can be turned
+/// into a flamegraph using `inferno-flamegraph`.
+pub fn init(event_loop: &LoopHandle<'static, State>) -> Result<()> {
+    let path = env::var_os("EPITAPH_PROFILE").unwrap_or_else(|| DEFAULT_PATH.into());
+    let (layer, guard) =
+        FlameLayer::with_file(path).map_err(|err| Error::Profiling(err.to_string()))?;
+
+    tracing::subscriber::set_global_default(tracing_subscriber::registry().with(layer))
+        .map_err(|err| Error::Profiling(err.to_string()))?;
+
+    // Write samples periodically, since epitaph is usually terminated by a signal.
+    event_loop.insert_source(Timer::from_duration(FLUSH_INTERVAL), move |now, _, _| {
+        if let Err(err) = guard.flush() {
+            eprintln!("Error: Couldn't write profile: {err}");
+        }
+        TimeoutAction::ToInstant(now + FLUSH_INTERVAL)
+    })?;
+
+    Ok(())
+}
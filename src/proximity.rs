@@ -0,0 +1,96 @@
+//! Screen blanking while the phone is held to the ear.
+
+use std::io::{ErrorKind, Read};
+use std::process::Command;
+
+use calloop::generic::Generic;
+use calloop::{Interest, LoopHandle, Mode, PostAction};
+
+use crate::module::ProcessStream;
+use crate::{Result, State};
+
+/// Signal emitted by ModemManager when a call is created.
+const CALL_ADDED_SIGNAL: &str = "org.freedesktop.ModemManager1.Modem.Voice.CallAdded";
+
+/// Signal emitted by ModemManager when a call is removed.
+const CALL_DELETED_SIGNAL: &str = "org.freedesktop.ModemManager1.Modem.Voice.CallDeleted";
+
+/// Proximity sensor and call state.
+#[derive(Default)]
+pub struct Proximity {
+    near: bool,
+    calls: usize,
+}
+
+impl Proximity {
+    /// Check if rendering and touch input should be suppressed.
+    pub fn blanked(&self) -> bool {
+        self.near && self.calls > 0
+    }
+}
+
+/// Watch the proximity sensor and active calls.
+pub fn watch(event_loop: &LoopHandle<'static, State>) -> Result<()> {
+    // Follow proximity changes reported by iio-sensor-proxy.
+    let stream = ProcessStream::spawn(Command::new("monitor-sensor"))?;
+    watch_lines(event_loop, stream, "proximity sensor", |state, line| {
+        // Initial state is reported as `Has proximity sensor (near: 1)`.
+        let near = match line.split_once("near: ") {
+            Some((_, near)) => near.starts_with('1'),
+            None => match line.split_once("Proximity value changed: ") {
+                Some((_, near)) => near.trim() == "1",
+                None => return,
+            },
+        };
+        state.update_proximity(|proximity| proximity.near = near);
+    })?;
+
+    // Follow calls created and removed by ModemManager.
+    let mut gdbus = Command::new("gdbus");
+    gdbus.args(["monitor", "--system", "--dest", "org.freedesktop.ModemManager1"]);
+    let stream = ProcessStream::spawn(gdbus)?;
+    watch_lines(event_loop, stream, "calls", |state, line| {
+        if line.contains(CALL_ADDED_SIGNAL) {
+            state.update_proximity(|proximity| proximity.calls += 1);
+        } else if line.contains(CALL_DELETED_SIGNAL) {
+            state.update_proximity(|proximity| proximity.calls = proximity.calls.saturating_sub(1));
+        }
+    })?;
+
+    Ok(())
+}
+
+/// Call `callback` for every line written by a process.
+fn watch_lines<F>(
+    event_loop: &LoopHandle<'static, State>,
+    stream: ProcessStream,
+    name: &'static str,
+    mut callback: F,
+) -> Result<()>
+where
+    F: FnMut(&mut State, &str) + 'static,
+{
+    let mut buffer = Vec::new();
+    let source = Generic::new(stream, Interest::READ, Mode::Level);
+    event_loop.insert_source(source, move |_, stream, state| {
+        let mut data = [0; 1024];
+        let len = match stream.read(&mut data) {
+            Ok(len) if len > 0 => len,
+            Err(err) if err.kind() == ErrorKind::WouldBlock => return Ok(PostAction::Continue),
+            _ => {
+                eprintln!("Error: Stopped watching for {name}");
+                return Ok(PostAction::Remove);
+            },
+        };
+        buffer.extend_from_slice(&data[..len]);
+
+        while let Some(end) = buffer.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            callback(state, &String::from_utf8_lossy(&line));
+        }
+
+        Ok(PostAction::Continue)
+    })?;
+
+    Ok(())
+}
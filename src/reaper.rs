@@ -1,71 +1,131 @@
 //! Watchdog for spawning subprocesses.
 
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::ffi::OsStr;
-use std::io;
+use std::future::Future;
+use std::io::{self, ErrorKind};
 use std::os::unix::process::CommandExt;
-use std::process::{Child, Command, Output, Stdio};
+use std::pin::Pin;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
 
 use calloop::signals::{Signal, Signals};
 use calloop::LoopHandle;
 
 use crate::{Result, State};
 
-/// Callback invoked after reaping.
-type Callback = Box<dyn FnOnce(&mut State, Output)>;
+/// Supervised children by their PID.
+type Processes = Rc<RefCell<HashMap<u32, Process>>>;
 
 /// Watchdog for reaping dead children.
 pub struct Reaper {
-    processes: HashMap<u32, (Child, Callback)>,
+    processes: Processes,
 }
 
 impl Reaper {
     pub fn new(event_loop: &LoopHandle<'static, State>) -> Result<Self> {
+        let processes = Processes::default();
+
         // Register calloop SIGCHLD handler.
-        let signals = Signals::new(&[Signal::SIGCHLD]).unwrap();
-        event_loop.insert_source(signals, |signal, _, state| {
-            if let Ok((callback, output)) = state.reaper.kill(signal.full_info().ssi_pid) {
-                callback(state, output);
-            }
+        let signals = Signals::new(&[Signal::SIGCHLD])?;
+        let reaped = processes.clone();
+        event_loop.insert_source(signals, move |_, _, _| {
+            // Signals are coalesced, so check every supervised child.
+            reaped.borrow_mut().retain(|_, process| !process.reap());
         })?;
 
-        Ok(Self { processes: Default::default() })
+        Ok(Self { processes })
     }
 
     /// Start watching a child.
-    pub fn watch(&mut self, mut child: Command, callback: Callback) {
-        // Set STDIO handles so callees don't have to handle it.
-        child.stdin(Stdio::null());
-        child.stdout(Stdio::piped());
-        child.stderr(Stdio::piped());
-
-        // Try to spawn the child process.
-        let child = match child.spawn() {
-            Ok(child) => child,
-            Err(err) => {
-                println!("Error: Child process failed: {err}");
-                return;
-            },
-        };
-
+    pub fn watch(&self, child: Child) -> Exit {
         let pid = child.id();
-        self.processes.insert(pid, (child, callback));
+        let process = Process { child, status: None, waker: None, detached: false };
+        self.processes.borrow_mut().insert(pid, process);
+
+        Exit { pid, processes: self.processes.clone() }
+    }
+}
+
+/// Supervised child process.
+struct Process {
+    child: Child,
+    status: Option<io::Result<ExitStatus>>,
+    waker: Option<Waker>,
+    detached: bool,
+}
+
+impl Process {
+    /// Collect the child's exit status once it has exited.
+    ///
+    /// Returns `true` if the process is no longer supervised.
+    fn reap(&mut self) -> bool {
+        if self.status.is_none() {
+            self.status = self.child.try_wait().transpose();
+            if self.status.is_none() {
+                return false;
+            }
+
+            if let Some(waker) = self.waker.take() {
+                waker.wake();
+            }
+        }
+
+        self.detached
     }
+}
+
+/// Future resolving once a supervised child has exited.
+///
+/// Dropping this before the child has exited will kill it.
+pub struct Exit {
+    processes: Processes,
+    pid: u32,
+}
 
-    /// Kill a kid.
-    pub fn kill(&mut self, pid: u32) -> Result<(Callback, Output)> {
-        let (mut child, callback) = match self.processes.remove(&pid) {
+impl Future for Exit {
+    type Output = io::Result<ExitStatus>;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<io::Result<ExitStatus>> {
+        let mut processes = self.processes.borrow_mut();
+        let process = match processes.get_mut(&self.pid) {
             Some(process) => process,
-            None => return Err(format!("{pid}: PID not supervised by reaper").into()),
+            None => return Poll::Ready(Err(ErrorKind::NotFound.into())),
         };
 
-        // Ensure child is dead.
-        let _ = child.kill();
+        // Check for exit directly, in case the child died before it was watched.
+        process.reap();
 
-        // Wait for child completion.
-        let output = child.wait_with_output()?;
+        match process.status.take() {
+            Some(status) => {
+                processes.remove(&self.pid);
+                Poll::Ready(status)
+            },
+            None => {
+                process.waker = Some(context.waker().clone());
+                Poll::Pending
+            },
+        }
+    }
+}
+
+impl Drop for Exit {
+    fn drop(&mut self) {
+        let mut processes = self.processes.borrow_mut();
+        let process = match processes.get_mut(&self.pid) {
+            Some(process) => process,
+            None => return,
+        };
 
-        Ok((callback, output))
+        // Kill running children, leaving them to the SIGCHLD handler for reaping.
+        if process.status.is_none() {
+            let _ = process.child.kill();
+            process.detached = true;
+        } else {
+            processes.remove(&self.pid);
+        }
     }
 }
 
@@ -80,6 +140,13 @@ where
 {
     let mut command = Command::new(program);
     command.args(args);
+    detach(command)
+}
+
+/// Spawn a prepared command as an unsupervised daemon.
+///
+/// See [`daemon`] for details.
+pub fn detach(mut command: Command) -> io::Result<()> {
     command.stdin(Stdio::null());
     command.stdout(Stdio::null());
     command.stderr(Stdio::null());
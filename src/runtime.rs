@@ -0,0 +1,223 @@
+//! Async runtime for modules.
+
+use std::cell::RefCell;
+use std::future::Future;
+use std::io::{self, ErrorKind, Read};
+use std::mem;
+use std::os::unix::io::AsRawFd;
+use std::pin::Pin;
+use std::process::{ChildStderr, ChildStdout, Command, Output, Stdio};
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+use calloop::futures::{self, Scheduler};
+use calloop::io::Async;
+use calloop::timer::{TimeoutAction, Timer};
+use calloop::{LoopHandle, RegistrationToken};
+
+use crate::reaper::{Exit, Reaper};
+use crate::{Result, State};
+
+/// Maximum runtime of commands before they are killed.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// State update produced by a completed future.
+type Task = Box<dyn FnOnce(&mut State)>;
+
+/// Executor for futures on the calloop event loop.
+pub struct Runtime {
+    event_loop: LoopHandle<'static, State>,
+    scheduler: Scheduler<Task>,
+    reaper: Reaper,
+}
+
+impl Runtime {
+    pub fn new(event_loop: &LoopHandle<'static, State>) -> Result<Self> {
+        let (executor, scheduler) = futures::executor()?;
+        event_loop.insert_source(executor, |task, _, state| {
+            #[cfg(feature = "profiling")]
+            let _span = tracing::info_span!("runtime_task").entered();
+
+            task(state)
+        })?;
+
+        // Create process reaper.
+        let reaper = Reaper::new(event_loop)?;
+
+        Ok(Self { event_loop: event_loop.clone(), scheduler, reaper })
+    }
+
+    /// Spawn a future on the event loop.
+    ///
+    /// The future's output is called with the shared state once it completes.
+    pub fn spawn<F, U>(&self, future: F)
+    where
+        F: Future<Output = U> + 'static,
+        U: FnOnce(&mut State) + 'static,
+    {
+        let future = async move { Box::new(future.await) as Task };
+        if let Err(err) = self.scheduler.schedule(future) {
+            eprintln!("Error: Couldn't spawn future: {err}");
+        }
+    }
+
+    /// Run a command to completion and collect its output.
+    ///
+    /// Commands which don't complete within thirty seconds are killed.
+    pub fn command_output(&self, command: Command) -> impl Future<Output = io::Result<Output>> {
+        let output = CommandOutput::spawn(&self.event_loop, &self.reaper, command);
+        async move { output?.await }
+    }
+}
+
+/// Future collecting a command's output.
+struct CommandOutput {
+    stdout_pipe: Option<Async<'static, ChildStdout>>,
+    stderr_pipe: Option<Async<'static, ChildStderr>>,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+    deadline: Deadline,
+    exit: Exit,
+}
+
+impl CommandOutput {
+    fn spawn(
+        event_loop: &LoopHandle<'static, State>,
+        reaper: &Reaper,
+        mut command: Command,
+    ) -> io::Result<Self> {
+        command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+        let mut child = command.spawn()?;
+
+        let stdout_pipe = child.stdout.take().map(|pipe| event_loop.adapt_io(pipe));
+        let stderr_pipe = child.stderr.take().map(|pipe| event_loop.adapt_io(pipe));
+
+        // Hand the child to the reaper first, so it is killed if anything fails.
+        let exit = reaper.watch(child);
+
+        Ok(Self {
+            stdout_pipe: stdout_pipe.transpose()?,
+            stderr_pipe: stderr_pipe.transpose()?,
+            deadline: Deadline::new(event_loop, COMMAND_TIMEOUT)?,
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+            exit,
+        })
+    }
+}
+
+impl Future for CommandOutput {
+    type Output = io::Result<Output>;
+
+    fn poll(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<io::Result<Output>> {
+        let this = &mut *self;
+
+        // Dropping the exit future kills the command.
+        if this.deadline.poll_expired(context) {
+            return Poll::Ready(Err(ErrorKind::TimedOut.into()));
+        }
+
+        // Drain both pipes simultaneously, so the child can't block writing to either.
+        let stdout = poll_pipe(&mut this.stdout_pipe, &mut this.stdout, context)?;
+        let stderr = poll_pipe(&mut this.stderr_pipe, &mut this.stderr, context)?;
+        if stdout.is_pending() || stderr.is_pending() {
+            return Poll::Pending;
+        }
+
+        // Wait for the child to exit, since it might have closed its output early.
+        let status = match Pin::new(&mut this.exit).poll(context) {
+            Poll::Ready(status) => status?,
+            Poll::Pending => return Poll::Pending,
+        };
+
+        let (stdout, stderr) = (mem::take(&mut this.stdout), mem::take(&mut this.stderr));
+        Poll::Ready(Ok(Output { status, stdout, stderr }))
+    }
+}
+
+/// Read available data from a pipe, closing it once it reached EOF.
+fn poll_pipe<R: Read + AsRawFd>(
+    pipe: &mut Option<Async<'static, R>>,
+    buffer: &mut Vec<u8>,
+    context: &mut Context<'_>,
+) -> Poll<io::Result<()>> {
+    let reader = match pipe {
+        Some(reader) => reader,
+        None => return Poll::Ready(Ok(())),
+    };
+
+    let mut data = [0; 4096];
+    loop {
+        match reader.get_mut().read(&mut data) {
+            Ok(0) => {
+                *pipe = None;
+                return Poll::Ready(Ok(()));
+            },
+            Ok(len) => buffer.extend_from_slice(&data[..len]),
+            Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                if Pin::new(&mut reader.readable()).poll(context).is_pending() {
+                    return Poll::Pending;
+                }
+            },
+            Err(err) if err.kind() == ErrorKind::Interrupted => (),
+            Err(err) => return Poll::Ready(Err(err)),
+        }
+    }
+}
+
+/// Timer waking a future once it has expired.
+struct Deadline {
+    event_loop: LoopHandle<'static, State>,
+    token: RegistrationToken,
+    state: Rc<RefCell<DeadlineState>>,
+}
+
+impl Deadline {
+    fn new(event_loop: &LoopHandle<'static, State>, timeout: Duration) -> io::Result<Self> {
+        let state = Rc::new(RefCell::new(DeadlineState::default()));
+
+        let timer_state = state.clone();
+        let timer = Timer::from_duration(timeout);
+        let token = event_loop
+            .insert_source(timer, move |_, _, _| {
+                timer_state.borrow_mut().expire();
+                TimeoutAction::Drop
+            })
+            .map_err(|err| io::Error::from(err.error))?;
+
+        Ok(Self { event_loop: event_loop.clone(), token, state })
+    }
+
+    /// Check whether the deadline has passed, waking the task once it does.
+    fn poll_expired(&self, context: &mut Context<'_>) -> bool {
+        let mut state = self.state.borrow_mut();
+        if !state.expired {
+            state.waker = Some(context.waker().clone());
+        }
+        state.expired
+    }
+}
+
+impl Drop for Deadline {
+    fn drop(&mut self) {
+        self.event_loop.remove(self.token);
+    }
+}
+
+/// Data shared between a deadline and its timer.
+#[derive(Default)]
+struct DeadlineState {
+    expired: bool,
+    waker: Option<Waker>,
+}
+
+impl DeadlineState {
+    /// Mark the deadline as expired and wake up its future.
+    fn expire(&mut self) {
+        self.expired = true;
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Per-seat input devices.
+
+use smithay_client_toolkit::reexports::client::protocol::wl_seat::WlSeat;
+use smithay_client_toolkit::reexports::client::protocol::wl_touch::WlTouch;
+use smithay_client_toolkit::reexports::client::Proxy;
+use smithay_client_toolkit::reexports::protocols::wp::tablet::zv2::client::zwp_tablet_seat_v2::ZwpTabletSeatV2;
+use smithay_client_toolkit::reexports::protocols::wp::tablet::zv2::client::zwp_tablet_tool_v2::ZwpTabletToolV2;
+
+/// Touch point identifier, unique across all touch devices.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TouchId {
+    device: u32,
+    id: i32,
+}
+
+impl TouchId {
+    pub fn new(touch: &WlTouch, id: i32) -> Self {
+        Self { device: touch.id().protocol_id(), id }
+    }
+
+    /// Touch point of a tablet tool.
+    ///
+    /// Each tool only has a single contact point.
+    pub fn from_tool(tool: &ZwpTabletToolV2) -> Self {
+        Self { device: tool.id().protocol_id(), id: 0 }
+    }
+
+    /// Check if this touch point belongs to a touch device.
+    pub fn is_from(&self, touch: &WlTouch) -> bool {
+        self.device == touch.id().protocol_id()
+    }
+}
+
+/// Input devices of all seats.
+#[derive(Default)]
+pub struct Seats {
+    seats: Vec<Seat>,
+}
+
+impl Seats {
+    /// Start tracking a new seat.
+    pub fn add_seat(&mut self, seat: WlSeat) {
+        self.seats.push(Seat { seat, touch: None, tablet: None });
+    }
+
+    /// Stop tracking a seat, releasing all its devices.
+    ///
+    /// Returns the touch device of the removed seat.
+    pub fn remove_seat(&mut self, seat: &WlSeat) -> Option<WlTouch> {
+        let index = self.seats.iter().position(|tracked| &tracked.seat == seat)?;
+        let removed = self.seats.swap_remove(index);
+
+        if let Some(tablet) = removed.tablet {
+            tablet.destroy();
+        }
+
+        let touch = removed.touch?;
+        touch.release();
+        Some(touch)
+    }
+
+    /// Check if a seat already has a touch device.
+    pub fn has_touch(&self, seat: &WlSeat) -> bool {
+        self.seat(seat).map_or(false, |seat| seat.touch.is_some())
+    }
+
+    /// Set the touch device of a seat.
+    pub fn set_touch(&mut self, seat: &WlSeat, touch: WlTouch) {
+        // Track seats which were added before their capabilities were announced.
+        if self.seat(seat).is_none() {
+            self.add_seat(seat.clone());
+        }
+
+        if let Some(seat) = self.seats.iter_mut().find(|tracked| &tracked.seat == seat) {
+            seat.touch = Some(touch);
+        }
+    }
+
+    /// Release the touch device of a seat.
+    ///
+    /// Returns the removed touch device.
+    pub fn remove_touch(&mut self, seat: &WlSeat) -> Option<WlTouch> {
+        let seat = self.seats.iter_mut().find(|tracked| &tracked.seat == seat)?;
+        let touch = seat.touch.take()?;
+        touch.release();
+        Some(touch)
+    }
+
+    /// Get the seat owning a touch device.
+    pub fn touch_seat(&self, touch: &WlTouch) -> Option<&WlSeat> {
+        let seat = self.seats.iter().find(|tracked| tracked.touch.as_ref() == Some(touch))?;
+        Some(&seat.seat)
+    }
+
+    /// Set the tablet seat of a seat.
+    pub fn set_tablet(&mut self, seat: &WlSeat, tablet: ZwpTabletSeatV2) {
+        match self.seats.iter_mut().find(|tracked| &tracked.seat == seat) {
+            Some(seat) => seat.tablet = Some(tablet),
+            None => tablet.destroy(),
+        }
+    }
+
+    /// Get a tracked seat.
+    fn seat(&self, seat: &WlSeat) -> Option<&Seat> {
+        self.seats.iter().find(|tracked| &tracked.seat == seat)
+    }
+}
+
+/// Input devices of a single seat.
+struct Seat {
+    seat: WlSeat,
+    touch: Option<WlTouch>,
+    tablet: Option<ZwpTabletSeatV2>,
+}
@@ -0,0 +1,49 @@
+//! System suspend and resume.
+
+use std::io::{ErrorKind, Read};
+use std::process::Command;
+
+use calloop::generic::Generic;
+use calloop::{Interest, LoopHandle, Mode, PostAction};
+
+use crate::module::ProcessStream;
+use crate::{Result, State};
+
+/// Signal emitted by logind before suspend and after resume.
+const SLEEP_SIGNAL: &str = "org.freedesktop.login1.Manager.PrepareForSleep";
+
+/// Watch logind's `PrepareForSleep` signal to handle resume from suspend.
+pub fn watch(event_loop: &LoopHandle<'static, State>) -> Result<()> {
+    let mut gdbus = Command::new("gdbus");
+    gdbus.args(["monitor", "--system", "--dest", "org.freedesktop.login1"]);
+    gdbus.args(["--object-path", "/org/freedesktop/login1"]);
+    let stream = ProcessStream::spawn(gdbus)?;
+
+    let mut buffer = Vec::new();
+    let source = Generic::new(stream, Interest::READ, Mode::Level);
+    event_loop.insert_source(source, move |_, stream, state| {
+        let mut data = [0; 1024];
+        let len = match stream.read(&mut data) {
+            Ok(len) if len > 0 => len,
+            Err(err) if err.kind() == ErrorKind::WouldBlock => return Ok(PostAction::Continue),
+            _ => {
+                eprintln!("Error: Stopped watching for system resume");
+                return Ok(PostAction::Remove);
+            },
+        };
+        buffer.extend_from_slice(&data[..len]);
+
+        // Signal argument is `false` once the system has resumed.
+        while let Some(end) = buffer.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            if line.contains(SLEEP_SIGNAL) && line.contains("(false,)") {
+                state.resume();
+            }
+        }
+
+        Ok(PostAction::Continue)
+    })?;
+
+    Ok(())
+}
@@ -0,0 +1,194 @@
+//! Offscreen rendering of serialized panel state.
+
+use std::fs;
+use std::path::Path;
+
+use epitaph_modules::Result as ModuleResult;
+use epitaph_ui::text::Svg;
+use epitaph_ui::Size;
+use serde::Deserialize;
+use smithay_client_toolkit::reexports::client::Connection;
+use tiny_skia::Pixmap;
+
+use crate::drawer::Drawer;
+use crate::error::Error;
+use crate::module::{
+    Alignment, DrawerModule, Module, PanelModule, PanelModuleContent, Section, Slider, Toggle,
+};
+use crate::panel::Panel;
+use crate::{Result, State};
+
+/// Serialized panel and drawer state.
+#[derive(Deserialize)]
+struct Snapshot {
+    /// Logical output size.
+    width: i32,
+    height: i32,
+
+    /// Output scale factor.
+    #[serde(default = "default_scale_factor")]
+    scale_factor: i32,
+
+    /// Drawer offset in logical pixels, the drawer is fully opened by default.
+    #[serde(default = "default_drawer_offset")]
+    drawer_offset: f64,
+
+    /// Modules in panel and drawer order.
+    modules: Vec<SnapshotModule>,
+}
+
+/// Static module content.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum SnapshotModule {
+    Text {
+        alignment: Alignment,
+        text: String,
+    },
+    Icon {
+        alignment: Alignment,
+        svg: Svg,
+    },
+    Toggle {
+        svg: Svg,
+        enabled: bool,
+        #[serde(default)]
+        section: Section,
+    },
+    Slider {
+        svg: Svg,
+        value: f64,
+        #[serde(default)]
+        section: Section,
+    },
+}
+
+impl Module for SnapshotModule {
+    fn panel_module(&self) -> Option<&dyn PanelModule> {
+        match self {
+            Self::Text { .. } | Self::Icon { .. } => Some(self),
+            _ => None,
+        }
+    }
+
+    fn drawer_module(&mut self) -> Option<DrawerModule> {
+        match self {
+            Self::Toggle { .. } => Some(DrawerModule::Toggle(self)),
+            Self::Slider { .. } => Some(DrawerModule::Slider(self)),
+            _ => None,
+        }
+    }
+
+    fn section(&self) -> Section {
+        match self {
+            Self::Toggle { section, .. } | Self::Slider { section, .. } => *section,
+            _ => Section::default(),
+        }
+    }
+}
+
+impl PanelModule for SnapshotModule {
+    fn alignment(&self) -> Alignment {
+        match self {
+            Self::Text { alignment, .. } | Self::Icon { alignment, .. } => *alignment,
+            _ => Alignment::Right,
+        }
+    }
+
+    fn content(&self) -> PanelModuleContent {
+        match self {
+            Self::Text { text, .. } => PanelModuleContent::Text(text.clone()),
+            _ => PanelModuleContent::Svg(self.svg()),
+        }
+    }
+}
+
+impl Toggle for SnapshotModule {
+    fn toggle(&mut self) -> ModuleResult<()> {
+        Ok(())
+    }
+
+    fn enabled(&self) -> bool {
+        matches!(self, Self::Toggle { enabled: true, .. })
+    }
+
+    fn svg(&self) -> Svg {
+        SnapshotModule::svg(self)
+    }
+}
+
+impl Slider for SnapshotModule {
+    fn set_value(&mut self, _value: f64) -> ModuleResult<()> {
+        Ok(())
+    }
+
+    fn get_value(&self) -> f64 {
+        match self {
+            Self::Slider { value, .. } => *value,
+            _ => 0.,
+        }
+    }
+
+    fn svg(&self) -> Svg {
+        SnapshotModule::svg(self)
+    }
+}
+
+impl SnapshotModule {
+    /// Module icon.
+    fn svg(&self) -> Svg {
+        match self {
+            Self::Icon { svg, .. } | Self::Toggle { svg, .. } | Self::Slider { svg, .. } => *svg,
+            Self::Text { .. } => Svg::Brightness,
+        }
+    }
+}
+
+/// Render a serialized state description to PNG files.
+///
+/// The panel and drawer are written next to the state file, using the
+/// `.panel.png` and `.drawer.png` extensions respectively.
+pub fn render_to_png(path: &Path) -> Result<()> {
+    let snapshot: Snapshot = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let mut modules = snapshot.modules;
+
+    // Rendering is offscreen, but EGL still requires a Wayland display.
+    let connection = Connection::connect_to_env()?;
+    let queue = connection.new_event_queue::<State>();
+    let egl_config = crate::egl_config(&connection)?;
+
+    // Render the panel.
+    let panel_modules: Vec<(_, &dyn Module)> =
+        modules.iter().map(|module| (None, module as _)).collect();
+    let pixmap =
+        Panel::snapshot(&egl_config, snapshot.width, snapshot.scale_factor, &panel_modules)?;
+    save_png(&pixmap, &path.with_extension("panel.png"))?;
+
+    // Render the drawer.
+    let mut drawer_modules: Vec<&mut dyn Module> =
+        modules.iter_mut().map(|module| module as _).collect();
+    let size = Size::new(snapshot.width, snapshot.height) * snapshot.scale_factor as f64;
+    let mut drawer = Drawer::new(queue.handle(), &egl_config, false)?;
+    let pixmap = drawer.snapshot(
+        size,
+        snapshot.scale_factor,
+        &mut drawer_modules,
+        snapshot.drawer_offset,
+    )?;
+    save_png(&pixmap, &path.with_extension("drawer.png"))?;
+
+    Ok(())
+}
+
+/// Write a rendered buffer to a PNG file.
+fn save_png(pixmap: &Pixmap, path: &Path) -> Result<()> {
+    pixmap.save_png(path).map_err(|err| Error::Png(err.to_string()))
+}
+
+fn default_scale_factor() -> i32 {
+    1
+}
+
+fn default_drawer_offset() -> f64 {
+    f64::INFINITY
+}
@@ -0,0 +1,44 @@
+//! Notification sounds.
+
+use std::ffi::OsStr;
+
+use crate::config;
+use crate::module::Urgency;
+use crate::{reaper, Result};
+
+/// Notification sound player.
+pub struct Sound {
+    config: config::Sound,
+}
+
+impl Sound {
+    pub fn new(config: config::Sound) -> Self {
+        Self { config }
+    }
+
+    /// Play the sound for a notification.
+    pub fn play(&self, urgency: Urgency, app: &str) -> Result<()> {
+        if self.config.muted.iter().any(|muted| muted == app) {
+            return Ok(());
+        }
+
+        let sound = match urgency {
+            Urgency::Normal => &self.config.normal,
+            Urgency::Critical => &self.config.critical,
+        };
+        let sound = match sound {
+            Some(sound) => sound,
+            None => return Ok(()),
+        };
+
+        // Sounds are disabled without a playback command.
+        let (program, args) = match self.config.command.split_first() {
+            Some(command) => command,
+            None => return Ok(()),
+        };
+        let args = args.iter().map(OsStr::new).chain([sound.as_os_str()]);
+        reaper::daemon(OsStr::new(program), args)?;
+
+        Ok(())
+    }
+}
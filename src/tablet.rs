@@ -0,0 +1,288 @@
+//! Tablet stylus input.
+
+use std::mem;
+
+use smithay_client_toolkit::reexports::client::globals::GlobalList;
+use smithay_client_toolkit::reexports::client::protocol::wl_seat::WlSeat;
+use smithay_client_toolkit::reexports::client::protocol::wl_surface::WlSurface;
+use smithay_client_toolkit::reexports::client::{
+    event_created_child, Connection, Dispatch, Proxy, QueueHandle,
+};
+use smithay_client_toolkit::reexports::protocols::wp::tablet::zv2::client::zwp_tablet_manager_v2::ZwpTabletManagerV2;
+use smithay_client_toolkit::reexports::protocols::wp::tablet::zv2::client::zwp_tablet_pad_group_v2::{self, ZwpTabletPadGroupV2};
+use smithay_client_toolkit::reexports::protocols::wp::tablet::zv2::client::zwp_tablet_pad_ring_v2::ZwpTabletPadRingV2;
+use smithay_client_toolkit::reexports::protocols::wp::tablet::zv2::client::zwp_tablet_pad_strip_v2::ZwpTabletPadStripV2;
+use smithay_client_toolkit::reexports::protocols::wp::tablet::zv2::client::zwp_tablet_pad_v2::{self, ZwpTabletPadV2};
+use smithay_client_toolkit::reexports::protocols::wp::tablet::zv2::client::zwp_tablet_seat_v2::{self, ZwpTabletSeatV2};
+use smithay_client_toolkit::reexports::protocols::wp::tablet::zv2::client::zwp_tablet_tool_v2::{self, ZwpTabletToolV2};
+use smithay_client_toolkit::reexports::protocols::wp::tablet::zv2::client::zwp_tablet_v2::ZwpTabletV2;
+
+use crate::seat::TouchId;
+use crate::State;
+
+/// Tablet tools of all seats.
+pub struct Tablets {
+    manager: Option<ZwpTabletManagerV2>,
+    tools: Vec<Tool>,
+}
+
+impl Tablets {
+    pub fn new(globals: &GlobalList, queue: &QueueHandle<State>) -> Self {
+        Self { manager: globals.bind(queue, 1..=1, ()).ok(), tools: Default::default() }
+    }
+
+    /// Get the tablet seat for a seat.
+    ///
+    /// Returns `None` if the compositor doesn't support tablets.
+    pub fn tablet_seat(
+        &self,
+        seat: &WlSeat,
+        queue: &QueueHandle<State>,
+    ) -> Option<ZwpTabletSeatV2> {
+        let manager = self.manager.as_ref()?;
+        Some(manager.get_tablet_seat(seat, queue, ()))
+    }
+
+    /// Get a tracked tool.
+    fn tool_mut(&mut self, tool: &ZwpTabletToolV2) -> Option<&mut Tool> {
+        self.tools.iter_mut().find(|tracked| &tracked.tool == tool)
+    }
+}
+
+/// Stylus or eraser of a tablet.
+///
+/// Tool events are buffered until the compositor sends a `frame`.
+struct Tool {
+    tool: ZwpTabletToolV2,
+    surface: Option<WlSurface>,
+    position: (f64, f64),
+    contact: bool,
+    pending_contact: Option<bool>,
+    pending_leave: bool,
+    moved: bool,
+}
+
+impl Tool {
+    fn new(tool: ZwpTabletToolV2) -> Self {
+        Self {
+            tool,
+            surface: Default::default(),
+            position: Default::default(),
+            contact: Default::default(),
+            pending_contact: Default::default(),
+            pending_leave: Default::default(),
+            moved: Default::default(),
+        }
+    }
+}
+
+impl State {
+    /// Apply all buffered events of a tool.
+    fn tool_frame(&mut self, tool: &ZwpTabletToolV2) {
+        let tracked = match self.tablets.tool_mut(tool) {
+            Some(tracked) => tracked,
+            None => return,
+        };
+
+        let surface = match &tracked.surface {
+            Some(surface) => surface.clone(),
+            None => return,
+        };
+        let id = TouchId::from_tool(&tracked.tool);
+        let position = tracked.position;
+        let moved = mem::take(&mut tracked.moved);
+        let leave = mem::take(&mut tracked.pending_leave);
+        let contact = tracked.pending_contact.take();
+
+        let was_down = tracked.contact;
+        let is_down = !leave && contact.unwrap_or(was_down);
+        tracked.contact = is_down;
+        if leave {
+            tracked.surface = None;
+        }
+
+        // Treat stylus contact like a touch point.
+        match (was_down, is_down) {
+            (false, true) => {
+                self.hover(&surface, None);
+                self.touch_down(id, &surface, position);
+            },
+            (true, true) if moved => self.touch_motion(id, position),
+            (true, false) => {
+                if moved {
+                    self.touch_motion(id, position);
+                }
+                self.touch_up(id);
+            },
+            _ => (),
+        }
+
+        // Highlight drawer elements below a hovering stylus.
+        if leave {
+            self.hover(&surface, None);
+        } else if moved && !is_down {
+            self.hover(&surface, Some(position));
+        }
+    }
+
+    /// Stop tracking a removed tool.
+    fn remove_tool(&mut self, tool: &ZwpTabletToolV2) {
+        let index = match self.tablets.tools.iter().position(|tracked| &tracked.tool == tool) {
+            Some(index) => index,
+            None => return,
+        };
+        let removed = self.tablets.tools.swap_remove(index);
+        removed.tool.destroy();
+
+        // Release the stylus if it was still pressed down.
+        if let Some(surface) = removed.surface {
+            if removed.contact {
+                self.touch_up(TouchId::from_tool(tool));
+            }
+            self.hover(&surface, None);
+        }
+    }
+}
+
+impl Dispatch<ZwpTabletManagerV2, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _manager: &ZwpTabletManagerV2,
+        _event: <ZwpTabletManagerV2 as Proxy>::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpTabletSeatV2, ()> for State {
+    event_created_child!(State, ZwpTabletSeatV2, [
+        zwp_tablet_seat_v2::EVT_TABLET_ADDED_OPCODE => (ZwpTabletV2, ()),
+        zwp_tablet_seat_v2::EVT_TOOL_ADDED_OPCODE => (ZwpTabletToolV2, ()),
+        zwp_tablet_seat_v2::EVT_PAD_ADDED_OPCODE => (ZwpTabletPadV2, ()),
+    ]);
+
+    fn event(
+        state: &mut Self,
+        _seat: &ZwpTabletSeatV2,
+        event: zwp_tablet_seat_v2::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+        if let zwp_tablet_seat_v2::Event::ToolAdded { id } = event {
+            state.tablets.tools.push(Tool::new(id));
+        }
+    }
+}
+
+impl Dispatch<ZwpTabletToolV2, ()> for State {
+    fn event(
+        state: &mut Self,
+        tool: &ZwpTabletToolV2,
+        event: zwp_tablet_tool_v2::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+        if let zwp_tablet_tool_v2::Event::Removed = event {
+            state.remove_tool(tool);
+            return;
+        }
+
+        if let zwp_tablet_tool_v2::Event::Frame { .. } = event {
+            state.tool_frame(tool);
+            return;
+        }
+
+        let tracked = match state.tablets.tool_mut(tool) {
+            Some(tracked) => tracked,
+            None => return,
+        };
+
+        match event {
+            zwp_tablet_tool_v2::Event::ProximityIn { surface, .. } => {
+                tracked.surface = Some(surface);
+                tracked.pending_leave = false;
+            },
+            zwp_tablet_tool_v2::Event::ProximityOut => tracked.pending_leave = true,
+            zwp_tablet_tool_v2::Event::Down { .. } => tracked.pending_contact = Some(true),
+            zwp_tablet_tool_v2::Event::Up => tracked.pending_contact = Some(false),
+            zwp_tablet_tool_v2::Event::Motion { x, y } => {
+                tracked.position = (x, y);
+                tracked.moved = true;
+            },
+            _ => (),
+        }
+    }
+}
+
+impl Dispatch<ZwpTabletV2, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _tablet: &ZwpTabletV2,
+        _event: <ZwpTabletV2 as Proxy>::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpTabletPadV2, ()> for State {
+    event_created_child!(State, ZwpTabletPadV2, [
+        zwp_tablet_pad_v2::EVT_GROUP_OPCODE => (ZwpTabletPadGroupV2, ()),
+    ]);
+
+    fn event(
+        _state: &mut Self,
+        _pad: &ZwpTabletPadV2,
+        _event: zwp_tablet_pad_v2::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpTabletPadGroupV2, ()> for State {
+    event_created_child!(State, ZwpTabletPadGroupV2, [
+        zwp_tablet_pad_group_v2::EVT_RING_OPCODE => (ZwpTabletPadRingV2, ()),
+        zwp_tablet_pad_group_v2::EVT_STRIP_OPCODE => (ZwpTabletPadStripV2, ()),
+    ]);
+
+    fn event(
+        _state: &mut Self,
+        _group: &ZwpTabletPadGroupV2,
+        _event: zwp_tablet_pad_group_v2::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpTabletPadRingV2, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _ring: &ZwpTabletPadRingV2,
+        _event: <ZwpTabletPadRingV2 as Proxy>::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpTabletPadStripV2, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _strip: &ZwpTabletPadStripV2,
+        _event: <ZwpTabletPadStripV2 as Proxy>::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+    }
+}
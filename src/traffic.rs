@@ -0,0 +1,157 @@
+//! Daily network traffic accounting.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use chrono::offset::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::error::Error;
+use crate::Result;
+
+/// Traffic file name inside the XDG state directory.
+const TRAFFIC_FILE: &str = "epitaph/traffic.toml";
+
+/// Directory containing all network interfaces.
+const NET_DIR: &str = "/sys/class/net";
+
+/// Number of days kept in the traffic history.
+const HISTORY_DAYS: usize = 62;
+
+/// Received and transmitted bytes.
+#[derive(Serialize, Deserialize, Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub struct Traffic {
+    pub rx: u64,
+    pub tx: u64,
+}
+
+impl Traffic {
+    /// Sum of received and transmitted bytes.
+    pub fn total(&self) -> u64 {
+        self.rx + self.tx
+    }
+}
+
+/// Persisted traffic per interface and day.
+#[derive(Serialize, Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct TrafficHistory {
+    /// Last `YYYY-MM` month the data cap warning was shown for.
+    pub cap_warning: Option<String>,
+
+    /// Traffic by `YYYY-MM-DD` date and interface name.
+    ///
+    /// This must be the last field, since TOML tables can't be followed by values.
+    days: BTreeMap<String, BTreeMap<String, Traffic>>,
+
+    /// Interface counters at the last update.
+    #[serde(skip)]
+    counters: HashMap<String, Traffic>,
+}
+
+impl TrafficHistory {
+    /// Load the persisted traffic history.
+    pub fn load() -> Self {
+        traffic_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| toml::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Persist the traffic history to the state file.
+    pub fn save(&self) -> Result<()> {
+        let path = traffic_path().ok_or(Error::MissingDirectory("state"))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, toml::to_string(self)?)?;
+
+        Ok(())
+    }
+
+    /// Add the traffic of all interfaces since the last update.
+    ///
+    /// Traffic before the first update is not counted, since the kernel's
+    /// counters might include traffic which was already recorded.
+    ///
+    /// Returns `true` if any traffic was added.
+    pub fn update(&mut self) -> Result<bool> {
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        let mut changed = false;
+
+        for entry in fs::read_dir(NET_DIR)? {
+            let interface = entry?.file_name().to_string_lossy().into_owned();
+            if interface == "lo" {
+                continue;
+            }
+
+            let counters = match interface_counters(&interface) {
+                Some(counters) => counters,
+                None => continue,
+            };
+
+            let last = match self.counters.insert(interface.clone(), counters) {
+                Some(last) => last,
+                None => continue,
+            };
+
+            // Treat decreasing counters as recreated interfaces.
+            let delta = |current: u64, last: u64| current.checked_sub(last).unwrap_or(current);
+            let rx = delta(counters.rx, last.rx);
+            let tx = delta(counters.tx, last.tx);
+            if rx == 0 && tx == 0 {
+                continue;
+            }
+
+            let traffic = self.days.entry(today.clone()).or_default().entry(interface).or_default();
+            traffic.rx += rx;
+            traffic.tx += tx;
+            changed = true;
+        }
+
+        // Drop days which are no longer needed for monthly totals.
+        let expired = self.days.len().saturating_sub(HISTORY_DAYS);
+        let expired: Vec<_> = self.days.keys().take(expired).cloned().collect();
+        for date in expired {
+            self.days.remove(&date);
+        }
+
+        Ok(changed)
+    }
+
+    /// Get the traffic of interfaces starting with any of the prefixes.
+    ///
+    /// Only days starting with `date_prefix` are included, allowing queries
+    /// for single days with `YYYY-MM-DD` or months with `YYYY-MM`.
+    pub fn traffic(&self, interfaces: &[String], date_prefix: &str) -> Traffic {
+        let mut total = Traffic::default();
+        let days = self.days.iter().filter(|(date, _)| date.starts_with(date_prefix));
+        for (_, day) in days {
+            for (interface, traffic) in day {
+                if interfaces.iter().any(|prefix| interface.starts_with(prefix.as_str())) {
+                    total.rx += traffic.rx;
+                    total.tx += traffic.tx;
+                }
+            }
+        }
+        total
+    }
+}
+
+/// Get the current RX/TX byte counters of an interface.
+fn interface_counters(interface: &str) -> Option<Traffic> {
+    let counter = |name: &str| {
+        let path = format!("{NET_DIR}/{interface}/statistics/{name}");
+        u64::from_str(fs::read_to_string(path).ok()?.trim()).ok()
+    };
+    Some(Traffic { rx: counter("rx_bytes")?, tx: counter("tx_bytes")? })
+}
+
+/// Location of the traffic state file.
+fn traffic_path() -> Option<PathBuf> {
+    Some(config::state_home()?.join(TRAFFIC_FILE))
+}